@@ -1,4 +1,25 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
 use isahc::ReadResponseExt;
+use once_cell::sync::Lazy;
+
+/// The wait before retrying a failed download, which doubles after each failure.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(60);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+struct FailedDownload {
+    retry_at: Instant,
+    retry_delay: Duration,
+}
+
+/// Failed downloads are remembered for the session,
+/// so that frequent callers such as syntax highlighting do not retry them on every call.
+static FAILED_DOWNLOADS: Lazy<Mutex<HashMap<String, FailedDownload>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
 
 /// Download the file from `url` and cache it under `folder_name` as `file_name`,
 /// so that it will not be downloaded again.
@@ -7,11 +28,56 @@ pub fn cache_download(url: &str, folder_name: &str, file_name: &str) -> anyhow::
     std::fs::create_dir_all(cache_dir.clone())?;
     let cache_path = cache_dir.join(file_name);
     if let Ok(text) = std::fs::read_to_string(cache_path.clone()) {
-        Ok(text)
-    } else {
-        let text = isahc::get(url)?.text()?;
-        std::fs::write(cache_path, &text)?;
+        return Ok(text);
+    }
+    let failed_downloads = || {
+        FAILED_DOWNLOADS
+            .lock()
+            .map_err(|error| anyhow::anyhow!("{}", error))
+    };
+    if failed_downloads()?
+        .get(url)
+        .is_some_and(|failed_download| Instant::now() < failed_download.retry_at)
+    {
+        return Err(anyhow::anyhow!(
+            "Downloading {} failed recently, it will be retried later",
+            url
+        ));
+    }
+    // The lock is not held while downloading, so that other downloads are not blocked
+    let result = download(url);
+    let mut failed_downloads = failed_downloads()?;
+    match result {
+        Ok(text) => {
+            failed_downloads.remove(url);
+            std::fs::write(cache_path, &text)?;
+            Ok(text)
+        }
+        Err(error) => {
+            let retry_delay = failed_downloads
+                .get(url)
+                .map(|failed_download| (failed_download.retry_delay * 2).min(MAX_RETRY_DELAY))
+                .unwrap_or(INITIAL_RETRY_DELAY);
+            failed_downloads.insert(
+                url.to_string(),
+                FailedDownload {
+                    retry_at: Instant::now() + retry_delay,
+                    retry_delay,
+                },
+            );
+            Err(error)
+        }
+    }
+}
 
-        Ok(text)
+fn download(url: &str) -> anyhow::Result<String> {
+    let mut response = isahc::get(url)?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Unable to download {}: {}",
+            url,
+            response.status()
+        ));
     }
+    Ok(response.text()?)
 }
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The unit of indentation inserted by one level of indent.
+pub enum Indent {
+    Tabs,
+    Spaces(usize),
+}
+
+impl Default for Indent {
+    fn default() -> Self {
+        Indent::Spaces(4)
+    }
+}
+
+impl Indent {
+    /// The character repeated by this indentation.
+    pub fn char(&self) -> char {
+        match self {
+            Indent::Tabs => '\t',
+            Indent::Spaces(_) => ' ',
+        }
+    }
+
+    /// The number of characters of one level of indentation.
+    pub fn len(&self) -> usize {
+        match self {
+            Indent::Tabs => 1,
+            Indent::Spaces(width) => *width,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The string of one level of indentation.
+    pub fn unit(&self) -> String {
+        std::iter::repeat(self.char()).take(self.len()).collect()
+    }
+
+    /// Detect the indentation used by `content`.
+    /// Returns `None` if `content` has no indented line.
+    ///
+    /// The width of space indentation is the most common increase of leading spaces
+    /// between consecutive non-empty lines.
+    pub fn detect(content: &str) -> Option<Indent> {
+        let mut tab_indented_lines = 0;
+        let mut space_indented_lines = 0;
+        let mut increments: HashMap<usize, usize> = HashMap::new();
+        let mut previous_spaces = 0;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            if line.starts_with('\t') {
+                tab_indented_lines += 1;
+                continue;
+            }
+            let spaces = line.chars().take_while(|c| c == &' ').count();
+            if spaces > 0 {
+                space_indented_lines += 1;
+            }
+            if spaces > previous_spaces {
+                *increments.entry(spaces - previous_spaces).or_default() += 1;
+            }
+            previous_spaces = spaces;
+        }
+        if tab_indented_lines == 0 && space_indented_lines == 0 {
+            None
+        } else if tab_indented_lines > space_indented_lines {
+            Some(Indent::Tabs)
+        } else {
+            increments
+                .into_iter()
+                // Prefer the smaller width when there is a tie
+                .max_by_key(|(width, count)| (*count, std::cmp::Reverse(*width)))
+                .map(|(width, _)| Indent::Spaces(width))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_indent {
+    use super::Indent;
+
+    #[test]
+    fn detect() {
+        assert_eq!(Indent::detect("a\nb"), None);
+        assert_eq!(Indent::detect("a\n\tb\n\t\tc\nd"), Some(Indent::Tabs));
+        assert_eq!(
+            Indent::detect("a\n  b\n    c\n      d\n  e\nf"),
+            Some(Indent::Spaces(2))
+        );
+        assert_eq!(
+            Indent::detect("a\n    b\n        c\n    d\n  (continuation)"),
+            Some(Indent::Spaces(4))
+        );
+    }
+}
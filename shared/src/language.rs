@@ -3,8 +3,12 @@ use serde_json::Value;
 
 pub(crate) use crate::process_command::ProcessCommand;
 use crate::{
-    canonicalized_path::CanonicalizedPath, formatter::Formatter,
-    ts_highlight_query::get_highlight_query,
+    canonicalized_path::CanonicalizedPath,
+    formatter::Formatter,
+    indent::Indent,
+    ts_highlight_query::{
+        get_highlight_query, get_indent_query, get_injection_query, get_textobjects_queries,
+    },
 };
 
 pub(crate) use crate::languages::LANGUAGES;
@@ -40,9 +44,16 @@ pub struct Language {
     pub(crate) file_names: &'static [&'static str],
     pub(crate) lsp_language_id: Option<LanguageId>,
     pub(crate) lsp_command: Option<LspCommand>,
+    /// The debug adapter, see [`DapCommand`]
+    pub(crate) dap_command: Option<DapCommand>,
     pub(crate) tree_sitter_grammar_config: Option<GrammarConfig>,
     pub(crate) highlight_query: Option<&'static str>,
     pub(crate) formatter_command: Option<Command>,
+    /// The default indentation, used when it cannot be detected from the file or `.editorconfig`
+    pub(crate) indent: Indent,
+    pub(crate) line_comment_token: Option<&'static str>,
+    /// The opening and closing tokens of a block comment
+    pub(crate) block_comment_tokens: Option<(&'static str, &'static str)>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -59,6 +70,15 @@ impl LspCommand {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DapCommand {
+    pub(crate) command: Command,
+    /// The arguments of the `launch` request as a JSON object, in which
+    /// `${file}`, `${fileDirname}`, `${workspaceFolder}` and `${workspaceFolderBasename}`
+    /// are replaced by the paths of the current file and the working directory.
+    pub(crate) launch_arguments: &'static str,
+}
+
 impl Language {
     pub const fn new() -> Self {
         Self {
@@ -67,8 +87,12 @@ impl Language {
             lsp_language_id: None,
             highlight_query: None,
             lsp_command: None,
+            dap_command: None,
             tree_sitter_grammar_config: None,
             formatter_command: None,
+            indent: Indent::Spaces(4),
+            line_comment_token: None,
+            block_comment_tokens: None,
         }
     }
 
@@ -142,8 +166,61 @@ impl Language {
         None
     }
 
-    pub fn injection_query(&self) -> Option<&'static str> {
-        None
+    pub fn injection_query(&self) -> Option<String> {
+        get_injection_query(self.tree_sitter_grammar_config.clone()?.id)
+            .ok()
+            .map(|result| result.query)
+            .or_else(|| {
+                grammar::grammar::load_runtime_file(
+                    &self.tree_sitter_grammar_config()?.grammar_id,
+                    "injections.scm",
+                )
+                .ok()
+            })
+            .map(|query| {
+                query
+                    .replace("lua-match", "match")
+                    .replace("vim-match", "match")
+                    // `nvim-treesitter` sets the language of fenced code blocks with a custom directive,
+                    // which is replaced by capturing the info string as the injection language
+                    .replace("(#set-lang-from-info-string! @_lang)", "")
+                    .replace("@_lang", "@injection.language")
+            })
+    }
+
+    /// The query of `nvim-treesitter` that captures the nodes which change the indentation
+    /// of the lines around them, such as `@indent.begin`.
+    ///
+    /// The query might be downloaded, so this should not be called on the UI thread.
+    pub fn indent_query(&self) -> anyhow::Result<Option<String>> {
+        let Some(config) = self.tree_sitter_grammar_config.as_ref() else {
+            return Ok(None);
+        };
+        Ok(Some(get_indent_query(config.id)?.query)
+            .filter(|query| !query.trim().is_empty())
+            .map(|query| {
+                query
+                    .replace("lua-match", "match")
+                    .replace("vim-match", "match")
+            }))
+    }
+
+    /// Queries defining textobjects such as `@function.inner` and `@function.outer`.
+    ///
+    /// The queries might be downloaded, so this should not be called on the UI thread.
+    pub fn textobjects_queries(&self) -> anyhow::Result<Vec<String>> {
+        let Some(config) = self.tree_sitter_grammar_config.as_ref() else {
+            return Ok(Vec::new());
+        };
+        Ok(get_textobjects_queries(config.id)?
+            .into_iter()
+            .filter(|query| !query.trim().is_empty())
+            .map(|query| {
+                query
+                    .replace("lua-match", "match")
+                    .replace("vim-match", "match")
+            })
+            .collect())
     }
 
     pub fn lsp_process_command(&self) -> Option<ProcessCommand> {
@@ -152,6 +229,16 @@ impl Language {
             .map(|command| ProcessCommand::new(command.command.0, command.command.1))
     }
 
+    pub fn dap_process_command(&self) -> Option<ProcessCommand> {
+        self.dap_command
+            .as_ref()
+            .map(|command| ProcessCommand::new(command.command.0, command.command.1))
+    }
+
+    pub fn dap_launch_arguments(&self) -> Option<&'static str> {
+        Some(self.dap_command.as_ref()?.launch_arguments)
+    }
+
     pub fn tree_sitter_grammar_id(&self) -> Option<String> {
         Some(self.tree_sitter_grammar_config()?.grammar_id)
     }
@@ -169,6 +256,18 @@ impl Language {
     pub fn formatter(&self) -> Option<Formatter> {
         self.formatter_command().map(Formatter::from)
     }
+
+    pub fn indent(&self) -> Indent {
+        self.indent
+    }
+
+    pub fn line_comment_token(&self) -> Option<&'static str> {
+        self.line_comment_token
+    }
+
+    pub fn block_comment_tokens(&self) -> Option<(&'static str, &'static str)> {
+        self.block_comment_tokens
+    }
 }
 
 pub fn from_path(path: &CanonicalizedPath) -> Option<Language> {
@@ -184,6 +283,40 @@ pub fn from_extension(extension: &str) -> Option<Language> {
         .map(|language| (*language).clone())
 }
 
+/// Find a language by the name used in injection queries and Markdown code fences,
+/// which can be the grammar id, the LSP language id, a file extension or a common alias.
+pub fn from_name(name: &str) -> Option<Language> {
+    let name = name.trim().to_lowercase();
+    let name = match name.as_str() {
+        "shell" | "sh" | "zsh" | "console" => "bash",
+        "c++" => "cpp",
+        "golang" => "go",
+        "py" | "python3" => "python",
+        "rs" => "rust",
+        "js" | "ecmascript" => "javascript",
+        "ts" => "typescript",
+        "yml" => "yaml",
+        "md" => "markdown",
+        "patch" => "diff",
+        name => name,
+    };
+    LANGUAGES
+        .iter()
+        .find(|language| {
+            language
+                .tree_sitter_grammar_config
+                .as_ref()
+                .is_some_and(|config| config.id == name)
+                || language.lsp_language_id.is_some_and(|id| id.0 == name)
+        })
+        .or_else(|| {
+            LANGUAGES
+                .iter()
+                .find(|language| language.extensions().contains(&name))
+        })
+        .map(|language| (*language).clone())
+}
+
 pub(crate) fn from_filename(path: &CanonicalizedPath) -> Option<Language> {
     let file_name = path.file_name()?;
     LANGUAGES
@@ -213,4 +346,15 @@ mod test_language {
         run_test_case("justfile", "just")?;
         Ok(())
     }
+
+    #[test]
+    fn test_from_name() {
+        let grammar_id =
+            |name: &str| from_name(name).and_then(|language| language.tree_sitter_grammar_id());
+        assert_eq!(grammar_id("rust").as_deref(), Some("rust"));
+        assert_eq!(grammar_id("rs").as_deref(), Some("rust"));
+        assert_eq!(grammar_id("JavaScript").as_deref(), Some("javascript"));
+        assert_eq!(grammar_id("py").as_deref(), Some("python"));
+        assert_eq!(grammar_id("unknown"), None);
+    }
 }
@@ -1,4 +1,7 @@
-use super::language::{Command, GrammarConfig, Language, LanguageId, LspCommand};
+use super::{
+    indent::Indent,
+    language::{Command, DapCommand, GrammarConfig, Language, LanguageId, LspCommand},
+};
 
 pub const LANGUAGES: &[&Language] = &[
     &bash(),
@@ -25,6 +28,7 @@ pub const LANGUAGES: &[&Language] = &[
     &just(),
     &json(),
     &lua(),
+    &makefile(),
     &nix(),
     &markdown(),
     &python(),
@@ -58,6 +62,7 @@ const fn bash() -> Language {
             subpath: None,
         }),
         formatter_command: Some(Command("shfmt", &[".sh", ".bash"])),
+        line_comment_token: Some("#"),
         ..Language::new()
     }
 }
@@ -79,6 +84,10 @@ const fn c() -> Language {
         }),
         highlight_query: None,
         formatter_command: Some(Command("clang-format", &[])),
+        indent: Indent::Spaces(4),
+        line_comment_token: Some("//"),
+        block_comment_tokens: Some(("/*", "*/")),
+        ..Language::new()
     }
 }
 
@@ -96,6 +105,10 @@ const fn common_lisp() -> Language {
         }),
         highlight_query: None,
         formatter_command: None,
+        indent: Indent::Spaces(2),
+        line_comment_token: Some(";"),
+        block_comment_tokens: Some(("#|", "|#")),
+        ..Language::new()
     }
 }
 
@@ -116,6 +129,10 @@ const fn cpp() -> Language {
         }),
         highlight_query: None,
         formatter_command: Some(Command("clang-format", &[])),
+        indent: Indent::Spaces(4),
+        line_comment_token: Some("//"),
+        block_comment_tokens: Some(("/*", "*/")),
+        ..Language::new()
     }
 }
 
@@ -133,6 +150,10 @@ const fn csv() -> Language {
             commit: "main",
             subpath: None,
         }),
+        indent: Indent::Spaces(4),
+        line_comment_token: None,
+        block_comment_tokens: None,
+        ..Language::new()
     }
 }
 
@@ -163,6 +184,10 @@ const fn css() -> Language {
             commit: "master",
             subpath: None,
         }),
+        indent: Indent::Spaces(2),
+        line_comment_token: None,
+        block_comment_tokens: Some(("/*", "*/")),
+        ..Language::new()
     }
 }
 
@@ -180,6 +205,10 @@ const fn dockerfile() -> Language {
             commit: "main",
             subpath: None,
         }),
+        indent: Indent::Spaces(4),
+        line_comment_token: Some("#"),
+        block_comment_tokens: None,
+        ..Language::new()
     }
 }
 
@@ -198,6 +227,8 @@ const fn elixir() -> Language {
             initialization_options: None,
         }),
         formatter_command: Some(Command("mix", &["format", "-"])),
+        indent: Indent::Spaces(2),
+        line_comment_token: Some("#"),
         ..Language::new()
     }
 }
@@ -217,6 +248,8 @@ const fn gleam() -> Language {
             command: Command("gleam", &["lsp"]),
             ..LspCommand::default()
         }),
+        indent: Indent::Spaces(2),
+        line_comment_token: Some("//"),
         ..Language::new()
     }
 }
@@ -236,6 +269,8 @@ const fn graphql() -> Language {
             command: Command("graphql-lsp", &["server", "-m", "stream"]),
             initialization_options: Some(r#"{ "graphql-config.load.legacy": true }"#),
         }),
+        indent: Indent::Spaces(2),
+        line_comment_token: Some("#"),
         ..Language::new()
     }
 }
@@ -249,6 +284,7 @@ const fn gitattributes() -> Language {
             commit: "master",
             subpath: None,
         }),
+        line_comment_token: Some("#"),
         ..Language::new()
     }
 }
@@ -262,6 +298,7 @@ const fn gitcommit() -> Language {
             commit: "main",
             subpath: None,
         }),
+        line_comment_token: Some("#"),
         ..Language::new()
     }
 }
@@ -275,6 +312,7 @@ const fn gitconfig() -> Language {
             commit: "main",
             subpath: None,
         }),
+        line_comment_token: Some("#"),
         ..Language::new()
     }
 }
@@ -288,6 +326,7 @@ const fn gitignore() -> Language {
             commit: "main",
             subpath: None,
         }),
+        line_comment_token: Some("#"),
         ..Language::new()
     }
 }
@@ -301,6 +340,7 @@ const fn gitrebase() -> Language {
             commit: "main",
             subpath: None,
         }),
+        line_comment_token: Some("#"),
         ..Language::new()
     }
 }
@@ -314,6 +354,7 @@ const fn hare() -> Language {
             commit: "master",
             subpath: None,
         }),
+        line_comment_token: Some("//"),
         ..Language::new()
     }
 }
@@ -336,6 +377,8 @@ const fn heex() -> Language {
             "mix",
             &["format", "--stdin-filename", "file.heex", "-"],
         )),
+        indent: Indent::Spaces(2),
+        block_comment_tokens: Some(("<%!--", "--%>")),
         ..Language::new()
     }
 }
@@ -355,6 +398,8 @@ const fn html() -> Language {
             subpath: None,
         }),
         formatter_command: Some(Command("prettierd", &[".html"])),
+        indent: Indent::Spaces(2),
+        block_comment_tokens: Some(("<!--", "-->")),
         ..Language::new()
     }
 }
@@ -378,6 +423,9 @@ const fn javascript(jsx: bool) -> Language {
             subpath: None,
         }),
         formatter_command: Some(Command("prettierd", if jsx { &[".jsx"] } else { &[".js"] })),
+        indent: Indent::Spaces(2),
+        line_comment_token: Some("//"),
+        block_comment_tokens: Some(("/*", "*/")),
         ..Language::new()
     }
 }
@@ -396,6 +444,10 @@ const fn json() -> Language {
         }),
         highlight_query: None,
         formatter_command: Some(Command("prettierd", &[".json"])),
+        indent: Indent::Spaces(2),
+        line_comment_token: None,
+        block_comment_tokens: None,
+        ..Language::new()
     }
 }
 
@@ -413,6 +465,10 @@ const fn just() -> Language {
         }),
         highlight_query: None,
         formatter_command: None,
+        indent: Indent::Spaces(4),
+        line_comment_token: Some("#"),
+        block_comment_tokens: None,
+        ..Language::new()
     }
 }
 
@@ -431,6 +487,8 @@ const fn lua() -> Language {
             subpath: None,
         }),
         formatter_command: Some(Command("stylua", &["-"])),
+        line_comment_token: Some("--"),
+        block_comment_tokens: Some(("--[[", "]]")),
         ..Language::new()
     }
 }
@@ -452,6 +510,21 @@ const fn nix() -> Language {
         }),
         highlight_query: None,
         formatter_command: Some(Command("nixfmt", &[])),
+        indent: Indent::Spaces(2),
+        line_comment_token: Some("#"),
+        block_comment_tokens: Some(("/*", "*/")),
+        ..Language::new()
+    }
+}
+
+const fn makefile() -> Language {
+    Language {
+        file_names: &["Makefile", "makefile", "GNUmakefile"],
+        extensions: &["mk"],
+        // Recipes must be indented with tabs
+        indent: Indent::Tabs,
+        line_comment_token: Some("#"),
+        ..Language::new()
     }
 }
 
@@ -470,6 +543,7 @@ const fn markdown() -> Language {
             subpath: Some("tree-sitter-markdown"),
         }),
         formatter_command: Some(Command("prettierd", &[".md"])),
+        block_comment_tokens: Some(("<!--", "-->")),
         ..Language::new()
     }
 }
@@ -488,7 +562,16 @@ const fn python() -> Language {
             commit: "master",
             subpath: None,
         }),
+        dap_command: Some(DapCommand {
+            command: Command("python3", &["-m", "debugpy.adapter"]),
+            launch_arguments: r#"{
+                "program": "${file}",
+                "cwd": "${workspaceFolder}",
+                "console": "internalConsole"
+            }"#,
+        }),
         formatter_command: Some(Command("ruff", &["format", "--stdin-filename", ".py"])),
+        line_comment_token: Some("#"),
         ..Language::new()
     }
 }
@@ -513,6 +596,10 @@ const fn rescript() -> Language {
             "./node_modules/.bin/rescript",
             &["format", "-stdin", ".res"],
         )),
+        indent: Indent::Spaces(2),
+        line_comment_token: Some("//"),
+        block_comment_tokens: Some(("/*", "*/")),
+        ..Language::new()
     }
 }
 
@@ -525,6 +612,13 @@ const fn rust() -> Language {
             command: Command("rust-analyzer", &[]),
             ..LspCommand::default()
         }),
+        dap_command: Some(DapCommand {
+            command: Command("lldb-dap", &[]),
+            launch_arguments: r#"{
+                "program": "${workspaceFolder}/target/debug/${workspaceFolderBasename}",
+                "cwd": "${workspaceFolder}"
+            }"#,
+        }),
         highlight_query: None,
         tree_sitter_grammar_config: Some(GrammarConfig {
             id: "rust",
@@ -533,6 +627,9 @@ const fn rust() -> Language {
             subpath: None,
         }),
         formatter_command: Some(Command("rustfmt", &["--edition=2021"])),
+        indent: Indent::Spaces(4),
+        line_comment_token: Some("//"),
+        block_comment_tokens: Some(("/*", "*/")),
     }
 }
 
@@ -548,6 +645,8 @@ const fn sql() -> Language {
             subpath: None,
         }),
         formatter_command: Some(Command("sql-formatter", &["--language", "postgresql"])),
+        line_comment_token: Some("--"),
+        block_comment_tokens: Some(("/*", "*/")),
         ..Language::new()
     }
 }
@@ -567,6 +666,8 @@ const fn swift() -> Language {
             subpath: None,
         }),
         formatter_command: Some(Command("swiftformat", &[])),
+        line_comment_token: Some("//"),
+        block_comment_tokens: Some(("/*", "*/")),
         ..Language::new()
     }
 }
@@ -585,6 +686,10 @@ const fn toml() -> Language {
         }),
         highlight_query: None,
         formatter_command: None,
+        indent: Indent::Spaces(4),
+        line_comment_token: Some("#"),
+        block_comment_tokens: None,
+        ..Language::new()
     }
 }
 
@@ -602,6 +707,10 @@ const fn tree_sitter_query() -> Language {
         }),
         highlight_query: None,
         formatter_command: None,
+        indent: Indent::Spaces(2),
+        line_comment_token: Some(";"),
+        block_comment_tokens: None,
+        ..Language::new()
     }
 }
 
@@ -632,6 +741,9 @@ const fn typescript(tsx: bool) -> Language {
             subpath: Some(choice(tsx, "tsx", "typescript")),
         }),
         formatter_command: Some(Command("prettierd", choice(tsx, &[".tsx"], &[".ts"]))),
+        indent: Indent::Spaces(2),
+        line_comment_token: Some("//"),
+        block_comment_tokens: Some(("/*", "*/")),
         ..Language::new()
     }
 }
@@ -650,6 +762,10 @@ const fn xml() -> Language {
         }),
         formatter_command: None,
         highlight_query: None,
+        indent: Indent::Spaces(2),
+        line_comment_token: None,
+        block_comment_tokens: Some(("<!--", "-->")),
+        ..Language::new()
     }
 }
 
@@ -667,6 +783,10 @@ const fn yaml() -> Language {
         }),
         formatter_command: None,
         highlight_query: None,
+        indent: Indent::Spaces(2),
+        line_comment_token: Some("#"),
+        block_comment_tokens: None,
+        ..Language::new()
     }
 }
 
@@ -687,6 +807,10 @@ const fn zig() -> Language {
         }),
         formatter_command: Some(Command("zig", &["fmt", "--stdin"])),
         highlight_query: None,
+        indent: Indent::Spaces(4),
+        line_comment_token: Some("//"),
+        block_comment_tokens: None,
+        ..Language::new()
     }
 }
 
@@ -699,6 +823,10 @@ const fn golang() -> Language {
             command: Command("gopls", &[]),
             ..LspCommand::default()
         }),
+        dap_command: Some(DapCommand {
+            command: Command("dlv", &["dap"]),
+            launch_arguments: r#"{ "mode": "debug", "program": "${fileDirname}" }"#,
+        }),
         tree_sitter_grammar_config: Some(GrammarConfig {
             id: "go",
             url: "https://github.com/tree-sitter/tree-sitter-go",
@@ -707,5 +835,9 @@ const fn golang() -> Language {
         }),
         formatter_command: Some(Command("gofmt", &[])),
         highlight_query: None,
+        indent: Indent::Tabs,
+        line_comment_token: Some("//"),
+        block_comment_tokens: Some(("/*", "*/")),
+        ..Language::new()
     }
 }
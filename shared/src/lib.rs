@@ -3,6 +3,7 @@ pub mod download;
 pub(crate) mod formatter;
 pub mod grammar;
 pub mod icons;
+pub mod indent;
 pub mod language;
 pub(crate) mod languages;
 pub mod process_command;
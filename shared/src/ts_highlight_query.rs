@@ -23,9 +23,74 @@ pub fn clear_cache() -> anyhow::Result<()> {
 
 /// Get highlight query from cache or `nvim-treesitter` repo.
 pub(crate) fn get_highlight_query(language_id: &str) -> anyhow::Result<GetHighlightQueryResult> {
+    get_query(language_id, QueryKind::Highlights)
+}
+
+/// Get injection query from cache or `nvim-treesitter` repo.
+///
+/// The query is empty if the language has no injection query.
+pub(crate) fn get_injection_query(language_id: &str) -> anyhow::Result<GetHighlightQueryResult> {
+    get_query(language_id, QueryKind::Injections)
+}
+
+/// Get indent query from cache or `nvim-treesitter` repo.
+///
+/// The query is empty if the language has no indent query.
+pub(crate) fn get_indent_query(language_id: &str) -> anyhow::Result<GetHighlightQueryResult> {
+    get_query(language_id, QueryKind::Indents)
+}
+
+/// Get the textobjects queries from cache or the `nvim-treesitter-textobjects` and `helix` repos.
+///
+/// Both are returned because they define different textobjects, for example only `helix` defines tests.
+/// The queries are empty if the language has no textobjects query.
+pub(crate) fn get_textobjects_queries(language_id: &str) -> anyhow::Result<Vec<String>> {
+    [QueryKind::NvimTextObjects, QueryKind::HelixTextObjects]
+        .into_iter()
+        .map(|kind| Ok(get_query(language_id, kind)?.query))
+        .collect()
+}
+
+#[derive(Clone, Copy)]
+enum QueryKind {
+    Highlights,
+    Injections,
+    Indents,
+    NvimTextObjects,
+    HelixTextObjects,
+}
+
+impl QueryKind {
+    fn url(&self, language_id: &str) -> String {
+        match self {
+            QueryKind::Highlights => format!("https://raw.githubusercontent.com/nvim-treesitter/nvim-treesitter/master/queries/{}/highlights.scm", language_id),
+            QueryKind::Injections => format!("https://raw.githubusercontent.com/nvim-treesitter/nvim-treesitter/master/queries/{}/injections.scm", language_id),
+            QueryKind::Indents => format!("https://raw.githubusercontent.com/nvim-treesitter/nvim-treesitter/master/queries/{}/indents.scm", language_id),
+            QueryKind::NvimTextObjects => format!("https://raw.githubusercontent.com/nvim-treesitter/nvim-treesitter-textobjects/master/queries/{}/textobjects.scm", language_id),
+            QueryKind::HelixTextObjects => format!("https://raw.githubusercontent.com/helix-editor/helix/master/runtime/queries/{}/textobjects.scm", language_id),
+        }
+    }
+
+    fn cache_file_name(&self, language_id: &str) -> String {
+        match self {
+            QueryKind::Highlights => format!("{}.scm", language_id),
+            QueryKind::Injections => format!("{}.injections.scm", language_id),
+            QueryKind::Indents => format!("{}.indents.scm", language_id),
+            QueryKind::NvimTextObjects => format!("{}.textobjects.scm", language_id),
+            QueryKind::HelixTextObjects => format!("{}.helix-textobjects.scm", language_id),
+        }
+    }
+
+    /// Unlike highlight queries, not every language has these queries
+    fn is_optional(&self) -> bool {
+        !matches!(self, QueryKind::Highlights)
+    }
+}
+
+fn get_query(language_id: &str, kind: QueryKind) -> anyhow::Result<GetHighlightQueryResult> {
     let cache_dir = cache_dir();
     std::fs::create_dir_all(cache_dir.clone())?;
-    let cache_path = cache_dir.join(format!("{}.scm", language_id));
+    let cache_path = cache_dir.join(kind.cache_file_name(language_id));
     if let Ok(text) = std::fs::read_to_string(cache_path.clone()) {
         return Ok(GetHighlightQueryResult {
             query: text,
@@ -33,12 +98,23 @@ pub(crate) fn get_highlight_query(language_id: &str) -> anyhow::Result<GetHighli
         });
     }
 
-    let nvim_tree_sitter_highlight_query_url = format!("https://raw.githubusercontent.com/nvim-treesitter/nvim-treesitter/master/queries/{}/highlights.scm", language_id);
-
-    let current = isahc::get(nvim_tree_sitter_highlight_query_url)?.text()?;
+    let mut response = isahc::get(kind.url(language_id))?;
+    let current = if kind.is_optional() && response.status() == isahc::http::StatusCode::NOT_FOUND {
+        // Cache the absence of the query as an empty query
+        String::new()
+    } else if !response.status().is_success() {
+        // Other failures are not cached, so that the query is fetched again next time
+        anyhow::bail!(
+            "Failed to fetch {}: {}",
+            kind.url(language_id),
+            response.status()
+        )
+    } else {
+        response.text()?
+    };
     let parent = get_highlight_query_parents(&current)
         .into_iter()
-        .map(|parent| -> anyhow::Result<_> { Ok(get_highlight_query(&parent)?.query) })
+        .map(|parent| -> anyhow::Result<_> { Ok(get_query(&parent, kind)?.query) })
         .collect::<Result<Vec<_>, _>>()?
         .join("\n\n");
 
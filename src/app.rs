@@ -1,40 +1,55 @@
 use crate::{
-    buffer::Buffer,
+    buffer::{self, Buffer},
     clipboard::CopiedTexts,
     components::{
         component::{Component, ComponentId, GetGridResult},
         dropdown::{DropdownItem, DropdownRender},
         editor::{Direction, DispatchEditor, Editor, IfCurrentNotFound, Movement},
+        file_explorer::{self, DispatchFileExplorer},
         keymap_legend::{
             Keymap, KeymapLegendBody, KeymapLegendConfig, KeymapLegendSection, Keymaps,
         },
-        prompt::{Prompt, PromptConfig, PromptHistoryKey},
+        prompt::{FireDispatchesOnChange, Prompt, PromptConfig, PromptHistoryKey},
         suggestive_editor::{
-            DispatchSuggestiveEditor, Info, SuggestiveEditor, SuggestiveEditorFilter,
+            Decoration, DispatchSuggestiveEditor, Info, SuggestiveEditor, SuggestiveEditorFilter,
         },
     },
     context::{Context, GlobalMode, LocalSearchConfigMode, QuickfixListSource, Search},
+    dap::session::{DebugResume, DebugSession, DebugUpdate},
+    diff_view::{DiffSource, DiffView, DiffViewKind},
+    file_format::FileFormat,
     frontend::Frontend,
-    git,
-    grid::{Grid, LineUpdate},
+    git::{self, conflict::parse_conflicts, log::CommitSummary, GitOperation},
+    grid::{Grid, LineUpdate, StyleKey},
     history::History,
     layout::Layout,
     list::{self, grep::RegexConfig, WalkBuilderConfig},
     lsp::{
-        completion::CompletionItem,
+        completion::{CompletionItem, PositionalEdit},
         goto_definition_response::GotoDefinitionResponse,
         manager::LspManager,
         process::{FromEditor, LspNotification, ResponseContext},
         symbols::Symbols,
         workspace_edit::WorkspaceEdit,
     },
+    plugin::{
+        protocol::{EditorNotification, PluginMessage, PluginNotification, PluginRequest},
+        PluginCommand, PluginConfig, PluginManager,
+    },
     position::Position,
     quickfix_list::{Location, QuickfixList, QuickfixListItem, QuickfixListType},
+    recovery::{self, AutoSaveConfig, RecoveryFile},
+    rectangle::{SplitOrientation, WindowDirection},
+    replace_preview::ReplacePreview,
     screen::{Screen, Window},
     selection::SelectionMode,
+    selection_range::SelectionRange,
+    server::{Server, ServerCommand},
     syntax_highlight::{HighlighedSpans, SyntaxHighlightRequest},
+    task::{ReceivedOutput, Task, TaskRun},
     ui_tree::{ComponentKind, KindedComponent},
 };
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
 use event::event::Event;
 use itertools::{Either, Itertools};
 use name_variant::NamedVariant;
@@ -42,12 +57,15 @@ use shared::{canonicalized_path::CanonicalizedPath, language::Language};
 use std::{
     any::TypeId,
     cell::RefCell,
+    collections::HashMap,
+    ops::Range,
     path::{Path, PathBuf},
     rc::Rc,
     sync::{
         mpsc::{Receiver, Sender},
         Mutex,
     },
+    time::Instant,
 };
 use DispatchEditor::*;
 
@@ -77,6 +95,51 @@ pub(crate) struct App<T: Frontend> {
     file_path_history: History<CanonicalizedPath>,
     status_line_components: Vec<StatusLineComponent>,
     last_action_description: Option<String>,
+
+    /// Set when the commit message editor is opened,
+    /// the commit is created when the commit message is saved and closed.
+    pending_commit: Option<PendingCommit>,
+
+    /// Set when the replace preview is opened,
+    /// the accepted changes are applied when the preview is saved.
+    pending_replace_preview: Option<PendingReplacePreview>,
+
+    /// Accepts commands from other processes, such as `ki edit`.
+    server: Option<Server>,
+
+    plugin_manager: PluginManager,
+
+    /// The last task that was run, kept after it exits so that it can be rerun.
+    task_run: Option<TaskRun>,
+
+    debug_session: Option<DebugSession>,
+
+    /// Where the undo histories of opened files are persisted, they are not persisted if `None`.
+    undo_history_directory: Option<PathBuf>,
+
+    auto_save: AutoSaveConfig,
+    /// Used for auto-saving after idling
+    last_event_at: Instant,
+    recovery_directory: PathBuf,
+    /// The edit count of every buffer when its recovery file was written by this process
+    recovery_edit_counts: HashMap<CanonicalizedPath, usize>,
+    /// Recovery files left behind by crashed processes, which are yet to be recovered or discarded
+    orphaned_recovery_files: Vec<RecoveryFile>,
+}
+
+struct PendingCommit {
+    path: CanonicalizedPath,
+    amend: bool,
+    /// Whether the commit message was saved since the editor was opened
+    saved: bool,
+}
+
+struct PendingReplacePreview {
+    path: CanonicalizedPath,
+    preview: ReplacePreview,
+    /// The preview document is only useful while its replacement is pending,
+    /// so it is a temporary file that is removed when this is dropped.
+    _file: tempfile::NamedTempFile,
 }
 
 const GLOBAL_TITLE_BAR_HEIGHT: u16 = 1;
@@ -87,11 +150,12 @@ pub(crate) enum StatusLineComponent {
     GitBranch,
     Mode,
     SelectionMode,
+    /// The encoding and line ending of the current file
+    FileFormat,
     LastDispatch,
 }
 
 impl<T: Frontend> App<T> {
-    #[cfg(test)]
     pub(crate) fn new(
         frontend: Rc<Mutex<T>>,
         working_directory: CanonicalizedPath,
@@ -107,11 +171,23 @@ impl<T: Frontend> App<T> {
         )
     }
 
-    #[cfg(test)]
     pub(crate) fn disable_lsp(&mut self) {
         self.enable_lsp = false
     }
 
+    pub(crate) fn set_undo_history_directory(&mut self, directory: Option<PathBuf>) {
+        self.undo_history_directory = directory
+    }
+
+    pub(crate) fn set_recovery_directory(&mut self, directory: PathBuf) {
+        self.recovery_directory = directory
+    }
+
+    #[cfg(test)]
+    pub(crate) fn recovery_directory(&self) -> &Path {
+        &self.recovery_directory
+    }
+
     pub(crate) fn from_channel(
         frontend: Rc<Mutex<T>>,
         working_directory: CanonicalizedPath,
@@ -139,9 +215,363 @@ impl<T: Frontend> App<T> {
 
             status_line_components,
             last_action_description: None,
+            pending_commit: None,
+            pending_replace_preview: None,
+            server: None,
+            plugin_manager: PluginManager::default(),
+            task_run: None,
+            debug_session: None,
+            undo_history_directory: Some(buffer::default_undo_history_directory()),
+            auto_save: AutoSaveConfig::default(),
+            last_event_at: Instant::now(),
+            recovery_directory: recovery::default_directory(),
+            recovery_edit_counts: HashMap::new(),
+            orphaned_recovery_files: Vec::new(),
         };
         Ok(app)
     }
+    /// The files are opened in reverse order, so that the first file is focused
+    /// and the others can be reached by navigating the file history.
+    /// The first path is focused.
+    /// Editors without a path, namely those of stdin and of files that do not exist yet,
+    /// are shown in windows of their own when they are not focused,
+    /// because they cannot be reopened from the background.
+    pub(crate) fn open_entry(&mut self, entry: EntryConfig) -> anyhow::Result<()> {
+        let EntryConfig {
+            paths,
+            stdin,
+            diff,
+            readonly,
+        } = entry;
+        let opens_paths = !paths.is_empty();
+        let mut pathless_editors = Vec::new();
+        for EntryPath { path, position } in paths.into_iter().rev() {
+            if path.is_dir() {
+                self.layout.open_file_explorer();
+                continue;
+            }
+            let editor = if path.exists() {
+                self.open_file(&path.as_path().try_into()?, OpenFileOption::Focus)?
+            } else {
+                // The file is only created when the buffer is saved
+                let editor = Rc::new(RefCell::new(SuggestiveEditor::from_buffer(
+                    Rc::new(RefCell::new(Buffer::for_new_file(path))),
+                    SuggestiveEditorFilter::CurrentWord,
+                )));
+                self.layout
+                    .replace_and_focus_current_suggestive_editor(editor.clone());
+                pathless_editors.push(editor.clone());
+                editor
+            };
+            editor
+                .borrow_mut()
+                .editor_mut()
+                .buffer_mut()
+                .set_readonly(readonly);
+            if let Some(position) = position {
+                let dispatches = editor
+                    .borrow_mut()
+                    .editor_mut()
+                    .set_position_range(position..position)?;
+                self.handle_dispatches(dispatches)?;
+            }
+        }
+        let focused_component_id = self.current_component().borrow().id();
+        for editor in pathless_editors {
+            if editor.borrow().id() != focused_component_id {
+                self.layout.open_window(editor)
+            }
+        }
+        if let Some(content) = stdin {
+            let mut buffer = Buffer::new(None, &content);
+            buffer.set_readonly(readonly);
+            let mut editor = SuggestiveEditor::from_buffer(
+                Rc::new(RefCell::new(buffer)),
+                SuggestiveEditorFilter::CurrentWord,
+            );
+            editor.set_title("[stdin]".to_string());
+            let editor = Rc::new(RefCell::new(editor));
+            if opens_paths {
+                self.layout.open_window(editor)
+            } else {
+                self.layout
+                    .replace_and_focus_current_suggestive_editor(editor)
+            }
+        }
+        if let Some((left, right)) = diff {
+            let editor = self.open_file(&right, OpenFileOption::Focus)?;
+            editor
+                .borrow_mut()
+                .editor_mut()
+                .buffer_mut()
+                .set_readonly(readonly);
+            self.open_diff_view(
+                DiffViewKind::SideBySide,
+                DiffSource::File(left.into_path_buf()),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// An invalid `plugins.json` is reported instead of returned,
+    /// so that it does not prevent Ki from starting.
+    pub(crate) fn start_plugins(&mut self) {
+        match crate::plugin::load_configs() {
+            Ok(configs) => self.start_plugin_processes(configs),
+            Err(error) => self.show_startup_error("Failed to load plugins", error),
+        }
+    }
+
+    pub(crate) fn start_plugin_processes(&mut self, configs: Vec<PluginConfig>) {
+        let (plugin_manager, errors) =
+            PluginManager::start(configs, self.sender.clone(), &self.working_directory);
+        self.plugin_manager = plugin_manager;
+        if !errors.is_empty() {
+            self.show_global_info(Info::new(
+                "Failed to start plugins".to_string(),
+                errors.iter().map(|error| error.to_string()).join("\n"),
+            ))
+        }
+    }
+
+    /// Loads the auto-save config, then starts recovery with it.
+    ///
+    /// Failures are reported instead of returned, so that they do not prevent Ki from starting.
+    pub(crate) fn start_recovery(&mut self) {
+        let auto_save = recovery::load_auto_save_config().unwrap_or_else(|error| {
+            self.show_startup_error("Failed to load the auto-save config", error);
+            AutoSaveConfig::default()
+        });
+        self.start_recovery_with_auto_save(auto_save)
+    }
+
+    /// Loads the recovery files left behind by crashed processes,
+    /// then starts ticking, which writes recovery files and auto-saves after idling.
+    pub(crate) fn start_recovery_with_auto_save(&mut self, auto_save: AutoSaveConfig) {
+        self.auto_save = auto_save;
+        match recovery::load_orphaned(&self.recovery_directory) {
+            Ok(files) => self.orphaned_recovery_files = files,
+            Err(error) => self.show_startup_error("Failed to load the recovery files", error),
+        }
+        let sender = self.sender.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(recovery::TICK_INTERVAL);
+            if sender.send(AppMessage::Tick).is_err() {
+                break;
+            }
+        });
+    }
+
+    fn show_startup_error(&mut self, title: &str, error: anyhow::Error) {
+        log::error!("{}: {:?}", title, error);
+        self.show_global_info(Info::new(title.to_string(), error.to_string()))
+    }
+
+    fn notify_plugins_of_selections(&mut self) -> anyhow::Result<()> {
+        let component = self.current_component();
+        let path = component.borrow().path();
+        let selections = self.selection_position_ranges(&component)?;
+        self.plugin_manager
+            .notify_selection_changed(EditorNotification::SelectionChanged {
+                path: path.map(|path| path.to_path_buf().clone()),
+                selections,
+            });
+        Ok(())
+    }
+
+    fn selection_position_ranges(
+        &self,
+        component: &Rc<RefCell<dyn Component>>,
+    ) -> anyhow::Result<Vec<Range<Position>>> {
+        let component = component.borrow();
+        let editor = component.editor();
+        let buffer = editor.buffer();
+        std::iter::once(editor.selection_set.primary_selection())
+            .chain(editor.selection_set.secondary_selections())
+            .map(|selection| buffer.char_index_range_to_position_range(selection.extended_range()))
+            .collect()
+    }
+
+    /// Returns the focused component if `path` is `None`.
+    fn plugin_target_component(
+        &mut self,
+        path: Option<PathBuf>,
+    ) -> anyhow::Result<Rc<RefCell<dyn Component>>> {
+        match path {
+            Some(path) => {
+                let path: CanonicalizedPath =
+                    self.working_directory.to_path_buf().join(path).try_into()?;
+                Ok(self.open_file(&path, OpenFileOption::Background)?)
+            }
+            None => Ok(self.current_component()),
+        }
+    }
+
+    fn handle_plugin_message(
+        &mut self,
+        plugin: String,
+        message: PluginMessage,
+    ) -> anyhow::Result<()> {
+        match message {
+            PluginMessage::Request { id, request } => {
+                let result = self.handle_plugin_request(request);
+                self.plugin_manager.respond(&plugin, id, result)
+            }
+            PluginMessage::Notification(notification) => {
+                self.handle_plugin_notification(plugin, notification)
+            }
+        }
+    }
+
+    fn handle_plugin_request(
+        &mut self,
+        request: PluginRequest,
+    ) -> anyhow::Result<serde_json::Value> {
+        match request {
+            PluginRequest::BufferContent { path } => {
+                let component = self.plugin_target_component(path)?;
+                let content = component.borrow().content();
+                Ok(serde_json::to_value(content)?)
+            }
+            PluginRequest::BufferSelections { path } => {
+                let component = self.plugin_target_component(path)?;
+                Ok(serde_json::to_value(
+                    self.selection_position_ranges(&component)?,
+                )?)
+            }
+        }
+    }
+
+    fn handle_plugin_notification(
+        &mut self,
+        plugin: String,
+        notification: PluginNotification,
+    ) -> anyhow::Result<()> {
+        let resolve = |path: PathBuf| -> anyhow::Result<CanonicalizedPath> {
+            self.working_directory.to_path_buf().join(path).try_into()
+        };
+        match notification {
+            PluginNotification::RegisterCommand {
+                name,
+                description,
+                key,
+            } => {
+                event::parse_key_event(&key)?;
+                self.context.register_plugin_command(PluginCommand {
+                    plugin,
+                    name,
+                    description,
+                    key,
+                });
+            }
+            PluginNotification::ApplyEdits { path, edits } => {
+                let path = resolve(path)?;
+                let component = self.open_file(&path, OpenFileOption::Background)?;
+                let dispatches = component.borrow_mut().editor_mut().apply_positional_edits(
+                    edits
+                        .into_iter()
+                        .map(|edit| PositionalEdit {
+                            range: edit.range,
+                            new_text: edit.new_text,
+                        })
+                        .collect(),
+                )?;
+                self.handle_dispatches(dispatches)?;
+            }
+            PluginNotification::SetDecorations { path, decorations } => {
+                let path = resolve(path)?;
+                let component = self.open_file(&path, OpenFileOption::Background)?;
+                component
+                    .borrow_mut()
+                    .editor_mut()
+                    .buffer_mut()
+                    .set_plugin_decorations(
+                        plugin,
+                        decorations
+                            .into_iter()
+                            .map(|decoration| {
+                                Decoration::new(
+                                    SelectionRange::Position(decoration.range),
+                                    StyleKey::Syntax(decoration.style),
+                                )
+                            })
+                            .collect_vec(),
+                    );
+            }
+            PluginNotification::SetQuickfixList { title, items } => {
+                let items = items
+                    .into_iter()
+                    .map(|item| -> anyhow::Result<_> {
+                        Ok(QuickfixListItem::new(
+                            Location {
+                                path: resolve(item.path)?,
+                                range: item.range,
+                            },
+                            item.info.map(|info| Info::new(plugin.clone(), info)),
+                        ))
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                self.set_quickfix_list_type(
+                    ResponseContext::default().set_description(&title),
+                    QuickfixListType::Items(items),
+                )?;
+            }
+            PluginNotification::ShowInfo { title, content } => {
+                self.show_global_info(Info::new(title, content))
+            }
+        }
+        Ok(())
+    }
+
+    /// Listens for commands from other processes started in the working directory.
+    /// Does nothing if another instance is already listening.
+    pub(crate) fn start_server(&mut self) {
+        match Server::start(&self.working_directory, self.sender.clone()) {
+            Ok(server) => self.server = Some(server),
+            Err(error) => log::info!("App::start_server {:?}", error),
+        }
+    }
+
+    fn handle_server_command(&mut self, command: ServerCommand) -> anyhow::Result<()> {
+        let resolve = |path: PathBuf| -> anyhow::Result<CanonicalizedPath> {
+            self.working_directory.to_path_buf().join(path).try_into()
+        };
+        match command {
+            ServerCommand::Open { path, line, column } => {
+                let path = resolve(path)?;
+                if path.is_dir() {
+                    return self.reveal_path_in_explorer(&path);
+                }
+                let position = Position::new(line.unwrap_or_default(), column.unwrap_or_default());
+                self.go_to_location(&Location {
+                    path,
+                    range: position..position,
+                })
+            }
+            ServerCommand::RevealInExplorer { path } => {
+                let path = resolve(path)?;
+                self.reveal_path_in_explorer(&path)
+            }
+            ServerCommand::SetQuickfixList { title, items } => {
+                let items = items
+                    .into_iter()
+                    .map(|item| -> anyhow::Result<_> {
+                        let position = Position::new(item.line, item.column);
+                        Ok(QuickfixListItem::from(Location {
+                            path: resolve(item.path)?,
+                            range: position..position,
+                        }))
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                self.set_quickfix_list_type(
+                    ResponseContext::default().set_description(&title),
+                    QuickfixListType::Items(items),
+                )
+            }
+            ServerCommand::Dispatch { action } => self.run_command_palette_action(&action),
+        }
+    }
+
     fn update_highlighted_spans(
         &self,
         component_id: ComponentId,
@@ -151,10 +581,7 @@ impl<T: Frontend> App<T> {
             .update_highlighted_spans(component_id, highlighted_spans)
     }
 
-    pub(crate) fn run(
-        mut self,
-        entry_path: Option<CanonicalizedPath>,
-    ) -> Result<(), anyhow::Error> {
+    pub(crate) fn run(mut self, entry: EntryConfig) -> Result<(), anyhow::Error> {
         {
             let mut frontend = self.frontend.lock().unwrap();
             frontend.enter_alternate_screen()?;
@@ -162,48 +589,94 @@ impl<T: Frontend> App<T> {
             frontend.enable_mouse_capture()?;
         }
 
-        if let Some(entry_path) = entry_path {
-            if entry_path.as_ref().is_dir() {
-                self.layout.open_file_explorer();
-            } else {
-                self.open_file(&entry_path, OpenFileOption::Focus)?;
-            }
-        }
+        self.open_entry(entry)?;
+        self.offer_recovery()?;
 
         self.render()?;
 
         while let Ok(message) = self.receiver.recv() {
-            match message {
-                AppMessage::Event(event) => self.handle_event(event),
-                AppMessage::LspNotification(notification) => {
-                    self.handle_lsp_notification(notification).map(|_| false)
-                }
-                AppMessage::QuitAll => {
-                    self.quit()?;
-                    Ok(true)
-                }
-                AppMessage::SyntaxHighlightResponse {
-                    component_id,
-                    highlighted_spans,
-                } => self
-                    .update_highlighted_spans(component_id, highlighted_spans)
-                    .map(|_| false),
-            }
-            .unwrap_or_else(|e| {
-                self.show_global_info(Info::new("ERROR".to_string(), e.to_string()));
-                false
-            });
+            let should_render = self.handle_app_message(message)?;
 
             if self.should_quit() {
                 break;
             }
 
-            self.render()?;
+            if should_render {
+                self.render()?;
+            }
         }
 
         self.quit()
     }
 
+    /// Returns false if the screen does not need to be re-rendered.
+    fn handle_app_message(&mut self, message: AppMessage) -> anyhow::Result<bool> {
+        // Ticks are frequent, so the screen is only re-rendered if a tick changed something
+        let mut should_render = true;
+        match message {
+            AppMessage::Event(event) => self.handle_event(event),
+            AppMessage::LspNotification(notification) => {
+                self.handle_lsp_notification(notification).map(|_| false)
+            }
+            AppMessage::QuitAll => {
+                self.quit()?;
+                Ok(true)
+            }
+            AppMessage::SyntaxHighlightResponse {
+                component_id,
+                highlighted_spans,
+            } => self
+                .update_highlighted_spans(component_id, highlighted_spans)
+                .map(|_| false),
+            AppMessage::ServerCommand(command) => {
+                self.handle_server_command(command).map(|_| false)
+            }
+            AppMessage::PluginMessage { plugin, message } => {
+                self.handle_plugin_message(plugin, message).map(|_| false)
+            }
+            AppMessage::TaskOutput { id } => self.receive_task_output(id).map(|_| false),
+            AppMessage::TaskExited { id, code } => self.handle_task_exited(id, code).map(|_| false),
+            AppMessage::DapMessage(message) => self.handle_dap_message(message).map(|_| false),
+            AppMessage::GitLog { path, commits } => commits
+                .and_then(|commits| self.show_git_log(path, commits))
+                .map(|_| false),
+            AppMessage::GitRevisions { kind, revisions } => revisions
+                .and_then(|(branches, commits)| self.show_revision_picker(kind, branches, commits))
+                .map(|_| false),
+            AppMessage::FileExplorerIndex(index) => {
+                self.layout.set_file_explorer_index(index).map(|_| false)
+            }
+            AppMessage::Tick => self.handle_tick().map(|changed| {
+                should_render = changed;
+                false
+            }),
+        }
+        .unwrap_or_else(|e| {
+            self.show_global_info(Info::new("ERROR".to_string(), e.to_string()));
+            false
+        });
+        Ok(should_render)
+    }
+
+    /// Handles the messages sent by background threads until `is_expected` returns true for one of them,
+    /// because the test harness does not run the event loop.
+    #[cfg(test)]
+    pub(crate) fn wait_for_app_message(
+        &mut self,
+        is_expected: fn(&AppMessage) -> bool,
+    ) -> anyhow::Result<()> {
+        loop {
+            let message = self
+                .receiver
+                .recv_timeout(std::time::Duration::from_secs(10))?;
+            let expected = is_expected(&message);
+            self.handle_app_message(message)?;
+            if expected {
+                return Ok(());
+            }
+        }
+    }
+
     pub(crate) fn quit(&mut self) -> anyhow::Result<()> {
         let mut frontend = self.frontend.lock().unwrap();
         frontend.leave_alternate_screen()?;
@@ -211,6 +684,17 @@ impl<T: Frontend> App<T> {
         frontend.disable_mouse_capture()?;
         // self.lsp_manager.shutdown();
 
+        // Destructors are not run by `std::process::exit`
+        drop(self.server.take());
+        if let Some(task_run) = &self.task_run {
+            task_run.kill()
+        }
+        for path in self.recovery_edit_counts.keys() {
+            recovery::remove(&self.recovery_directory, path)
+                .unwrap_or_else(|error| log::error!("recovery::remove {:?}", error))
+        }
+        drop(self.pending_replace_preview.take());
+
         std::process::exit(0);
     }
 
@@ -220,8 +704,10 @@ impl<T: Frontend> App<T> {
 
     /// Returns true if the app should quit.
     fn handle_event(&mut self, event: Event) -> anyhow::Result<bool> {
+        self.last_event_at = Instant::now();
         // Pass event to focused window
         let component = self.current_component();
+        let focused_component_id = component.borrow().id();
         self.context
             .set_contextual_keymaps(component.borrow().contextual_keymaps());
         match event {
@@ -231,6 +717,13 @@ impl<T: Frontend> App<T> {
                     width: columns,
                 });
             }
+            Event::Mouse(mouse_event) => {
+                let dispatches = self.handle_mouse_event(mouse_event);
+                self.handle_dispatches_result(dispatches)
+                    .unwrap_or_else(|e| {
+                        self.show_global_info(Info::new("ERROR".to_string(), e.to_string()))
+                    });
+            }
             event => {
                 let dispatches = component.borrow_mut().handle_event(&self.context, event);
                 self.handle_dispatches_result(dispatches)
@@ -239,10 +732,66 @@ impl<T: Frontend> App<T> {
                     });
             }
         }
+        if self.auto_save.on_focus_change
+            && self.current_component().borrow().id() != focused_component_id
+        {
+            self.auto_save(AutoSaveTrigger::FocusChange)?;
+        }
+        if !self.plugin_manager.is_empty() {
+            self.notify_plugins_of_selections()
+                .unwrap_or_else(|error| log::error!("notify_plugins_of_selections {:?}", error));
+        }
 
         Ok(false)
     }
 
+    /// Left-clicking a component focuses it before the event is passed to it,
+    /// except for dropdowns, whose clicks are handled by the component owning the dropdown.
+    ///
+    /// Scrolling scrolls the component under the pointer without focusing it.
+    fn handle_mouse_event(&mut self, mouse_event: MouseEvent) -> anyhow::Result<Dispatches> {
+        let position = Position::new(mouse_event.row as usize, mouse_event.column as usize);
+        if matches!(
+            mouse_event.kind,
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown
+        ) {
+            return match self.layout.component_at(&position) {
+                Some(component) => component
+                    .component()
+                    .borrow_mut()
+                    .handle_mouse_event(mouse_event),
+                None => Ok(Default::default()),
+            };
+        }
+        if mouse_event.kind == MouseEventKind::Down(MouseButton::Left) {
+            if let Some(component) = self.layout.component_at(&position) {
+                let id = component.component().borrow().id();
+                if component.kind() == ComponentKind::Dropdown {
+                    let line_index = {
+                        let component = component.component();
+                        let component = component.borrow();
+                        let editor = component.editor();
+                        let Some(char_index) = editor.mouse_event_char_index(&mouse_event) else {
+                            return Ok(Default::default());
+                        };
+                        let position = editor.buffer().char_to_position(char_index)?;
+                        position.line
+                    };
+                    return match self.layout.parent_component(id) {
+                        Some(owner) => owner
+                            .borrow_mut()
+                            .handle_dropdown_click(&self.context, line_index),
+                        None => Ok(Default::default()),
+                    };
+                }
+                self.layout.focus_component(id);
+            }
+        }
+        self.current_component()
+            .borrow_mut()
+            .handle_mouse_event(mouse_event)
+    }
+
     /// Return true if there's no more windows
     fn should_quit(&mut self) -> bool {
         self.layout.components().is_empty()
@@ -324,6 +873,12 @@ impl<T: Frontend> App<T> {
                                 .editor()
                                 .display_selection_mode(),
                         ),
+                        StatusLineComponent::FileFormat => {
+                            let component = self.current_component();
+                            let component = component.borrow();
+                            let buffer = component.editor().buffer();
+                            buffer.path().map(|_| buffer.file_format().display())
+                        }
                         StatusLineComponent::LastDispatch => self.last_action_description.clone(),
                     })
                     .join(" │ ")
@@ -414,9 +969,7 @@ impl<T: Frontend> App<T> {
     pub(crate) fn handle_dispatch(&mut self, dispatch: Dispatch) -> Result<(), anyhow::Error> {
         log::info!("App::handle_dispatch = {}", dispatch.variant_name());
         match dispatch {
-            Dispatch::CloseCurrentWindow => {
-                self.close_current_window();
-            }
+            Dispatch::CloseCurrentWindow => self.close_current_window()?,
             Dispatch::CloseCurrentWindowAndFocusParent => {
                 self.close_current_window_and_focus_parent();
             }
@@ -591,6 +1144,28 @@ impl<T: Frontend> App<T> {
                 }
             }
             Dispatch::DocumentDidSave { path } => {
+                if self.recovery_edit_counts.remove(&path).is_some() {
+                    recovery::remove(&self.recovery_directory, &path)?
+                }
+                if let Some(pending_commit) = self
+                    .pending_commit
+                    .as_mut()
+                    .filter(|pending_commit| pending_commit.path == path)
+                {
+                    pending_commit.saved = true;
+                    return Ok(());
+                }
+                if self
+                    .pending_replace_preview
+                    .as_ref()
+                    .is_some_and(|pending| pending.path == path)
+                {
+                    return self.apply_replace_preview();
+                }
+                self.sync_breakpoints(&path)?;
+                self.plugin_manager.notify(EditorNotification::BufferSaved {
+                    path: path.to_path_buf().clone(),
+                });
                 self.lsp_manager.send_message(
                     path.clone(),
                     FromEditor::TextDocumentDidSave { file_path: path },
@@ -614,6 +1189,7 @@ impl<T: Frontend> App<T> {
             Dispatch::ToEditor(dispatch_editor) => self.handle_dispatch_editor(dispatch_editor)?,
             Dispatch::GotoLocation(location) => self.go_to_location(&location)?,
             Dispatch::OpenMoveToIndexPrompt => self.open_move_to_index_prompt()?,
+            Dispatch::OpenUndoToMinutesAgoPrompt => self.open_undo_to_minutes_ago_prompt()?,
             Dispatch::QuitAll => self.quit_all()?,
             Dispatch::SaveQuitAll => self.save_quit_all()?,
             Dispatch::RevealInExplorer(path) => self.reveal_path_in_explorer(&path)?,
@@ -621,14 +1197,35 @@ impl<T: Frontend> App<T> {
             Dispatch::OpenMoveFilePrompt(path) => self.open_move_file_prompt(path)?,
             Dispatch::OpenAddPathPrompt(path) => self.open_add_path_prompt(path)?,
             Dispatch::DeletePath(path) => self.delete_path(&path)?,
+            Dispatch::DeletePaths(paths) => {
+                for path in paths {
+                    self.delete_path(&path)?
+                }
+            }
+            Dispatch::OpenMovePathsPrompt(paths) => self.open_move_paths_prompt(paths)?,
+            Dispatch::MovePaths { from, to_directory } => {
+                for path in from {
+                    let to = to_directory.join(path.to_path_buf().file_name().unwrap_or_default());
+                    self.move_file(path, to)?
+                }
+            }
+            Dispatch::OpenCopyPathPrompt(path) => self.open_copy_path_prompt(path)?,
+            Dispatch::CopyPath { from, to } => self.copy_path(from, to)?,
+            Dispatch::DuplicatePath(path) => self.duplicate_path(path)?,
+            Dispatch::ToFileExplorer(dispatch) => {
+                let dispatches = self.layout.handle_dispatch_file_explorer(dispatch)?;
+                self.handle_dispatches(dispatches)?
+            }
+            Dispatch::BuildFileExplorerIndex => self.build_file_explorer_index(),
+            Dispatch::OpenFileExplorerFilterPrompt(filter) => {
+                self.open_file_explorer_filter_prompt(filter)?
+            }
             Dispatch::Null => {
                 // do nothing
             }
             Dispatch::MoveFile { from, to } => self.move_file(from, to)?,
             Dispatch::AddPath(path) => self.add_path(path)?,
-            Dispatch::RefreshFileExplorer => {
-                self.layout.refresh_file_explorer(&self.working_directory)?
-            }
+            Dispatch::RefreshFileExplorer => self.refresh_file_explorer()?,
             Dispatch::SetClipboardContent {
                 copied_texts: contents,
                 use_system_clipboard,
@@ -642,6 +1239,25 @@ impl<T: Frontend> App<T> {
                 self.handle_event(Event::Key(key_event))?;
             }
             Dispatch::GetRepoGitHunks(diff_mode) => self.get_repo_git_hunks(diff_mode)?,
+            Dispatch::GetRepoMergeConflicts => self.get_repo_merge_conflicts()?,
+            Dispatch::StageHunks { path, line_ranges } => self.stage_hunks(path, line_ranges)?,
+            Dispatch::OpenDiffView { kind, source } => self.open_diff_view(kind, source)?,
+            Dispatch::OpenDiffFilePicker(kind) => self.open_diff_file_picker(kind)?,
+            Dispatch::OpenGitLog(path) => self.open_git_log(path),
+            Dispatch::ShowCommit(revision) => self.show_commit(revision)?,
+            Dispatch::OpenRevisionPicker(kind) => self.open_revision_picker(kind),
+            Dispatch::OpenFileAtRevisionPicker(revision) => {
+                self.open_file_at_revision_picker(revision)?
+            }
+            Dispatch::OpenFileAtRevision { revision, path } => {
+                self.open_file_at_revision(revision, path)?
+            }
+            Dispatch::OpenCommitMessageEditor { amend } => {
+                self.open_commit_message_editor(amend)?
+            }
+            Dispatch::UnstageHunks { path, line_ranges } => {
+                self.unstage_hunks(path, line_ranges)?
+            }
             Dispatch::SaveAll => self.save_all()?,
             #[cfg(test)]
             Dispatch::TerminalDimensionChanged(dimension) => self.resize(dimension),
@@ -696,6 +1312,7 @@ impl<T: Frontend> App<T> {
                 })?,
                 Scope::Global => self.global_replace()?,
             },
+            Dispatch::OpenGlobalReplacePreview => self.open_global_replace_preview()?,
             #[cfg(test)]
             Dispatch::HandleLspNotification(notification) => {
                 self.handle_lsp_notification(notification)?
@@ -706,6 +1323,10 @@ impl<T: Frontend> App<T> {
             }
             #[cfg(test)]
             Dispatch::HandleKeyEvents(key_events) => self.handle_key_events(key_events)?,
+            #[cfg(test)]
+            Dispatch::HandleMouseEvent(mouse_event) => {
+                self.handle_event(Event::Mouse(mouse_event))?;
+            }
             Dispatch::CloseDropdown => self.layout.close_dropdown(),
             Dispatch::CloseEditorInfo => self.layout.close_editor_info(),
             Dispatch::RenderDropdown { render } => {
@@ -724,11 +1345,33 @@ impl<T: Frontend> App<T> {
                 self.open_code_actions_prompt(code_actions)?;
             }
             Dispatch::OtherWindow => self.layout.cycle_window(),
+            Dispatch::SplitWindow(orientation) => self.layout.split_window(orientation)?,
+            Dispatch::MoveWindowFocus(direction) => self.layout.move_window_focus(direction),
+            Dispatch::ResizeWindow { grow } => self.layout.resize_window(grow),
             Dispatch::GoToPreviousFile => self.go_to_previous_file()?,
             Dispatch::GoToNextFile => self.go_to_next_file()?,
             Dispatch::CycleBuffer(direction) => self.cycle_buffer(direction)?,
             Dispatch::PushPromptHistory { key, line } => self.push_history_prompt(key, line),
             Dispatch::OpenThemePrompt => self.open_theme_prompt()?,
+            Dispatch::OpenCommandPalette => self.open_command_palette()?,
+            Dispatch::ExecutePluginCommand { plugin, name } => self
+                .plugin_manager
+                .notify_plugin(&plugin, EditorNotification::ExecuteCommand { name }),
+            Dispatch::OpenRunTaskPrompt => self.open_run_task_prompt()?,
+            Dispatch::RunTask(task) => self.run_task(task)?,
+            Dispatch::RerunTask => self.rerun_task()?,
+            Dispatch::ToggleBreakpoint { path, line } => self.toggle_breakpoint(path, line)?,
+            Dispatch::StartDebugging(path) => self.start_debugging(path)?,
+            Dispatch::ResumeDebugging(resume) => {
+                self.debug_session()?.resume(resume)?;
+                self.render_debug_panels()?
+            }
+            Dispatch::EvaluateInDebugSession(expression) => {
+                self.debug_session()?.evaluate(&expression)?
+            }
+            Dispatch::StopDebugging => self.stop_debugging()?,
+            Dispatch::RecoverFile { path, content } => self.recover_file(path, content)?,
+            Dispatch::DiscardRecoveryFile(path) => self.discard_recovery_file(path)?,
             Dispatch::SetLastNonContiguousSelectionMode(selection_mode) => self
                 .context
                 .set_last_non_contiguous_selection_mode(selection_mode),
@@ -749,8 +1392,29 @@ impl<T: Frontend> App<T> {
         self.layout.get_current_component()
     }
 
-    fn close_current_window(&mut self) {
-        self.layout.close_current_window()
+    fn close_current_window(&mut self) -> anyhow::Result<()> {
+        let path = self.current_component().borrow().path();
+        if path.is_some()
+            && self
+                .pending_commit
+                .as_ref()
+                .map(|pending_commit| &pending_commit.path)
+                == path.as_ref()
+        {
+            return self.commit();
+        }
+        // Closing the replace preview without saving it cancels the replacement
+        if path.is_some()
+            && self
+                .pending_replace_preview
+                .as_ref()
+                .map(|pending| &pending.path)
+                == path.as_ref()
+        {
+            self.pending_replace_preview = None;
+        }
+        self.layout.close_current_window();
+        Ok(())
     }
 
     fn local_search(&mut self, if_current_not_found: IfCurrentNotFound) -> anyhow::Result<()> {
@@ -794,6 +1458,21 @@ impl<T: Frontend> App<T> {
         )
     }
 
+    fn open_undo_to_minutes_ago_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Undo to minutes ago".to_string(),
+                on_enter: DispatchPrompt::UndoToMinutesAgo,
+                items: vec![],
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::UndoToMinutesAgo,
+            None,
+        )
+    }
+
     fn open_rename_prompt(&mut self, current_name: Option<String>) -> anyhow::Result<()> {
         self.open_prompt(
             PromptConfig {
@@ -864,6 +1543,76 @@ impl<T: Frontend> App<T> {
         )
     }
 
+    fn open_move_paths_prompt(&mut self, paths: Vec<CanonicalizedPath>) -> anyhow::Result<()> {
+        let directory = paths
+            .first()
+            .and_then(|path| path.parent().ok().flatten())
+            .map(|parent| parent.display_absolute());
+        self.open_prompt(
+            PromptConfig {
+                title: format!("Move {} paths to directory", paths.len()),
+                on_enter: DispatchPrompt::MovePaths { from: paths },
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: false,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::MovePath,
+            directory,
+        )
+    }
+
+    fn open_copy_path_prompt(&mut self, path: CanonicalizedPath) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Copy path".to_string(),
+                on_enter: DispatchPrompt::CopyPath { from: path.clone() },
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: false,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::CopyPath,
+            Some(path.display_absolute()),
+        )
+    }
+
+    fn refresh_file_explorer(&mut self) -> anyhow::Result<()> {
+        let dispatches = self.layout.refresh_file_explorer(&self.working_directory)?;
+        self.handle_dispatches(dispatches)
+    }
+
+    fn build_file_explorer_index(&self) {
+        let working_directory = self.working_directory.clone();
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            let index = file_explorer::Index::new(&working_directory);
+            let _ = sender.send(AppMessage::FileExplorerIndex(index));
+        });
+    }
+
+    fn open_file_explorer_filter_prompt(&mut self, filter: String) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Filter file explorer".to_string(),
+                on_enter: DispatchPrompt::FilterFileExplorer,
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: false,
+                fire_dispatches_on_change: Some(FireDispatchesOnChange {
+                    // Restore the original filter if the prompt is cancelled
+                    undo: Dispatches::one(Dispatch::ToFileExplorer(
+                        DispatchFileExplorer::SetFilter(filter.clone()),
+                    )),
+                    // There are no items, the filter is applied as it is typed
+                    on_enter_if_unmatched: true,
+                }),
+            },
+            PromptHistoryKey::FilterFileExplorer,
+            Some(filter),
+        )
+    }
+
     fn open_symbol_picker(&mut self, symbols: Symbols) -> anyhow::Result<()> {
         self.open_prompt(
             PromptConfig {
@@ -888,7 +1637,7 @@ impl<T: Frontend> App<T> {
         let working_directory = self.working_directory.clone();
         self.open_prompt(
             PromptConfig {
-                title: format!("Open file: {}", kind.display()),
+                title: kind.prompt_title(),
                 on_enter: DispatchPrompt::OpenFile { working_directory },
                 items: {
                     match kind {
@@ -905,6 +1654,9 @@ impl<T: Frontend> App<T> {
                                 .map(|entry| entry.new_path().into_path_buf())
                                 .collect_vec()
                         }
+                        FilePickerKind::GitConflicts => {
+                            git::GitRepo::try_from(&self.working_directory)?.conflicted_paths()?
+                        }
                         FilePickerKind::Opened => self
                             .layout
                             .get_opened_files()
@@ -914,25 +1666,9 @@ impl<T: Frontend> App<T> {
                     }
                     .into_iter()
                     .map(|path| {
-                        DropdownItem::new({
-                            let name = path
-                                .file_name()
-                                .unwrap_or_default()
-                                .to_string_lossy()
-                                .to_string();
-                            let icon = shared::canonicalized_path::get_path_icon(&path);
-                            format!("{icon} {name}")
-                        })
-                        .set_group(path.parent().map(|parent| {
-                            let relative = parent
-                                .strip_prefix(&self.working_directory)
-                                .map(|path| path.display().to_string())
-                                .unwrap_or_else(|_| parent.display().to_string());
-                            format!("{} {}", shared::icons::get_icon_config().folder, relative,)
-                        }))
-                        .set_dispatches(Dispatches::one(
-                            crate::app::Dispatch::OpenFileFromPathBuf(path),
-                        ))
+                        file_dropdown_item(&self.working_directory, &path).set_dispatches(
+                            Dispatches::one(crate::app::Dispatch::OpenFileFromPathBuf(path)),
+                        )
                     })
                     .collect_vec()
                 },
@@ -961,7 +1697,12 @@ impl<T: Frontend> App<T> {
             return Ok(matching_editor);
         }
 
-        let buffer = Buffer::from_path(path, true)?;
+        let mut buffer = Buffer::from_path_with_undo_history_directory(
+            path,
+            true,
+            self.undo_history_directory.clone(),
+        )?;
+        buffer.set_breakpoint_lines(&self.context.breakpoints().lines(path));
         let language = buffer.language();
         let content = buffer.content();
         let buffer = Rc::new(RefCell::new(buffer));
@@ -982,6 +1723,10 @@ impl<T: Frontend> App<T> {
         if self.enable_lsp {
             self.lsp_manager.open_file(path.clone())?;
         }
+        self.plugin_manager
+            .notify(EditorNotification::BufferOpened {
+                path: path.to_path_buf().clone(),
+            });
         Ok(component)
     }
 
@@ -1250,6 +1995,106 @@ impl<T: Frontend> App<T> {
         self.layout.reload_buffers(affected_paths)
     }
 
+    fn open_global_replace_preview(&mut self) -> anyhow::Result<()> {
+        let global_search_config = self.context.global_search_config();
+        let walk_builder_config = WalkBuilderConfig {
+            root: self.working_directory.clone().into(),
+            include: global_search_config.include_glob(),
+            exclude: global_search_config.exclude_glob(),
+        };
+        let config = global_search_config.local_config();
+        let buffer_contents = self
+            .layout
+            .buffers()
+            .iter()
+            .filter_map(|buffer| {
+                let buffer = buffer.borrow();
+                Some((buffer.path()?, buffer.content()))
+            })
+            .collect();
+        let files =
+            list::grep::preview_replace(walk_builder_config, config.clone(), buffer_contents)?;
+        let preview = ReplacePreview::new(&self.working_directory, files);
+        if preview.is_empty() {
+            self.show_global_info(Info::new(
+                "Replace preview".to_string(),
+                "No matches found.".to_string(),
+            ));
+            return Ok(());
+        }
+        let file = tempfile::Builder::new()
+            .prefix("REPLACE_PREVIEW_")
+            .tempfile()?;
+        std::fs::write(file.path(), preview.render())?;
+        let path: CanonicalizedPath = file.path().try_into()?;
+        self.open_file(&path, OpenFileOption::Focus)?;
+        self.pending_replace_preview = Some(PendingReplacePreview {
+            path,
+            preview,
+            _file: file,
+        });
+        Ok(())
+    }
+
+    fn apply_replace_preview(&mut self) -> anyhow::Result<()> {
+        let Some(pending) = self.pending_replace_preview.take() else {
+            return Ok(());
+        };
+        let replaced_files = match self.apply_replaced_files(&pending) {
+            Ok(replaced_files) => replaced_files,
+            Err(error) => {
+                // Keep the preview around so that the user can fix it and try again
+                self.pending_replace_preview = Some(pending);
+                return Err(error);
+            }
+        };
+        if self.current_component().borrow().path().as_ref() == Some(&pending.path) {
+            self.close_current_window()?;
+        }
+        self.layout.remove_suggestive_editor(&pending.path);
+        self.show_global_info(Info::new(
+            "Replace preview".to_string(),
+            format!("Modified {} file(s).", replaced_files),
+        ));
+        Ok(())
+    }
+
+    /// The accepted changes are applied to the buffer of each file as an undoable edit,
+    /// but only if none of the buffers has changed since the preview was generated,
+    /// so that either every file is modified or none of them is.
+    /// Buffers with unsaved changes are left unsaved, so that those changes are not written unexpectedly.
+    /// Returns the number of modified files.
+    fn apply_replaced_files(&mut self, pending: &PendingReplacePreview) -> anyhow::Result<usize> {
+        let replaced_files = pending.preview.apply(&pending.path.read()?)?;
+        let editors = replaced_files
+            .iter()
+            .map(|file| self.open_file(&file.path, OpenFileOption::Background))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        for (file, editor) in replaced_files.iter().zip(&editors) {
+            if editor.borrow().editor().buffer().content() != file.before {
+                return Err(anyhow::anyhow!(
+                    "{} was modified after the replace preview was generated",
+                    file.path
+                        .display_relative_to(&self.working_directory)
+                        .unwrap_or_else(|_| file.path.display_absolute())
+                ));
+            }
+        }
+        for (file, editor) in replaced_files.iter().zip(&editors) {
+            let has_unsaved_changes = editor.borrow().editor().buffer().is_dirty();
+            let dispatches = editor
+                .borrow_mut()
+                .editor_mut()
+                .replace_content(&file.after)?;
+            self.handle_dispatches(dispatches)?;
+            if !has_unsaved_changes {
+                let dispatches = editor.borrow_mut().editor_mut().save_without_formatting()?;
+                self.handle_dispatches(dispatches)?;
+            }
+        }
+        Ok(replaced_files.len())
+    }
+
     fn global_search(&mut self) -> anyhow::Result<()> {
         let working_directory = self.working_directory.clone();
 
@@ -1327,7 +2172,7 @@ impl<T: Frontend> App<T> {
             std::fs::remove_file(path)?;
         }
         self.layout.remove_suggestive_editor(path);
-        self.layout.refresh_file_explorer(&self.working_directory)?;
+        self.refresh_file_explorer()?;
         Ok(())
     }
 
@@ -1335,7 +2180,7 @@ impl<T: Frontend> App<T> {
         use std::fs;
         self.add_path_parent(&to)?;
         fs::rename(from.clone(), to.clone())?;
-        self.layout.refresh_file_explorer(&self.working_directory)?;
+        self.refresh_file_explorer()?;
         let to = to.try_into()?;
         self.reveal_path_in_explorer(&to)?;
         self.lsp_manager.send_message(
@@ -1348,6 +2193,46 @@ impl<T: Frontend> App<T> {
         self.layout.remove_suggestive_editor(&from);
         Ok(())
     }
+    fn copy_path(&mut self, from: CanonicalizedPath, to: PathBuf) -> anyhow::Result<()> {
+        if to.exists() {
+            return Err(anyhow::anyhow!(
+                "The path \"{}\" already exists",
+                to.display()
+            ));
+        }
+        self.add_path_parent(&to)?;
+        copy_recursively(from.to_path_buf(), &to)?;
+        self.refresh_file_explorer()?;
+        self.reveal_path_in_explorer(&to.try_into()?)?;
+        Ok(())
+    }
+
+    /// Copy `path` into the same directory, with " copy" appended to its file stem.
+    fn duplicate_path(&mut self, path: CanonicalizedPath) -> anyhow::Result<()> {
+        let path_buf = path.to_path_buf();
+        let stem = path_buf
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let extension = path_buf
+            .extension()
+            .map(|extension| format!(".{}", extension.to_string_lossy()))
+            .unwrap_or_default();
+        let to = (1..)
+            .map(|count| {
+                let suffix = if count == 1 {
+                    " copy".to_string()
+                } else {
+                    format!(" copy {}", count)
+                };
+                path_buf.with_file_name(format!("{stem}{suffix}{extension}"))
+            })
+            .find(|to| !to.exists())
+            .unwrap_or_default();
+        self.copy_path(path, to)
+    }
+
     fn add_path_parent(&self, path: &Path) -> anyhow::Result<()> {
         if let Some(new_dir) = path.parent() {
             std::fs::create_dir_all(new_dir)?;
@@ -1366,7 +2251,7 @@ impl<T: Frontend> App<T> {
             self.add_path_parent(&path)?;
             std::fs::File::create(&path)?;
         }
-        self.layout.refresh_file_explorer(&self.working_directory)?;
+        self.refresh_file_explorer()?;
         self.reveal_path_in_explorer(&path.try_into()?)?;
 
         Ok(())
@@ -1397,7 +2282,21 @@ impl<T: Frontend> App<T> {
             .get_selected_texts()
     }
 
-    #[cfg(test)]
+    /// Unlike saving, the file is not formatted, so that only the changes made by the user are written.
+    pub(crate) fn save_file_without_formatting(
+        &self,
+        path: &CanonicalizedPath,
+    ) -> anyhow::Result<()> {
+        if let Some(editor) = self.layout.get_existing_editor(path) {
+            editor
+                .borrow_mut()
+                .editor_mut()
+                .buffer_mut()
+                .save_without_formatting()?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn get_file_content(&self, path: &CanonicalizedPath) -> String {
         self.layout
             .get_existing_editor(path)
@@ -1426,38 +2325,457 @@ impl<T: Frontend> App<T> {
         Ok(())
     }
 
-    fn get_repo_git_hunks(&mut self, diff_mode: git::DiffMode) -> anyhow::Result<()> {
-        let working_directory = self.working_directory.clone();
-        let repo = git::GitRepo::try_from(&working_directory)?;
-        let diffs = repo.diffs(diff_mode)?;
-        self.set_quickfix_list_type(
-            ResponseContext::default().set_description("Git Hunks"),
-            QuickfixListType::Items(
-                diffs
-                    .into_iter()
-                    .flat_map(|file_diff| {
-                        file_diff
-                            .hunks()
-                            .iter()
-                            .map(|hunk| {
-                                let line_range = hunk.line_range();
-                                let location = Location {
-                                    path: file_diff.path().clone(),
-                                    range: Position {
-                                        line: line_range.start,
-                                        column: 0,
-                                    }..Position {
-                                        line: line_range.end,
-                                        column: 0,
-                                    },
-                                };
-                                QuickfixListItem::new(location, hunk.to_info())
-                            })
-                            .collect_vec()
-                    })
-                    .collect_vec(),
-            ),
-        )
+    fn get_repo_git_hunks(&mut self, diff_mode: git::DiffMode) -> anyhow::Result<()> {
+        let working_directory = self.working_directory.clone();
+        let repo = git::GitRepo::try_from(&working_directory)?;
+        let diffs = repo.diffs(diff_mode)?;
+        self.set_quickfix_list_type(
+            ResponseContext::default().set_description(&git_hunks_title(&diff_mode)),
+            QuickfixListType::Items(
+                diffs
+                    .into_iter()
+                    .flat_map(|file_diff| {
+                        file_diff
+                            .hunks()
+                            .iter()
+                            .map(|hunk| {
+                                let line_range = hunk.line_range();
+                                let location = Location {
+                                    path: file_diff.path().clone(),
+                                    range: Position {
+                                        line: line_range.start,
+                                        column: 0,
+                                    }..Position {
+                                        line: line_range.end,
+                                        column: 0,
+                                    },
+                                };
+                                QuickfixListItem::new(location, hunk.to_info())
+                            })
+                            .collect_vec()
+                    })
+                    .collect_vec(),
+            ),
+        )
+    }
+
+    /// Files that are opened are read from their buffers,
+    /// so that conflicts that are resolved but not saved yet are excluded.
+    fn get_repo_merge_conflicts(&mut self) -> anyhow::Result<()> {
+        let items = git::GitRepo::try_from(&self.working_directory)?
+            .conflicted_paths()?
+            .into_iter()
+            .filter_map(|path| {
+                let path = CanonicalizedPath::try_from(path).ok()?;
+                let content = match self.layout.get_existing_editor(&path) {
+                    Some(editor) => editor.borrow().content(),
+                    None => FileFormat::read(path.as_ref()).ok()?.0,
+                };
+                Some(
+                    parse_conflicts(&content)
+                        .into_iter()
+                        .map(|conflict| {
+                            let line = |byte: usize| content[..byte].matches('\n').count();
+                            let location = Location {
+                                path: path.clone(),
+                                range: Position {
+                                    line: line(conflict.range.start),
+                                    column: 0,
+                                }..Position {
+                                    line: line(conflict.range.end),
+                                    column: 0,
+                                },
+                            };
+                            QuickfixListItem::new(location, None)
+                        })
+                        .collect_vec(),
+                )
+            })
+            .flatten()
+            .collect_vec();
+        self.set_quickfix_list_type(
+            ResponseContext::default().set_description("Merge conflicts"),
+            QuickfixListType::Items(items),
+        )
+    }
+
+    fn open_diff_view(&mut self, kind: DiffViewKind, source: DiffSource) -> anyhow::Result<()> {
+        let (title, old, new) = {
+            let component = self.current_component();
+            let component = component.borrow();
+            let editor = component.editor();
+            let path = editor.path();
+            let current_file = || {
+                path.clone()
+                    .ok_or_else(|| anyhow::anyhow!("The current buffer is not a file"))
+            };
+            match source {
+                DiffSource::Git(diff_mode) => {
+                    let path = current_file()?;
+                    let repo = git::GitRepo::try_from(&self.working_directory)?;
+                    (
+                        format!(
+                            "{} ({})",
+                            path.display_relative_to(&self.working_directory)?,
+                            diff_mode.display()
+                        ),
+                        path.content_at_last_commit(&diff_mode, &repo)?,
+                        editor.buffer().content(),
+                    )
+                }
+                DiffSource::Clipboard => (
+                    "Clipboard ↔ Selection".to_string(),
+                    self.context
+                        .get_clipboard_content(false, 0)?
+                        .map(|copied_texts| copied_texts.get(0))
+                        .unwrap_or_default(),
+                    editor
+                        .buffer()
+                        .slice(&editor.selection_set.primary_selection().extended_range())?
+                        .to_string(),
+                ),
+                DiffSource::File(other) => {
+                    let path = current_file()?;
+                    let other: CanonicalizedPath = other.try_into()?;
+                    (
+                        format!(
+                            "{} ↔ {}",
+                            other.display_relative_to(&self.working_directory)?,
+                            path.display_relative_to(&self.working_directory)?
+                        ),
+                        FileFormat::read(other.as_ref())?.0,
+                        editor.buffer().content(),
+                    )
+                }
+            }
+        };
+        let width = self.layout.focused_window_width() as usize;
+        let diff_view = DiffView::new(kind, &old, &new, width);
+        let mut editor = Editor::from_text(None, &diff_view.content);
+        editor.set_decorations(&diff_view.decorations);
+        editor.set_title(format!("Diff {}: {}", kind.display(), title));
+        self.layout.show_diff_viewer(Rc::new(RefCell::new(editor)));
+        Ok(())
+    }
+
+    fn open_diff_file_picker(&mut self, kind: DiffViewKind) -> anyhow::Result<()> {
+        let working_directory = self.working_directory.clone();
+        self.open_prompt(
+            PromptConfig {
+                title: "Compare with file".to_string(),
+                on_enter: DispatchPrompt::Null,
+                items: list::WalkBuilderConfig::non_git_ignored_files(working_directory.clone())?
+                    .into_iter()
+                    .map(|path| {
+                        file_dropdown_item(&working_directory, &path).set_dispatches(
+                            Dispatches::one(Dispatch::OpenDiffView {
+                                kind,
+                                source: DiffSource::File(path),
+                            }),
+                        )
+                    })
+                    .collect_vec(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::OpenFile,
+            None,
+        )
+    }
+
+    /// The log is computed in the background, because following the history of a file
+    /// might walk through every commit, and the prompt is opened once it is received.
+    fn open_git_log(&mut self, path: Option<CanonicalizedPath>) {
+        let working_directory = self.working_directory.clone();
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            let commits = git::GitRepo::try_from(&working_directory)
+                .and_then(|repo| repo.log(path.as_ref(), git::log::MAX_LOG_LENGTH));
+            let _ = sender.send(AppMessage::GitLog { path, commits });
+        });
+    }
+
+    fn show_git_log(
+        &mut self,
+        path: Option<CanonicalizedPath>,
+        commits: Vec<CommitSummary>,
+    ) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: match &path {
+                    Some(path) => format!(
+                        "File history ({})",
+                        path.display_relative_to(&self.working_directory)
+                            .unwrap_or_else(|_| path.display_absolute())
+                    ),
+                    None => "Git log".to_string(),
+                },
+                on_enter: DispatchPrompt::Null,
+                items: commits
+                    .into_iter()
+                    .map(|commit| {
+                        DropdownItem::new(format!("{} {}", commit.short_id(), commit.summary))
+                            .set_info(Some(Info::new(
+                                "Commit".to_string(),
+                                format!(
+                                    "{}\nAuthor: {}\nDate: {}",
+                                    commit.id,
+                                    commit.author,
+                                    commit.age()
+                                ),
+                            )))
+                            .set_dispatches(Dispatches::one(Dispatch::ShowCommit(commit.id)))
+                    })
+                    .collect_vec(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::GitRevision,
+            None,
+        )
+    }
+
+    /// The commit is shown in a readonly buffer, where the hunks can be navigated using the next and previous movements.
+    fn show_commit(&mut self, revision: String) -> anyhow::Result<()> {
+        let patch = git::GitRepo::try_from(&self.working_directory)?.commit_patch(&revision)?;
+        let diff_view = DiffView::from_patch(&patch);
+        let mut editor = Editor::from_text(None, &diff_view.content);
+        editor.set_decorations(&diff_view.decorations);
+        editor.buffer_mut().set_readonly(true);
+        editor.set_title(format!("Commit {}", git::log::short_revision(&revision)));
+        self.layout.show_diff_viewer(Rc::new(RefCell::new(editor)));
+        self.handle_dispatch(Dispatch::ToEditor(SetSelectionMode(
+            IfCurrentNotFound::LookForward,
+            SelectionMode::Find {
+                search: Search {
+                    mode: LocalSearchConfigMode::Regex(RegexConfig {
+                        escaped: false,
+                        case_sensitive: true,
+                        match_whole_word: false,
+                    }),
+                    search: crate::diff_view::HUNK_HEADER_REGEX.to_string(),
+                },
+            },
+        )))
+    }
+
+    /// Lists the local and remote branches, followed by the recent commits.
+    /// The revisions are listed in the background like `Self::open_git_log`,
+    /// because listing the commits of a large repository is slow.
+    fn open_revision_picker(&mut self, kind: RevisionPickerKind) {
+        let working_directory = self.working_directory.clone();
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            let revisions = git::GitRepo::try_from(&working_directory)
+                .and_then(|repo| Ok((repo.branches()?, repo.log(None, git::log::MAX_LOG_LENGTH)?)));
+            let _ = sender.send(AppMessage::GitRevisions { kind, revisions });
+        });
+    }
+
+    fn show_revision_picker(
+        &mut self,
+        kind: RevisionPickerKind,
+        branches: Vec<String>,
+        commits: Vec<CommitSummary>,
+    ) -> anyhow::Result<()> {
+        let branches = branches.into_iter().map(|branch| {
+            DropdownItem::new(branch.clone())
+                .set_group(Some("Branches".to_string()))
+                .set_dispatches(Dispatches::one(kind.dispatch(branch)))
+        });
+        let commits = commits.into_iter().map(|commit| {
+            DropdownItem::new(format!("{} {}", commit.short_id(), commit.summary))
+                .set_group(Some("Commits".to_string()))
+                .set_info(Some(Info::new(
+                    "Commit".to_string(),
+                    format!("Author: {}\nDate: {}", commit.author, commit.age()),
+                )))
+                .set_dispatches(Dispatches::one(kind.dispatch(commit.id)))
+        });
+        self.open_prompt(
+            PromptConfig {
+                title: kind.prompt_title(),
+                on_enter: DispatchPrompt::Null,
+                items: branches.chain(commits).collect_vec(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::GitRevision,
+            None,
+        )
+    }
+
+    fn open_file_at_revision_picker(&mut self, revision: String) -> anyhow::Result<()> {
+        let working_directory = self.working_directory.clone();
+        self.open_prompt(
+            PromptConfig {
+                title: format!("Open file at {}", git::log::short_revision(&revision)),
+                on_enter: DispatchPrompt::Null,
+                items: git::GitRepo::try_from(&self.working_directory)?
+                    .files_at_revision(&revision)?
+                    .into_iter()
+                    .map(|path| {
+                        file_dropdown_item(&working_directory, &path).set_dispatches(
+                            Dispatches::one(Dispatch::OpenFileAtRevision {
+                                revision: revision.clone(),
+                                path,
+                            }),
+                        )
+                    })
+                    .collect_vec(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::OpenFile,
+            None,
+        )
+    }
+
+    fn open_file_at_revision(&mut self, revision: String, path: PathBuf) -> anyhow::Result<()> {
+        let content = git::GitRepo::try_from(&self.working_directory)?
+            .content_at_revision(&path, &revision)?;
+        let language = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(shared::language::from_extension);
+        let mut editor = Editor::from_text(
+            language.and_then(|language| language.tree_sitter_language()),
+            &content,
+        );
+        editor.buffer_mut().set_readonly(true);
+        editor.set_title(format!(
+            "{} @ {}",
+            path.display(),
+            git::log::short_revision(&revision)
+        ));
+        self.layout.show_diff_viewer(Rc::new(RefCell::new(editor)));
+        Ok(())
+    }
+
+    fn stage_hunks(
+        &mut self,
+        path: CanonicalizedPath,
+        line_ranges: Option<Vec<Range<usize>>>,
+    ) -> anyhow::Result<()> {
+        if line_ranges.is_some() {
+            self.ensure_saved(&path, "staging its hunks")?;
+        }
+        git::GitRepo::try_from(&self.working_directory)?
+            .stage_hunks(&path, line_ranges.as_deref())?;
+        self.refresh_git_views()
+    }
+
+    fn unstage_hunks(
+        &mut self,
+        path: CanonicalizedPath,
+        line_ranges: Option<Vec<Range<usize>>>,
+    ) -> anyhow::Result<()> {
+        if line_ranges.is_some() {
+            self.ensure_saved(&path, "unstaging its hunks")?;
+        }
+        git::GitRepo::try_from(&self.working_directory)?
+            .unstage_hunks(&path, line_ranges.as_deref())?;
+        self.refresh_git_views()
+    }
+
+    /// The hunks are computed from the file, so acting on hunks by line ranges is refused
+    /// while the buffer has unsaved changes, as its lines might not match the file.
+    fn ensure_saved(&self, path: &CanonicalizedPath, action: &str) -> anyhow::Result<()> {
+        let has_unsaved_changes = self.layout.buffers().iter().any(|buffer| {
+            let buffer = buffer.borrow();
+            buffer.path().as_ref() == Some(path) && buffer.is_dirty()
+        });
+        if has_unsaved_changes {
+            return Err(anyhow::anyhow!(
+                "Save {} before {}",
+                path.display_relative_to(&self.working_directory)
+                    .unwrap_or_else(|_| path.display_absolute()),
+                action
+            ));
+        }
+        Ok(())
+    }
+
+    fn open_commit_message_editor(&mut self, amend: bool) -> anyhow::Result<()> {
+        let repo = git::GitRepo::try_from(&self.working_directory)?;
+        let path = repo.commit_message_path();
+        std::fs::write(&path, repo.commit_message_template(amend)?)?;
+        let path: CanonicalizedPath = path.try_into()?;
+        // The commit message file might have been opened before
+        self.layout.reload_buffers(vec![path.clone()])?;
+        self.open_file(&path, OpenFileOption::Focus)?;
+        self.pending_commit = Some(PendingCommit {
+            path,
+            amend,
+            saved: false,
+        });
+        Ok(())
+    }
+
+    /// Called when the commit message editor, which is the current component, is closed.
+    /// The commit is only created if the commit message was saved,
+    /// otherwise it is aborted, like closing the editor of `git commit` without saving.
+    fn commit(&mut self) -> anyhow::Result<()> {
+        let Some(pending_commit) = self.pending_commit.take() else {
+            return Ok(());
+        };
+        let is_dirty = self
+            .current_component()
+            .borrow()
+            .editor()
+            .buffer()
+            .is_dirty();
+        if !pending_commit.saved || is_dirty {
+            self.layout.close_current_window();
+            self.layout.remove_suggestive_editor(&pending_commit.path);
+            self.show_global_info(Info::new(
+                "Commit aborted".to_string(),
+                "The commit message was closed without being saved.".to_string(),
+            ));
+            return Ok(());
+        }
+        let summary =
+            match git::GitRepo::try_from(&self.working_directory)?.commit(pending_commit.amend) {
+                Ok(summary) => summary,
+                Err(error) => {
+                    // Keep the commit message editor around so that the user can try again
+                    self.pending_commit = Some(pending_commit);
+                    return Err(error);
+                }
+            };
+        self.layout.close_current_window();
+        self.layout.remove_suggestive_editor(&pending_commit.path);
+        self.show_global_info(Info::new("Committed".to_string(), summary));
+        self.refresh_git_views()
+    }
+
+    /// Recompute the Git hunks quickfix list and the Git status file picker
+    /// if either of them is being shown, so that they reflect the latest index.
+    fn refresh_git_views(&mut self) -> anyhow::Result<()> {
+        for diff_mode in [
+            git::DiffMode::UnstagedAgainstCurrentBranch,
+            git::DiffMode::UnstagedAgainstMainBranch,
+        ] {
+            let showing_git_hunks = self
+                .context
+                .quickfix_list_state()
+                .as_ref()
+                .map(|state| state.title == git_hunks_title(&diff_mode))
+                .unwrap_or(false);
+            if showing_git_hunks {
+                self.get_repo_git_hunks(diff_mode.clone())?;
+            }
+            let kind = FilePickerKind::GitStatus(diff_mode);
+            if self.current_component().borrow().title(&self.context) == kind.prompt_title() {
+                self.close_current_window()?;
+                self.open_file_picker(kind)?;
+            }
+        }
+        Ok(())
     }
 
     #[cfg(test)]
@@ -1732,11 +3050,23 @@ impl<T: Frontend> App<T> {
                 }))
                 .chain(Some(KeymapLegendSection {
                     title: "Actions".to_string(),
-                    keymaps: Keymaps::new(&[Keymap::new(
-                        "R",
-                        "Replace all".to_string(),
-                        Dispatch::Replace { scope },
-                    )]),
+                    keymaps: Keymaps::new(
+                        &Some(Keymap::new(
+                            "R",
+                            "Replace all".to_string(),
+                            Dispatch::Replace { scope },
+                        ))
+                        .into_iter()
+                        .chain(match scope {
+                            Scope::Local => Vec::new(),
+                            Scope::Global => vec![Keymap::new(
+                                "P",
+                                "Preview replace".to_string(),
+                                Dispatch::OpenGlobalReplacePreview,
+                            )],
+                        })
+                        .collect_vec(),
+                    ),
                 }))
                 .collect(),
             },
@@ -1839,8 +3169,10 @@ impl<T: Frontend> App<T> {
         self.current_component().borrow().editor().content()
     }
 
-    #[cfg(test)]
-    fn handle_key_events(&mut self, key_events: Vec<event::KeyEvent>) -> anyhow::Result<()> {
+    pub(crate) fn handle_key_events(
+        &mut self,
+        key_events: Vec<event::KeyEvent>,
+    ) -> anyhow::Result<()> {
         for key_event in key_events.into_iter() {
             self.handle_event(Event::Key(key_event.to_owned()))?;
         }
@@ -2041,15 +3373,66 @@ impl<T: Frontend> App<T> {
                 title: "Theme".to_string(),
                 enter_selects_first_matching_item: true,
                 leaves_current_line_empty: true,
-                fire_dispatches_on_change: Some(Dispatches::one(Dispatch::SetTheme(
-                    self.context.theme().clone(),
-                ))),
+                fire_dispatches_on_change: Some(FireDispatchesOnChange {
+                    undo: Dispatches::one(Dispatch::SetTheme(self.context.theme().clone())),
+                    on_enter_if_unmatched: false,
+                }),
             },
             PromptHistoryKey::Theme,
             None,
         )
     }
 
+    /// The dispatches of the chosen action are handled after the palette is closed,
+    /// so they apply to the component that was focused when the palette was opened.
+    fn open_command_palette(&mut self) -> anyhow::Result<()> {
+        let component = self.current_component();
+        let component = component.borrow();
+        // The contextual keymaps are listed under the space legend
+        self.context
+            .set_contextual_keymaps(component.contextual_keymaps());
+        let editor = component.editor();
+        let items = editor
+            .command_palette_actions(&self.context)
+            .into_iter()
+            .map(|action| {
+                DropdownItem::new(action.display()).set_dispatches(action.dispatches(&editor.mode))
+            })
+            .unique_by(|item| item.display())
+            .collect_vec();
+        drop(component);
+        self.open_prompt(
+            PromptConfig {
+                on_enter: DispatchPrompt::Null,
+                items,
+                title: "Command palette".to_string(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::CommandPalette,
+            None,
+        )
+    }
+
+    /// Runs the command palette action whose description matches `action`, ignoring case.
+    fn run_command_palette_action(&mut self, action: &str) -> anyhow::Result<()> {
+        let component = self.current_component();
+        let component = component.borrow();
+        // The contextual keymaps are listed under the space legend
+        self.context
+            .set_contextual_keymaps(component.contextual_keymaps());
+        let editor = component.editor();
+        let dispatches = editor
+            .command_palette_actions(&self.context)
+            .into_iter()
+            .find(|palette_action| palette_action.description().eq_ignore_ascii_case(action))
+            .map(|palette_action| palette_action.dispatches(&editor.mode))
+            .ok_or_else(|| anyhow::anyhow!("No action is described as {:?}", action))?;
+        drop(component);
+        self.handle_dispatches(dispatches)
+    }
+
     fn update_current_completion_item(
         &mut self,
         completion_item: CompletionItem,
@@ -2079,6 +3462,356 @@ impl<T: Frontend> App<T> {
         )
     }
 
+    fn open_run_task_prompt(&mut self) -> anyhow::Result<()> {
+        let tasks = crate::task::load_tasks(&self.working_directory)?;
+        self.open_prompt(
+            PromptConfig {
+                title: "Run task (name or command)".to_string(),
+                items: tasks
+                    .iter()
+                    .map(|task| {
+                        DropdownItem::new(task.name.clone())
+                            .set_info(Some(Info::new("Command".to_string(), task.command.clone())))
+                    })
+                    .collect(),
+                on_enter: DispatchPrompt::RunTask { tasks },
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::RunTask,
+            None,
+        )
+    }
+
+    /// The task that is still running, if any, is killed.
+    fn run_task(&mut self, task: Task) -> anyhow::Result<()> {
+        if let Some(task_run) = self.task_run.take() {
+            task_run.kill()
+        }
+        self.task_run = Some(TaskRun::start(
+            task,
+            self.working_directory.to_path_buf(),
+            self.sender.clone(),
+        )?);
+        self.render_task_output("running")
+    }
+
+    fn rerun_task(&mut self) -> anyhow::Result<()> {
+        let task = self
+            .task_run
+            .as_ref()
+            .map(|task_run| task_run.task().clone())
+            .ok_or_else(|| anyhow::anyhow!("No task has been run yet"))?;
+        self.run_task(task)
+    }
+
+    /// Shows the output printed since the last `AppMessage::TaskOutput`.
+    /// Output of tasks that were replaced by a newer task is ignored.
+    fn receive_task_output(&mut self, id: usize) -> anyhow::Result<()> {
+        let Some(task_run) = self
+            .task_run
+            .as_mut()
+            .filter(|task_run| task_run.id() == id)
+        else {
+            return Ok(());
+        };
+        let dispatches = match task_run.receive_output() {
+            ReceivedOutput::Nothing => return Ok(()),
+            ReceivedOutput::Truncated => return self.render_task_output("running"),
+            ReceivedOutput::Appended(output) => self
+                .layout
+                .append_task_output(Self::task_output_title(task_run, "running"), &output)?,
+        };
+        self.handle_dispatches(dispatches)
+    }
+
+    /// Tasks that were replaced by a newer task are ignored.
+    fn handle_task_exited(&mut self, id: usize, code: Option<i32>) -> anyhow::Result<()> {
+        let Some(task_run) = self
+            .task_run
+            .as_mut()
+            .filter(|task_run| task_run.id() == id)
+        else {
+            return Ok(());
+        };
+        // The whole output is shown below, along with the exit status
+        task_run.receive_output();
+        let status = match code {
+            Some(0) => "succeeded".to_string(),
+            Some(code) => format!("failed with exit code {}", code),
+            None => "terminated".to_string(),
+        };
+        let title = format!("Task {:?} {}", task_run.task().name, status);
+        let items = task_run
+            .task()
+            .format
+            .parse(task_run.output(), &self.working_directory)?;
+        self.render_task_output(&status)?;
+        if items.is_empty() {
+            self.show_global_info(Info::new("Task".to_string(), title));
+            return Ok(());
+        }
+        self.set_quickfix_list_type(
+            ResponseContext::default().set_description(&title),
+            QuickfixListType::Items(items),
+        )
+    }
+
+    fn render_task_output(&mut self, status: &str) -> anyhow::Result<()> {
+        let Some(task_run) = self.task_run.as_ref() else {
+            return Ok(());
+        };
+        let dispatches = self
+            .layout
+            .show_task_output(Self::task_output_title(task_run, status), task_run.output())?;
+        self.handle_dispatches(dispatches)
+    }
+
+    fn task_output_title(task_run: &TaskRun, status: &str) -> String {
+        format!("Task: {} ({})", task_run.task().name, status)
+    }
+
+    fn toggle_breakpoint(&mut self, path: CanonicalizedPath, line: usize) -> anyhow::Result<()> {
+        self.open_file(&path, OpenFileOption::Background)?
+            .borrow_mut()
+            .editor_mut()
+            .buffer_mut()
+            .toggle_breakpoint(line)?;
+        self.sync_breakpoints(&path)
+    }
+
+    /// Persists the breakpoints of the buffer of `path` and sends them to the debugging session,
+    /// if they moved or were toggled.
+    fn sync_breakpoints(&mut self, path: &CanonicalizedPath) -> anyhow::Result<()> {
+        let Some(editor) = self.layout.get_existing_editor(path) else {
+            return Ok(());
+        };
+        let lines = editor.borrow().editor().buffer().breakpoint_lines();
+        if lines == self.context.breakpoints().lines(path) {
+            return Ok(());
+        }
+        self.context.set_breakpoints(path, lines);
+        self.context.breakpoints().save(&self.working_directory)?;
+        if let Some(session) = self.debug_session.as_mut() {
+            session.set_breakpoints(self.context.breakpoints().clone(), path)?
+        }
+        Ok(())
+    }
+
+    /// The running session, if any, is stopped first.
+    fn start_debugging(&mut self, path: CanonicalizedPath) -> anyhow::Result<()> {
+        if let Some(mut session) = self.debug_session.take() {
+            session.disconnect()?
+        }
+        let language = shared::language::from_path(&path)
+            .ok_or_else(|| anyhow::anyhow!("Unknown language of {}", path.display_absolute()))?;
+        let (Some(command), Some(launch_arguments)) = (
+            language.dap_process_command(),
+            language.dap_launch_arguments(),
+        ) else {
+            return Err(anyhow::anyhow!(
+                "No debug adapter is declared for {}",
+                path.display_absolute()
+            ));
+        };
+        self.debug_session = Some(DebugSession::start(
+            command,
+            &language.id().map(|id| id.to_string()).unwrap_or_default(),
+            crate::dap::session::launch_arguments(
+                launch_arguments,
+                &path,
+                &self.working_directory,
+            )?,
+            self.context.breakpoints().clone(),
+            self.sender.clone(),
+        )?);
+        self.render_debug_panels()
+    }
+
+    fn debug_session(&mut self) -> anyhow::Result<&mut DebugSession> {
+        self.debug_session
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("No debugging session is running"))
+    }
+
+    /// The session is dropped when the adapter reports that it terminated.
+    fn stop_debugging(&mut self) -> anyhow::Result<()> {
+        self.debug_session()?.disconnect()?;
+        self.layout.hide_debug_panels();
+        Ok(())
+    }
+
+    fn handle_dap_message(&mut self, message: serde_json::Value) -> anyhow::Result<()> {
+        let Some(session) = self.debug_session.as_mut() else {
+            return Ok(());
+        };
+        for update in session.handle_message(message)? {
+            match update {
+                DebugUpdate::GotoLocation { path, position } => self.go_to_location(&Location {
+                    path: path.try_into()?,
+                    range: position..position,
+                })?,
+                DebugUpdate::RenderPanels => self.render_debug_panels()?,
+                DebugUpdate::Evaluated { expression, result } => self.show_global_info(Info::new(
+                    "Evaluate".to_string(),
+                    format!("{} = {}", expression, result),
+                )),
+                DebugUpdate::Terminated => {
+                    self.debug_session = None;
+                    self.layout.hide_debug_panels();
+                    self.show_global_info(Info::new(
+                        "Debug".to_string(),
+                        "The debugging session has terminated".to_string(),
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn render_debug_panels(&mut self) -> anyhow::Result<()> {
+        let Some(session) = self.debug_session.as_ref() else {
+            return Ok(());
+        };
+        self.layout
+            .show_debug_panels(&session.render_call_stack(), &session.render_variables())
+    }
+
+    /// Returns true if any file was auto-saved, because only then the screen changes.
+    fn handle_tick(&mut self) -> anyhow::Result<bool> {
+        let saved = self
+            .auto_save
+            .after_idle()
+            .is_some_and(|idle| self.last_event_at.elapsed() >= idle)
+            && self.auto_save(AutoSaveTrigger::Idle)?;
+        self.write_recovery_files()?;
+        Ok(saved)
+    }
+
+    /// Returns true if saving `path` does more than writing the file,
+    /// such as creating a commit.
+    fn is_pending_on_save(&self, path: &CanonicalizedPath) -> bool {
+        self.pending_commit
+            .as_ref()
+            .is_some_and(|pending_commit| &pending_commit.path == path)
+            || self
+                .pending_replace_preview
+                .as_ref()
+                .is_some_and(|pending| &pending.path == path)
+    }
+
+    /// Saves every modified file, except readonly files and files that are pending on save.
+    /// Returns true if any file was saved.
+    fn auto_save(&mut self, trigger: AutoSaveTrigger) -> anyhow::Result<bool> {
+        let mut saved = false;
+        for editor in self.layout.dirty_editors() {
+            let skipped = {
+                let editor = editor.borrow();
+                let buffer = editor.editor().buffer();
+                buffer.readonly()
+                    || buffer
+                        .path()
+                        .is_some_and(|path| self.is_pending_on_save(&path))
+            };
+            if skipped {
+                continue;
+            }
+            let dispatches = {
+                let mut editor = editor.borrow_mut();
+                match trigger {
+                    AutoSaveTrigger::FocusChange => editor.editor_mut().auto_save()?,
+                    AutoSaveTrigger::Idle => editor.editor_mut().save_without_formatting()?,
+                }
+            };
+            self.handle_dispatches(dispatches)?;
+            saved = true;
+        }
+        Ok(saved)
+    }
+
+    /// Recovery files are only rewritten when the buffer was edited since they were written,
+    /// and are removed when the content no longer differs from the file.
+    fn write_recovery_files(&mut self) -> anyhow::Result<()> {
+        for buffer in self.layout.buffers() {
+            let buffer = buffer.borrow();
+            let Some(path) = buffer.path() else { continue };
+            if self.recovery_edit_counts.get(&path) == Some(&buffer.edit_count()) {
+                continue;
+            }
+            if buffer.is_dirty() && !self.is_pending_on_save(&path) {
+                recovery::write(&self.recovery_directory, &path, &buffer.content())?;
+                self.recovery_edit_counts.insert(path, buffer.edit_count());
+            } else if self.recovery_edit_counts.remove(&path).is_some() {
+                recovery::remove(&self.recovery_directory, &path)?
+            }
+        }
+        Ok(())
+    }
+
+    /// Shows the diff between the first orphaned recovery file and its file,
+    /// and asks whether to recover or discard it.
+    pub(crate) fn offer_recovery(&mut self) -> anyhow::Result<()> {
+        while let Some(recovery_file) = self.orphaned_recovery_files.first().cloned() {
+            let Ok(path) = CanonicalizedPath::try_from(recovery_file.path.clone()) else {
+                log::info!(
+                    "Skipped recovery of {:?} because it no longer exists",
+                    recovery_file.path
+                );
+                self.orphaned_recovery_files.remove(0);
+                continue;
+            };
+            let title = format!(
+                "Unsaved changes of {}",
+                path.display_relative_to(&self.working_directory)
+                    .unwrap_or_else(|_| path.display_absolute())
+            );
+            let diff = diffy::create_patch(&path.read()?, &recovery_file.content).to_string();
+            self.show_global_info(Info::new(title.clone(), diff));
+            return self.handle_dispatch(Dispatch::ShowKeymapLegend(KeymapLegendConfig {
+                title: "Recovery".to_string(),
+                body: KeymapLegendBody::MultipleSections {
+                    sections: [KeymapLegendSection {
+                        title,
+                        keymaps: Keymaps::new(&[
+                            Keymap::new(
+                                "r",
+                                "Recover".to_string(),
+                                Dispatch::RecoverFile {
+                                    path: path.clone(),
+                                    content: recovery_file.content,
+                                },
+                            ),
+                            Keymap::new(
+                                "d",
+                                "Discard".to_string(),
+                                Dispatch::DiscardRecoveryFile(path),
+                            ),
+                        ]),
+                    }]
+                    .to_vec(),
+                },
+            }));
+        }
+        Ok(())
+    }
+
+    /// The recovered content is not saved, and the recovery can be undone.
+    fn recover_file(&mut self, path: CanonicalizedPath, content: String) -> anyhow::Result<()> {
+        let editor = self.open_file(&path, OpenFileOption::Focus)?;
+        let dispatches = editor.borrow_mut().editor_mut().replace_content(&content)?;
+        self.handle_dispatches(dispatches)?;
+        self.discard_recovery_file(path)
+    }
+
+    fn discard_recovery_file(&mut self, path: CanonicalizedPath) -> anyhow::Result<()> {
+        self.orphaned_recovery_files
+            .retain(|recovery_file| recovery_file.path != *path.to_path_buf());
+        // The recovery file of a recovered file is rewritten on the next tick
+        recovery::remove(&self.recovery_directory, &path)?;
+        self.offer_recovery()
+    }
+
     fn use_last_non_contiguous_selection_mode(
         &mut self,
         if_current_not_found: IfCurrentNotFound,
@@ -2248,6 +3981,7 @@ pub(crate) enum Dispatch {
     RequestDocumentSymbols,
     GotoLocation(Location),
     OpenMoveToIndexPrompt,
+    OpenUndoToMinutesAgoPrompt,
     QuitAll,
     SaveQuitAll,
     RevealInExplorer(CanonicalizedPath),
@@ -2255,6 +3989,22 @@ pub(crate) enum Dispatch {
     OpenMoveFilePrompt(CanonicalizedPath),
     OpenAddPathPrompt(CanonicalizedPath),
     DeletePath(CanonicalizedPath),
+    DeletePaths(Vec<CanonicalizedPath>),
+    OpenMovePathsPrompt(Vec<CanonicalizedPath>),
+    MovePaths {
+        from: Vec<CanonicalizedPath>,
+        to_directory: PathBuf,
+    },
+    OpenCopyPathPrompt(CanonicalizedPath),
+    CopyPath {
+        from: CanonicalizedPath,
+        to: PathBuf,
+    },
+    DuplicatePath(CanonicalizedPath),
+    ToFileExplorer(DispatchFileExplorer),
+    /// Walk the working directory in the background, see `file_explorer::Index`
+    BuildFileExplorerIndex,
+    OpenFileExplorerFilterPrompt(String),
     Null,
     MoveFile {
         from: CanonicalizedPath,
@@ -2271,7 +4021,38 @@ pub(crate) enum Dispatch {
     HandleKeyEvent(event::KeyEvent),
     #[cfg(test)]
     HandleKeyEvents(Vec<event::KeyEvent>),
+    #[cfg(test)]
+    HandleMouseEvent(MouseEvent),
     GetRepoGitHunks(git::DiffMode),
+    GetRepoMergeConflicts,
+    /// `line_ranges` are 0-based; `None` means the whole file.
+    StageHunks {
+        path: CanonicalizedPath,
+        line_ranges: Option<Vec<Range<usize>>>,
+    },
+    OpenCommitMessageEditor {
+        amend: bool,
+    },
+    OpenDiffView {
+        kind: DiffViewKind,
+        source: DiffSource,
+    },
+    OpenDiffFilePicker(DiffViewKind),
+    /// Lists the commits that changed `path`, or every commit if `path` is `None`
+    OpenGitLog(Option<CanonicalizedPath>),
+    ShowCommit(String),
+    OpenRevisionPicker(RevisionPickerKind),
+    OpenFileAtRevisionPicker(String),
+    /// `path` is relative to the repository.
+    OpenFileAtRevision {
+        revision: String,
+        path: PathBuf,
+    },
+    /// `line_ranges` are 0-based; `None` means the whole file.
+    UnstageHunks {
+        path: CanonicalizedPath,
+        line_ranges: Option<Vec<Range<usize>>>,
+    },
     SaveAll,
     #[cfg(test)]
     TerminalDimensionChanged(Dimension),
@@ -2309,6 +4090,7 @@ pub(crate) enum Dispatch {
     Replace {
         scope: Scope,
     },
+    OpenGlobalReplacePreview,
     #[cfg(test)]
     HandleLspNotification(LspNotification),
     CloseDropdown,
@@ -2324,6 +4106,11 @@ pub(crate) enum Dispatch {
     ShowEditorInfo(Info),
     ReceiveCodeActions(Vec<crate::lsp::code_action::CodeAction>),
     OtherWindow,
+    SplitWindow(SplitOrientation),
+    MoveWindowFocus(WindowDirection),
+    ResizeWindow {
+        grow: bool,
+    },
     CloseCurrentWindowAndFocusParent,
     CloseEditorInfo,
     GoToPreviousFile,
@@ -2334,6 +4121,29 @@ pub(crate) enum Dispatch {
         line: String,
     },
     OpenThemePrompt,
+    OpenCommandPalette,
+    ExecutePluginCommand {
+        plugin: String,
+        name: String,
+    },
+    OpenRunTaskPrompt,
+    RunTask(Task),
+    RerunTask,
+    ToggleBreakpoint {
+        path: CanonicalizedPath,
+        /// 0-based
+        line: usize,
+    },
+    /// Starts debugging `path` with the debug adapter of its language
+    StartDebugging(CanonicalizedPath),
+    ResumeDebugging(DebugResume),
+    EvaluateInDebugSession(String),
+    StopDebugging,
+    RecoverFile {
+        path: CanonicalizedPath,
+        content: String,
+    },
+    DiscardRecoveryFile(CanonicalizedPath),
     ResolveCompletionItem(lsp_types::CompletionItem),
     OpenPipeToShellPrompt,
     SetLastNonContiguousSelectionMode(Either<SelectionMode, GlobalMode>),
@@ -2372,6 +4182,8 @@ pub(crate) struct YesNoPrompt {
 pub(crate) enum FilePickerKind {
     NonGitIgnored,
     GitStatus(git::DiffMode),
+    /// Files with unresolved merge conflicts
+    GitConflicts,
     Opened,
 }
 impl FilePickerKind {
@@ -2379,9 +4191,97 @@ impl FilePickerKind {
         match self {
             FilePickerKind::NonGitIgnored => "Not Git Ignored".to_string(),
             FilePickerKind::GitStatus(diff_mode) => format!("Git Status ({})", diff_mode.display()),
+            FilePickerKind::GitConflicts => "Git Conflicts".to_string(),
             FilePickerKind::Opened => "Opened".to_string(),
         }
     }
+
+    fn prompt_title(&self) -> String {
+        format!("Open file: {}", self.display())
+    }
+}
+
+/// What to do with the revision chosen from the revision picker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RevisionPickerKind {
+    /// Pick a file to open at the chosen revision
+    OpenFile,
+    /// Find the git hunks against the chosen revision
+    GitHunk {
+        scope: Scope,
+        if_current_not_found: IfCurrentNotFound,
+    },
+    /// Compare the current file against the chosen revision
+    DiffView(DiffViewKind),
+}
+impl RevisionPickerKind {
+    fn prompt_title(&self) -> String {
+        match self {
+            RevisionPickerKind::OpenFile => "Open file at revision".to_string(),
+            RevisionPickerKind::GitHunk { .. } => "Git hunks against revision".to_string(),
+            RevisionPickerKind::DiffView(kind) => {
+                format!("Compare against revision ({})", kind.display())
+            }
+        }
+    }
+
+    fn dispatch(&self, revision: String) -> Dispatch {
+        let diff_mode = git::DiffMode::UnstagedAgainstRevision(revision.clone());
+        match self {
+            RevisionPickerKind::OpenFile => Dispatch::OpenFileAtRevisionPicker(revision),
+            RevisionPickerKind::GitHunk {
+                scope: Scope::Global,
+                ..
+            } => Dispatch::GetRepoGitHunks(diff_mode),
+            RevisionPickerKind::GitHunk {
+                scope: Scope::Local,
+                if_current_not_found,
+            } => Dispatch::ToEditor(SetSelectionMode(
+                *if_current_not_found,
+                SelectionMode::GitHunk(diff_mode),
+            )),
+            RevisionPickerKind::DiffView(kind) => Dispatch::OpenDiffView {
+                kind: *kind,
+                source: DiffSource::Git(diff_mode),
+            },
+        }
+    }
+}
+
+fn copy_recursively(from: &Path, to: &Path) -> anyhow::Result<()> {
+    if from.is_dir() {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        std::fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+fn file_dropdown_item(working_directory: &CanonicalizedPath, path: &Path) -> DropdownItem {
+    DropdownItem::new({
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let icon = shared::canonicalized_path::get_path_icon(path);
+        format!("{icon} {name}")
+    })
+    .set_group(path.parent().map(|parent| {
+        let relative = parent
+            .strip_prefix(working_directory)
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|_| parent.display().to_string());
+        format!("{} {}", shared::icons::get_icon_config().folder, relative,)
+    }))
+}
+
+fn git_hunks_title(diff_mode: &git::DiffMode) -> String {
+    format!("Git Hunks ({})", diff_mode.display())
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -2418,11 +4318,59 @@ pub(crate) enum Scope {
     Global,
 }
 
+/// What to open when the app starts.
+#[derive(Default, Clone)]
+pub(crate) struct EntryConfig {
+    pub(crate) paths: Vec<EntryPath>,
+    /// Content piped through stdin, opened as a scratch buffer
+    pub(crate) stdin: Option<String>,
+    /// Compare the first file (shown on the left) against the second file
+    pub(crate) diff: Option<(CanonicalizedPath, CanonicalizedPath)>,
+    /// Prevent the opened buffers from being edited or saved
+    pub(crate) readonly: bool,
+}
+
+#[derive(Clone)]
+pub(crate) struct EntryPath {
+    /// The file at this path is created when it is saved, if it does not exist yet
+    pub(crate) path: PathBuf,
+    pub(crate) position: Option<Position>,
+}
+
 #[derive(Debug)]
 pub(crate) enum AppMessage {
     LspNotification(LspNotification),
     Event(Event),
     QuitAll,
+    ServerCommand(ServerCommand),
+    PluginMessage {
+        plugin: String,
+        message: PluginMessage,
+    },
+    /// Sent periodically while the task prints, see `TaskRun::start`
+    TaskOutput {
+        id: usize,
+    },
+    TaskExited {
+        id: usize,
+        /// `None` if the task was terminated by a signal
+        code: Option<i32>,
+    },
+    DapMessage(serde_json::Value),
+    /// The result of `App::open_git_log`
+    GitLog {
+        path: Option<CanonicalizedPath>,
+        commits: anyhow::Result<Vec<CommitSummary>>,
+    },
+    /// The result of `App::open_revision_picker`, which are the branches and the commits
+    GitRevisions {
+        kind: RevisionPickerKind,
+        revisions: anyhow::Result<(Vec<String>, Vec<CommitSummary>)>,
+    },
+    /// The result of `Dispatch::BuildFileExplorerIndex`
+    FileExplorerIndex(file_explorer::Index),
+    /// Sent periodically, see `App::start_recovery`
+    Tick,
     SyntaxHighlightResponse {
         component_id: ComponentId,
         highlighted_spans: HighlighedSpans,
@@ -2436,6 +4384,7 @@ pub(crate) enum DispatchPrompt {
         if_current_not_found: IfCurrentNotFound,
     },
     MoveSelectionByIndex,
+    UndoToMinutesAgo,
     RenameSymbol,
     UpdateLocalSearchConfigSearch {
         scope: Scope,
@@ -2446,6 +4395,13 @@ pub(crate) enum DispatchPrompt {
     MovePath {
         from: CanonicalizedPath,
     },
+    MovePaths {
+        from: Vec<CanonicalizedPath>,
+    },
+    CopyPath {
+        from: CanonicalizedPath,
+    },
+    FilterFileExplorer,
     Null,
     // TODO: remove the following variants
     // Because the following action already embeds dispatches
@@ -2462,6 +4418,10 @@ pub(crate) enum DispatchPrompt {
     #[cfg(test)]
     SetContent,
     PipeToShell,
+    /// Runs the task of the given name, or runs the text as a command
+    RunTask {
+        tasks: Vec<Task>,
+    },
     FilterSelectionMatchingSearch {
         maintain: bool,
     },
@@ -2485,6 +4445,12 @@ impl DispatchPrompt {
                     [Dispatch::ToEditor(MoveSelection(Movement::Index(index)))].to_vec(),
                 ))
             }
+            DispatchPrompt::UndoToMinutesAgo => {
+                let minutes = text.trim().parse::<u64>()?;
+                Ok(Dispatches::one(Dispatch::ToEditor(UndoToMinutesAgo(
+                    minutes,
+                ))))
+            }
             DispatchPrompt::RenameSymbol => Ok(Dispatches::new(vec![Dispatch::RenameSymbol {
                 new_name: text.to_string(),
             }])),
@@ -2511,6 +4477,17 @@ impl DispatchPrompt {
                 }]
                 .to_vec(),
             )),
+            DispatchPrompt::MovePaths { from } => Ok(Dispatches::one(Dispatch::MovePaths {
+                from,
+                to_directory: text.into(),
+            })),
+            DispatchPrompt::CopyPath { from } => Ok(Dispatches::one(Dispatch::CopyPath {
+                from,
+                to: text.into(),
+            })),
+            DispatchPrompt::FilterFileExplorer => Ok(Dispatches::one(Dispatch::ToFileExplorer(
+                DispatchFileExplorer::SetFilter(text.to_string()),
+            ))),
             DispatchPrompt::SelectSymbol { symbols } => {
                 // TODO: make Prompt generic over the item type,
                 // so that we don't have to do this,
@@ -2554,6 +4531,12 @@ impl DispatchPrompt {
                     command: text.to_string(),
                 },
             ))),
+            DispatchPrompt::RunTask { tasks } => Ok(Dispatches::one(Dispatch::RunTask(
+                tasks
+                    .into_iter()
+                    .find(|task| task.name == text)
+                    .unwrap_or_else(|| Task::from_command(text)),
+            ))),
             DispatchPrompt::FilterSelectionMatchingSearch { maintain } => Ok(Dispatches::one(
                 Dispatch::ToEditor(DispatchEditor::FilterSelectionMatchingSearch {
                     maintain,
@@ -2579,3 +4562,11 @@ impl OpenFileOption {
         self == &OpenFileOption::Focus
     }
 }
+
+#[derive(Clone, Copy)]
+enum AutoSaveTrigger {
+    FocusChange,
+    /// Files are not formatted, because formatting would move the content
+    /// under the cursor after a mere pause in typing.
+    Idle,
+}
@@ -0,0 +1,115 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use itertools::Itertools;
+use once_cell::sync::OnceCell;
+use shared::language::Language;
+use tree_sitter::{Query, QueryCursor, Tree};
+
+type TreeSitterGrammarId = String;
+
+/// The indent queries compiled when the highlight configurations are loaded,
+/// because fetching and compiling them is too slow for the UI thread.
+/// `None` means the language has no indent query.
+static QUERIES: OnceCell<Mutex<HashMap<TreeSitterGrammarId, Option<Arc<Query>>>>> = OnceCell::new();
+
+fn queries() -> &'static Mutex<HashMap<TreeSitterGrammarId, Option<Arc<Query>>>> {
+    QUERIES.get_or_init(Default::default)
+}
+
+pub(crate) fn is_registered(grammar_id: &str) -> bool {
+    queries()
+        .lock()
+        .is_ok_and(|queries| queries.contains_key(grammar_id))
+}
+
+pub(crate) fn register_query(language: &Language, source: Option<String>) {
+    let Some(grammar_id) = language.tree_sitter_grammar_id() else {
+        return;
+    };
+    let query = language
+        .tree_sitter_language()
+        .zip(source)
+        .and_then(|(tree_sitter_language, source)| {
+            Query::new(&tree_sitter_language, &source)
+                .map_err(|error| log::info!("indent_query_error = {:#?}", error))
+                .ok()
+        })
+        .map(Arc::new);
+    if let Ok(mut queries) = queries().lock() {
+        queries.insert(grammar_id, query);
+    }
+}
+
+/// How the indentation of a new line differs from the line it is inserted after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IndentChange {
+    Indent,
+    Keep,
+    Dedent,
+}
+
+/// Decide the indentation of a newline inserted at `byte`, using the captures of `nvim-treesitter`:
+/// - `@indent.begin`: the new line is indented if `byte` is inside such a node that starts on the line of `byte`,
+///   unless one of its `@indent.end` nodes ends before `byte`
+/// - `@indent.dedent`: the new line is dedented if such a node ends the line before `byte`
+/// - `@indent.branch`: the new line is dedented if it starts with such a node, for example `}` or `else`
+///
+/// Returns `None` if the language has no indent query, or if it is not loaded yet.
+pub(crate) fn newline_indent_change(
+    language: &Language,
+    tree: &Tree,
+    source_code: &str,
+    byte: usize,
+) -> Option<IndentChange> {
+    let query = queries()
+        .lock()
+        .ok()?
+        .get(&language.tree_sitter_grammar_id()?)?
+        .clone()?;
+    let line_start = source_code[..byte].rfind('\n').map_or(0, |index| index + 1);
+    let line_end = source_code[byte..]
+        .find('\n')
+        .map_or(source_code.len(), |index| byte + index);
+    let mut cursor = QueryCursor::new();
+    cursor.set_byte_range(line_start..line_end);
+    let captures = cursor
+        .matches(&query, tree.root_node(), source_code.as_bytes())
+        .flat_map(|query_match| query_match.captures.to_vec())
+        .map(|capture| (capture.index, capture.node))
+        .collect_vec();
+    let nodes = |name: &str| {
+        let index = query.capture_index_for_name(name);
+        captures
+            .iter()
+            .filter(|(capture_index, _)| Some(*capture_index) == index)
+            .map(|(_, node)| *node)
+            .collect_vec()
+    };
+    let ends = nodes("indent.end");
+    let indents = nodes("indent.begin").into_iter().any(|node| {
+        (line_start..byte).contains(&node.start_byte())
+            && byte < node.end_byte()
+            && !ends
+                .iter()
+                .any(|end| node.start_byte() <= end.start_byte() && end.end_byte() <= byte)
+    });
+    let dedents = nodes("indent.dedent").into_iter().any(|node| {
+        line_start <= node.start_byte()
+            && node.end_byte() <= byte
+            && source_code[node.end_byte()..byte].trim().is_empty()
+    });
+    let next_line = &source_code[byte..line_end];
+    let next_line_start = byte + (next_line.len() - next_line.trim_start().len());
+    let branches = next_line_start < line_end
+        && nodes("indent.branch")
+            .into_iter()
+            .any(|node| node.start_byte() == next_line_start);
+    Some(match (indents, dedents || branches) {
+        (true, false) => IndentChange::Indent,
+        (true, true) | (false, false) => IndentChange::Keep,
+        (false, true) => IndentChange::Dedent,
+    })
+}
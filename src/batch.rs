@@ -0,0 +1,128 @@
+//! Apply a key sequence to files without a terminal,
+//! so that structural refactors can be scripted using the selection modes of Ki.
+
+use std::{rc::Rc, sync::Mutex};
+
+use itertools::Itertools;
+use shared::canonicalized_path::CanonicalizedPath;
+use similar::TextDiff;
+
+use crate::{
+    app::{App, Dispatch},
+    frontend::{mock::MockFrontend, NullWriter},
+    list::WalkBuilderConfig,
+};
+
+pub(crate) struct BatchConfig {
+    /// Space-separated keys, as parsed by `event::parse_key_events`
+    pub(crate) keys: String,
+    /// Directories are expanded to the files in them that are not ignored by Git
+    pub(crate) paths: Vec<CanonicalizedPath>,
+    pub(crate) working_directory: CanonicalizedPath,
+    /// Print the diff of each file instead of writing it
+    pub(crate) dry_run: bool,
+}
+
+pub(crate) fn run(config: BatchConfig) -> anyhow::Result<()> {
+    let key_events = event::parse_key_events(&config.keys)?;
+    let paths = config
+        .paths
+        .into_iter()
+        .map(|path| -> anyhow::Result<Vec<CanonicalizedPath>> {
+            if path.is_dir() {
+                WalkBuilderConfig::non_git_ignored_files(path)?
+                    .into_iter()
+                    .map(CanonicalizedPath::try_from)
+                    .collect()
+            } else {
+                Ok(vec![path])
+            }
+        })
+        .flatten_ok()
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    for path in paths.into_iter().sorted().dedup() {
+        let (old, new) = apply(
+            &config.working_directory,
+            &path,
+            key_events.clone(),
+            config.dry_run,
+        )
+        .map_err(|error| anyhow::anyhow!("{}: {}", path.display_absolute(), error))?;
+        if old == new {
+            continue;
+        }
+        let name = path
+            .display_relative_to(&config.working_directory)
+            .unwrap_or_else(|_| path.display_absolute());
+        if config.dry_run {
+            print!(
+                "{}",
+                TextDiff::from_lines(&old, &new)
+                    .unified_diff()
+                    .header(&format!("a/{}", name), &format!("b/{}", name))
+            );
+        } else {
+            println!("Modified {}", name);
+        }
+    }
+    Ok(())
+}
+
+/// Each file is edited by a new app, so that the state left by the key sequence
+/// in one file does not affect the other files.
+///
+/// Returns the content of the file before and after applying the key events,
+/// the file is written unless `dry_run` is true.
+fn apply(
+    working_directory: &CanonicalizedPath,
+    path: &CanonicalizedPath,
+    key_events: Vec<event::KeyEvent>,
+    dry_run: bool,
+) -> anyhow::Result<(String, String)> {
+    let mut app = App::new(
+        Rc::new(Mutex::new(MockFrontend::new(Box::new(NullWriter)))),
+        working_directory.clone(),
+        Vec::new(),
+    )?;
+    app.disable_lsp();
+    // Batch mode edits files without anyone at the keyboard, so their undo histories are left alone
+    app.set_undo_history_directory(None);
+    app.handle_dispatch(Dispatch::OpenFile(path.clone()))?;
+    let old = app.get_file_content(path);
+    app.handle_key_events(key_events)?;
+    let new = app.get_file_content(path);
+    if !dry_run && old != new {
+        app.save_file_without_formatting(path)?;
+    }
+    Ok((old, new))
+}
+
+#[cfg(test)]
+mod test_batch {
+    use super::{run, BatchConfig};
+
+    #[test]
+    fn apply_keys_to_every_file() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let first = directory.path().join("first.txt");
+        let second = directory.path().join("second.txt");
+        std::fs::write(&first, "world")?;
+        std::fs::write(&second, "there")?;
+        let config = |dry_run: bool| -> anyhow::Result<BatchConfig> {
+            Ok(BatchConfig {
+                keys: "i h e l l o space esc".to_string(),
+                paths: vec![directory.path().try_into()?],
+                working_directory: directory.path().try_into()?,
+                dry_run,
+            })
+        };
+
+        run(config(true)?)?;
+        assert_eq!(std::fs::read_to_string(&first)?, "world");
+
+        run(config(false)?)?;
+        assert_eq!(std::fs::read_to_string(&first)?, "hello world");
+        assert_eq!(std::fs::read_to_string(&second)?, "hello there");
+        Ok(())
+    }
+}
@@ -7,23 +7,38 @@ use crate::{
     components::{editor::Movement, suggestive_editor::Decoration},
     context::{LocalSearchConfig, LocalSearchConfigMode},
     edit::{Action, ActionGroup, Edit, EditTransaction},
+    editorconfig::EditorConfig,
+    file_format::FileFormat,
     position::Position,
-    selection::{CharIndex, Selection, SelectionSet},
+    selection::{CharIndex, Selection, SelectionMode, SelectionSet},
     selection_mode::{AstGrep, ByteRange},
-    syntax_highlight::{HighlighedSpan, HighlighedSpans},
+    syntax_highlight::{
+        injection::{self, Injection},
+        HighlighedSpan, HighlighedSpans,
+    },
     undo_tree::{Applicable, OldNew, UndoTree},
     utils::find_previous,
 };
 use itertools::Itertools;
+use nonempty::NonEmpty;
 use regex::Regex;
 use ropey::Rope;
+use serde::{Deserialize, Serialize};
 use shared::{
     canonicalized_path::CanonicalizedPath,
+    indent::Indent,
     language::{self, Language},
 };
-use std::{collections::HashSet, ops::Range};
+use std::{
+    cell::{Cell, OnceCell},
+    collections::{BTreeMap, HashSet},
+    ops::Range,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
 use tree_sitter::{Node, Parser, Tree};
 use tree_sitter_traversal::{traverse, Order};
+use unicode_segmentation::{GraphemeCursor, GraphemeIncomplete};
 
 #[derive(Clone)]
 pub(crate) struct Buffer {
@@ -33,12 +48,42 @@ pub(crate) struct Buffer {
     undo_tree: UndoTree<Patch>,
     language: Option<Language>,
     path: Option<CanonicalizedPath>,
+    /// Where the file is created when the buffer is saved, for a file that does not exist yet
+    new_file_path: Option<PathBuf>,
     highlighted_spans: HighlighedSpans,
     marks: Vec<CharIndexRange>,
+    /// The ranges of the lines that have a breakpoint, including their newline,
+    /// so that breakpoints move along with their lines when the buffer is edited
+    breakpoints: Vec<CharIndexRange>,
+    /// The closing symbols inserted by auto-pairing, which are moved over when they are typed,
+    /// they move along with the edits, and are forgotten when the whole content is replaced
+    auto_inserted_closers: Vec<CharIndexRange>,
     diagnostics: Vec<Diagnostic>,
     quickfix_list_items: Vec<QuickfixListItem>,
     decorations: Vec<Decoration>,
+    /// The decorations set by each plugin, keyed by the name of the plugin,
+    /// so that a plugin only replaces its own decorations
+    plugin_decorations: BTreeMap<String, Vec<Decoration>>,
     selection_set_history: History<SelectionSet>,
+    indent: Indent,
+    editor_config: EditorConfig,
+    file_format: FileFormat,
+    /// Parsed lazily, because they are only needed for navigating syntax nodes
+    injections: OnceCell<Vec<Injection>>,
+    /// Edits and saves are rejected when this is true
+    readonly: bool,
+    /// The hash of the content when it was last loaded or saved, used for detecting unsaved changes
+    saved_content_hash: u64,
+    /// Incremented whenever the content changes,
+    /// so that buffers that did not change can be skipped without reading their content
+    edit_count: usize,
+    /// The `edit_count` and the result of the last `is_dirty`,
+    /// so that the content is hashed at most once per change
+    dirty_cache: Cell<Option<(usize, bool)>>,
+    /// Where the undo history is persisted, `None` if it is not persisted
+    undo_history_directory: Option<PathBuf>,
+    /// True if the undo history changed since it was last restored or persisted
+    undo_tree_modified: bool,
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
@@ -65,15 +110,63 @@ impl Buffer {
                 })
             },
             path: None,
+            new_file_path: None,
             highlighted_spans: HighlighedSpans::default(),
             marks: Vec::new(),
+            breakpoints: Vec::new(),
+            auto_inserted_closers: Vec::new(),
             decorations: Vec::new(),
+            plugin_decorations: BTreeMap::new(),
             undo_tree: UndoTree::new(),
             diagnostics: Vec::new(),
             quickfix_list_items: Vec::new(),
             selection_set_history: History::new(),
+            indent: Indent::default(),
+            editor_config: EditorConfig::default(),
+            file_format: FileFormat::default(),
+            injections: OnceCell::new(),
+            readonly: false,
+            saved_content_hash: content_hash(text),
+            edit_count: 0,
+            dirty_cache: Cell::new(None),
+            undo_history_directory: None,
+            undo_tree_modified: false,
         }
     }
+
+    pub(crate) fn set_readonly(&mut self, readonly: bool) {
+        self.readonly = readonly
+    }
+
+    pub(crate) fn readonly(&self) -> bool {
+        self.readonly
+    }
+
+    /// Returns true if the buffer is a file whose content differs from the last loaded or saved content.
+    pub(crate) fn is_dirty(&self) -> bool {
+        if self.path.is_none() {
+            return false;
+        }
+        if let Some((edit_count, is_dirty)) = self.dirty_cache.get() {
+            if edit_count == self.edit_count {
+                return is_dirty;
+            }
+        }
+        let is_dirty = content_hash(&self.content()) != self.saved_content_hash;
+        self.dirty_cache.set(Some((self.edit_count, is_dirty)));
+        is_dirty
+    }
+
+    pub(crate) fn edit_count(&self) -> usize {
+        self.edit_count
+    }
+
+    /// Marks the current content as loaded or saved.
+    fn mark_saved(&mut self) {
+        self.saved_content_hash = content_hash(&self.content());
+        self.dirty_cache.set(Some((self.edit_count, false)))
+    }
+
     pub(crate) fn clear_quickfix_list_items(&mut self) {
         self.quickfix_list_items.clear()
     }
@@ -85,9 +178,14 @@ impl Buffer {
     }
     pub(crate) fn reload(&mut self) -> anyhow::Result<()> {
         if let Some(path) = self.path() {
-            let updated_content = path.read()?;
+            let (updated_content, mut file_format) = FileFormat::read(path.as_ref())?;
+            if let Some(line_ending) = self.editor_config.end_of_line() {
+                file_format.line_ending = line_ending
+            }
+            self.file_format = file_format;
 
             self.update_content(&updated_content, SelectionSet::default())?;
+            self.mark_saved();
         }
         Ok(())
     }
@@ -95,13 +193,20 @@ impl Buffer {
         self.rope.to_string()
     }
 
-    pub(crate) fn decorations(&self) -> &Vec<Decoration> {
-        &self.decorations
+    /// Includes the decorations set by plugins.
+    pub(crate) fn decorations(&self) -> impl Iterator<Item = &Decoration> {
+        self.decorations
+            .iter()
+            .chain(self.plugin_decorations.values().flatten())
     }
     pub(crate) fn set_decorations(&mut self, decorations: &[Decoration]) {
         decorations.clone_into(&mut self.decorations)
     }
 
+    pub(crate) fn set_plugin_decorations(&mut self, plugin: String, decorations: Vec<Decoration>) {
+        self.plugin_decorations.insert(plugin, decorations);
+    }
+
     pub(crate) fn save_marks(&mut self, new_ranges: Vec<CharIndexRange>) {
         let old_ranges = std::mem::take(&mut self.marks)
             .into_iter()
@@ -119,6 +224,10 @@ impl Buffer {
         self.path.clone()
     }
 
+    pub(crate) fn new_file_path(&self) -> Option<&Path> {
+        self.new_file_path.as_deref()
+    }
+
     #[cfg(test)]
     pub(crate) fn set_path(&mut self, path: CanonicalizedPath) {
         self.path = Some(path);
@@ -258,6 +367,16 @@ impl Buffer {
 
     pub(crate) fn update(&mut self, text: &str) {
         (self.rope, self.tree) = Self::get_rope_and_tree(self.treesitter_language.clone(), text);
+        self.edit_count += 1;
+        self.injections = OnceCell::new();
+        self.auto_inserted_closers.clear();
+    }
+
+    /// Unlike `update`, only the tree is rebuilt, so that appending to a large buffer is cheap.
+    pub(crate) fn append(&mut self, text: &str) -> anyhow::Result<()> {
+        self.rope.append(Rope::from_str(text));
+        self.edit_count += 1;
+        self.reparse_tree()
     }
 
     pub(crate) fn get_line_by_char_index(&self, char_index: CharIndex) -> anyhow::Result<Rope> {
@@ -379,9 +498,6 @@ impl Buffer {
         selection: &Selection,
         get_largest_end: bool,
     ) -> anyhow::Result<Option<Node<'a>>> {
-        let Some(tree) = self.tree.as_ref() else {
-            return Ok(None);
-        };
         let range = selection.range();
         let start = self.char_to_byte(range.start)?;
         let (start, end) = if get_largest_end {
@@ -389,6 +505,9 @@ impl Buffer {
         } else {
             (start, self.char_to_byte(range.end)?)
         };
+        let Some(tree) = self.tree_at(start..end) else {
+            return Ok(None);
+        };
         let node = tree
             .root_node()
             .descendant_for_byte_range(start, end)
@@ -417,6 +536,59 @@ impl Buffer {
         Ok(Some(node))
     }
 
+    /// The innermost syntax tree that contains `byte_range`.
+    ///
+    /// An injected tree is only used when `byte_range` is within its content, but not the whole
+    /// content, so that the nodes of the host tree remain reachable from the injected tree.
+    fn tree_at(&self, byte_range: Range<usize>) -> Option<&Tree> {
+        self.injections()
+            .iter()
+            .filter(|injection| {
+                injection.byte_range.start <= byte_range.start
+                    && byte_range.end <= injection.byte_range.end
+                    && injection.byte_range != byte_range
+            })
+            .min_by_key(|injection| injection.byte_range.len())
+            .map(|injection| &injection.tree)
+            .or(self.tree.as_ref())
+    }
+
+    /// The language of the innermost injection that contains `byte_range`,
+    /// otherwise the language of this buffer.
+    pub(crate) fn language_at(&self, byte_range: Range<usize>) -> Option<Language> {
+        self.injections()
+            .iter()
+            .filter(|injection| {
+                injection.byte_range.start <= byte_range.start
+                    && byte_range.end <= injection.byte_range.end
+            })
+            .min_by_key(|injection| injection.byte_range.len())
+            .map(|injection| injection.language.clone())
+            .or_else(|| self.language())
+    }
+
+    /// The root node of the injected tree whose content is exactly `byte_range`.
+    pub(crate) fn get_injected_root(&self, byte_range: Range<usize>) -> Option<Node> {
+        self.injections()
+            .iter()
+            .find(|injection| injection.byte_range == byte_range)
+            .map(|injection| injection.tree.root_node())
+    }
+
+    fn injections(&self) -> &[Injection] {
+        if let Some(injections) = self.injections.get() {
+            return injections;
+        }
+        let (Some(language), Some(tree)) = (self.language.as_ref(), self.tree.as_ref()) else {
+            return &[];
+        };
+        match injection::parse_injections(language, tree, &self.rope.to_string()) {
+            Some(injections) => self.injections.get_or_init(|| injections),
+            // The injection query is not loaded yet
+            None => &[],
+        }
+    }
+
     #[cfg(test)]
     pub(crate) fn get_next_token(&self, char_index: CharIndex, is_named: bool) -> Option<Node> {
         let byte = self.char_to_byte(char_index).ok()?;
@@ -439,6 +611,9 @@ impl Buffer {
         current_selection_set: SelectionSet,
         reparse_tree: bool,
     ) -> Result<SelectionSet, anyhow::Error> {
+        if self.readonly && !edit_transaction.edits().is_empty() {
+            return Err(anyhow::anyhow!("The buffer is readonly"));
+        }
         let before = self.rope.to_string();
         let new_selection_set = edit_transaction
             .non_empty_selections()
@@ -447,6 +622,7 @@ impl Buffer {
         let current_buffer_state = BufferState {
             selection_set: current_selection_set,
             marks: self.marks.clone(),
+            breakpoints: self.breakpoints.clone(),
         };
 
         edit_transaction
@@ -457,6 +633,7 @@ impl Buffer {
         let new_buffer_state = BufferState {
             selection_set: new_selection_set.clone(),
             marks: self.marks.clone(),
+            breakpoints: self.breakpoints.clone(),
         };
 
         self.add_undo_patch(current_buffer_state, new_buffer_state.clone(), &before);
@@ -485,6 +662,7 @@ impl Buffer {
         self.rope.try_remove(edit.range.start.0..edit.end().0)?;
         self.rope
             .try_insert(edit.range.start.0, edit.new.to_string().as_str())?;
+        self.edit_count += 1;
 
         // Update all the positional spans (by using the char index ranges computed before the content is updated
         self.quickfix_list_items = quickfix_list_items_with_char_index_range
@@ -502,6 +680,14 @@ impl Buffer {
             .into_iter()
             .filter_map(|mark| mark.apply_edit(edit))
             .collect();
+        self.breakpoints = std::mem::take(&mut self.breakpoints)
+            .into_iter()
+            .filter_map(|breakpoint| breakpoint.apply_edit(edit))
+            .collect();
+        self.auto_inserted_closers = std::mem::take(&mut self.auto_inserted_closers)
+            .into_iter()
+            .filter_map(|closer| closer.apply_edit(edit))
+            .collect();
         self.diagnostics = std::mem::take(&mut self.diagnostics)
             .into_iter()
             .filter_map(|diagnostic| {
@@ -547,6 +733,73 @@ impl Buffer {
         self.undo_tree
             .edit(&mut before.to_owned(), old_new)
             .unwrap();
+        self.undo_tree_modified = true;
+    }
+
+    /// Undoes or redoes to the last edit of the current branch that was made
+    /// at least `minutes` ago.
+    pub(crate) fn undo_to_minutes_ago(
+        &mut self,
+        minutes: u64,
+    ) -> anyhow::Result<Option<SelectionSet>> {
+        let time = SystemTime::now()
+            .checked_sub(Duration::from_secs(minutes * 60))
+            .ok_or_else(|| anyhow::anyhow!("{minutes} minutes ago is out of range"))?;
+        let mut content = self.rope.to_string();
+        let state = self.undo_tree.go_to_time(&mut content, time)?;
+        self.undo_tree_modified = true;
+        self.update(&content);
+
+        Ok(state.map(
+            |BufferState {
+                 selection_set,
+                 marks,
+                 breakpoints,
+             }| {
+                self.marks = marks;
+                self.breakpoints = breakpoints;
+                selection_set
+            },
+        ))
+    }
+
+    /// The undo history is only rewritten if it changed since it was last persisted.
+    fn persist_undo_tree(&mut self, path: &CanonicalizedPath) -> anyhow::Result<()> {
+        let Some(directory) = self.undo_history_directory.as_ref() else {
+            return Ok(());
+        };
+        if !self.undo_tree_modified {
+            return Ok(());
+        }
+        let persisted = PersistedUndoTree {
+            content_hash: content_hash(&self.content()),
+            undo_tree: self.undo_tree.clone(),
+        };
+        std::fs::create_dir_all(directory)?;
+        std::fs::write(
+            undo_history_path(directory, path),
+            serde_json::to_string(&persisted)?,
+        )?;
+        prune_undo_histories(directory)?;
+        self.undo_tree_modified = false;
+        Ok(())
+    }
+
+    /// The persisted undo history is ignored if the file was modified outside of Ki.
+    fn restore_undo_tree(&mut self, path: &CanonicalizedPath) -> anyhow::Result<()> {
+        let Some(directory) = self.undo_history_directory.as_ref() else {
+            return Ok(());
+        };
+        let undo_history_path = undo_history_path(directory, path);
+        if !undo_history_path.exists() {
+            return Ok(());
+        }
+        let persisted: PersistedUndoTree =
+            serde_json::from_str(&std::fs::read_to_string(undo_history_path)?)?;
+        if persisted.content_hash == content_hash(&self.content()) {
+            self.undo_tree = persisted.undo_tree;
+        }
+        Ok(())
     }
 
     pub(crate) fn display_history(&self) -> String {
@@ -559,14 +812,17 @@ impl Buffer {
     ) -> anyhow::Result<Option<SelectionSet>> {
         let mut content = self.rope.to_string();
         let state = self.undo_tree.apply_movement(&mut content, movement)?;
+        self.undo_tree_modified = true;
         self.update(&content);
 
         if let Some(BufferState {
             selection_set,
             marks,
+            breakpoints,
         }) = state
         {
             self.marks = marks;
+            self.breakpoints = breakpoints;
 
             Ok(Some(selection_set))
         } else {
@@ -588,11 +844,47 @@ impl Buffer {
         }
     }
 
+    /// The undo history of the file is not persisted,
+    /// see `Buffer::from_path_with_undo_history_directory`.
     pub(crate) fn from_path(
         path: &CanonicalizedPath,
         enable_tree_sitter: bool,
     ) -> anyhow::Result<Buffer> {
-        let content = path.read()?;
+        Self::from_path_with_undo_history_directory(path, enable_tree_sitter, None)
+    }
+
+    /// Only loads what searching needs, because a search loads every file of the project:
+    /// neither the EditorConfig nor the undo history of the file is looked up.
+    pub(crate) fn for_search(
+        path: &CanonicalizedPath,
+        enable_tree_sitter: bool,
+    ) -> anyhow::Result<Buffer> {
+        let (content, file_format) = FileFormat::read(path.as_ref())?;
+        let language = if enable_tree_sitter {
+            language::from_path(path)
+        } else {
+            None
+        };
+        let mut buffer = Buffer::new(
+            language
+                .as_ref()
+                .and_then(|language| language.tree_sitter_language()),
+            &content,
+        );
+        buffer.file_format = file_format;
+        buffer.path = Some(path.clone());
+        buffer.language = language;
+        Ok(buffer)
+    }
+
+    /// If `undo_history_directory` is given, the undo history of the file
+    /// is restored from and persisted to it.
+    pub(crate) fn from_path_with_undo_history_directory(
+        path: &CanonicalizedPath,
+        enable_tree_sitter: bool,
+        undo_history_directory: Option<PathBuf>,
+    ) -> anyhow::Result<Buffer> {
+        let (content, mut file_format) = FileFormat::read(path.as_ref())?;
         let language = if enable_tree_sitter {
             language::from_path(path)
         } else {
@@ -606,17 +898,58 @@ impl Buffer {
             &content,
         );
 
+        let editor_config = EditorConfig::for_path(path.as_ref());
+        // Precedence: .editorconfig, then the file content, then the language default
+        buffer.indent = editor_config.indent(
+            Indent::detect(&content)
+                .into_iter()
+                .chain(language::from_path(path).map(|language| language.indent())),
+        );
+        if let Some(line_ending) = editor_config.end_of_line() {
+            file_format.line_ending = line_ending
+        }
+        buffer.file_format = file_format;
+        buffer.editor_config = editor_config;
         buffer.path = Some(path.clone());
         buffer.language = language;
+        buffer.undo_history_directory = undo_history_directory;
+        if let Err(error) = buffer.restore_undo_tree(path) {
+            log::error!("Unable to restore undo history of {:?}: {:?}", path, error)
+        }
 
         Ok(buffer)
     }
 
+    /// An empty buffer for a file that does not exist yet,
+    /// which is only created at `path` when the buffer is saved.
+    pub(crate) fn for_new_file(path: PathBuf) -> Buffer {
+        let language = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(language::from_extension);
+        let mut buffer = Buffer::new(
+            language
+                .as_ref()
+                .and_then(|language| language.tree_sitter_language()),
+            "",
+        );
+        let editor_config = EditorConfig::for_path(&path);
+        buffer.indent = editor_config.indent(language.as_ref().map(|language| language.indent()));
+        if let Some(line_ending) = editor_config.end_of_line() {
+            buffer.file_format.line_ending = line_ending
+        }
+        buffer.editor_config = editor_config;
+        buffer.language = language;
+        buffer.new_file_path = Some(path);
+        buffer
+    }
+
     pub(crate) fn reparse_tree(&mut self) -> anyhow::Result<()> {
         let mut parser = tree_sitter::Parser::new();
         if let Some(tree) = self.tree.as_ref() {
             parser.set_language(&tree.language())?;
             self.tree = parser.parse(self.rope.to_string(), None);
+            self.injections = OnceCell::new();
         }
         Ok(())
     }
@@ -641,8 +974,21 @@ impl Buffer {
     }
 
     pub(crate) fn save_without_formatting(&mut self) -> anyhow::Result<Option<CanonicalizedPath>> {
+        if self.readonly {
+            return Err(anyhow::anyhow!("The buffer is readonly"));
+        }
+        if let (None, Some(new_file_path)) = (&self.path, &self.new_file_path) {
+            // The file is created first, because only existing paths can be canonicalized
+            std::fs::write(new_file_path, "")?;
+            self.path = Some(new_file_path.as_path().try_into()?);
+            self.new_file_path = None;
+        }
         if let Some(path) = &self.path.clone() {
-            path.write(&self.content())?;
+            self.file_format.write(path.as_ref(), &self.content())?;
+            self.mark_saved();
+            if let Err(error) = self.persist_undo_tree(path) {
+                log::error!("Unable to persist undo history of {:?}: {:?}", path, error)
+            }
 
             Ok(Some(path.clone()))
         } else {
@@ -655,8 +1001,12 @@ impl Buffer {
         &mut self,
         current_selection_set: SelectionSet,
     ) -> anyhow::Result<Option<CanonicalizedPath>> {
-        if let Some(formatted_content) = self.get_formatted_content() {
-            self.update_content(&formatted_content, current_selection_set)?;
+        let content = self
+            .get_formatted_content()
+            .unwrap_or_else(|| self.content());
+        let content = self.editor_config.apply(&content);
+        if content != self.content() {
+            self.update_content(&content, current_selection_set)?;
         }
 
         self.save_without_formatting()
@@ -686,14 +1036,30 @@ impl Buffer {
         spans
     }
 
+    pub(crate) fn file_format(&self) -> FileFormat {
+        self.file_format
+    }
+
+    /// The new format takes effect when the buffer is saved.
+    pub(crate) fn set_file_format(&mut self, file_format: FileFormat) {
+        self.file_format = file_format
+    }
+
+    /// The unit of indentation used when indenting lines of this buffer.
+    pub(crate) fn indent(&self) -> Indent {
+        self.indent
+    }
+
     pub(crate) fn language(&self) -> Option<Language> {
         self.language.clone()
     }
 
     #[cfg(test)]
     pub(crate) fn set_language(&mut self, language: Language) -> anyhow::Result<()> {
+        self.treesitter_language = language.tree_sitter_language();
         self.language = Some(language);
-        self.reparse_tree()
+        self.update(&self.content());
+        Ok(())
     }
 
     pub(crate) fn treesitter_language(&self) -> Option<tree_sitter::Language> {
@@ -705,6 +1071,69 @@ impl Buffer {
         self.rope.get_char(char_index)
     }
 
+    pub(crate) fn get_char(&self, char_index: CharIndex) -> Option<char> {
+        self.rope.get_char(char_index.0)
+    }
+
+    /// Returns the ranges of the extended grapheme clusters of the line of `char_index`.
+    pub(crate) fn line_graphemes(&self, char_index: CharIndex) -> Vec<CharIndexRange> {
+        let Some((line_start, line)) = self
+            .char_to_line(char_index)
+            .ok()
+            .and_then(|line| Some((self.line_to_char(line).ok()?, self.rope.get_line(line)?)))
+        else {
+            return Vec::new();
+        };
+        // The boundaries are found chunk by chunk, so that the line is not copied into a string
+        let mut chunks = line.chunks();
+        let mut chunk = chunks.next().unwrap_or_default();
+        let mut chunk_start = 0;
+        let mut cursor = GraphemeCursor::new(0, line.len_bytes(), true);
+        let mut start = line_start;
+        let mut ranges = Vec::new();
+        loop {
+            match cursor.next_boundary(chunk, chunk_start) {
+                Ok(Some(byte_index)) => {
+                    let end = line_start + line.byte_to_char(byte_index);
+                    ranges.push((start..end).into());
+                    start = end;
+                }
+                Ok(None) => return ranges,
+                Err(GraphemeIncomplete::NextChunk) => {
+                    chunk_start += chunk.len();
+                    chunk = chunks.next().unwrap_or_default();
+                }
+                Err(GraphemeIncomplete::PreContext(byte_index)) => {
+                    let (context, context_start, _, _) =
+                        line.chunk_at_byte(byte_index.saturating_sub(1));
+                    cursor.provide_context(context, context_start);
+                }
+                Err(_) => return ranges,
+            }
+        }
+    }
+
+    /// Returns the range of the extended grapheme cluster that contains `char_index`.
+    pub(crate) fn grapheme_range(&self, char_index: CharIndex) -> CharIndexRange {
+        self.line_graphemes(char_index)
+            .into_iter()
+            .find(|range| range.start <= char_index && char_index < range.end)
+            .unwrap_or_else(|| (char_index..char_index + 1).into())
+    }
+
+    pub(crate) fn next_grapheme_boundary(&self, char_index: CharIndex) -> CharIndex {
+        self.grapheme_range(char_index)
+            .end
+            .min(CharIndex(self.len_chars()))
+    }
+
+    pub(crate) fn previous_grapheme_boundary(&self, char_index: CharIndex) -> CharIndex {
+        if char_index.0 == 0 {
+            return char_index;
+        }
+        self.grapheme_range(char_index - 1).start
+    }
+
     pub(crate) fn tree(&self) -> Option<&Tree> {
         self.tree.as_ref()
     }
@@ -728,6 +1157,62 @@ impl Buffer {
         self.marks.clone()
     }
 
+    pub(crate) fn is_auto_inserted_closer(&self, char_index: CharIndex) -> bool {
+        self.auto_inserted_closers
+            .iter()
+            .any(|closer| closer.start == char_index && closer.end == char_index + 1)
+    }
+
+    pub(crate) fn add_auto_inserted_closer(&mut self, char_index: CharIndex) {
+        self.auto_inserted_closers
+            .push((char_index..char_index + 1).into())
+    }
+
+    /// A closer is only moved over once, because the user might type another closer afterwards.
+    pub(crate) fn remove_auto_inserted_closer(&mut self, char_index: CharIndex) {
+        self.auto_inserted_closers
+            .retain(|closer| closer.start != char_index)
+    }
+
+    /// Returns the sorted 0-based line indices of the breakpoints.
+    pub(crate) fn breakpoint_lines(&self) -> Vec<usize> {
+        self.breakpoints
+            .iter()
+            .filter_map(|breakpoint| self.char_to_line(breakpoint.start).ok())
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    /// Replaces the breakpoints, lines that are out of bound are ignored.
+    pub(crate) fn set_breakpoint_lines(&mut self, lines: &[usize]) {
+        self.breakpoints = lines
+            .iter()
+            .filter_map(|line| self.line_char_index_range(*line).ok())
+            .collect()
+    }
+
+    /// Adds a breakpoint at `line`, or removes it if it already exists.
+    pub(crate) fn toggle_breakpoint(&mut self, line: usize) -> anyhow::Result<()> {
+        let count = self.breakpoints.len();
+        self.breakpoints
+            .retain(|breakpoint| self.rope.try_char_to_line(breakpoint.start.0).ok() != Some(line));
+        if self.breakpoints.len() == count {
+            let range = self.line_char_index_range(line)?;
+            self.breakpoints.push(range)
+        }
+        Ok(())
+    }
+
+    /// The range includes the newline of the line, if any.
+    fn line_char_index_range(&self, line: usize) -> anyhow::Result<CharIndexRange> {
+        let start = self.line_to_char(line)?;
+        let end = self
+            .line_to_char(line + 1)
+            .unwrap_or(CharIndex(self.len_chars()));
+        Ok((start..end).into())
+    }
+
     pub(crate) fn byte_to_position(&self, byte_index: usize) -> anyhow::Result<Position> {
         let char_index = self.byte_to_char(byte_index)?;
         self.char_to_position(char_index)
@@ -768,7 +1253,7 @@ impl Buffer {
     }
 
     /// Get an `EditTransaction` by getting the line diffs between the content of this buffer and the given `new` string
-    fn get_edit_transaction(&self, new: &str) -> anyhow::Result<EditTransaction> {
+    pub(crate) fn get_edit_transaction(&self, new: &str) -> anyhow::Result<EditTransaction> {
         let old = self.rope.to_string();
         let new = new.to_string();
         let edits = {
@@ -973,7 +1458,7 @@ impl Buffer {
 mod test_buffer {
     use itertools::Itertools;
 
-    use crate::selection::SelectionSet;
+    use crate::{file_format::LineEnding, selection::SelectionSet};
 
     use super::Buffer;
 
@@ -1107,6 +1592,96 @@ fn f(
         }
     }
 
+    #[test]
+    fn undo_history_persists_across_restarts() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file_path = dir.path().join("notes.txt");
+        std::fs::write(&file_path, "a")?;
+        let path = shared::canonicalized_path::CanonicalizedPath::try_from(file_path)?;
+        let undo_history_directory = dir.path().join("undo_history");
+        let from_path = |path: &shared::canonicalized_path::CanonicalizedPath| {
+            Buffer::from_path_with_undo_history_directory(
+                path,
+                false,
+                Some(undo_history_directory.clone()),
+            )
+        };
+
+        let mut buffer = from_path(&path)?;
+        buffer.update_content("ab", SelectionSet::default())?;
+        buffer.update_content("abc", SelectionSet::default())?;
+        buffer.save_without_formatting()?;
+
+        // The undo history is restored when the file is unchanged
+        let mut buffer = from_path(&path)?;
+        buffer.undo_tree_apply_movement(crate::components::editor::Movement::Left)?;
+        assert_eq!(buffer.content(), "ab");
+
+        // Every edit was made less than a day ago
+        buffer.undo_to_minutes_ago(60 * 24)?;
+        assert_eq!(buffer.content(), "a");
+        buffer.undo_to_minutes_ago(0)?;
+        assert_eq!(buffer.content(), "abc");
+
+        // The undo history is discarded when the file was modified elsewhere
+        path.write("xyz")?;
+        let mut buffer = from_path(&path)?;
+        buffer.undo_tree_apply_movement(crate::components::editor::Movement::Left)?;
+        assert_eq!(buffer.content(), "xyz");
+        Ok(())
+    }
+
+    #[test]
+    fn is_dirty_only_if_content_differs_from_saved_content() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file_path = dir.path().join("notes.txt");
+        std::fs::write(&file_path, "a")?;
+        let path = shared::canonicalized_path::CanonicalizedPath::try_from(file_path)?;
+        let mut buffer = Buffer::from_path(&path, false)?;
+        assert!(!buffer.is_dirty());
+
+        buffer.update_content("ab", SelectionSet::default())?;
+        assert!(buffer.edit_count() > 0);
+        assert!(buffer.is_dirty());
+
+        // Undoing the edit by hand makes the buffer clean again
+        buffer.update_content("a", SelectionSet::default())?;
+        assert!(!buffer.is_dirty());
+
+        buffer.update_content("abc", SelectionSet::default())?;
+        buffer.save_without_formatting()?;
+        assert!(!buffer.is_dirty());
+        Ok(())
+    }
+
+    #[test]
+    fn reload_uses_the_format_of_the_reloaded_file() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file_path = dir.path().join("notes.txt");
+        std::fs::write(&file_path, "a\n")?;
+        let path = shared::canonicalized_path::CanonicalizedPath::try_from(file_path.clone())?;
+        let mut buffer = Buffer::from_path(&path, false)?;
+        std::fs::write(&file_path, "a\r\nb\r\n")?;
+        buffer.reload()?;
+        assert_eq!(buffer.content(), "a\nb\n");
+        assert_eq!(buffer.file_format().line_ending, LineEnding::Crlf);
+        Ok(())
+    }
+
+    #[test]
+    fn undo_histories_beyond_the_limit_are_removed() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        for index in 0..super::MAX_UNDO_HISTORIES + 2 {
+            std::fs::write(dir.path().join(format!("{index}.json")), "")?;
+        }
+        super::prune_undo_histories(dir.path())?;
+        assert_eq!(
+            std::fs::read_dir(dir.path())?.count(),
+            super::MAX_UNDO_HISTORIES
+        );
+        Ok(())
+    }
+
     mod auto_format {
         use std::fs::File;
 
@@ -1222,6 +1797,21 @@ fn f(
         }
     }
 
+    #[test]
+    fn line_graphemes_span_rope_chunks() {
+        // The line is longer than a rope chunk, so some grapheme clusters span two chunks
+        let line = "e\u{301}".repeat(2000);
+        let buffer = Buffer::new(None, &format!("{}\nx", line));
+        let graphemes = buffer.line_graphemes(crate::selection::CharIndex(0));
+        // The last grapheme cluster is the newline character
+        assert_eq!(graphemes.len(), 2001);
+        assert!(graphemes
+            .iter()
+            .take(2000)
+            .enumerate()
+            .all(|(index, range)| range.start.0 == index * 2 && range.end.0 == index * 2 + 2));
+    }
+
     mod patch_edit {
         use crate::edit::EditTransaction;
 
@@ -1338,17 +1928,113 @@ fn main() {
     }
 }
 
-#[derive(Clone)]
+#[derive(Serialize, Deserialize)]
+struct PersistedUndoTree {
+    content_hash: u64,
+    undo_tree: UndoTree<Patch>,
+}
+
+/// The undo history of every file is stored in the cache directory,
+/// under the hash of the file path.
+/// Undo histories of the least recently saved files are removed beyond this count
+const MAX_UNDO_HISTORIES: usize = 500;
+
+pub(crate) fn default_undo_history_directory() -> PathBuf {
+    grammar::cache_dir().join("undo_history")
+}
+
+fn undo_history_path(directory: &Path, path: &CanonicalizedPath) -> PathBuf {
+    directory.join(format!(
+        "{:016x}.json",
+        content_hash(&path.display_absolute())
+    ))
+}
+
+fn prune_undo_histories(directory: &Path) -> anyhow::Result<()> {
+    let entries = std::fs::read_dir(directory)?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            Some((entry.metadata().ok()?.modified().ok()?, entry.path()))
+        })
+        .collect_vec();
+    for (_, path) in entries.into_iter().sorted().rev().skip(MAX_UNDO_HISTORIES) {
+        std::fs::remove_file(path)?
+    }
+    Ok(())
+}
+
+/// FNV-1a, because the hash must be stable across builds,
+/// which is not guaranteed by `std::hash::DefaultHasher`.
+pub(crate) fn content_hash(content: &str) -> u64 {
+    content.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub(crate) struct Patch {
     /// Why don't we store this is diffy::Patch? Because it requires a lifetime parameter
     pub(crate) patch: String,
     pub(crate) state: BufferState,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "PersistedBufferState", into = "PersistedBufferState")]
 pub(crate) struct BufferState {
     pub(crate) selection_set: SelectionSet,
     pub(crate) marks: Vec<CharIndexRange>,
+    pub(crate) breakpoints: Vec<CharIndexRange>,
+}
+
+/// Only the ranges are persisted, because the other details of selections,
+/// such as the selection mode, are irrelevant after a restart.
+#[derive(Serialize, Deserialize)]
+struct PersistedBufferState {
+    selections: Vec<Range<usize>>,
+    marks: Vec<Range<usize>>,
+    /// Absent in undo histories persisted before breakpoints were tracked
+    #[serde(default)]
+    breakpoints: Vec<Range<usize>>,
+}
+
+impl From<BufferState> for PersistedBufferState {
+    fn from(value: BufferState) -> Self {
+        let to_range = |range: CharIndexRange| range.start.0..range.end.0;
+        Self {
+            selections: value
+                .selection_set
+                .map(|selection| to_range(selection.extended_range()))
+                .into(),
+            marks: value.marks.into_iter().map(to_range).collect(),
+            breakpoints: value.breakpoints.into_iter().map(to_range).collect(),
+        }
+    }
+}
+
+impl From<PersistedBufferState> for BufferState {
+    fn from(value: PersistedBufferState) -> Self {
+        let to_range = |range: Range<usize>| {
+            CharIndexRange::from(CharIndex(range.start)..CharIndex(range.end))
+        };
+        let selection_set = NonEmpty::from_vec(
+            value
+                .selections
+                .into_iter()
+                .map(|range| Selection::new(to_range(range)))
+                .collect(),
+        )
+        .map(|selections| {
+            let mut selection_set = SelectionSet::new(selections);
+            selection_set.mode = SelectionMode::Custom;
+            selection_set
+        })
+        .unwrap_or_default();
+        Self {
+            selection_set,
+            marks: value.marks.into_iter().map(to_range).collect(),
+            breakpoints: value.breakpoints.into_iter().map(to_range).collect(),
+        }
+    }
 }
 
 impl std::fmt::Display for Patch {
@@ -1,11 +1,22 @@
+use std::io::Read;
+
 use clap::{Args, Parser, Subcommand};
+use itertools::Itertools;
 use shared::canonicalized_path::CanonicalizedPath;
 
+use crate::{
+    app::{EntryConfig, EntryPath},
+    position::Position,
+    server::ServerCommand,
+};
+
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+    #[command(flatten)]
+    edit: EditArgs,
 }
 
 #[derive(Subcommand)]
@@ -18,22 +29,47 @@ enum Commands {
         #[command(subcommand)]
         command: HighlightQuery,
     },
-    /// Edit the file of the given path, creates a new file at the path
-    /// if not exist
+    /// Edit the files of the given paths,
+    /// a file that does not exist is created when it is saved.
+    /// The files are opened in the instance running in the directory of the first file, if any.
     Edit(EditArgs),
     /// Prints the log file path
     Log,
     /// Run Ki in the given path, treating the path as the working directory
     In(InArgs),
+    /// Apply a key sequence to each of the given files without opening a terminal,
+    /// then write the results
+    Batch(BatchArgs),
 }
 #[derive(Args)]
 struct EditArgs {
-    path: String,
+    /// The first path is focused.
+    /// Use `path:line:column` or `+line path` to place the cursor,
+    /// and `-` to edit the content read from stdin
+    paths: Vec<String>,
+    /// Compare two files side by side
+    #[arg(long, num_args = 2, value_names = ["OLD", "NEW"])]
+    diff: Option<Vec<String>>,
+    /// Prevent the opened files from being edited or saved
+    #[arg(long)]
+    readonly: bool,
 }
 #[derive(Args)]
 struct InArgs {
     path: String,
 }
+#[derive(Args)]
+struct BatchArgs {
+    /// Space-separated keys, for example "s d" or "i h e l l o esc"
+    #[arg(long)]
+    keys: String,
+    /// Directories are expanded to the files in them that are not ignored by Git
+    #[arg(required = true)]
+    paths: Vec<String>,
+    /// Print the diff of each file instead of writing it
+    #[arg(long)]
+    dry_run: bool,
+}
 #[derive(Subcommand)]
 enum Grammar {
     Build,
@@ -69,24 +105,7 @@ pub(crate) fn cli() -> anyhow::Result<()> {
                 };
                 Ok(())
             }
-            Commands::Edit(args) => {
-                let path = std::path::PathBuf::from(args.path.clone());
-                if !path.exists() {
-                    std::fs::write(path, "")?;
-                }
-
-                let path: Option<CanonicalizedPath> = Some(args.path.try_into()?);
-                let working_directory = match path.clone() {
-                    Some(value) if value.is_dir() => Some(value),
-                    Some(value) => value.parent()?,
-                    _ => Default::default(),
-                };
-
-                crate::run(crate::RunConfig {
-                    entry_path: path,
-                    working_directory,
-                })
-            }
+            Commands::Edit(args) => edit(args),
             Commands::Log => {
                 println!(
                     "{}",
@@ -98,8 +117,208 @@ pub(crate) fn cli() -> anyhow::Result<()> {
                 working_directory: Some(args.path.try_into()?),
                 ..Default::default()
             }),
+            Commands::Batch(args) => crate::batch::run(crate::batch::BatchConfig {
+                keys: args.keys,
+                paths: args
+                    .paths
+                    .into_iter()
+                    .map(CanonicalizedPath::try_from)
+                    .collect::<anyhow::Result<_>>()?,
+                working_directory: ".".try_into()?,
+                dry_run: args.dry_run,
+            }),
         }
     } else {
-        crate::run(Default::default())
+        edit(cli.edit)
+    }
+}
+
+fn edit(args: EditArgs) -> anyhow::Result<()> {
+    let mut stdin = None;
+    let mut paths = Vec::new();
+    for entry in parse_entries(&args.paths)? {
+        match entry {
+            Entry::Stdin => {
+                let mut content = String::new();
+                std::io::stdin().read_to_string(&mut content)?;
+                stdin = Some(content)
+            }
+            Entry::Path { path, position } => paths.push(EntryPath {
+                path: std::env::current_dir()?.join(path),
+                position,
+            }),
+        }
+    }
+    let diff = args
+        .diff
+        .map(
+            |paths| -> anyhow::Result<(CanonicalizedPath, CanonicalizedPath)> {
+                let Some((old, new)) = paths.into_iter().collect_tuple() else {
+                    return Err(anyhow::anyhow!("--diff expects exactly two paths"));
+                };
+                Ok((old.try_into()?, new.try_into()?))
+            },
+        )
+        .transpose()?;
+
+    let working_directory = match paths
+        .first()
+        .map(|entry| entry.path.as_path())
+        .or(diff.as_ref().map(|(_, new)| new.to_path_buf().as_path()))
+    {
+        Some(value) if value.is_dir() => Some(value.try_into()?),
+        Some(value) => value
+            .parent()
+            .map(CanonicalizedPath::try_from)
+            .transpose()?,
+        _ => Default::default(),
+    };
+
+    // Open the files in the instance that is already running in this project, if any.
+    // Files that do not exist yet are not forwarded, because the running instance only opens
+    // existing files.
+    let forwardable = stdin.is_none()
+        && diff.is_none()
+        && !args.readonly
+        && !paths.is_empty()
+        && paths.iter().all(|entry| entry.path.exists());
+    if let Some(working_directory) = working_directory.as_ref().filter(|_| forwardable) {
+        let mut forwarded = false;
+        // In reverse order, so that the first path is focused
+        for EntryPath { path, position } in paths.iter().rev() {
+            let command = ServerCommand::Open {
+                path: path.clone(),
+                line: position.map(|position| position.line),
+                column: position.map(|position| position.column),
+            };
+            forwarded = crate::server::forward(working_directory, &command)?;
+            if !forwarded {
+                break;
+            }
+        }
+        if forwarded {
+            return Ok(());
+        }
+    }
+
+    crate::run(crate::RunConfig {
+        entry: EntryConfig {
+            paths,
+            stdin,
+            diff,
+            readonly: args.readonly,
+        },
+        working_directory,
+    })
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Entry {
+    Stdin,
+    Path {
+        path: String,
+        position: Option<Position>,
+    },
+}
+
+/// Line and column numbers in `args` are 1-based.
+fn parse_entries(args: &[String]) -> anyhow::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut line = None;
+    for arg in args {
+        if let Some(number) = arg
+            .strip_prefix('+')
+            .and_then(|number| number.parse::<usize>().ok())
+        {
+            line = Some(number.saturating_sub(1));
+            continue;
+        }
+        if arg == "-" {
+            entries.push(Entry::Stdin);
+            continue;
+        }
+        let (path, position) = split_position(arg);
+        let line_position = line.take().map(|line| Position::new(line, 0));
+        entries.push(Entry::Path {
+            path: path.to_string(),
+            position: position.or(line_position),
+        })
+    }
+    if let Some(line) = line {
+        return Err(anyhow::anyhow!("Expected a path after +{}", line + 1));
+    }
+    Ok(entries)
+}
+
+/// Splits `path:line:column` or `path:line` into the path and the 0-based position,
+/// unless `arg` is an existing path.
+fn split_position(arg: &str) -> (&str, Option<Position>) {
+    if std::path::Path::new(arg).exists() {
+        return (arg, None);
+    }
+    let parse = |number: &str| {
+        number
+            .parse::<usize>()
+            .ok()
+            .map(|number| number.saturating_sub(1))
+    };
+    let Some((rest, last)) = arg.rsplit_once(':') else {
+        return (arg, None);
+    };
+    let Some(last) = parse(last) else {
+        return (arg, None);
+    };
+    match rest
+        .rsplit_once(':')
+        .and_then(|(path, line)| Some((path, parse(line)?)))
+    {
+        Some((path, line)) => (path, Some(Position::new(line, last))),
+        None => (rest, Some(Position::new(last, 0))),
+    }
+}
+
+#[cfg(test)]
+mod test_cli {
+    use super::{parse_entries, Entry};
+    use crate::position::Position;
+
+    fn parse(args: &[&str]) -> anyhow::Result<Vec<Entry>> {
+        parse_entries(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+    }
+
+    fn path(path: &str, position: Option<(usize, usize)>) -> Entry {
+        Entry::Path {
+            path: path.to_string(),
+            position: position.map(|(line, column)| Position::new(line, column)),
+        }
+    }
+
+    #[test]
+    fn parse_line_and_column() -> anyhow::Result<()> {
+        assert_eq!(
+            parse(&["a.rs:3:5", "b.rs:7", "c.rs", "+10", "d.rs", "-"])?,
+            vec![
+                path("a.rs", Some((2, 4))),
+                path("b.rs", Some((6, 0))),
+                path("c.rs", None),
+                path("d.rs", Some((9, 0))),
+                Entry::Stdin
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn position_of_path_takes_precedence_over_plus_line() -> anyhow::Result<()> {
+        assert_eq!(
+            parse(&["+10", "a.rs:3:5", "b.rs"])?,
+            vec![path("a.rs", Some((2, 4))), path("b.rs", None)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn plus_line_without_path() {
+        assert!(parse(&["a.rs", "+10"]).is_err())
     }
 }
@@ -0,0 +1,171 @@
+/// Comment or uncomment `content`, which consists of whole lines.
+///
+/// Line comments are preferred, block comments are used when the language has no line comment.
+/// Returns `None` if the language has no comment syntax.
+pub(crate) fn toggle_comment(
+    content: &str,
+    line_comment_token: Option<&str>,
+    block_comment_tokens: Option<(&str, &str)>,
+) -> Option<String> {
+    if let Some(token) = line_comment_token {
+        if is_line_commented(content, token) {
+            return Some(uncomment_lines(content, token));
+        }
+    }
+    if let Some((open, close)) = block_comment_tokens {
+        if let Some(uncommented) = uncomment_block(content, open, close) {
+            return Some(uncommented);
+        }
+    }
+    match (line_comment_token, block_comment_tokens) {
+        (Some(token), _) => Some(comment_lines(content, token)),
+        (None, Some((open, close))) => Some(comment_block(content, open, close)),
+        (None, None) => None,
+    }
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+fn is_line_commented(content: &str, token: &str) -> bool {
+    let mut lines = content.lines().filter(|line| !is_blank(line)).peekable();
+    lines.peek().is_some()
+        && lines.all(|line| strip_token(split_indentation(line).1, token).is_some())
+}
+
+/// The token is only a comment token when it is followed by whitespace or the end of the line,
+/// so that `//` is not stripped from a `/// doc comment`.
+fn strip_token<'a>(rest: &'a str, token: &str) -> Option<&'a str> {
+    rest.strip_prefix(token)
+        .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
+
+/// Split `line` into its indentation and the rest.
+fn split_indentation(line: &str) -> (&str, &str) {
+    let rest = line.trim_start_matches([' ', '\t']);
+    (&line[..line.len() - rest.len()], rest)
+}
+
+/// The token is inserted at the smallest indentation among the lines,
+/// so that the commented lines stay aligned. Blank lines are left untouched.
+fn comment_lines(content: &str, token: &str) -> String {
+    let Some(indentation_len) = content
+        .lines()
+        .filter(|line| !is_blank(line))
+        .map(|line| split_indentation(line).0.len())
+        .min()
+    else {
+        return content.to_string();
+    };
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            if is_blank(line) {
+                line.to_string()
+            } else {
+                let (indentation, rest) = line.split_at(indentation_len);
+                format!("{}{} {}", indentation, token, rest)
+            }
+        })
+        .collect()
+}
+
+fn uncomment_lines(content: &str, token: &str) -> String {
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            let (indentation, rest) = split_indentation(line);
+            match strip_token(rest, token) {
+                Some(rest) => format!("{}{}", indentation, rest.strip_prefix(' ').unwrap_or(rest)),
+                None => line.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Split `content` into its leading whitespace, body and trailing whitespace.
+fn split_body(content: &str) -> (&str, &str, &str) {
+    let start = content.len() - content.trim_start().len();
+    let end = content.trim_end().len().max(start);
+    (&content[..start], &content[start..end], &content[end..])
+}
+
+fn comment_block(content: &str, open: &str, close: &str) -> String {
+    let (leading, body, trailing) = split_body(content);
+    if body.is_empty() {
+        return content.to_string();
+    }
+    format!("{}{} {} {}{}", leading, open, body, close, trailing)
+}
+
+fn uncomment_block(content: &str, open: &str, close: &str) -> Option<String> {
+    let (leading, body, trailing) = split_body(content);
+    if body.len() < open.len() + close.len() {
+        return None;
+    }
+    let inner = body.strip_prefix(open)?.strip_suffix(close)?;
+    let inner = inner.strip_prefix(' ').unwrap_or(inner);
+    let inner = inner.strip_suffix(' ').unwrap_or(inner);
+    Some(format!("{}{}{}", leading, inner, trailing))
+}
+
+#[cfg(test)]
+mod test_comment {
+    use super::toggle_comment;
+
+    #[test]
+    fn line_comment_preserves_indentation() {
+        let content = "  fn f() {\n\n    a();\n  }\n";
+        let commented = toggle_comment(content, Some("//"), Some(("/*", "*/"))).unwrap();
+        assert_eq!(commented, "  // fn f() {\n\n  //   a();\n  // }\n");
+        assert_eq!(
+            toggle_comment(&commented, Some("//"), Some(("/*", "*/"))).unwrap(),
+            content
+        );
+    }
+
+    #[test]
+    fn partially_commented_lines_are_commented() {
+        assert_eq!(
+            toggle_comment("# a\nb\n", Some("#"), None).unwrap(),
+            "# # a\n# b\n"
+        );
+    }
+
+    #[test]
+    fn token_must_be_followed_by_whitespace() {
+        let content = "/// doc\n";
+        let commented = toggle_comment(content, Some("//"), None).unwrap();
+        assert_eq!(commented, "// /// doc\n");
+        assert_eq!(
+            toggle_comment(&commented, Some("//"), None).unwrap(),
+            content
+        );
+        assert_eq!(toggle_comment("//\n", Some("//"), None).unwrap(), "\n");
+    }
+
+    #[test]
+    fn block_comment() {
+        let content = "  <p>hello</p>\n";
+        let commented = toggle_comment(content, None, Some(("<!--", "-->"))).unwrap();
+        assert_eq!(commented, "  <!-- <p>hello</p> -->\n");
+        assert_eq!(
+            toggle_comment(&commented, None, Some(("<!--", "-->"))).unwrap(),
+            content
+        );
+    }
+
+    #[test]
+    fn uncomment_block_comment_of_language_with_line_comment() {
+        assert_eq!(
+            toggle_comment("/* a */\n", Some("//"), Some(("/*", "*/"))).unwrap(),
+            "a\n"
+        );
+    }
+
+    #[test]
+    fn no_comment_syntax() {
+        assert_eq!(toggle_comment("a\n", None, None), None);
+    }
+}
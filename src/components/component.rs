@@ -140,6 +140,15 @@ pub trait Component: Any + AnyComponent {
         event: event::KeyEvent,
     ) -> anyhow::Result<Dispatches>;
 
+    /// Handle the click on the line of `line_index` of the dropdown owned by this component.
+    fn handle_dropdown_click(
+        &mut self,
+        _context: &Context,
+        _line_index: usize,
+    ) -> anyhow::Result<Dispatches> {
+        Ok(Default::default())
+    }
+
     fn get_cursor_position(&self) -> anyhow::Result<Position> {
         self.editor().get_cursor_position()
     }
@@ -152,6 +152,22 @@ impl Dropdown {
         item_index + group_index * group_title_size + group_gap + group_title_size
     }
 
+    /// Change the current item to the item rendered at the given `line_index`.
+    /// Returns `false` if no item is rendered at that line.
+    pub(crate) fn change_index_by_line(&mut self, line_index: usize) -> bool {
+        let Some(item_index) = self
+            .filtered_item_groups
+            .iter()
+            .flat_map(|group| &group.items)
+            .map(|item| item.item_index as usize)
+            .find(|item_index| self.item_line_index(*item_index) == line_index)
+        else {
+            return false;
+        };
+        self.change_index(item_index);
+        true
+    }
+
     pub(crate) fn next_item(&mut self) {
         self.change_index(self.current_item_index + 1)
     }
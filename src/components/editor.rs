@@ -1,12 +1,18 @@
 use crate::{
     app::{Dispatches, RequestParams, Scope},
+    auto_indent::{self, IndentChange},
     buffer::Line,
     char_index_range::CharIndexRange,
     clipboard::CopiedTexts,
     context::{Context, GlobalMode, LocalSearchConfigMode, Search},
+    git::{
+        conflict::{parse_conflicts, ConflictResolution},
+        hunk::{self, Hunk},
+        DiffMode, GitOperation, GitRepo,
+    },
     lsp::{completion::CompletionItemEdit, process::ResponseContext},
     selection_mode::{self, regex::get_regex},
-    surround::EnclosureKind,
+    surround::{self, AutoPair, EnclosureKind},
     transformation::{MyRegex, Transformation},
 };
 
@@ -16,9 +22,10 @@ use std::{
     cell::{Ref, RefCell, RefMut},
     ops::{Not, Range},
     rc::Rc,
+    time::{Duration, Instant},
 };
 
-use crossterm::event::{KeyCode, MouseButton, MouseEventKind};
+use crossterm::event::{KeyCode, MouseButton, MouseEvent, MouseEventKind};
 use event::KeyEvent;
 use itertools::{Either, Itertools};
 use my_proc_macros::key;
@@ -29,6 +36,7 @@ use crate::{
     buffer::Buffer,
     components::component::Component,
     edit::{Action, ActionGroup, Edit, EditTransaction},
+    file_format::{Encoding, FileFormat, LineEnding},
     lsp::completion::PositionalEdit,
     position::Position,
     rectangle::Rectangle,
@@ -83,7 +91,7 @@ impl Component for Editor {
 
     fn title(&self, context: &Context) -> String {
         let title = self.title.clone();
-        title
+        let title = title
             .or_else(|| {
                 let path = self.buffer().path()?;
                 let current_working_directory = context.current_working_directory();
@@ -93,7 +101,17 @@ impl Component for Editor {
                 let icon = path.icon();
                 Some(format!(" {} {}", icon, string))
             })
-            .unwrap_or_else(|| "[No title]".to_string())
+            .or_else(|| {
+                let path = self.buffer().new_file_path()?.to_path_buf();
+                let icon = shared::canonicalized_path::get_path_icon(&path);
+                Some(format!(" {} {} [new file]", icon, path.display()))
+            })
+            .unwrap_or_else(|| "[No title]".to_string());
+        if self.buffer().readonly() {
+            format!("{} [readonly]", title)
+        } else {
+            title
+        }
     }
 
     fn set_title(&mut self, title: String) {
@@ -141,7 +159,8 @@ impl Component for Editor {
                 self.apply_scroll(Direction::End, SCROLL_HEIGHT);
                 Ok(Default::default())
             }
-            MouseEventKind::Down(MouseButton::Left) => Ok(Default::default()),
+            MouseEventKind::Down(MouseButton::Left) => self.handle_mouse_click(&mouse_event),
+            MouseEventKind::Drag(MouseButton::Left) => Ok(self.handle_mouse_drag(&mouse_event)),
             _ => Ok(Default::default()),
         }
     }
@@ -209,6 +228,26 @@ impl Component for Editor {
             EnableSelectionExtension => self.enable_selection_extension(),
             EnterVMode => self.enter_v_mode(),
             EnterUndoTreeMode => return Ok(self.enter_undo_tree_mode()),
+            UndoToMinutesAgo(minutes) => return self.undo_to_minutes_ago(minutes),
+            ResolveConflicts(resolution) => return self.resolve_conflicts(resolution),
+            SetLineEnding(line_ending) => {
+                let file_format = self.buffer().file_format();
+                self.buffer_mut().set_file_format(FileFormat {
+                    line_ending,
+                    ..file_format
+                })
+            }
+            SetEncoding {
+                encoding,
+                byte_order_mark,
+            } => {
+                let file_format = self.buffer().file_format();
+                self.buffer_mut().set_file_format(FileFormat {
+                    encoding,
+                    byte_order_mark,
+                    ..file_format
+                })
+            }
             EnterInsertMode(direction) => return self.enter_insert_mode(direction),
             Delete(direction) => return self.delete(direction, None),
             Insert(string) => return self.insert(&string),
@@ -260,6 +299,7 @@ impl Component for Editor {
                 self.apply_syntax_highlighting(context)?;
             }
             Save => return self.save(),
+            RevertHunks(diff_mode) => return self.revert_hunks(context, &diff_mode),
             ReplaceCurrentSelectionWith(string) => {
                 return self.replace_current_selection_with(|_| Some(Rope::from_str(&string)))
             }
@@ -294,11 +334,15 @@ impl Component for Editor {
             } => return self.paste(direction, context, use_system_clipboard),
             SwapCursorWithAnchor => self.swap_cursor_with_anchor(),
             SetDecorations(decorations) => self.buffer_mut().set_decorations(&decorations),
-            MoveCharacterBack => self.selection_set.move_left(&self.cursor_direction),
+            MoveCharacterBack => {
+                let buffer = self.buffer.borrow();
+                self.selection_set
+                    .move_left(&buffer, &self.cursor_direction)
+            }
             MoveCharacterForward => {
-                let len_chars = self.buffer().len_chars();
+                let buffer = self.buffer.borrow();
                 self.selection_set
-                    .move_right(&self.cursor_direction, len_chars)
+                    .move_right(&buffer, &self.cursor_direction)
             }
             Open(direction) => return self.open(direction),
             TryReplaceCurrentLongWord(replacement) => {
@@ -334,6 +378,7 @@ impl Component for Editor {
             ShowCurrentTreeSitterNodeSexp => return self.show_current_tree_sitter_node_sexp(),
             Indent => return self.indent(),
             Dedent => return self.dedent(),
+            ToggleComment => return self.toggle_comment(),
             CyclePrimarySelection(direction) => self.cycle_primary_selection(direction),
             SwapExtensionDirection => self.selection_set.swap_initial_range_direction(),
             CollapseSelection(direction) => return self.collapse_selection(context, direction),
@@ -368,6 +413,7 @@ impl Clone for Editor {
             current_view_alignment: None,
             regex_highlight_rules: Vec::new(),
             copied_text_history_offset: Default::default(),
+            last_mouse_click: None,
         }
     }
 }
@@ -390,8 +436,21 @@ pub(crate) struct Editor {
     id: ComponentId,
     pub(crate) current_view_alignment: Option<ViewAlignment>,
     copied_text_history_offset: Counter,
+    /// Used for detecting double-clicks and triple-clicks, and as the anchor of mouse dragging.
+    last_mouse_click: Option<MouseClick>,
 }
 
+#[derive(Clone, Copy)]
+struct MouseClick {
+    char_index: CharIndex,
+    time: Instant,
+    /// 1 for single-click, 2 for double-click, 3 for triple-click
+    count: usize,
+}
+
+/// Clicks on the same character within this interval are counted as multi-clicks.
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+
 #[derive(Default)]
 struct Counter {
     value: isize,
@@ -538,6 +597,7 @@ impl Editor {
             current_view_alignment: None,
             regex_highlight_rules: Vec::new(),
             copied_text_history_offset: Default::default(),
+            last_mouse_click: None,
         }
     }
 
@@ -555,6 +615,7 @@ impl Editor {
             current_view_alignment: None,
             regex_highlight_rules: Vec::new(),
             copied_text_history_offset: Default::default(),
+            last_mouse_click: None,
         }
     }
 
@@ -578,6 +639,82 @@ impl Editor {
         self.select(SelectionMode::Line, movement)
     }
 
+    /// Returns the index of the character under the pointer of `mouse_event`.
+    pub(crate) fn mouse_event_char_index(&self, mouse_event: &MouseEvent) -> Option<CharIndex> {
+        let origin = &self.rectangle.origin;
+        let position = Position::new(
+            (mouse_event.row as usize).checked_sub(origin.line)?,
+            (mouse_event.column as usize).checked_sub(origin.column)?,
+        );
+        self.screen_position_to_char_index(position)
+    }
+
+    /// Single-click selects the character under the pointer,
+    /// double-click selects the word, and triple-click selects the syntax node.
+    fn handle_mouse_click(&mut self, mouse_event: &MouseEvent) -> anyhow::Result<Dispatches> {
+        let Some(char_index) = self.mouse_event_char_index(mouse_event) else {
+            return Ok(Default::default());
+        };
+        let count = match self.last_mouse_click {
+            Some(click)
+                if click.char_index == char_index
+                    && click.time.elapsed() < MULTI_CLICK_INTERVAL =>
+            {
+                click.count % 3 + 1
+            }
+            _ => 1,
+        };
+        self.last_mouse_click = Some(MouseClick {
+            char_index,
+            time: Instant::now(),
+            count,
+        });
+        let dispatches = self.select_mouse_range(char_index, char_index, true);
+        let selection_mode = match count {
+            2 => SelectionMode::Word,
+            3 => SelectionMode::SyntaxNode,
+            _ => return Ok(dispatches),
+        };
+        self.select(
+            selection_mode,
+            Movement::Current(IfCurrentNotFound::LookForward),
+        )
+    }
+
+    fn handle_mouse_drag(&mut self, mouse_event: &MouseEvent) -> Dispatches {
+        let Some(anchor) = self.last_mouse_click.map(|click| click.char_index) else {
+            return Default::default();
+        };
+        let Some(char_index) = self.mouse_event_char_index(mouse_event) else {
+            return Default::default();
+        };
+        self.select_mouse_range(anchor, char_index, false)
+    }
+
+    /// Select from the grapheme of `anchor` to the grapheme of `char_index` inclusively.
+    /// In Insert mode, only the cursor is moved to `char_index`.
+    fn select_mouse_range(
+        &mut self,
+        anchor: CharIndex,
+        char_index: CharIndex,
+        store_history: bool,
+    ) -> Dispatches {
+        let range = if self.mode == Mode::Insert {
+            char_index..char_index
+        } else {
+            let buffer = self.buffer();
+            let (start, end) = if anchor <= char_index {
+                (anchor, char_index)
+            } else {
+                (char_index, anchor)
+            };
+            buffer.grapheme_range(start).start..buffer.grapheme_range(end).end
+        };
+        let selection_set = SelectionSet::new(NonEmpty::singleton(Selection::new(range.into())))
+            .set_mode(SelectionMode::Custom);
+        self.update_selection_set(selection_set, store_history)
+    }
+
     pub(crate) fn select_line_at(&mut self, line: usize) -> anyhow::Result<Dispatches> {
         let start = self.buffer.borrow().line_to_char(line)?;
         let selection_set = SelectionSet::new(NonEmpty::singleton(Selection::new(
@@ -594,6 +731,24 @@ impl Editor {
         Ok(self.update_selection_set(selection_set, false))
     }
 
+    /// Appends `text` to the end of the buffer, and selects the last line.
+    /// Unlike `set_content`, the existing content is not rebuilt.
+    pub(crate) fn append_and_select_last_line(&mut self, text: &str) -> anyhow::Result<Dispatches> {
+        self.buffer.borrow_mut().append(text)?;
+        let last_line = {
+            let buffer = self.buffer();
+            let rope = buffer.rope();
+            // A trailing newline is followed by an empty line, which is not selected
+            let ends_with_newline = rope
+                .len_chars()
+                .checked_sub(1)
+                .is_some_and(|index| rope.char(index) == '\n');
+            rope.len_lines()
+                .saturating_sub(if ends_with_newline { 2 } else { 1 })
+        };
+        self.select_line_at(last_line)
+    }
+
     #[cfg(test)]
     pub(crate) fn reset(&mut self) {
         self.selection_set.escape_highlight_mode();
@@ -844,9 +999,14 @@ impl Editor {
         Ok(copy_dispatches.chain(dispatches))
     }
 
+    /// The new line copies the indentation of the current line,
+    /// and is indented or dedented one level if the indent query of the language says so.
+    ///
+    /// When the cursor is between a pair of brackets, the closing bracket is moved to another line.
     fn enter_newline(&mut self) -> anyhow::Result<Dispatches> {
         let edit_transaction = EditTransaction::from_action_groups({
             let buffer = self.buffer();
+            let source_code = buffer.content();
             self.selection_set
                 .map(|selection| -> anyhow::Result<_> {
                     let cursor = selection.extended_range().start;
@@ -854,23 +1014,58 @@ impl Editor {
 
                     let current_line = buffer.get_line_by_line_index(current_line_index);
 
-                    let indent = "\n".to_string()
-                        + current_line
-                            .map(|line| {
-                                line.to_string()
-                                    .chars()
-                                    .take_while(|c| c.is_whitespace() && c != &'\n')
-                                    .join("")
+                    let current_indent = current_line
+                        .map(|line| {
+                            line.to_string()
+                                .chars()
+                                .take_while(|c| c.is_whitespace() && c != &'\n')
+                                .join("")
+                        })
+                        .unwrap_or_default();
+                    let between_brackets = surround::is_bracket_pair(
+                        cursor
+                            .0
+                            .checked_sub(1)
+                            .and_then(|index| buffer.get_char(CharIndex(index))),
+                        buffer.get_char(cursor),
+                    );
+                    let indent_change = if between_brackets {
+                        IndentChange::Indent
+                    } else {
+                        buffer
+                            .language()
+                            .zip(buffer.tree())
+                            .and_then(|(language, tree)| {
+                                auto_indent::newline_indent_change(
+                                    &language,
+                                    tree,
+                                    &source_code,
+                                    buffer.char_to_byte(cursor).ok()?,
+                                )
                             })
-                            .unwrap_or_default()
-                            .as_str();
-
+                            .unwrap_or(IndentChange::Keep)
+                    };
+                    let unit = buffer.indent().unit();
+                    let indent = "\n".to_string()
+                        + &match indent_change {
+                            IndentChange::Indent => current_indent.clone() + &unit,
+                            IndentChange::Keep => current_indent.clone(),
+                            IndentChange::Dedent => current_indent
+                                .strip_suffix(unit.as_str())
+                                .unwrap_or(&current_indent)
+                                .to_string(),
+                        };
                     let range_start = cursor + indent.chars().count();
+                    let new = if between_brackets {
+                        format!("{}\n{}", indent, current_indent)
+                    } else {
+                        indent
+                    };
                     Ok(ActionGroup::new(
                         [
                             Action::Edit(Edit {
                                 range: (cursor..cursor).into(),
-                                new: indent.into(),
+                                new: new.into(),
                             }),
                             Action::Select(
                                 selection
@@ -1311,6 +1506,77 @@ impl Editor {
         self.apply_edit_transaction(edit_transaction)
     }
 
+    /// Insert a character typed by the user, brackets and quotes are auto-paired
+    /// in buffers of a programming language.
+    pub(crate) fn insert_char(&mut self, c: char) -> anyhow::Result<Dispatches> {
+        if self.buffer().language().is_none() {
+            return self.insert(&c.to_string());
+        }
+        let auto_pairs: Vec<_> = {
+            let buffer = self.buffer();
+            self.selection_set
+                .map(|selection| {
+                    let cursor = selection.extended_range().start;
+                    let previous = cursor
+                        .0
+                        .checked_sub(1)
+                        .and_then(|index| buffer.get_char(CharIndex(index)));
+                    let auto_pair = surround::auto_pair(
+                        c,
+                        previous,
+                        buffer.get_char(cursor),
+                        buffer.is_auto_inserted_closer(cursor),
+                    );
+                    (cursor, auto_pair)
+                })
+                .into()
+        };
+        for (cursor, auto_pair) in &auto_pairs {
+            if auto_pair == &AutoPair::SkipClosing {
+                self.buffer_mut().remove_auto_inserted_closer(*cursor)
+            }
+        }
+        let edit_transaction = EditTransaction::from_action_groups(
+            self.selection_set
+                .map_with_index(|index, selection| {
+                    let (cursor, auto_pair) = &auto_pairs[index];
+                    let cursor = *cursor;
+                    let new = match auto_pair {
+                        AutoPair::SkipClosing => None,
+                        AutoPair::InsertPair(close) => Some(format!("{}{}", c, close)),
+                        AutoPair::None => Some(c.to_string()),
+                    };
+                    let select = Action::Select(
+                        selection.clone().set_range((cursor + 1..cursor + 1).into()),
+                    );
+                    ActionGroup::new(match new {
+                        Some(new) => [
+                            Action::Edit(Edit {
+                                range: (cursor..cursor).into(),
+                                new: new.into(),
+                            }),
+                            select,
+                        ]
+                        .to_vec(),
+                        None => [select].to_vec(),
+                    })
+                })
+                .into(),
+        );
+        let dispatches = self.apply_edit_transaction(edit_transaction)?;
+        // Each inserted closing symbol is right after the cursor of its selection
+        let cursors: Vec<_> = self
+            .selection_set
+            .map(|selection| selection.extended_range().start)
+            .into();
+        for (cursor, (_, auto_pair)) in cursors.into_iter().zip(&auto_pairs) {
+            if matches!(auto_pair, AutoPair::InsertPair(_)) {
+                self.buffer_mut().add_auto_inserted_closer(cursor)
+            }
+        }
+        Ok(dispatches)
+    }
+
     pub(crate) fn get_request_params(&self) -> Option<RequestParams> {
         let position = self.get_cursor_position().ok()?;
         self.path().map(|path| RequestParams {
@@ -1851,15 +2117,27 @@ impl Editor {
         };
     }
 
+    /// An auto-paired enclosure is deleted entirely when the cursor is between its symbols,
+    /// if its closing symbol was auto-inserted.
     pub(crate) fn backspace(&mut self) -> anyhow::Result<Dispatches> {
+        let buffer = self.buffer();
         let edit_transaction = EditTransaction::from_action_groups(
             self.selection_set
                 .map(|selection| {
-                    let start = CharIndex(selection.extended_range().start.0.saturating_sub(1));
+                    let cursor = selection.extended_range().start;
+                    let start = buffer.previous_grapheme_boundary(cursor);
+                    let end = if start < cursor
+                        && buffer.is_auto_inserted_closer(cursor)
+                        && surround::is_auto_pair(buffer.get_char(start), buffer.get_char(cursor))
+                    {
+                        cursor + 1
+                    } else {
+                        cursor
+                    };
                     ActionGroup::new(
                         [
                             Action::Edit(Edit {
-                                range: (start..selection.extended_range().start).into(),
+                                range: (start..end).into(),
                                 new: Rope::from(""),
                             }),
                             Action::Select(selection.clone().set_range((start..start).into())),
@@ -1869,6 +2147,7 @@ impl Editor {
                 })
                 .into(),
         );
+        drop(buffer);
 
         self.apply_edit_transaction(edit_transaction)
     }
@@ -2179,6 +2458,31 @@ impl Editor {
             }))
     }
 
+    /// Unlike `save`, the content is not formatted, and the mode and the cursors are kept.
+    pub(crate) fn save_without_formatting(&mut self) -> anyhow::Result<Dispatches> {
+        let Some(path) = self.buffer.borrow_mut().save_without_formatting()? else {
+            return Ok(Default::default());
+        };
+        Ok(Dispatches::one(Dispatch::DocumentDidSave { path }))
+    }
+
+    /// Unlike `set_content`, the replacement can be undone.
+    pub(crate) fn replace_content(&mut self, content: &str) -> anyhow::Result<Dispatches> {
+        let edit_transaction = self.buffer().get_edit_transaction(content)?;
+        self.apply_edit_transaction(edit_transaction)
+    }
+
+    /// Unlike `save`, the mode and the cursors are kept, so that editing is not interrupted.
+    pub(crate) fn auto_save(&mut self) -> anyhow::Result<Dispatches> {
+        let Some(path) = self.buffer.borrow_mut().save(self.selection_set.clone())? else {
+            return Ok(Default::default());
+        };
+
+        self.clamp()?;
+        Ok(Dispatches::one(Dispatch::DocumentDidSave { path })
+            .chain(self.get_document_did_change_dispatch()))
+    }
+
     /// Clamp everything that might be out of bound after the buffer content is modified elsewhere
     fn clamp(&mut self) -> anyhow::Result<()> {
         let len_chars = self.buffer().len_chars();
@@ -2255,6 +2559,45 @@ impl Editor {
         self.apply_edit_transaction(edit_transaction)
     }
 
+    fn resolve_conflicts(&mut self, resolution: ConflictResolution) -> anyhow::Result<Dispatches> {
+        let buffer = self.buffer();
+        let content = buffer.content();
+        let selected_ranges: Vec<Range<usize>> = self
+            .selection_set
+            .map(|selection| buffer.char_index_range_to_byte_range(selection.extended_range()))
+            .into_iter()
+            .try_collect()?;
+        let action_groups: Vec<ActionGroup> = parse_conflicts(&content)
+            .into_iter()
+            .filter(|conflict| {
+                selected_ranges.iter().any(|range| {
+                    // An empty selection intersects with the conflict that contains it
+                    range.start < conflict.range.end
+                        && conflict.range.start < range.end.max(range.start + 1)
+                })
+            })
+            .map(|conflict| -> anyhow::Result<_> {
+                let new: Rope = conflict.resolve(&content, resolution)?.into();
+                let new_char_count = new.len_chars();
+                let range = buffer.byte_range_to_char_index_range(&conflict.range)?;
+                Ok(ActionGroup::new(
+                    [
+                        Action::Edit(Edit { range, new }),
+                        Action::Select(Selection::new(
+                            (range.start..range.start + new_char_count).into(),
+                        )),
+                    ]
+                    .to_vec(),
+                ))
+            })
+            .try_collect()?;
+        drop(buffer);
+        if action_groups.is_empty() {
+            return Err(anyhow::anyhow!("No merge conflicts are selected"));
+        }
+        self.apply_edit_transaction(EditTransaction::from_action_groups(action_groups))
+    }
+
     pub(crate) fn display_mode(&self) -> String {
         let prefix = if self.selection_set.is_extended() {
             "+"
@@ -2469,6 +2812,15 @@ impl Editor {
         })
     }
 
+    fn undo_to_minutes_ago(&mut self, minutes: u64) -> anyhow::Result<Dispatches> {
+        let selection_set = self.buffer_mut().undo_to_minutes_ago(minutes)?;
+
+        Ok(selection_set
+            .map(|selection_set| self.update_selection_set(selection_set, false))
+            .unwrap_or_default()
+            .chain(self.get_document_did_change_dispatch()))
+    }
+
     fn navigate_undo_tree(&mut self, movement: Movement) -> Result<Dispatches, anyhow::Error> {
         let selection_set = self.buffer_mut().undo_tree_apply_movement(movement)?;
 
@@ -2836,11 +3188,76 @@ impl Editor {
         ))))
     }
 
+    /// Returns the 0-based line ranges covered by the selections.
+    pub(crate) fn selected_line_ranges(&self) -> Vec<Range<usize>> {
+        self.selection_set
+            .map(|selection| {
+                self.buffer()
+                    .char_index_range_to_line_range(selection.extended_range())
+                    .ok()
+                    .map(|range| range.start..range.end.max(range.start + 1))
+            })
+            .into_iter()
+            .flatten()
+            .collect_vec()
+    }
+
+    /// Revert the Git hunks intersecting with the selections to their content in `diff_mode`.
+    /// Like staging, this is refused while the buffer has unsaved changes,
+    /// so that the reverted hunks are the ones shown by `SelectionMode::GitHunk`.
+    fn revert_hunks(
+        &mut self,
+        context: &Context,
+        diff_mode: &DiffMode,
+    ) -> anyhow::Result<Dispatches> {
+        let Some(path) = self.buffer().path() else {
+            return Ok(Default::default());
+        };
+        if self.buffer().is_dirty() {
+            return Err(anyhow::anyhow!(
+                "Save {} before reverting its hunks",
+                path.display_relative_to(context.current_working_directory())
+                    .unwrap_or_else(|_| path.display_absolute())
+            ));
+        }
+        let repo = GitRepo::try_from(context.current_working_directory())?;
+        let old = path.content_at_last_commit(diff_mode, &repo)?;
+        let old_lines = old.split_inclusive('\n').collect_vec();
+        let line_ranges = self.selected_line_ranges();
+        let primary_selection = self.selection_set.primary_selection().clone();
+        let edit_transaction = EditTransaction::from_action_groups(
+            Hunk::get(&old, &self.buffer().content())
+                .into_iter()
+                .filter(|hunk| hunk::intersects(hunk.line_range(), &line_ranges))
+                .map(|hunk| -> anyhow::Result<_> {
+                    let line_range = hunk.line_range();
+                    let start = self.buffer().line_to_char(line_range.start)?;
+                    let end = self.buffer().line_to_char(line_range.end)?;
+                    let new: Rope = old_lines
+                        .get(hunk.old_line_range().clone())
+                        .unwrap_or_default()
+                        .concat()
+                        .into();
+                    let select_range = (start..start + new.len_chars()).into();
+                    Ok(ActionGroup::new(
+                        [
+                            Action::Edit(Edit {
+                                range: (start..end).into(),
+                                new,
+                            }),
+                            Action::Select(primary_selection.clone().set_range(select_range)),
+                        ]
+                        .to_vec(),
+                    ))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        );
+        self.apply_edit_transaction(edit_transaction)
+    }
+
     fn indent(&mut self) -> Result<Dispatches, anyhow::Error> {
-        let indentation: Rope = std::iter::repeat(INDENT_CHAR)
-            .take(INDENT_WIDTH)
-            .collect::<String>()
-            .into();
+        let indent = self.buffer().indent();
+        let indentation: Rope = indent.unit().into();
         let edit_transaction = EditTransaction::from_action_groups(
             self.selection_set
                 .map(|selection| -> anyhow::Result<_> {
@@ -2873,7 +3290,7 @@ impl Editor {
                         .join("")
                         .into();
                     let select_range = {
-                        let offset: isize = INDENT_WIDTH as isize;
+                        let offset: isize = indent.len() as isize;
                         let start = original_range.start.apply_offset(offset);
                         let original_len = original_range.len();
                         let end =
@@ -2900,6 +3317,7 @@ impl Editor {
     }
 
     fn dedent(&mut self) -> Result<Dispatches, anyhow::Error> {
+        let indent = self.buffer().indent();
         let edit_transaction = EditTransaction::from_action_groups(
             self.selection_set
                 .map(|selection| -> anyhow::Result<_> {
@@ -2913,8 +3331,8 @@ impl Editor {
                     let content = self.buffer().slice(&linewise_range)?;
                     let get_remove_leading_char_count = |line: &str| {
                         let leading_indent_count =
-                            line.chars().take_while(|c| c == &INDENT_CHAR).count();
-                        leading_indent_count.min(INDENT_WIDTH)
+                            line.chars().take_while(|c| c == &indent.char()).count();
+                        leading_indent_count.min(indent.len())
                     };
                     let modified_lines = content
                         .lines()
@@ -2973,6 +3391,51 @@ impl Editor {
         self.apply_edit_transaction(edit_transaction)
     }
 
+    /// The comment syntax is chosen by the language at each selection,
+    /// so that injected languages are commented with their own syntax.
+    fn toggle_comment(&mut self) -> Result<Dispatches, anyhow::Error> {
+        let edit_transaction = EditTransaction::from_action_groups(
+            self.selection_set
+                .map(|selection| -> anyhow::Result<_> {
+                    let buffer = self.buffer();
+                    let original_range = selection.extended_range();
+                    let line_range = buffer.char_index_range_to_line_range(original_range)?;
+                    let linewise_range = buffer.line_range_to_full_char_index_range(line_range)?;
+                    let content = buffer.slice(&linewise_range)?.to_string();
+                    let language = buffer
+                        .language_at(buffer.char_index_range_to_byte_range(original_range)?)
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("Unable to toggle comment: unknown language")
+                        })?;
+                    let new = crate::comment::toggle_comment(
+                        &content,
+                        language.line_comment_token(),
+                        language.block_comment_tokens(),
+                    )
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Unable to toggle comment: the language has no comment syntax"
+                        )
+                    })?;
+                    let start = linewise_range.start;
+                    let select_range = start..start + new.trim_end_matches('\n').chars().count();
+                    Ok(ActionGroup::new(
+                        [
+                            Action::Edit(Edit {
+                                range: linewise_range,
+                                new: new.into(),
+                            }),
+                            Action::Select(selection.clone().set_range(select_range.into())),
+                        ]
+                        .to_vec(),
+                    ))
+                })
+                .into_iter()
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        );
+        self.apply_edit_transaction(edit_transaction)
+    }
+
     #[cfg(test)]
     pub(crate) fn primary_selection(&self) -> anyhow::Result<String> {
         Ok(self
@@ -3232,6 +3695,13 @@ pub(crate) enum DispatchEditor {
     Transform(Transformation),
     SetSelectionMode(IfCurrentNotFound, SelectionMode),
     Save,
+    /// Takes effect when the buffer is saved
+    SetLineEnding(LineEnding),
+    /// Takes effect when the buffer is saved
+    SetEncoding {
+        encoding: Encoding,
+        byte_order_mark: bool,
+    },
     FindOneChar(IfCurrentNotFound),
     MoveSelection(Movement),
     SwitchViewAlignment,
@@ -3252,6 +3722,10 @@ pub(crate) enum DispatchEditor {
         use_system_clipboard: bool,
     },
     EnterUndoTreeMode,
+    /// Moves to the last edit of the current undo branch made at least this many minutes ago
+    UndoToMinutesAgo(u64),
+    /// Resolves the merge conflicts that intersect with any selection
+    ResolveConflicts(ConflictResolution),
     EnterInsertMode(Direction),
     ReplaceWithCopiedText {
         cut: bool,
@@ -3323,6 +3797,7 @@ pub(crate) enum DispatchEditor {
     ShowCurrentTreeSitterNodeSexp,
     Indent,
     Dedent,
+    ToggleComment,
     SwapExtensionDirection,
     CollapseSelection(Direction),
     FilterSelectionMatchingSearch {
@@ -3332,6 +3807,129 @@ pub(crate) enum DispatchEditor {
     EnterNewline,
     DeleteCurrentCursor(Direction),
     BreakSelection,
+    RevertHunks(DiffMode),
+}
+
+/// Lists the actions that take no argument, which can be run from the command palette.
+/// Every other variant must be excluded explicitly,
+/// so that a new action fails to compile until it is either listed or excluded.
+macro_rules! parameterless_actions {
+    (
+        listed: { $($variant:ident => $description:literal,)* }
+        excluded: { $($(#[$meta:meta])* $excluded:ident,)* }
+    ) => {
+        impl DispatchEditor {
+            pub(crate) fn parameterless_actions() -> Vec<(&'static str, DispatchEditor)> {
+                let _exhaustive = |dispatch: &DispatchEditor| match dispatch {
+                    $(DispatchEditor::$variant => {})*
+                    $($(#[$meta])* DispatchEditor::$excluded { .. } => {})*
+                };
+                [$(($description, DispatchEditor::$variant)),*].to_vec()
+            }
+        }
+    };
+}
+
+parameterless_actions! {
+    listed: {
+        ScrollPageDown => "Scroll page down",
+        ScrollPageUp => "Scroll page up",
+        Save => "Save",
+        SwitchViewAlignment => "Switch view alignment",
+        GoBack => "Go back",
+        GoForward => "Go forward",
+        SelectAll => "Select all",
+        EnableSelectionExtension => "Enable selection extension",
+        EnterVMode => "Enter visual mode",
+        Change => "Change",
+        EnterUndoTreeMode => "Enter undo tree mode",
+        ReplaceWithPattern => "Replace with pattern",
+        MoveToLineStart => "Move to line start",
+        MoveToLineEnd => "Move to line end",
+        ToggleMark => "Toggle mark",
+        EnterNormalMode => "Enter normal mode",
+        EnterExchangeMode => "Enter exchange mode",
+        EnterReplaceMode => "Enter replace mode",
+        EnterMultiCursorMode => "Enter multi-cursor mode",
+        CursorAddToAllSelections => "Add cursor to all selections",
+        CursorKeepPrimaryOnly => "Keep primary cursor only",
+        Undo => "Undo",
+        Redo => "Redo",
+        SwapCursorWithAnchor => "Swap cursor with anchor",
+        MoveCharacterBack => "Move character back",
+        MoveCharacterForward => "Move character forward",
+        ReplaceWithPreviousCopiedText => "Replace with previous copied text",
+        ReplaceWithNextCopiedText => "Replace with next copied text",
+        MoveToLastChar => "Move to last character",
+        ShowCurrentTreeSitterNodeSexp => "Show current Tree-sitter node S-expression",
+        Indent => "Indent",
+        Dedent => "Dedent",
+        ToggleComment => "Toggle comment",
+        SwapExtensionDirection => "Swap extension direction",
+        Backspace => "Backspace",
+        EnterNewline => "Enter newline",
+        BreakSelection => "Break selection",
+    }
+    excluded: {
+        // The keymap legends are expanded into their keymaps by the command palette
+        ShowKeymapLegendNormalMode,
+        ShowKeymapLegendInsertMode,
+        ShowKeymapLegendHelp,
+        Surround,
+        #[cfg(test)]
+        SetScrollOffset,
+        ShowJumps,
+        #[cfg(test)]
+        AlignViewTop,
+        #[cfg(test)]
+        AlignViewBottom,
+        Transform,
+        SetSelectionMode,
+        SetLineEnding,
+        SetEncoding,
+        FindOneChar,
+        MoveSelection,
+        Copy,
+        SetContent,
+        SetDecorations,
+        #[cfg(test)]
+        SetRectangle,
+        ChangeCut,
+        UndoToMinutesAgo,
+        ResolveConflicts,
+        EnterInsertMode,
+        ReplaceWithCopiedText,
+        SelectLine,
+        Delete,
+        Insert,
+        #[cfg(test)]
+        MatchLiteral,
+        SelectSurround,
+        Open,
+        CyclePrimarySelection,
+        ReplacePattern,
+        KillLine,
+        #[cfg(test)]
+        Reset,
+        DeleteWordBackward,
+        #[cfg(test)]
+        SetLanguage,
+        #[cfg(test)]
+        ApplySyntaxHighlight,
+        ReplaceCurrentSelectionWith,
+        TryReplaceCurrentLongWord,
+        SelectLineAt,
+        Paste,
+        DeleteSurround,
+        ChangeSurround,
+        Replace,
+        ApplyPositionalEdits,
+        PipeToShell,
+        CollapseSelection,
+        FilterSelectionMatchingSearch,
+        DeleteCurrentCursor,
+        RevertHunks,
+    }
 }
 
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -3348,6 +3946,3 @@ impl std::fmt::Display for SurroundKind {
         }
     }
 }
-
-const INDENT_CHAR: char = ' ';
-const INDENT_WIDTH: usize = 4;
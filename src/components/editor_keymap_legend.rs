@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use crossterm::event::KeyCode;
 use SelectionMode::*;
 
@@ -6,13 +8,18 @@ use event::KeyEvent;
 use itertools::Itertools;
 
 use crate::{
-    app::{Dispatch, Dispatches, FilePickerKind, Scope},
+    app::{Dispatch, Dispatches, FilePickerKind, RevisionPickerKind, Scope},
     components::{editor::Movement, keymap_legend::KeymapLegendSection},
     context::{Context, LocalSearchConfigMode, Search},
-    git::DiffMode,
+    dap::session::DebugResume,
+    diff_view::{DiffSource, DiffViewKind},
+    file_format::{Encoding, LineEnding},
+    git::{conflict::ConflictResolution, DiffMode},
     list::grep::RegexConfig,
     quickfix_list::{DiagnosticSeverityRange, QuickfixListType},
+    rectangle::{SplitOrientation, WindowDirection},
     selection::SelectionMode,
+    selection_mode::text_object::TextObjectKind,
     surround::EnclosureKind,
     transformation::Transformation,
 };
@@ -27,6 +34,49 @@ use super::{
 
 use DispatchEditor::*;
 use Movement::*;
+
+/// An action listed in the command palette.
+pub(crate) struct CommandPaletteAction {
+    /// The key sequence that triggers this action, if it is bound to any key
+    keys: Option<String>,
+    description: String,
+    /// The mode that the key sequence is pressed in, which is entered before running this action
+    mode: Option<Mode>,
+    dispatch: Dispatch,
+}
+
+impl CommandPaletteAction {
+    pub(crate) fn display(&self) -> String {
+        match &self.keys {
+            Some(keys) => format!("{} ({})", self.description, keys),
+            None => self.description.clone(),
+        }
+    }
+
+    pub(crate) fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The dispatches that run this action in an editor that is in `current_mode`.
+    pub(crate) fn dispatches(&self, current_mode: &Mode) -> Dispatches {
+        let enter_mode = match &self.mode {
+            Some(mode) if mode == current_mode => None,
+            Some(Mode::Insert) => Some(EnterInsertMode(Direction::Start)),
+            Some(_) => Some(EnterNormalMode),
+            None => None,
+        };
+        Dispatches::new(
+            enter_mode
+                .map(Dispatch::ToEditor)
+                .into_iter()
+                .chain([
+                    self.dispatch.clone(),
+                    Dispatch::SetLastActionDescription(self.description.clone()),
+                ])
+                .collect_vec(),
+        )
+    }
+}
 impl Editor {
     pub(crate) fn keymap_core_movements(&self) -> KeymapLegendSection {
         KeymapLegendSection {
@@ -79,11 +129,19 @@ impl Editor {
                         use_current_selection_mode: true,
                     }),
                 ),
-                Keymap::new(
-                    MOVEMENT_CORE_TO_INDEX,
-                    "To Index (1-based)".to_string(),
-                    Dispatch::OpenMoveToIndexPrompt,
-                ),
+                if self.mode == Mode::UndoTree {
+                    Keymap::new(
+                        MOVEMENT_CORE_TO_INDEX,
+                        "Undo to minutes ago".to_string(),
+                        Dispatch::OpenUndoToMinutesAgoPrompt,
+                    )
+                } else {
+                    Keymap::new(
+                        MOVEMENT_CORE_TO_INDEX,
+                        "To Index (1-based)".to_string(),
+                        Dispatch::OpenMoveToIndexPrompt,
+                    )
+                },
             ]),
         }
     }
@@ -636,7 +694,7 @@ impl Editor {
         {
             Ok(dispatches)
         } else if let KeyCode::Char(c) = event.code {
-            return self.insert(&c.to_string());
+            return self.insert_char(c);
         } else {
             Ok(Default::default())
         }
@@ -764,6 +822,107 @@ impl Editor {
                 .collect_vec(),
         )
     }
+    /// The legend shown by an editor dispatch, if any.
+    /// Unlike [`Dispatch::ShowKeymapLegend`], these legends can show each other,
+    /// for example the help legend shows the normal mode legend.
+    fn shown_keymap_legend_config(
+        &self,
+        context: &Context,
+        dispatch: &Dispatch,
+    ) -> Option<KeymapLegendConfig> {
+        match dispatch {
+            Dispatch::ToEditor(ShowKeymapLegendNormalMode) => {
+                Some(self.normal_mode_keymap_legend_config(context))
+            }
+            Dispatch::ToEditor(ShowKeymapLegendInsertMode) => {
+                Some(self.insert_mode_keymap_legend_config())
+            }
+            Dispatch::ToEditor(ShowKeymapLegendHelp) => Some(self.help_keymap_legend_config()),
+            _ => None,
+        }
+    }
+
+    /// Every action of the command palette.
+    ///
+    /// The keymaps of every legend come first, paired with the key sequence that triggers them.
+    /// The legends of normal mode, visual mode and insert mode are the roots,
+    /// and keymaps that show another legend are expanded into the keymaps of that legend,
+    /// for example `space F u`.
+    /// The contextual keymaps of the focused component, such as those of the file explorer,
+    /// are included through the space legend.
+    ///
+    /// They are followed by the parameterless actions that are not bound to any key.
+    pub(crate) fn command_palette_actions(&self, context: &Context) -> Vec<CommandPaletteAction> {
+        // The keymaps of visual mode are run after returning to normal mode
+        let roots = [
+            (
+                "",
+                Mode::Normal,
+                self.normal_mode_keymap_legend_config(context),
+            ),
+            (
+                "v ",
+                Mode::Normal,
+                self.visual_mode_initialized_keymap_legend_config(),
+            ),
+            ("i ", Mode::Insert, self.insert_mode_keymap_legend_config()),
+        ];
+        let mut visited: HashSet<String> = roots
+            .iter()
+            .map(|(_, _, config)| config.title.clone())
+            .collect();
+        fn flatten(
+            editor: &Editor,
+            context: &Context,
+            visited: &mut HashSet<String>,
+            prefix: &str,
+            config: &KeymapLegendConfig,
+        ) -> Vec<(String, Keymap)> {
+            config
+                .keymaps()
+                .into_iter()
+                .flat_map(|keymap| {
+                    let keys = format!("{}{}", prefix, keymap.key());
+                    if let Dispatch::ShowKeymapLegend(config) = keymap.dispatch() {
+                        return flatten(editor, context, visited, &format!("{} ", keys), config);
+                    }
+                    match editor.shown_keymap_legend_config(context, keymap.dispatch()) {
+                        Some(config) if visited.insert(config.title.clone()) => {
+                            flatten(editor, context, visited, &format!("{} ", keys), &config)
+                        }
+                        Some(_) => Vec::new(),
+                        None => [(keys, keymap.clone())].to_vec(),
+                    }
+                })
+                .collect_vec()
+        }
+        let bound = roots
+            .iter()
+            .flat_map(|(prefix, mode, config)| {
+                flatten(self, context, &mut visited, prefix, config)
+                    .into_iter()
+                    .map(|(keys, keymap)| CommandPaletteAction {
+                        keys: Some(keys),
+                        description: keymap.description().to_string(),
+                        mode: Some(mode.clone()),
+                        dispatch: keymap.dispatch().clone(),
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+        let unbound = DispatchEditor::parameterless_actions()
+            .into_iter()
+            .map(|(description, dispatch)| CommandPaletteAction {
+                keys: None,
+                description: description.to_string(),
+                mode: None,
+                dispatch: Dispatch::ToEditor(dispatch),
+            })
+            .filter(|action| !bound.iter().any(|bound| bound.dispatch == action.dispatch))
+            .collect_vec();
+        bound.into_iter().chain(unbound).collect_vec()
+    }
+
     pub(crate) fn visual_mode_initialized_keymaps(&self) -> Keymaps {
         Keymaps::new(
             &self
@@ -910,6 +1069,7 @@ impl Editor {
                                     "Pick Files (Non git ignored)",
                                     FilePickerKind::NonGitIgnored,
                                 ),
+                                ("X", "Pick Git conflicts", FilePickerKind::GitConflicts),
                             ]
                             .into_iter()
                             .map(|(key, description, kind)| {
@@ -950,26 +1110,86 @@ impl Editor {
                     }])
                     .chain(Some(KeymapLegendSection {
                         title: "Misc".to_string(),
-                        keymaps: Keymaps::new(&[
-                            Keymap::new(
-                                "e",
-                                "Reveal file in Explorer".to_string(),
-                                Dispatch::RevealInExplorer(self.path().unwrap_or_else(|| {
-                                    context.current_working_directory().clone()
-                                })),
-                            ),
-                            Keymap::new(
-                                "z",
-                                "Undo Tree".to_string(),
-                                Dispatch::ToEditor(DispatchEditor::EnterUndoTreeMode),
-                            ),
-                            Keymap::new(
-                                "x",
-                                "Tree-sitter node S-expr".to_string(),
-                                Dispatch::ToEditor(DispatchEditor::ShowCurrentTreeSitterNodeSexp),
-                            ),
-                        ]),
+                        keymaps: Keymaps::new(
+                            &[
+                                Keymap::new(
+                                    "e",
+                                    "Reveal file in Explorer".to_string(),
+                                    Dispatch::RevealInExplorer(self.path().unwrap_or_else(|| {
+                                        context.current_working_directory().clone()
+                                    })),
+                                ),
+                                Keymap::new(
+                                    "z",
+                                    "Undo Tree".to_string(),
+                                    Dispatch::ToEditor(DispatchEditor::EnterUndoTreeMode),
+                                ),
+                                Keymap::new(
+                                    "x",
+                                    "Tree-sitter node S-expr".to_string(),
+                                    Dispatch::ToEditor(
+                                        DispatchEditor::ShowCurrentTreeSitterNodeSexp,
+                                    ),
+                                ),
+                                Keymap::new(
+                                    "W",
+                                    "Window".to_string(),
+                                    Dispatch::ShowKeymapLegend(self.window_keymap_legend_config()),
+                                ),
+                                Keymap::new(
+                                    "p",
+                                    "Command palette".to_string(),
+                                    Dispatch::OpenCommandPalette,
+                                ),
+                                Keymap::new(
+                                    "/",
+                                    "Toggle comment".to_string(),
+                                    Dispatch::ToEditor(DispatchEditor::ToggleComment),
+                                ),
+                                Keymap::new(
+                                    "F",
+                                    "File format".to_string(),
+                                    Dispatch::ShowKeymapLegend(
+                                        self.file_format_keymap_legend_config(),
+                                    ),
+                                ),
+                                Keymap::new(
+                                    "T",
+                                    "Run task".to_string(),
+                                    Dispatch::OpenRunTaskPrompt,
+                                ),
+                                Keymap::new(
+                                    "R",
+                                    "Rerun last task".to_string(),
+                                    Dispatch::RerunTask,
+                                ),
+                                Keymap::new(
+                                    "D",
+                                    "Debug".to_string(),
+                                    Dispatch::ShowKeymapLegend(self.debug_keymap_legend_config()),
+                                ),
+                                Keymap::new(
+                                    "M",
+                                    "Resolve merge conflicts".to_string(),
+                                    Dispatch::ShowKeymapLegend(
+                                        self.resolve_conflicts_keymap_legend_config(),
+                                    ),
+                                ),
+                            ]
+                            .into_iter()
+                            .chain((!context.plugin_commands().is_empty()).then(|| {
+                                Keymap::new(
+                                    "P",
+                                    "Plugins".to_string(),
+                                    Dispatch::ShowKeymapLegend(Self::plugins_keymap_legend_config(
+                                        context,
+                                    )),
+                                )
+                            }))
+                            .collect_vec(),
+                        ),
                     }))
+                    .chain(Some(self.git_keymap_legend_section()))
                     .chain(Some(KeymapLegendSection {
                         title: "File/Quitting".to_string(),
                         keymaps: Keymaps::new(&[
@@ -995,6 +1215,397 @@ impl Editor {
         }
     }
 
+    /// Commands registered later take precedence over earlier commands of the same key.
+    fn plugins_keymap_legend_config(context: &Context) -> KeymapLegendConfig {
+        KeymapLegendConfig {
+            title: "Plugins".to_string(),
+            body: KeymapLegendBody::SingleSection {
+                keymaps: Keymaps::new(
+                    &context
+                        .plugin_commands()
+                        .iter()
+                        .rev()
+                        .unique_by(|command| &command.key)
+                        .map(|command| {
+                            Keymap::new(
+                                &command.key,
+                                format!("{} ({})", command.description, command.plugin),
+                                Dispatch::ExecutePluginCommand {
+                                    plugin: command.plugin.clone(),
+                                    name: command.name.clone(),
+                                },
+                            )
+                        })
+                        .collect_vec(),
+                ),
+            },
+        }
+    }
+
+    fn resolve_conflicts_keymap_legend_config(&self) -> KeymapLegendConfig {
+        KeymapLegendConfig {
+            title: "Resolve merge conflicts".to_string(),
+            body: KeymapLegendBody::SingleSection {
+                keymaps: Keymaps::new(
+                    &[
+                        ("o", ConflictResolution::Ours),
+                        ("t", ConflictResolution::Theirs),
+                        ("b", ConflictResolution::Both),
+                        ("a", ConflictResolution::Base),
+                    ]
+                    .map(|(key, resolution)| {
+                        Keymap::new(
+                            key,
+                            resolution.display().to_string(),
+                            Dispatch::ToEditor(ResolveConflicts(resolution)),
+                        )
+                    }),
+                ),
+            },
+        }
+    }
+
+    fn debug_keymap_legend_config(&self) -> KeymapLegendConfig {
+        let path = self.path();
+        let line = self
+            .buffer()
+            .char_to_line(self.get_cursor_char_index())
+            .unwrap_or_default();
+        let expression = self
+            .buffer()
+            .slice(&self.selection_set.primary_selection().extended_range())
+            .map(|rope| rope.to_string())
+            .unwrap_or_default();
+        KeymapLegendConfig {
+            title: "Debug".to_string(),
+            body: KeymapLegendBody::SingleSection {
+                keymaps: Keymaps::new(
+                    &path
+                        .map(|path| {
+                            [
+                                Keymap::new(
+                                    "b",
+                                    "Toggle breakpoint".to_string(),
+                                    Dispatch::ToggleBreakpoint {
+                                        path: path.clone(),
+                                        line,
+                                    },
+                                ),
+                                Keymap::new(
+                                    "d",
+                                    "Start debugging".to_string(),
+                                    Dispatch::StartDebugging(path),
+                                ),
+                            ]
+                        })
+                        .into_iter()
+                        .flatten()
+                        .chain(
+                            [
+                                ("c", "Continue", DebugResume::Continue),
+                                ("n", "Step over", DebugResume::StepOver),
+                                ("i", "Step in", DebugResume::StepIn),
+                                ("o", "Step out", DebugResume::StepOut),
+                            ]
+                            .into_iter()
+                            .map(|(key, description, resume)| {
+                                Keymap::new(
+                                    key,
+                                    description.to_string(),
+                                    Dispatch::ResumeDebugging(resume),
+                                )
+                            }),
+                        )
+                        .chain([
+                            Keymap::new(
+                                "e",
+                                "Evaluate selection".to_string(),
+                                Dispatch::EvaluateInDebugSession(expression),
+                            ),
+                            Keymap::new("q", "Stop debugging".to_string(), Dispatch::StopDebugging),
+                        ])
+                        .collect_vec(),
+                ),
+            },
+        }
+    }
+
+    fn git_keymap_legend_section(&self) -> KeymapLegendSection {
+        let hunk_keymaps = self.path().map(|path| {
+            let line_ranges = self.selected_line_ranges();
+            [
+                Keymap::new(
+                    "i",
+                    "Stage hunks".to_string(),
+                    Dispatch::StageHunks {
+                        path: path.clone(),
+                        line_ranges: Some(line_ranges.clone()),
+                    },
+                ),
+                Keymap::new(
+                    "I",
+                    "Stage file".to_string(),
+                    Dispatch::StageHunks {
+                        path: path.clone(),
+                        line_ranges: None,
+                    },
+                ),
+                Keymap::new(
+                    "u",
+                    "Unstage hunks".to_string(),
+                    Dispatch::UnstageHunks {
+                        path: path.clone(),
+                        line_ranges: Some(line_ranges),
+                    },
+                ),
+                Keymap::new(
+                    "U",
+                    "Unstage file".to_string(),
+                    Dispatch::UnstageHunks {
+                        path,
+                        line_ranges: None,
+                    },
+                ),
+            ]
+            .into_iter()
+            .chain(
+                [
+                    ("v", DiffMode::UnstagedAgainstCurrentBranch),
+                    ("V", DiffMode::UnstagedAgainstMainBranch),
+                ]
+                .into_iter()
+                .map(|(key, diff_mode)| {
+                    Keymap::new(
+                        key,
+                        format!("Revert hunks ({})", diff_mode.display()),
+                        Dispatch::ToEditor(DispatchEditor::RevertHunks(diff_mode)),
+                    )
+                }),
+            )
+            .collect_vec()
+        });
+        KeymapLegendSection {
+            title: "Git".to_string(),
+            keymaps: Keymaps::new(
+                &hunk_keymaps
+                    .into_iter()
+                    .flatten()
+                    .chain([
+                        Keymap::new(
+                            "C",
+                            "Commit".to_string(),
+                            Dispatch::OpenCommitMessageEditor { amend: false },
+                        ),
+                        Keymap::new(
+                            "A",
+                            "Amend last commit".to_string(),
+                            Dispatch::OpenCommitMessageEditor { amend: true },
+                        ),
+                        Keymap::new(
+                            "l",
+                            "History".to_string(),
+                            Dispatch::ShowKeymapLegend(self.git_history_keymap_legend_config()),
+                        ),
+                    ])
+                    .chain(
+                        [
+                            ("o", DiffViewKind::SideBySide),
+                            ("O", DiffViewKind::Unified),
+                        ]
+                        .into_iter()
+                        .map(|(key, kind)| {
+                            Keymap::new(
+                                key,
+                                format!("Diff ({})", kind.display()),
+                                Dispatch::ShowKeymapLegend(self.diff_keymap_legend_config(kind)),
+                            )
+                        }),
+                    )
+                    .collect_vec(),
+            ),
+        }
+    }
+
+    fn git_history_keymap_legend_config(&self) -> KeymapLegendConfig {
+        KeymapLegendConfig {
+            title: "Git history".to_string(),
+            body: KeymapLegendBody::SingleSection {
+                keymaps: Keymaps::new(
+                    &[Keymap::new(
+                        "l",
+                        "Log".to_string(),
+                        Dispatch::OpenGitLog(None),
+                    )]
+                    .into_iter()
+                    .chain(self.path().map(|path| {
+                        Keymap::new(
+                            "f",
+                            "File history".to_string(),
+                            Dispatch::OpenGitLog(Some(path)),
+                        )
+                    }))
+                    .chain(Some(Keymap::new(
+                        "o",
+                        "Open file at revision".to_string(),
+                        Dispatch::OpenRevisionPicker(RevisionPickerKind::OpenFile),
+                    )))
+                    .collect_vec(),
+                ),
+            },
+        }
+    }
+
+    fn window_keymap_legend_config(&self) -> KeymapLegendConfig {
+        KeymapLegendConfig {
+            title: "Window".to_string(),
+            body: KeymapLegendBody::SingleSection {
+                keymaps: Keymaps::new(
+                    &[
+                        ("s", "Split side by side", SplitOrientation::SideBySide),
+                        ("S", "Split stacked", SplitOrientation::Stacked),
+                    ]
+                    .into_iter()
+                    .map(|(key, description, orientation)| {
+                        Keymap::new(
+                            key,
+                            description.to_string(),
+                            Dispatch::SplitWindow(orientation),
+                        )
+                    })
+                    .chain(
+                        [
+                            ("h", WindowDirection::Left),
+                            ("j", WindowDirection::Down),
+                            ("k", WindowDirection::Up),
+                            ("l", WindowDirection::Right),
+                        ]
+                        .into_iter()
+                        .map(|(key, direction)| {
+                            Keymap::new(
+                                key,
+                                format!("Focus {:?} window", direction).to_lowercase(),
+                                Dispatch::MoveWindowFocus(direction),
+                            )
+                        }),
+                    )
+                    .chain([
+                        Keymap::new(
+                            ">",
+                            "Grow window".to_string(),
+                            Dispatch::ResizeWindow { grow: true },
+                        ),
+                        Keymap::new(
+                            "<",
+                            "Shrink window".to_string(),
+                            Dispatch::ResizeWindow { grow: false },
+                        ),
+                    ])
+                    .collect_vec(),
+                ),
+            },
+        }
+    }
+
+    fn file_format_keymap_legend_config(&self) -> KeymapLegendConfig {
+        KeymapLegendConfig {
+            title: format!("File format ({})", self.buffer().file_format().display()),
+            body: KeymapLegendBody::MultipleSections {
+                sections: [
+                    KeymapLegendSection {
+                        title: "Line ending".to_string(),
+                        keymaps: Keymaps::new(
+                            &[("n", LineEnding::Lf), ("r", LineEnding::Crlf)]
+                                .into_iter()
+                                .map(|(key, line_ending)| {
+                                    Keymap::new(
+                                        key,
+                                        line_ending.display().to_string(),
+                                        Dispatch::ToEditor(SetLineEnding(line_ending)),
+                                    )
+                                })
+                                .collect_vec(),
+                        ),
+                    },
+                    KeymapLegendSection {
+                        title: "Encoding".to_string(),
+                        keymaps: Keymaps::new(
+                            &[
+                                ("u", Encoding::Utf8, false),
+                                ("U", Encoding::Utf8, true),
+                                ("l", Encoding::Latin1, false),
+                                ("w", Encoding::Utf16Le, true),
+                                ("W", Encoding::Utf16Be, true),
+                            ]
+                            .into_iter()
+                            .map(|(key, encoding, byte_order_mark)| {
+                                Keymap::new(
+                                    key,
+                                    format!(
+                                        "{}{}",
+                                        encoding.display(),
+                                        if byte_order_mark { " with BOM" } else { "" }
+                                    ),
+                                    Dispatch::ToEditor(SetEncoding {
+                                        encoding,
+                                        byte_order_mark,
+                                    }),
+                                )
+                            })
+                            .collect_vec(),
+                        ),
+                    },
+                ]
+                .to_vec(),
+            },
+        }
+    }
+
+    fn diff_keymap_legend_config(&self, kind: DiffViewKind) -> KeymapLegendConfig {
+        KeymapLegendConfig {
+            title: format!("Diff ({})", kind.display()),
+            body: KeymapLegendBody::SingleSection {
+                keymaps: Keymaps::new(
+                    &[
+                        ("g", DiffMode::UnstagedAgainstCurrentBranch),
+                        ("G", DiffMode::UnstagedAgainstMainBranch),
+                    ]
+                    .into_iter()
+                    .map(|(key, diff_mode)| {
+                        Keymap::new(
+                            key,
+                            format!("Compare {}", diff_mode.display()),
+                            Dispatch::OpenDiffView {
+                                kind,
+                                source: DiffSource::Git(diff_mode),
+                            },
+                        )
+                    })
+                    .chain([
+                        Keymap::new(
+                            "p",
+                            "Compare selection with clipboard".to_string(),
+                            Dispatch::OpenDiffView {
+                                kind,
+                                source: DiffSource::Clipboard,
+                            },
+                        ),
+                        Keymap::new(
+                            "f",
+                            "Compare with file".to_string(),
+                            Dispatch::OpenDiffFilePicker(kind),
+                        ),
+                        Keymap::new(
+                            "r",
+                            "Compare against revision".to_string(),
+                            Dispatch::OpenRevisionPicker(RevisionPickerKind::DiffView(kind)),
+                        ),
+                    ])
+                    .collect_vec(),
+                ),
+            },
+        }
+    }
+
     fn search_current_selection_keymap(
         &self,
         scope: Scope,
@@ -1080,6 +1691,17 @@ impl Editor {
                             }
                         },
                     ),
+                    Keymap::new(
+                        "x",
+                        "Merge conflict".to_string(),
+                        match scope {
+                            Scope::Global => Dispatch::GetRepoMergeConflicts,
+                            Scope::Local => Dispatch::ToEditor(SetSelectionMode(
+                                if_current_not_found,
+                                MergeConflict,
+                            )),
+                        },
+                    ),
                     Keymap::new(
                         "q",
                         "Quickfix".to_string(),
@@ -1116,6 +1738,14 @@ impl Editor {
                         )
                     }),
                 )
+                .chain(Some(Keymap::new(
+                    "H",
+                    "Git hunk (against revision)".to_string(),
+                    Dispatch::OpenRevisionPicker(RevisionPickerKind::GitHunk {
+                        scope,
+                        if_current_not_found,
+                    }),
+                )))
                 .collect_vec(),
             ),
         };
@@ -1263,13 +1893,47 @@ impl Editor {
         KeymapLegendConfig {
             title: format!("Select Surround ({:?})", kind),
 
-            body: KeymapLegendBody::SingleSection {
-                keymaps: generate_enclosures_keymaps(|enclosure| {
-                    Dispatch::ToEditor(SelectSurround {
-                        enclosure,
-                        kind: kind.clone(),
-                    })
-                }),
+            body: KeymapLegendBody::MultipleSections {
+                sections: [
+                    KeymapLegendSection {
+                        title: "Enclosure".to_string(),
+                        keymaps: generate_enclosures_keymaps(|enclosure| {
+                            Dispatch::ToEditor(SelectSurround {
+                                enclosure,
+                                kind: kind.clone(),
+                            })
+                        }),
+                    },
+                    KeymapLegendSection {
+                        title: "Text object".to_string(),
+                        keymaps: Keymaps::new(
+                            &[
+                                ("f", TextObjectKind::Function),
+                                ("c", TextObjectKind::Class),
+                                ("p", TextObjectKind::Parameter),
+                                ("#", TextObjectKind::Comment),
+                                ("t", TextObjectKind::Test),
+                                ("b", TextObjectKind::Block),
+                            ]
+                            .into_iter()
+                            .map(|(key, text_object_kind)| {
+                                Keymap::new(
+                                    key,
+                                    text_object_kind.display().to_string(),
+                                    Dispatch::ToEditor(SetSelectionMode(
+                                        IfCurrentNotFound::LookForward,
+                                        TextObject {
+                                            kind: text_object_kind,
+                                            surround_kind: kind.clone(),
+                                        },
+                                    )),
+                                )
+                            })
+                            .collect_vec(),
+                        ),
+                    },
+                ]
+                .to_vec(),
             },
         }
     }
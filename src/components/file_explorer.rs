@@ -1,7 +1,14 @@
+use std::collections::{HashMap, HashSet};
+
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use indexmap::IndexSet;
 use itertools::Itertools;
 use my_proc_macros::key;
 
-use crate::app::{Dispatch, Dispatches, YesNoPrompt};
+use crate::{
+    app::{Dispatch, Dispatches, YesNoPrompt},
+    git::{FileStatus, GitRepo},
+};
 use shared::canonicalized_path::CanonicalizedPath;
 
 use super::{
@@ -13,47 +20,269 @@ use super::{
 pub(crate) struct FileExplorer {
     editor: Editor,
     tree: Tree,
+    /// The tree that is rendered, which is `tree` with the filters below applied
+    visible_tree: Tree,
+    working_directory: CanonicalizedPath,
+    /// Only nodes whose name contains this (case-insensitively) are shown
+    filter: String,
+    show_hidden: bool,
+    show_git_ignored: bool,
+    /// Paths selected for batch operations
+    selected_paths: IndexSet<CanonicalizedPath>,
+    /// The markers of paths with uncommitted changes, and of the directories containing them
+    git_markers: HashMap<CanonicalizedPath, &'static str>,
+    /// Used for filtering collapsed directories and hiding git ignored files,
+    /// `None` until it is built in the background.
+    index: Option<Index>,
+    index_requested: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DispatchFileExplorer {
+    ToggleSelection(CanonicalizedPath),
+    ClearSelection,
+    SetFilter(String),
+    ToggleShowHidden,
+    ToggleShowGitIgnored,
 }
 
 impl FileExplorer {
     pub(crate) fn new(path: &CanonicalizedPath) -> anyhow::Result<Self> {
         let tree = Tree::new(path)?;
-        let text = tree.render();
+        let git_markers = git_markers(path);
+        let text = tree.render(&IndexSet::new(), &git_markers);
         let mut editor = Editor::from_text(
             shared::language::from_extension("yaml")
                 .and_then(|language| language.tree_sitter_language()),
             &format!("{}\n", text),
         );
         editor.set_title("File Explorer".to_string());
-        Ok(Self { editor, tree })
+        Ok(Self {
+            editor,
+            visible_tree: tree.clone(),
+            tree,
+            working_directory: path.clone(),
+            filter: String::new(),
+            show_hidden: true,
+            show_git_ignored: true,
+            selected_paths: IndexSet::new(),
+            git_markers,
+            index: None,
+            index_requested: false,
+        })
+    }
+
+    pub(crate) fn handle_dispatch(
+        &mut self,
+        dispatch: DispatchFileExplorer,
+    ) -> anyhow::Result<Dispatches> {
+        match dispatch {
+            DispatchFileExplorer::ToggleSelection(path) => {
+                if !self.selected_paths.shift_remove(&path) {
+                    self.selected_paths.insert(path);
+                }
+            }
+            DispatchFileExplorer::ClearSelection => self.selected_paths.clear(),
+            DispatchFileExplorer::SetFilter(filter) => self.filter = filter,
+            DispatchFileExplorer::ToggleShowHidden => self.show_hidden = !self.show_hidden,
+            DispatchFileExplorer::ToggleShowGitIgnored => {
+                self.show_git_ignored = !self.show_git_ignored
+            }
+        }
+        self.refresh_editor()?;
+        Ok(self.request_index())
+    }
+
+    /// The index is only built once it is needed, that is when filtering or hiding git ignored files.
+    fn request_index(&mut self) -> Dispatches {
+        let needs_index = !self.filter.is_empty() || !self.show_git_ignored;
+        if needs_index && self.index.is_none() && !self.index_requested {
+            self.index_requested = true;
+            Dispatches::one(Dispatch::BuildFileExplorerIndex)
+        } else {
+            Dispatches::default()
+        }
+    }
+
+    pub(crate) fn set_index(&mut self, index: Index) -> anyhow::Result<()> {
+        // The index might have been built before the working directory changed
+        if index.working_directory != self.working_directory {
+            return Ok(());
+        }
+        self.index = Some(index);
+        self.index_requested = false;
+        self.refresh_editor()
+    }
+
+    pub(crate) fn selected_paths(&self) -> Vec<CanonicalizedPath> {
+        self.selected_paths.iter().cloned().collect()
     }
 
     pub(crate) fn reveal(&mut self, path: &CanonicalizedPath) -> anyhow::Result<Dispatches> {
         let tree = std::mem::take(&mut self.tree);
         self.tree = tree.reveal(path)?;
         self.refresh_editor()?;
-        if let Some(index) = self.tree.find_index(path) {
+        if let Some(index) = self.visible_tree.find_index(path) {
             self.editor_mut().select_line_at(index)
         } else {
             Ok(Dispatches::default())
         }
     }
 
-    pub(crate) fn refresh(&mut self, working_directory: &CanonicalizedPath) -> anyhow::Result<()> {
+    /// The index is rebuilt, because paths might have been added, moved or deleted.
+    pub(crate) fn refresh(
+        &mut self,
+        working_directory: &CanonicalizedPath,
+    ) -> anyhow::Result<Dispatches> {
         let tree = std::mem::take(&mut self.tree);
         self.tree = tree.refresh(working_directory)?;
+        self.working_directory = working_directory.clone();
+        self.git_markers = git_markers(working_directory);
+        self.selected_paths
+            .retain(|path| path.to_path_buf().exists());
+        self.index = None;
+        self.index_requested = false;
         self.refresh_editor()?;
-        Ok(())
+        Ok(self.request_index())
     }
 
     fn refresh_editor(&mut self) -> anyhow::Result<()> {
-        let text = self.tree.render();
+        let index = self.index.as_ref();
+        let keep = |node: &Node| {
+            (self.show_hidden || !node.name.starts_with('.'))
+                && (self.show_git_ignored || index.map_or(true, |index| index.contains(&node.path)))
+        };
+        let visible_tree = self.tree.filter(&keep, &self.filter.to_lowercase(), index);
+        let text = visible_tree.render(&self.selected_paths, &self.git_markers);
+        self.visible_tree = visible_tree;
         self.editor_mut().set_content(&text)
     }
 
+    fn open_current_node(&mut self) -> anyhow::Result<Dispatches> {
+        if let Some(node) = self.get_current_node()? {
+            match node.kind {
+                NodeKind::File => Ok([
+                    Dispatch::CloseCurrentWindow,
+                    Dispatch::OpenFile(node.path.clone()),
+                ]
+                .to_vec()
+                .into()),
+                NodeKind::Directory { .. } => {
+                    let tree = std::mem::take(&mut self.tree);
+                    self.tree = tree.toggle(&node.path, |open| !open);
+                    self.refresh_editor()?;
+                    Ok(Vec::new().into())
+                }
+            }
+        } else {
+            Ok(Vec::new().into())
+        }
+    }
+
     fn get_current_node(&self) -> anyhow::Result<Option<Node>> {
         let position = self.editor().get_cursor_position()?;
-        Ok(self.tree.get(position.line))
+        Ok(self.visible_tree.get(position.line))
+    }
+}
+
+/// Returns the markers of the paths with uncommitted changes,
+/// where every ancestor directory of a changed path is marked with "●".
+fn git_markers(working_directory: &CanonicalizedPath) -> HashMap<CanonicalizedPath, &'static str> {
+    let Ok(statuses) = GitRepo::try_from(working_directory).and_then(|repo| repo.file_statuses())
+    else {
+        return HashMap::new();
+    };
+    let working_directory = working_directory.to_path_buf();
+    let mut markers = HashMap::new();
+    for (path, status) in statuses {
+        let directories = path
+            .to_path_buf()
+            .ancestors()
+            .skip(1)
+            .take_while(|directory| directory.starts_with(working_directory))
+            .filter(|directory| *directory != working_directory.as_path())
+            .filter_map(|directory| directory.to_path_buf().try_into().ok())
+            .collect_vec();
+        for directory in directories {
+            markers.entry(directory).or_insert("●");
+        }
+        let marker = match status {
+            FileStatus::Modified => "M",
+            FileStatus::Added => "A",
+            FileStatus::Untracked => "U",
+        };
+        markers.insert(path, marker);
+    }
+    markers
+}
+
+/// Every path of the working directory that is not ignored, grouped by their parent directory.
+/// Walking a large project is slow, so it is built in the background, see `Dispatch::BuildFileExplorerIndex`.
+#[derive(Debug)]
+pub(crate) struct Index {
+    working_directory: CanonicalizedPath,
+    children: HashMap<CanonicalizedPath, Vec<Node>>,
+    paths: HashSet<CanonicalizedPath>,
+}
+
+impl Index {
+    pub(crate) fn new(working_directory: &CanonicalizedPath) -> Self {
+        let mut children: HashMap<CanonicalizedPath, Vec<Node>> = HashMap::new();
+        let entries = ignore::WalkBuilder::new(working_directory)
+            .hidden(false)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .build()
+            .flatten()
+            .filter(|entry| entry.depth() > 0);
+        for entry in entries {
+            let Some((parent, path)): Option<(CanonicalizedPath, CanonicalizedPath)> = entry
+                .path()
+                .parent()
+                .and_then(|parent| Some((parent.try_into().ok()?, entry.path().try_into().ok()?)))
+            else {
+                continue;
+            };
+            let kind = if entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_dir())
+            {
+                NodeKind::Directory {
+                    open: false,
+                    children: None,
+                }
+            } else {
+                NodeKind::File
+            };
+            children.entry(parent).or_default().push(Node {
+                name: entry.file_name().to_string_lossy().to_string(),
+                path,
+                kind,
+            });
+        }
+        for nodes in children.values_mut() {
+            nodes.sort_by(|a, b| a.name.cmp(&b.name))
+        }
+        let paths = children
+            .values()
+            .flatten()
+            .map(|node| node.path.clone())
+            .collect();
+        Self {
+            working_directory: working_directory.clone(),
+            children,
+            paths,
+        }
+    }
+
+    /// Returns false if `path` is ignored.
+    fn contains(&self, path: &CanonicalizedPath) -> bool {
+        self.paths.contains(path)
+    }
+
+    fn tree(&self, directory: &CanonicalizedPath) -> Tree {
+        Tree {
+            nodes: self.children.get(directory).cloned().unwrap_or_default(),
+        }
     }
 }
 
@@ -81,7 +310,7 @@ fn get_nodes(path: &CanonicalizedPath) -> anyhow::Result<Vec<Node>> {
         .collect())
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Debug)]
 struct Tree {
     nodes: Vec<Node>,
 }
@@ -214,20 +443,78 @@ impl Tree {
         })
     }
 
-    fn render_with_indent(&self, indent: usize) -> String {
+    /// Keep only the nodes satisfying `keep`, and whose name contains `query`.
+    /// Directories are kept if any of their descendants is kept, in which case they are shown open,
+    /// even if they were never expanded.
+    /// The descendants of directories that were never expanded are looked up from `index`.
+    /// `query` should be lowercased.
+    fn filter(&self, keep: &impl Fn(&Node) -> bool, query: &str, index: Option<&Index>) -> Tree {
+        let nodes = self
+            .nodes
+            .iter()
+            .filter(|node| keep(node))
+            .filter_map(|node| {
+                let matches = node.name.to_lowercase().contains(query);
+                match &node.kind {
+                    NodeKind::File => matches.then(|| node.clone()),
+                    NodeKind::Directory { open, children } if matches => {
+                        // Every children of a matching directory is shown
+                        Some(Node {
+                            kind: NodeKind::Directory {
+                                open: *open,
+                                children: children
+                                    .as_ref()
+                                    .map(|tree| tree.filter(keep, "", index)),
+                            },
+                            ..node.clone()
+                        })
+                    }
+                    NodeKind::Directory { children, .. } => {
+                        let children = children
+                            .clone()
+                            .or_else(|| Some(index?.tree(&node.path)))?
+                            .filter(keep, query, index);
+                        (!children.nodes.is_empty()).then(|| Node {
+                            kind: NodeKind::Directory {
+                                open: true,
+                                children: Some(children),
+                            },
+                            ..node.clone()
+                        })
+                    }
+                }
+            })
+            .collect();
+        Tree { nodes }
+    }
+
+    fn render_with_indent(
+        &self,
+        indent: usize,
+        selected_paths: &IndexSet<CanonicalizedPath>,
+        git_markers: &HashMap<CanonicalizedPath, &'static str>,
+    ) -> String {
         self.nodes
             .iter()
             .map(|node| {
+                let git_marker = git_markers
+                    .get(&node.path)
+                    .map(|marker| format!(" {}", marker))
+                    .unwrap_or_default();
                 let content = match &node.kind {
-                    NodeKind::File => format!("{}  {}", node.path.icon(), node.name),
+                    NodeKind::File => {
+                        format!("{}  {}{}", node.path.icon(), node.name, git_marker)
+                    }
                     NodeKind::Directory { open, children } => {
                         let icon = if *open { "📂" } else { "📁" };
-                        let head = format!("{}  {}{}", icon, node.name, "/");
+                        let head = format!("{}  {}{}{}", icon, node.name, "/", git_marker);
 
                         let tail = if *open {
                             children
                                 .as_ref()
-                                .map(|tree| tree.render_with_indent(indent + 1))
+                                .map(|tree| {
+                                    tree.render_with_indent(indent + 1, selected_paths, git_markers)
+                                })
                                 .unwrap_or_default()
                         } else {
                             String::new()
@@ -239,14 +526,23 @@ impl Tree {
                         }
                     }
                 };
-                format!("{} - {}", "  ".repeat(indent), content)
+                let selection_marker = if selected_paths.contains(&node.path) {
+                    "✓ "
+                } else {
+                    ""
+                };
+                format!("{} - {}{}", "  ".repeat(indent), selection_marker, content)
             })
             .collect::<Vec<_>>()
             .join("\n")
     }
 
-    fn render(&self) -> String {
-        self.render_with_indent(0)
+    fn render(
+        &self,
+        selected_paths: &IndexSet<CanonicalizedPath>,
+        git_markers: &HashMap<CanonicalizedPath, &'static str>,
+    ) -> String {
+        self.render_with_indent(0, selected_paths, git_markers)
     }
 
     fn reveal(self, path: &CanonicalizedPath) -> anyhow::Result<Self> {
@@ -300,13 +596,13 @@ impl Tree {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 struct Node {
     name: String,
     path: CanonicalizedPath,
     kind: NodeKind,
 }
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 enum NodeKind {
     File,
     Directory {
@@ -329,29 +625,94 @@ impl Component for FileExplorer {
         self.get_current_node()
             .ok()
             .flatten()
-            .map(|node| super::keymap_legend::KeymapLegendSection {
-                title: "File Explorer".to_string(),
-                keymaps: Keymaps::new(&[
-                    Keymap::new(
-                        "a",
-                        "Add file (or postfix with / for folder)".to_string(),
-                        Dispatch::OpenAddPathPrompt(node.path.clone()),
-                    ),
-                    Keymap::new(
-                        "d",
-                        "Delete path".to_string(),
-                        Dispatch::OpenYesNoPrompt(YesNoPrompt {
-                            title: format!("Delete \"{}\"?", node.path.display_absolute()),
-                            yes: Box::new(Dispatch::DeletePath(node.path.clone())),
-                        }),
-                    ),
-                    Keymap::new(
-                        "m",
-                        "Move path".to_string(),
-                        Dispatch::OpenMoveFilePrompt(node.path.clone()),
-                    ),
-                    Keymap::new("r", "Refresh".to_string(), Dispatch::RefreshFileExplorer),
-                ]),
+            .map(|node| {
+                let selected_paths = self.selected_paths();
+                let (delete, r#move) = if selected_paths.is_empty() {
+                    (
+                        Keymap::new(
+                            "d",
+                            "Delete path".to_string(),
+                            Dispatch::OpenYesNoPrompt(YesNoPrompt {
+                                title: format!("Delete \"{}\"?", node.path.display_absolute()),
+                                yes: Box::new(Dispatch::DeletePath(node.path.clone())),
+                            }),
+                        ),
+                        Keymap::new(
+                            "m",
+                            "Move path".to_string(),
+                            Dispatch::OpenMoveFilePrompt(node.path.clone()),
+                        ),
+                    )
+                } else {
+                    (
+                        Keymap::new(
+                            "d",
+                            format!("Delete {} selected paths", selected_paths.len()),
+                            Dispatch::OpenYesNoPrompt(YesNoPrompt {
+                                title: format!("Delete {} selected paths?", selected_paths.len()),
+                                yes: Box::new(Dispatch::DeletePaths(selected_paths.clone())),
+                            }),
+                        ),
+                        Keymap::new(
+                            "m",
+                            format!("Move {} selected paths", selected_paths.len()),
+                            Dispatch::OpenMovePathsPrompt(selected_paths),
+                        ),
+                    )
+                };
+                let toggle = |show: bool, what: &str| {
+                    format!("{} {}", if show { "Hide" } else { "Show" }, what)
+                };
+                super::keymap_legend::KeymapLegendSection {
+                    title: "File Explorer".to_string(),
+                    keymaps: Keymaps::new(&[
+                        Keymap::new(
+                            "a",
+                            "Add file (or postfix with / for folder)".to_string(),
+                            Dispatch::OpenAddPathPrompt(node.path.clone()),
+                        ),
+                        Keymap::new(
+                            "c",
+                            "Copy path".to_string(),
+                            Dispatch::OpenCopyPathPrompt(node.path.clone()),
+                        ),
+                        Keymap::new(
+                            "y",
+                            "Duplicate path".to_string(),
+                            Dispatch::DuplicatePath(node.path.clone()),
+                        ),
+                        delete,
+                        r#move,
+                        Keymap::new("r", "Refresh".to_string(), Dispatch::RefreshFileExplorer),
+                        Keymap::new(
+                            "k",
+                            "Select/unselect path".to_string(),
+                            Dispatch::ToFileExplorer(DispatchFileExplorer::ToggleSelection(
+                                node.path.clone(),
+                            )),
+                        ),
+                        Keymap::new(
+                            "K",
+                            "Clear selection".to_string(),
+                            Dispatch::ToFileExplorer(DispatchFileExplorer::ClearSelection),
+                        ),
+                        Keymap::new(
+                            "n",
+                            "Filter".to_string(),
+                            Dispatch::OpenFileExplorerFilterPrompt(self.filter.clone()),
+                        ),
+                        Keymap::new(
+                            ".",
+                            toggle(self.show_hidden, "hidden files"),
+                            Dispatch::ToFileExplorer(DispatchFileExplorer::ToggleShowHidden),
+                        ),
+                        Keymap::new(
+                            ",",
+                            toggle(self.show_git_ignored, "git ignored files"),
+                            Dispatch::ToFileExplorer(DispatchFileExplorer::ToggleShowGitIgnored),
+                        ),
+                    ]),
+                }
             })
             .into_iter()
             .collect()
@@ -363,36 +724,31 @@ impl Component for FileExplorer {
         event: event::KeyEvent,
     ) -> Result<Dispatches, anyhow::Error> {
         match event {
-            key!("enter") => {
-                if let Some(node) = self.get_current_node()? {
-                    match node.kind {
-                        NodeKind::File => Ok([
-                            Dispatch::CloseCurrentWindow,
-                            Dispatch::OpenFile(node.path.clone()),
-                        ]
-                        .to_vec()
-                        .into()),
-                        NodeKind::Directory { .. } => {
-                            let tree = std::mem::take(&mut self.tree);
-                            self.tree = tree.toggle(&node.path, |open| !open);
-                            self.refresh_editor()?;
-                            Ok(Vec::new().into())
-                        }
-                    }
-                } else {
-                    Ok(Vec::new().into())
-                }
-            }
+            key!("enter") => self.open_current_node(),
             _ => self.editor.handle_key_event(context, event),
         }
     }
+
+    /// Clicking on a path opens the file, or toggles the directory.
+    fn handle_mouse_event(&mut self, mouse_event: MouseEvent) -> anyhow::Result<Dispatches> {
+        let clicked = matches!(mouse_event.kind, MouseEventKind::Down(MouseButton::Left))
+            && self.editor.mouse_event_char_index(&mouse_event).is_some();
+        let dispatches = self.editor.handle_mouse_event(mouse_event)?;
+        if clicked {
+            Ok(dispatches.chain(self.open_current_node()?))
+        } else {
+            Ok(dispatches)
+        }
+    }
 }
 
 #[cfg(test)]
 mod test_file_explorer {
     use my_proc_macros::{key, keys};
 
-    use crate::test_app::*;
+    use crate::{app::AppMessage, test_app::*};
+
+    use super::DispatchFileExplorer;
 
     #[test]
     fn reveal() -> Result<(), anyhow::Error> {
@@ -419,6 +775,95 @@ mod test_file_explorer {
         })
     }
 
+    #[test]
+    fn filter() -> anyhow::Result<()> {
+        execute_test(|s| {
+            Box::new([
+                App(RevealInExplorer(s.main_rs())),
+                App(ToFileExplorer(DispatchFileExplorer::SetFilter(
+                    "FOO".to_string(),
+                ))),
+                Expect(FileExplorerContent(
+                    "
+ - 📂  src/ :
+   - 🦀  foo.rs
+"
+                    .trim_matches('\n')
+                    .to_string(),
+                )),
+            ])
+        })
+    }
+
+    #[test]
+    fn filter_searches_collapsed_directories() -> anyhow::Result<()> {
+        execute_test(|s| {
+            Box::new([
+                App(RevealInExplorer(s.temp_dir().join("Cargo.toml").unwrap())),
+                App(ToFileExplorer(DispatchFileExplorer::SetFilter(
+                    "foo".to_string(),
+                ))),
+                // Collapsed directories are searched using the index
+                WaitForAppMessage(|message| matches!(message, AppMessage::FileExplorerIndex(_))),
+                Expect(FileExplorerContent(
+                    "
+ - 📂  src/ :
+   - 🦀  foo.rs
+"
+                    .trim_matches('\n')
+                    .to_string(),
+                )),
+            ])
+        })
+    }
+
+    #[test]
+    fn hide_git_ignored_files() -> anyhow::Result<()> {
+        execute_test(|s| {
+            let target = s.temp_dir().to_path_buf().join("target");
+            Box::new([
+                ExpectCustom(Box::new(move || {
+                    std::fs::create_dir_all(&target).unwrap();
+                    std::fs::write(target.join("ignored.txt"), "").unwrap();
+                })),
+                App(RefreshFileExplorer),
+                App(ToFileExplorer(DispatchFileExplorer::ToggleShowGitIgnored)),
+                WaitForAppMessage(|message| matches!(message, AppMessage::FileExplorerIndex(_))),
+                Expect(FileExplorerContent(
+                    "
+ - 🙈  .gitignore
+ - 🔒  Cargo.lock
+ - 📄  Cargo.toml
+ - 📁  src/ :
+"
+                    .trim_matches('\n')
+                    .to_string(),
+                )),
+            ])
+        })
+    }
+
+    #[test]
+    fn duplicate_path() -> anyhow::Result<()> {
+        execute_test(|s| {
+            Box::new([
+                App(DuplicatePath(s.foo_rs())),
+                App(ToFileExplorer(DispatchFileExplorer::SetFilter(
+                    "foo".to_string(),
+                ))),
+                Expect(FileExplorerContent(
+                    "
+ - 📂  src/ ● :
+   - 🦀  foo copy.rs U
+   - 🦀  foo.rs
+"
+                    .trim_matches('\n')
+                    .to_string(),
+                )),
+            ])
+        })
+    }
+
     #[test]
     fn move_path() -> anyhow::Result<()> {
         execute_test(|s| {
@@ -170,7 +170,7 @@ impl KeymapLegendConfig {
         {
             let conflicting_keymaps = keymaps
                 .iter()
-                .group_by(|keymap| keymap.key)
+                .group_by(|keymap| &keymap.key)
                 .into_iter()
                 .map(|(key, keymaps)| (key, keymaps.collect_vec()))
                 .filter(|(_, keymaps)| keymaps.len() > 1)
@@ -190,7 +190,7 @@ impl KeymapLegendConfig {
                 let keymap_key = RegexHighlightRule {
                     regex: Regex::new(&format!(
                         "(?<key>{})(?<arrow>{})({})",
-                        regex::escape(keymap.key),
+                        regex::escape(&keymap.key),
                         BETWEEN_KEY_AND_DESCRIPTION,
                         regex::escape(&keymap.description),
                     ))
@@ -226,7 +226,7 @@ impl KeymapLegendConfig {
                         regex: regex::Regex::new(
                             &(format!(
                                 "{}{}{}",
-                                regex::escape(keymap.key),
+                                regex::escape(&keymap.key),
                                 BETWEEN_KEY_AND_DESCRIPTION,
                                 marked_description
                             )),
@@ -247,16 +247,16 @@ impl KeymapLegendConfig {
 
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct Keymap {
-    key: &'static str,
+    key: String,
     description: String,
     event: KeyEvent,
     dispatch: Dispatch,
 }
 
 impl Keymap {
-    pub(crate) fn new(key: &'static str, description: String, dispatch: Dispatch) -> Keymap {
+    pub(crate) fn new(key: &str, description: String, dispatch: Dispatch) -> Keymap {
         Keymap {
-            key,
+            key: key.to_string(),
             description,
             dispatch,
             event: parse_key_event(key).unwrap(),
@@ -271,6 +271,18 @@ impl Keymap {
     pub(crate) fn event(&self) -> &KeyEvent {
         &self.event
     }
+
+    pub(crate) fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub(crate) fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub(crate) fn dispatch(&self) -> &Dispatch {
+        &self.dispatch
+    }
 }
 
 impl KeymapLegend {
@@ -279,7 +291,7 @@ impl KeymapLegend {
         let duplicates = config
             .keymaps()
             .into_iter()
-            .duplicates_by(|keymap| keymap.key)
+            .duplicates_by(|keymap| &keymap.key)
             .collect_vec();
 
         if !duplicates.is_empty() {
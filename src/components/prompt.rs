@@ -24,7 +24,7 @@ pub(crate) struct Prompt {
     on_enter: DispatchPrompt,
     enter_selects_first_matching_item: bool,
     prompt_history_key: PromptHistoryKey,
-    fire_dispatches_on_change: Option<Dispatches>,
+    fire_dispatches_on_change: Option<FireDispatchesOnChange>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -35,17 +35,28 @@ pub(crate) struct PromptConfig {
     pub(crate) enter_selects_first_matching_item: bool,
     pub(crate) leaves_current_line_empty: bool,
 
-    /// If defined, the `Dispatches` here is used for undoing the dispatches fired on change.
-    pub(crate) fire_dispatches_on_change: Option<Dispatches>,
+    /// If defined, the dispatches of the current item are fired whenever the input changes.
+    pub(crate) fire_dispatches_on_change: Option<FireDispatchesOnChange>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FireDispatchesOnChange {
+    /// Used for undoing the dispatches fired on change when the prompt is cancelled.
+    pub(crate) undo: Dispatches,
+    /// Fire the dispatches of `on_enter` with the current line if there is no matching item.
+    pub(crate) on_enter_if_unmatched: bool,
 }
 
 #[derive(Hash, PartialEq, Eq, Debug, Clone, Copy)]
 pub(crate) enum PromptHistoryKey {
     MoveToIndex,
+    UndoToMinutesAgo,
     Search(Scope),
     Rename,
     AddPath,
     MovePath,
+    CopyPath,
+    FilterFileExplorer,
     Symbol,
     OpenFile,
     FilterGlob(GlobalSearchFilterGlob),
@@ -54,7 +65,10 @@ pub(crate) enum PromptHistoryKey {
     #[cfg(test)]
     Null,
     Theme,
+    CommandPalette,
     PipeToShell,
+    RunTask,
+    GitRevision,
     FilterSelectionsMatchingSearch {
         maintain: bool,
     },
@@ -143,8 +157,12 @@ impl Component for Prompt {
     ) -> anyhow::Result<Dispatches> {
         match event {
             key!("esc") if self.editor().mode == Mode::Normal => {
-                Ok(Dispatches::one(Dispatch::CloseCurrentWindow)
-                    .chain(self.fire_dispatches_on_change.clone().unwrap_or_default()))
+                Ok(Dispatches::one(Dispatch::CloseCurrentWindow).chain(
+                    self.fire_dispatches_on_change
+                        .as_ref()
+                        .map(|config| config.undo.clone())
+                        .unwrap_or_default(),
+                ))
             }
             key!("tab") => {
                 if self.editor.completion_dropdown_opened() {
@@ -180,19 +198,38 @@ impl Component for Prompt {
             }
             _ => {
                 let dispatches = self.editor.handle_key_event(context, event)?;
-                Ok(if self.fire_dispatches_on_change.is_some() {
-                    dispatches.chain(
-                        self.editor
-                            .completion_dropdown_current_item()
-                            .map(|item| item.dispatches)
-                            .unwrap_or_default(),
-                    )
+                Ok(if let Some(config) = &self.fire_dispatches_on_change {
+                    let dispatches_on_change = match self.editor.completion_dropdown_current_item()
+                    {
+                        Some(item) => item.dispatches,
+                        None if config.on_enter_if_unmatched => self
+                            .on_enter
+                            .to_dispatches(&self.editor().current_line()?)?,
+                        None => Default::default(),
+                    };
+                    dispatches.chain(dispatches_on_change)
                 } else {
                     dispatches
                 })
             }
         }
     }
+
+    fn handle_dropdown_click(
+        &mut self,
+        context: &Context,
+        line_index: usize,
+    ) -> anyhow::Result<Dispatches> {
+        if !self.editor.change_completion_item_by_line(line_index) {
+            return Ok(Default::default());
+        }
+        let dispatches = if self.enter_selects_first_matching_item {
+            Default::default()
+        } else {
+            self.handle_key_event(context, key!("tab"))?
+        };
+        Ok(dispatches.chain(self.handle_key_event(context, key!("enter"))?))
+    }
 }
 
 #[cfg(test)]
@@ -428,9 +465,13 @@ mod test_prompt {
                         title: "".to_string(),
                         enter_selects_first_matching_item: true,
                         leaves_current_line_empty: true,
-                        fire_dispatches_on_change: Some(Dispatches::one(Dispatch::ShowEditorInfo(
-                            Info::new("".to_string(), "back to square one".to_string()),
-                        ))),
+                        fire_dispatches_on_change: Some(FireDispatchesOnChange {
+                            undo: Dispatches::one(Dispatch::ShowEditorInfo(Info::new(
+                                "".to_string(),
+                                "back to square one".to_string(),
+                            ))),
+                            on_enter_if_unmatched: false,
+                        }),
                     },
                 }),
                 App(HandleKeyEvents(keys!("f o o _").to_vec())),
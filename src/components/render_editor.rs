@@ -12,9 +12,15 @@ use crate::{
         editor::Mode,
     },
     context::Context,
-    grid::{CellUpdate, Grid, LineUpdate, RenderContentLineNumber, StyleKey},
+    git::conflict::parse_conflicts,
+    grid::{
+        line_number_width, CellUpdate, Grid, LineUpdate, RenderContentLineNumber, StyleKey,
+        LINE_NUMBER_SEPARATOR_WIDTH,
+    },
+    position::Position,
     selection::{CharIndex, Selection},
     selection_mode::{self, ByteRange},
+    soft_wrap,
     style::Style,
     themes::Theme,
 };
@@ -32,18 +38,13 @@ impl Editor {
         let content = rope.to_string();
 
         let diagnostics = buffer.diagnostics();
+        let breakpoints = buffer.breakpoint_lines();
 
         let len_lines = rope.len_lines().max(1) as u16;
         let (hidden_parent_lines, visible_parent_lines) =
             self.get_parent_lines().unwrap_or_default();
         let top_offset = hidden_parent_lines.len() as u16;
         let scroll_offset = self.scroll_offset();
-        let visible_lines = rope
-            .lines()
-            .enumerate()
-            .skip(scroll_offset as usize)
-            .take(height as usize)
-            .map(|(line_index, slice)| (line_index, slice.to_string()));
 
         let visible_lines_grid: Grid = Grid::new(Dimension { height, width });
 
@@ -177,7 +178,7 @@ impl Editor {
                 ),
             }
         });
-        let extra_decorations = buffer.decorations().iter().flat_map(|decoration| {
+        let extra_decorations = buffer.decorations().flat_map(|decoration| {
             Some(HighlightSpan {
                 set_symbol: None,
                 is_cursor: false,
@@ -191,6 +192,25 @@ impl Editor {
             })
         });
 
+        let merge_conflicts = parse_conflicts(&content).into_iter().flat_map(|conflict| {
+            conflict
+                .markers
+                .into_iter()
+                .map(|range| (range, ConflictMarker))
+                .chain([
+                    (conflict.ours, ConflictOurs),
+                    (conflict.theirs, ConflictTheirs),
+                ])
+                .chain(conflict.base.map(|base| (base, ConflictBase)))
+                .map(|(range, style_key)| HighlightSpan {
+                    set_symbol: None,
+                    is_cursor: false,
+                    range: HighlightSpanRange::ByteRange(range),
+                    source: Source::StyleKey(style_key),
+                })
+                .collect_vec()
+        });
+
         let hidden_parent_line_ranges = hidden_parent_lines
             .iter()
             .map(|line| line.line..line.line + 1);
@@ -278,6 +298,7 @@ impl Editor {
             .chain(visible_parent_lines)
             .chain(filtered_highlighted_spans)
             .chain(extra_decorations)
+            .chain(merge_conflicts)
             .chain(possible_selections)
             .chain(Some(primary_selection))
             .chain(secondary_selection)
@@ -301,10 +322,11 @@ impl Editor {
         };
 
         let visible_lines_grid = visible_lines_grid.render_content(
-            &visible_lines.map(|(_, line)| line).join(""),
+            &self.visible_lines_content(),
             RenderContentLineNumber::LineNumber {
                 start_line_index: scroll_offset as usize,
                 max_line_number: len_lines as usize,
+                breakpoints: breakpoints.clone(),
             },
             visible_lines_updates
                 .clone()
@@ -352,6 +374,7 @@ impl Editor {
                         RenderContentLineNumber::LineNumber {
                             start_line_index: line.line,
                             max_line_number: len_lines as usize,
+                            breakpoints: breakpoints.clone(),
                         },
                         updates,
                         Default::default(),
@@ -361,14 +384,12 @@ impl Editor {
             )
         };
 
-        let cursor_beyond_view_bottom =
-            if let Some(cursor_position) = visible_lines_grid.get_cursor_position() {
-                cursor_position
-                    .line
-                    .saturating_sub(height.saturating_sub(1).saturating_sub(top_offset) as usize)
-            } else {
-                0
-            };
+        let cursor_beyond_view_bottom = visible_lines_grid
+            .get_cursor_position()
+            .map(|cursor_position| {
+                cursor_beyond_view_bottom(cursor_position.line, height, top_offset as usize)
+            })
+            .unwrap_or(0);
         let grid = {
             let visible_lines_grid = visible_lines_grid.clamp_top(cursor_beyond_view_bottom);
             let clamp_bottom_by = visible_lines_grid
@@ -439,6 +460,77 @@ impl Editor {
             [line_range].to_vec(),
         )
     }
+
+    /// Convert a `position` relative to the origin of this editor into the
+    /// index of the character rendered at that cell, following the layout of `get_grid`.
+    ///
+    /// Returns `None` if the position is on the title, or below the last line.
+    pub(crate) fn screen_position_to_char_index(&self, position: Position) -> Option<CharIndex> {
+        let Dimension { height, width } = self.render_area();
+        let title_height = self.dimension().height.saturating_sub(height) as usize;
+        let row = position.line.checked_sub(title_height)?;
+        let buffer = self.buffer();
+        let gutter_width =
+            line_number_width(buffer.rope().len_lines()) + LINE_NUMBER_SEPARATOR_WIDTH;
+        let column = position.column.saturating_sub(gutter_width);
+        let content_container_width = (width as usize).saturating_sub(gutter_width);
+        let (hidden_parent_lines, _) = self.get_parent_lines().unwrap_or_default();
+        let position = if let Some(line) = hidden_parent_lines.get(row) {
+            let position = soft_wrap::soft_wrap(&line.content, content_container_width)
+                .uncalibrate(Position::new(0, column))?;
+            Position::new(line.line, position.column)
+        } else {
+            let scroll_offset = self.scroll_offset() as usize;
+            let wrapped_lines =
+                soft_wrap::soft_wrap(&self.visible_lines_content(), content_container_width);
+            let top_offset = hidden_parent_lines.len();
+            let cursor_beyond_view_bottom = self
+                .get_cursor_position()
+                .ok()
+                .filter(|cursor_position| cursor_position.line >= scroll_offset)
+                .and_then(|cursor_position| {
+                    wrapped_lines
+                        .calibrate(cursor_position.move_up(scroll_offset))
+                        .ok()?
+                        .first()
+                })
+                .map(|cursor_position| {
+                    cursor_beyond_view_bottom(cursor_position.line, height, top_offset)
+                })
+                .unwrap_or(0);
+            let position = wrapped_lines.uncalibrate(Position::new(
+                row - top_offset + cursor_beyond_view_bottom,
+                column,
+            ))?;
+            Position::new(position.line + scroll_offset, position.column)
+        };
+        // Clicks below the last line select the last character
+        let last_char_index = CharIndex(buffer.len_chars().saturating_sub(1));
+        Some(buffer.position_to_char(position).ok()?.min(last_char_index))
+    }
+
+    /// The content of the lines from the scroll offset that fit in the render area,
+    /// before the hidden parent lines are placed above them.
+    fn visible_lines_content(&self) -> String {
+        self.buffer()
+            .rope()
+            .lines()
+            .skip(self.scroll_offset() as usize)
+            .take(self.render_area().height as usize)
+            .map(|slice| slice.to_string())
+            .join("")
+    }
+}
+
+/// The number of wrapped lines that the visible lines are scrolled by,
+/// so that the cursor on `cursor_line` stays within the render area of `height`,
+/// below the `top_offset` hidden parent lines.
+fn cursor_beyond_view_bottom(cursor_line: usize, height: u16, top_offset: usize) -> usize {
+    cursor_line.saturating_sub(
+        (height as usize)
+            .saturating_sub(1)
+            .saturating_sub(top_offset),
+    )
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -133,6 +133,18 @@ impl Component for SuggestiveEditor {
             }))
     }
 
+    fn handle_dropdown_click(
+        &mut self,
+        context: &Context,
+        line_index: usize,
+    ) -> anyhow::Result<Dispatches> {
+        if self.change_completion_item_by_line(line_index) {
+            self.handle_key_event(context, key!("tab"))
+        } else {
+            Ok(Default::default())
+        }
+    }
+
     fn contextual_keymaps(&self) -> Vec<super::keymap_legend::KeymapLegendSection> {
         [KeymapLegendSection {
             title: "LSP".to_string(),
@@ -203,6 +215,12 @@ impl SuggestiveEditor {
         self.completion_dropdown.current_item()
     }
 
+    /// Returns `false` if no completion item is rendered at the given `line_index`.
+    pub(crate) fn change_completion_item_by_line(&mut self, line_index: usize) -> bool {
+        self.completion_dropdown_opened()
+            && self.completion_dropdown.change_index_by_line(line_index)
+    }
+
     pub(crate) fn completion_dropdown_opened(&self) -> bool {
         !self.completion_dropdown.items().is_empty()
     }
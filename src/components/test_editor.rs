@@ -7,6 +7,8 @@ use crate::components::editor::Movement::*;
 
 use crate::context::LocalSearchConfigMode;
 use crate::context::Search;
+use crate::file_format::LineEnding;
+use crate::git::conflict::ConflictResolution;
 use crate::list::grep::RegexConfig;
 use crate::lsp::process::LspNotification;
 use crate::quickfix_list::Location;
@@ -14,6 +16,7 @@ use crate::quickfix_list::QuickfixListItem;
 use crate::rectangle::Rectangle;
 
 use crate::selection::CharIndex;
+use crate::selection_mode::text_object::TextObjectKind;
 use crate::style::Style;
 use crate::test_app::*;
 
@@ -27,6 +30,7 @@ use crate::{
 
 use itertools::Itertools;
 use my_proc_macros::{hex, key, keys};
+use shared::canonicalized_path::CanonicalizedPath;
 
 use SelectionMode::*;
 
@@ -454,6 +458,36 @@ fn undo_tree() -> anyhow::Result<()> {
     })
 }
 
+#[test]
+fn resolve_merge_conflicts() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "a\n<<<<<<< HEAD\nb\n=======\nc\n>>>>>>> x\nd\n<<<<<<< HEAD\ne\n=======\nf\n>>>>>>> x\n"
+                    .to_string(),
+            )),
+            Editor(SetSelectionMode(
+                IfCurrentNotFound::LookForward,
+                MergeConflict,
+            )),
+            Expect(CurrentSelectedTexts(&[
+                "<<<<<<< HEAD\nb\n=======\nc\n>>>>>>> x\n",
+            ])),
+            Editor(ResolveConflicts(ConflictResolution::Theirs)),
+            Expect(CurrentComponentContent(
+                "a\nc\nd\n<<<<<<< HEAD\ne\n=======\nf\n>>>>>>> x\n",
+            )),
+            Editor(SetSelectionMode(
+                IfCurrentNotFound::LookForward,
+                MergeConflict,
+            )),
+            Editor(ResolveConflicts(ConflictResolution::Both)),
+            Expect(CurrentComponentContent("a\nc\nd\ne\nf\n")),
+        ])
+    })
+}
+
 #[test]
 fn multi_exchange_sibling() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -1649,6 +1683,78 @@ fn syntax_highlight_spans_updated_by_edit() -> anyhow::Result<()> {
     })
 }
 
+#[test]
+fn injected_language() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("<script>function f() {}</script>".to_string())),
+            Editor(SetLanguage(
+                shared::language::from_extension("html").unwrap(),
+            )),
+            Editor(ApplySyntaxHighlight),
+            // Expect the injected Javascript is highlighted
+            Expect(ExpectKind::HighlightSpans(
+                8..16,
+                StyleKey::Syntax("keyword.function".to_string()),
+            )),
+            // Expect syntax node selection can navigate into the injected tree
+            Editor(MatchLiteral("function f() {}".to_string())),
+            Editor(SetSelectionMode(
+                IfCurrentNotFound::LookForward,
+                SyntaxNodeFine,
+            )),
+            Editor(MoveSelection(Down)),
+            Expect(CurrentSelectedTexts(&["function"])),
+            Editor(MoveSelection(Next)),
+            Expect(CurrentSelectedTexts(&["f"])),
+        ])
+    })
+}
+
+#[test]
+fn toggle_comment() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn main() {\n    a();\n    b();\n}".to_string())),
+            Editor(MatchLiteral("a();".to_string())),
+            Editor(ToggleComment),
+            Expect(CurrentComponentContent(
+                "fn main() {\n    // a();\n    b();\n}",
+            )),
+            Expect(CurrentSelectedTexts(&["    // a();"])),
+            Editor(ToggleComment),
+            Expect(CurrentComponentContent(
+                "fn main() {\n    a();\n    b();\n}",
+            )),
+        ])
+    })
+}
+
+#[test]
+fn toggle_comment_in_injected_language() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "<p>hello</p>\n<script>\nlet x = 1;\n</script>".to_string(),
+            )),
+            Editor(SetLanguage(
+                shared::language::from_extension("html").unwrap(),
+            )),
+            Editor(ApplySyntaxHighlight),
+            Editor(MatchLiteral("let x".to_string())),
+            Editor(ToggleComment),
+            Editor(MatchLiteral("hello".to_string())),
+            Editor(ToggleComment),
+            Expect(CurrentComponentContent(
+                "<!-- <p>hello</p> -->\n<script>\n// let x = 1;\n</script>",
+            )),
+        ])
+    })
+}
+
 #[test]
 fn syntax_highlighting() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -2119,6 +2225,32 @@ fn select_surround_around() -> Result<(), anyhow::Error> {
     })
 }
 
+#[test]
+fn select_text_object() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn f(a: usize, b: usize) {}".to_string())),
+            // The textobjects queries are loaded along with the syntax highlighting
+            Editor(ApplySyntaxHighlight),
+            Editor(MatchLiteral("b".to_string())),
+            App(HandleKeyEvents(keys!("v a f").to_vec())),
+            Expect(CurrentSelectedTexts(&["fn f(a: usize, b: usize) {}"])),
+            Editor(MatchLiteral("a".to_string())),
+            Editor(SetSelectionMode(
+                IfCurrentNotFound::LookForward,
+                TextObject {
+                    kind: TextObjectKind::Parameter,
+                    surround_kind: SurroundKind::Inside,
+                },
+            )),
+            Expect(CurrentSelectedTexts(&["a: usize"])),
+            Editor(MoveSelection(Next)),
+            Expect(CurrentSelectedTexts(&["b: usize"])),
+        ])
+    })
+}
+
 #[test]
 fn select_surround_inside_same_symbols() -> Result<(), anyhow::Error> {
     execute_test(|s| {
@@ -2555,6 +2687,50 @@ fn main() {
     })
 }
 
+#[test]
+fn indent_follows_editorconfig() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let path = s.new_path("notes.txt");
+        std::fs::write(
+            s.new_path(".editorconfig"),
+            "[*.txt]\nindent_style = tab\ntrim_trailing_whitespace = true\ninsert_final_newline = true",
+        )
+        .unwrap();
+        std::fs::write(&path, "foo  \nbar").unwrap();
+        let path: CanonicalizedPath = path.try_into().unwrap();
+        Box::new([
+            App(OpenFile(path.clone())),
+            Editor(SetSelectionMode(IfCurrentNotFound::LookForward, Line)),
+            Editor(Indent),
+            Expect(CurrentComponentContent("\tfoo  \nbar")),
+            Editor(Save),
+            Expect(FileContent(path.clone(), "\tfoo\nbar\n".to_string())),
+        ])
+    })
+}
+
+#[test]
+fn line_ending_is_preserved_and_converted_on_save() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let path = s.new_path("windows.txt");
+        std::fs::write(&path, "a\r\nc\r\n").unwrap();
+        let path: CanonicalizedPath = path.try_into().unwrap();
+        Box::new([
+            App(OpenFile(path.clone())),
+            Expect(CurrentComponentContent("a\nc\n")),
+            Editor(MatchLiteral("a".to_string())),
+            Editor(EnterInsertMode(Direction::End)),
+            Editor(Insert("\nb".to_string())),
+            Editor(EnterNormalMode),
+            Editor(Save),
+            Expect(FileContent(path.clone(), "a\r\nb\r\nc\r\n".to_string())),
+            Editor(SetLineEnding(LineEnding::Lf)),
+            Editor(Save),
+            Expect(FileContent(path.clone(), "a\nb\nc\n".to_string())),
+        ])
+    })
+}
+
 #[test]
 fn test_dedent_in_column_mode() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -2680,6 +2856,90 @@ foo bar
     })
 }
 
+#[test]
+fn insert_mode_auto_pair() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("".to_string())),
+            Editor(EnterInsertMode(Direction::Start)),
+            // Closing symbols that are already there are skipped
+            App(HandleKeyEvents(keys!("f ( [ a ] ) ;").to_vec())),
+            Expect(CurrentComponentContent("f([a]);")),
+            // Backspace between a pair deletes both symbols
+            App(HandleKeyEvents(keys!("{ backspace").to_vec())),
+            Expect(CurrentComponentContent("f([a]);")),
+        ])
+    })
+}
+
+#[test]
+fn insert_mode_enter_between_brackets() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("  fn main() {}".to_string())),
+            Editor(MatchLiteral("{".to_string())),
+            Editor(EnterInsertMode(Direction::End)),
+            App(HandleKeyEvent(key!("enter"))),
+            Editor(Insert("x".to_string())),
+            Expect(CurrentComponentContent("  fn main() {\n      x\n  }")),
+        ])
+    })
+}
+
+#[test]
+fn insert_mode_enter_indent_by_query() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn main() {\n}".to_string())),
+            // The indent query is loaded along with the syntax highlighting
+            Editor(ApplySyntaxHighlight),
+            Editor(MatchLiteral("{".to_string())),
+            Editor(EnterInsertMode(Direction::End)),
+            App(HandleKeyEvent(key!("enter"))),
+            Editor(Insert("x".to_string())),
+            Expect(CurrentComponentContent("fn main() {\n    x\n}")),
+        ])
+    })
+}
+
+#[test]
+fn insert_mode_enter_before_indent_branch() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn main() {\n    x}".to_string())),
+            Editor(ApplySyntaxHighlight),
+            Editor(MatchLiteral("x".to_string())),
+            Editor(EnterInsertMode(Direction::End)),
+            // `}` is captured by `@indent.branch`, so it is dedented
+            App(HandleKeyEvent(key!("enter"))),
+            Expect(CurrentComponentContent("fn main() {\n    x\n}")),
+        ])
+    })
+}
+
+#[test]
+fn insert_mode_only_skips_auto_inserted_closing_symbols() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("f()".to_string())),
+            Editor(MatchLiteral("(".to_string())),
+            Editor(EnterInsertMode(Direction::End)),
+            // The closing parenthesis was not inserted by auto-pairing
+            App(HandleKeyEvent(key!(")"))),
+            Expect(CurrentComponentContent("f())")),
+            // Backspace only deletes the typed symbol
+            App(HandleKeyEvent(key!("backspace"))),
+            App(HandleKeyEvent(key!("backspace"))),
+            Expect(CurrentComponentContent("f)")),
+        ])
+    })
+}
+
 #[test]
 fn delete_line_should_not_dedent_next_line() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -3217,3 +3477,104 @@ fn visual_select_anchor_change_selection_mode() -> anyhow::Result<()> {
         ])
     })
 }
+
+#[test]
+fn mouse_click_and_drag() -> anyhow::Result<()> {
+    use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+    let mouse = |kind: MouseEventKind, row: u16, column: u16| {
+        HandleMouseEvent(MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        })
+    };
+    let click = |row: u16, column: u16| mouse(MouseEventKind::Down(MouseButton::Left), row, column);
+    let drag = |row: u16, column: u16| mouse(MouseEventKind::Drag(MouseButton::Left), row, column);
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("alpha beta gamma\nx".to_string())),
+            Editor(SetRectangle(Rectangle {
+                origin: Position::default(),
+                width: 16,
+                height: 5,
+            })),
+            // The first row is the title, and the first 2 columns are the line number.
+            // The first line is wrapped into "alpha beta " and "gamma".
+            App(click(1, 2)),
+            Expect(CurrentSelectedTexts(&["a"])),
+            App(drag(2, 3)),
+            Expect(CurrentSelectedTexts(&["alpha beta ga"])),
+            App(click(2, 3)),
+            Expect(CurrentSelectedTexts(&["a"])),
+            // Double-click selects the word
+            App(click(2, 3)),
+            Expect(CurrentSelectedTexts(&["gamma"])),
+            App(click(3, 2)),
+            Expect(CurrentSelectedTexts(&["x"])),
+            // Clicking past the end of the last line selects the last character
+            App(click(2, 3)),
+            Expect(CurrentSelectedTexts(&["a"])),
+            App(click(3, 10)),
+            Expect(CurrentSelectedTexts(&["x"])),
+            // Clicking the title does nothing
+            App(click(0, 2)),
+            Expect(CurrentSelectedTexts(&["x"])),
+        ])
+    })
+}
+
+#[test]
+fn mouse_click_selects_grapheme_cluster() -> anyhow::Result<()> {
+    use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("ae\u{301}b".to_string())),
+            Editor(SetRectangle(Rectangle {
+                origin: Position::default(),
+                width: 16,
+                height: 5,
+            })),
+            // "e\u{301}" occupies the cell after "a"
+            App(HandleMouseEvent(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 3,
+                row: 1,
+                modifiers: KeyModifiers::NONE,
+            })),
+            Expect(CurrentSelectedTexts(&["e\u{301}"])),
+        ])
+    })
+}
+
+#[test]
+fn grapheme_clusters() -> anyhow::Result<()> {
+    // The family emoji consists of 5 characters joined by zero-width joiners,
+    // and "e\u{301}" is "e" followed by a combining acute accent.
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("a👨\u{200D}👩\u{200D}👧e\u{301}b".to_string())),
+            Editor(SetSelectionMode(IfCurrentNotFound::LookForward, Character)),
+            Expect(CurrentSelectedTexts(&["a"])),
+            Editor(MoveSelection(Right)),
+            Expect(CurrentSelectedTexts(&["👨\u{200D}👩\u{200D}👧"])),
+            Editor(MoveSelection(Right)),
+            Expect(CurrentSelectedTexts(&["e\u{301}"])),
+            // Backspace deletes the whole grapheme cluster
+            Editor(EnterInsertMode(Direction::Start)),
+            Editor(Backspace),
+            Expect(CurrentComponentContent("ae\u{301}b")),
+            // Moving the cursor skips over the whole grapheme cluster
+            Editor(MoveCharacterForward),
+            Editor(Insert("c".to_string())),
+            Expect(CurrentComponentContent("ae\u{301}cb")),
+            Editor(MoveCharacterBack),
+            Editor(MoveCharacterBack),
+            Editor(Insert("d".to_string())),
+            Expect(CurrentComponentContent("ade\u{301}cb")),
+        ])
+    })
+}
@@ -10,7 +10,9 @@ use crate::{
     app::{GlobalSearchConfigUpdate, GlobalSearchFilterGlob, LocalSearchConfigUpdate, Scope},
     clipboard::{Clipboard, CopiedTexts},
     components::{keymap_legend::KeymapLegendSection, prompt::PromptHistoryKey},
+    dap::breakpoints::Breakpoints,
     list::grep::RegexConfig,
+    plugin::PluginCommand,
     quickfix_list::DiagnosticSeverityRange,
     selection::SelectionMode,
     themes::Theme,
@@ -30,6 +32,8 @@ pub(crate) struct Context {
     contextual_keymaps: Vec<KeymapLegendSection>,
     prompt_histories: HashMap<PromptHistoryKey, IndexSet<String>>,
     last_non_contiguous_selection_mode: Option<Either<SelectionMode, GlobalMode>>,
+    plugin_commands: Vec<PluginCommand>,
+    breakpoints: Breakpoints,
 }
 
 pub(crate) struct QuickfixListState {
@@ -77,14 +81,21 @@ impl Default for Context {
             contextual_keymaps: Default::default(),
             prompt_histories: Default::default(),
             last_non_contiguous_selection_mode: None,
+            plugin_commands: Vec::new(),
+            breakpoints: Breakpoints::default(),
         }
     }
 }
 
 impl Context {
     pub(crate) fn new(current_working_directory: CanonicalizedPath) -> Self {
+        let breakpoints = Breakpoints::load(&current_working_directory).unwrap_or_else(|error| {
+            log::error!("Context::new {:?}", error);
+            Breakpoints::default()
+        });
         Self {
             current_working_directory,
+            breakpoints,
             ..Self::default()
         }
     }
@@ -223,6 +234,25 @@ impl Context {
         self.contextual_keymaps = contextual_keymaps
     }
 
+    pub(crate) fn plugin_commands(&self) -> &[PluginCommand] {
+        &self.plugin_commands
+    }
+
+    /// Replaces the command of the same plugin and name, if any.
+    pub(crate) fn register_plugin_command(&mut self, command: PluginCommand) {
+        self.plugin_commands
+            .retain(|existing| existing.plugin != command.plugin || existing.name != command.name);
+        self.plugin_commands.push(command)
+    }
+
+    pub(crate) fn breakpoints(&self) -> &Breakpoints {
+        &self.breakpoints
+    }
+
+    pub(crate) fn set_breakpoints(&mut self, path: &CanonicalizedPath, lines: Vec<usize>) {
+        self.breakpoints.set(path, lines)
+    }
+
     pub(crate) fn push_history_prompt(&mut self, key: PromptHistoryKey, line: String) {
         if let Some(map) = self.prompt_histories.get_mut(&key) {
             map.shift_remove(&line);
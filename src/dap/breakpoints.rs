@@ -0,0 +1,126 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::PathBuf,
+};
+
+use shared::canonicalized_path::CanonicalizedPath;
+
+/// The breakpoints of every file, which are persisted in `.ki/breakpoints.json`
+/// of the working directory as a map from relative paths to 1-based line numbers.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct Breakpoints {
+    /// 0-based line indices
+    lines: HashMap<CanonicalizedPath, BTreeSet<usize>>,
+}
+
+pub(crate) fn config_path(working_directory: &CanonicalizedPath) -> PathBuf {
+    working_directory
+        .to_path_buf()
+        .join(".ki")
+        .join("breakpoints.json")
+}
+
+impl Breakpoints {
+    /// Breakpoints of files that no longer exist are dropped.
+    pub(crate) fn load(working_directory: &CanonicalizedPath) -> anyhow::Result<Breakpoints> {
+        let path = config_path(working_directory);
+        if !path.exists() {
+            return Ok(Breakpoints::default());
+        }
+        let saved: BTreeMap<String, Vec<usize>> =
+            serde_json::from_str(&std::fs::read_to_string(&path)?)
+                .map_err(|error| anyhow::anyhow!("Invalid {}: {}", path.display(), error))?;
+        Ok(Breakpoints {
+            lines: saved
+                .into_iter()
+                .filter_map(|(path, lines)| {
+                    Some((
+                        working_directory.join(&path).ok()?,
+                        lines
+                            .into_iter()
+                            .map(|line| line.saturating_sub(1))
+                            .collect(),
+                    ))
+                })
+                .collect(),
+        })
+    }
+
+    pub(crate) fn save(&self, working_directory: &CanonicalizedPath) -> anyhow::Result<()> {
+        let saved: BTreeMap<String, Vec<usize>> = self
+            .lines
+            .iter()
+            .filter(|(_, lines)| !lines.is_empty())
+            .map(|(path, lines)| {
+                (
+                    path.display_relative_to(working_directory)
+                        .unwrap_or_else(|_| path.display_absolute()),
+                    lines.iter().map(|line| line + 1).collect(),
+                )
+            })
+            .collect();
+        let path = config_path(working_directory);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(std::fs::write(path, serde_json::to_string_pretty(&saved)?)?)
+    }
+
+    /// Replaces the breakpoints of `path`.
+    /// The breakpoints of an opened file are owned by its buffer, which moves them along with
+    /// the edited lines, so they are set here whenever they are toggled or the file is saved.
+    pub(crate) fn set(&mut self, path: &CanonicalizedPath, lines: Vec<usize>) {
+        self.lines.insert(path.clone(), lines.into_iter().collect());
+    }
+
+    #[cfg(test)]
+    /// Adds a breakpoint at `line` of `path`, or removes it if it already exists.
+    pub(crate) fn toggle(&mut self, path: &CanonicalizedPath, line: usize) {
+        let lines = self.lines.entry(path.clone()).or_default();
+        if !lines.remove(&line) {
+            lines.insert(line);
+        }
+    }
+
+    /// Returns the sorted 0-based line indices of the breakpoints of `path`.
+    pub(crate) fn lines(&self, path: &CanonicalizedPath) -> Vec<usize> {
+        self.lines
+            .get(path)
+            .map(|lines| lines.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn paths(&self) -> Vec<CanonicalizedPath> {
+        self.lines.keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod test_breakpoints {
+    use shared::canonicalized_path::CanonicalizedPath;
+
+    use super::Breakpoints;
+
+    #[test]
+    fn toggle_and_persist() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        std::fs::write(directory.path().join("main.rs"), "fn main() {}")?;
+        let working_directory: CanonicalizedPath = directory.path().try_into()?;
+        let path = working_directory.join("main.rs")?;
+
+        let mut breakpoints = Breakpoints::default();
+        breakpoints.toggle(&path, 4);
+        breakpoints.toggle(&path, 1);
+        breakpoints.toggle(&path, 7);
+        breakpoints.toggle(&path, 4);
+        assert_eq!(breakpoints.lines(&path), vec![1, 7]);
+
+        breakpoints.save(&working_directory)?;
+        assert_eq!(
+            std::fs::read_to_string(super::config_path(&working_directory))?,
+            "{\n  \"main.rs\": [\n    2,\n    8\n  ]\n}"
+        );
+        assert_eq!(Breakpoints::load(&working_directory)?, breakpoints);
+        Ok(())
+    }
+}
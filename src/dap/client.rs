@@ -0,0 +1,131 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    process::{Child, ChildStdin},
+    sync::mpsc::Sender,
+};
+
+use anyhow::Context;
+use shared::process_command::ProcessCommand;
+
+use crate::app::AppMessage;
+
+/// The process of a debug adapter, which speaks the Debug Adapter Protocol over stdio.
+///
+/// Every message from the adapter is forwarded to the app as `AppMessage::DapMessage`.
+pub(crate) struct DapClient {
+    child: Child,
+    stdin: ChildStdin,
+    next_seq: i64,
+}
+
+impl DapClient {
+    pub(crate) fn start(
+        command: ProcessCommand,
+        app_message_sender: Sender<AppMessage>,
+    ) -> anyhow::Result<DapClient> {
+        let mut child = command.spawn().into_result()?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Unable to obtain stdin"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Unable to obtain stdout"))?;
+        let stderr = child
+            .stderr
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Unable to obtain stderr"))?;
+        std::thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            loop {
+                match read_message(&mut reader) {
+                    Ok(Some(message)) => {
+                        if app_message_sender
+                            .send(AppMessage::DapMessage(message))
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(error) => {
+                        log::error!("[DapClient] Invalid message: {:?}", error);
+                        break;
+                    }
+                }
+            }
+            log::info!("[DapClient] Stopped listening");
+        });
+        // The adapter might block if its stderr is not drained
+        std::thread::spawn(move || {
+            for line in BufReader::new(stderr).lines() {
+                let Ok(line) = line else { break };
+                log::info!("[DapClient] stderr: {}", line)
+            }
+        });
+        Ok(DapClient {
+            child,
+            stdin,
+            next_seq: 1,
+        })
+    }
+
+    /// Returns the `seq` of the request, which is the `request_seq` of its response.
+    pub(crate) fn send_request(
+        &mut self,
+        command: &str,
+        arguments: serde_json::Value,
+    ) -> anyhow::Result<i64> {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let json = serde_json::json!({
+            "seq": seq,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        })
+        .to_string();
+        log::info!("[DapClient] Sending request: {}", command);
+        // Messages are framed like LSP messages
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", json.len(), json)?;
+        self.stdin.flush()?;
+        Ok(seq)
+    }
+}
+
+impl Drop for DapClient {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
+
+/// Returns `None` if the adapter closed its stdout.
+fn read_message(reader: &mut impl BufRead) -> anyhow::Result<Option<serde_json::Value>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                content_length = Some(
+                    value
+                        .trim()
+                        .parse::<usize>()
+                        .with_context(|| "Parsing Content-Length: Failed to parse number.")?,
+                )
+            }
+        }
+    }
+    let content_length =
+        content_length.ok_or_else(|| anyhow::anyhow!("Missing Content-Length header"))?;
+    let mut buffer = vec![0; content_length];
+    reader.read_exact(&mut buffer)?;
+    Ok(Some(serde_json::from_slice(&buffer)?))
+}
@@ -0,0 +1,7 @@
+//! A client of the Debug Adapter Protocol.
+//!
+//! The debug adapter of a file is declared by its `Language`,
+//! see <https://microsoft.github.io/debug-adapter-protocol/specification>.
+pub(crate) mod breakpoints;
+mod client;
+pub(crate) mod session;
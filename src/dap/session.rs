@@ -0,0 +1,586 @@
+use std::{collections::HashMap, path::PathBuf, sync::mpsc::Sender};
+
+use itertools::Itertools;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use shared::{canonicalized_path::CanonicalizedPath, process_command::ProcessCommand};
+
+use crate::{app::AppMessage, position::Position};
+
+use super::{breakpoints::Breakpoints, client::DapClient};
+
+/// A debugging session of one debug adapter, which is stopped or running.
+pub(crate) struct DebugSession {
+    client: DapClient,
+    /// Sent after the response of the `initialize` request
+    launch_arguments: Value,
+    /// Sent when the adapter is initialized
+    breakpoints: Breakpoints,
+    pending_requests: HashMap<i64, PendingRequest>,
+    stopped: Option<Stopped>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PendingRequest {
+    Initialize,
+    StackTrace,
+    Scopes,
+    Variables { scope: String },
+    Evaluate { expression: String },
+    Other,
+}
+
+/// The state of the stopped thread.
+#[derive(Debug, Default)]
+struct Stopped {
+    thread_id: i64,
+    stack_frames: Vec<StackFrame>,
+    /// In the order of the scopes of the top stack frame
+    scopes: Vec<(String, Vec<Variable>)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct StackFrame {
+    id: i64,
+    name: String,
+    source: Option<Source>,
+    /// 1-based
+    line: usize,
+    /// 1-based
+    column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct Source {
+    path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Scope {
+    name: String,
+    variables_reference: i64,
+    #[serde(default)]
+    expensive: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct Variable {
+    name: String,
+    value: String,
+    #[serde(rename = "type")]
+    r#type: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DebugResume {
+    Continue,
+    StepOver,
+    StepIn,
+    StepOut,
+}
+
+impl DebugResume {
+    fn command(self) -> &'static str {
+        match self {
+            DebugResume::Continue => "continue",
+            DebugResume::StepOver => "next",
+            DebugResume::StepIn => "stepIn",
+            DebugResume::StepOut => "stepOut",
+        }
+    }
+}
+
+/// What the app should do after a message of the debug adapter is handled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DebugUpdate {
+    /// The current stack frame is located at `position` of `path`
+    GotoLocation {
+        path: PathBuf,
+        position: Position,
+    },
+    /// The call stack or variables changed
+    RenderPanels,
+    Evaluated {
+        expression: String,
+        result: String,
+    },
+    Terminated,
+}
+
+impl DebugSession {
+    pub(crate) fn start(
+        command: ProcessCommand,
+        adapter_id: &str,
+        launch_arguments: Value,
+        breakpoints: Breakpoints,
+        app_message_sender: Sender<AppMessage>,
+    ) -> anyhow::Result<DebugSession> {
+        let mut session = DebugSession {
+            client: DapClient::start(command, app_message_sender)?,
+            launch_arguments,
+            breakpoints,
+            pending_requests: HashMap::new(),
+            stopped: None,
+        };
+        session.send_request(
+            "initialize",
+            json!({
+                "clientID": "ki",
+                "clientName": "Ki",
+                "adapterID": adapter_id,
+                "linesStartAt1": true,
+                "columnsStartAt1": true,
+                "pathFormat": "path",
+            }),
+            PendingRequest::Initialize,
+        )?;
+        Ok(session)
+    }
+
+    fn send_request(
+        &mut self,
+        command: &str,
+        arguments: Value,
+        pending_request: PendingRequest,
+    ) -> anyhow::Result<()> {
+        let seq = self.client.send_request(command, arguments)?;
+        self.pending_requests.insert(seq, pending_request);
+        Ok(())
+    }
+
+    pub(crate) fn handle_message(&mut self, message: Value) -> anyhow::Result<Vec<DebugUpdate>> {
+        match message.get("type").and_then(Value::as_str) {
+            Some("response") => self.handle_response(message),
+            Some("event") => self.handle_event(message),
+            // Reverse requests, such as `runInTerminal`, are not supported
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn handle_response(&mut self, response: Value) -> anyhow::Result<Vec<DebugUpdate>> {
+        let Some(pending_request) = response
+            .get("request_seq")
+            .and_then(Value::as_i64)
+            .and_then(|seq| self.pending_requests.remove(&seq))
+        else {
+            return Ok(Vec::new());
+        };
+        if response.get("success").and_then(Value::as_bool) != Some(true) {
+            return Err(anyhow::anyhow!(
+                "Debug adapter failed to handle {:?}: {}",
+                response
+                    .get("command")
+                    .and_then(Value::as_str)
+                    .unwrap_or("request"),
+                response
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error")
+            ));
+        }
+        let body = response.get("body").cloned().unwrap_or_default();
+        match pending_request {
+            PendingRequest::Initialize => {
+                self.send_request(
+                    "launch",
+                    self.launch_arguments.clone(),
+                    PendingRequest::Other,
+                )?;
+                Ok(Vec::new())
+            }
+            PendingRequest::StackTrace => {
+                let stack_frames: Vec<StackFrame> =
+                    serde_json::from_value(body["stackFrames"].clone())?;
+                let Some(stopped) = self.stopped.as_mut() else {
+                    return Ok(Vec::new());
+                };
+                stopped.stack_frames = stack_frames;
+                let Some(frame) = stopped.stack_frames.first().cloned() else {
+                    return Ok(vec![DebugUpdate::RenderPanels]);
+                };
+                self.send_request(
+                    "scopes",
+                    json!({ "frameId": frame.id }),
+                    PendingRequest::Scopes,
+                )?;
+                Ok(frame
+                    .source
+                    .and_then(|source| source.path)
+                    .map(|path| DebugUpdate::GotoLocation {
+                        path,
+                        position: Position::new(
+                            frame.line.saturating_sub(1),
+                            frame.column.saturating_sub(1),
+                        ),
+                    })
+                    .into_iter()
+                    .chain(Some(DebugUpdate::RenderPanels))
+                    .collect())
+            }
+            PendingRequest::Scopes => {
+                let scopes: Vec<Scope> = serde_json::from_value(body["scopes"].clone())?;
+                for scope in scopes.into_iter().filter(|scope| !scope.expensive) {
+                    if let Some(stopped) = self.stopped.as_mut() {
+                        stopped.scopes.push((scope.name.clone(), Vec::new()))
+                    }
+                    self.send_request(
+                        "variables",
+                        json!({ "variablesReference": scope.variables_reference }),
+                        PendingRequest::Variables { scope: scope.name },
+                    )?;
+                }
+                Ok(Vec::new())
+            }
+            PendingRequest::Variables { scope } => {
+                let variables: Vec<Variable> = serde_json::from_value(body["variables"].clone())?;
+                if let Some((_, existing)) = self
+                    .stopped
+                    .as_mut()
+                    .and_then(|stopped| stopped.scopes.iter_mut().find(|(name, _)| name == &scope))
+                {
+                    *existing = variables
+                }
+                Ok(vec![DebugUpdate::RenderPanels])
+            }
+            PendingRequest::Evaluate { expression } => Ok(vec![DebugUpdate::Evaluated {
+                expression,
+                result: body["result"].as_str().unwrap_or_default().to_string(),
+            }]),
+            PendingRequest::Other => Ok(Vec::new()),
+        }
+    }
+
+    fn handle_event(&mut self, event: Value) -> anyhow::Result<Vec<DebugUpdate>> {
+        let body = event.get("body").cloned().unwrap_or_default();
+        match event.get("event").and_then(Value::as_str) {
+            Some("initialized") => {
+                for path in self.breakpoints.paths() {
+                    self.send_breakpoints(&path)?
+                }
+                self.send_request("configurationDone", json!({}), PendingRequest::Other)?;
+                Ok(Vec::new())
+            }
+            Some("stopped") => {
+                let thread_id = body["threadId"].as_i64().unwrap_or_default();
+                self.stopped = Some(Stopped {
+                    thread_id,
+                    ..Default::default()
+                });
+                self.send_request(
+                    "stackTrace",
+                    json!({ "threadId": thread_id }),
+                    PendingRequest::StackTrace,
+                )?;
+                Ok(vec![DebugUpdate::RenderPanels])
+            }
+            Some("continued") => {
+                self.stopped = None;
+                Ok(vec![DebugUpdate::RenderPanels])
+            }
+            Some("terminated") | Some("exited") => {
+                self.stopped = None;
+                Ok(vec![DebugUpdate::Terminated])
+            }
+            Some("output") => {
+                log::info!(
+                    "[DebugSession] {}",
+                    body["output"].as_str().unwrap_or_default().trim_end()
+                );
+                Ok(Vec::new())
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Replaces the breakpoints of `path` known by the adapter.
+    fn send_breakpoints(&mut self, path: &CanonicalizedPath) -> anyhow::Result<()> {
+        let breakpoints = self
+            .breakpoints
+            .lines(path)
+            .into_iter()
+            .map(|line| json!({ "line": line + 1 }))
+            .collect_vec();
+        self.send_request(
+            "setBreakpoints",
+            json!({
+                "source": { "path": path.display_absolute() },
+                "breakpoints": breakpoints,
+            }),
+            PendingRequest::Other,
+        )
+    }
+
+    pub(crate) fn set_breakpoints(
+        &mut self,
+        breakpoints: Breakpoints,
+        path: &CanonicalizedPath,
+    ) -> anyhow::Result<()> {
+        self.breakpoints = breakpoints;
+        self.send_breakpoints(path)
+    }
+
+    fn stopped_thread_id(&self) -> anyhow::Result<i64> {
+        self.stopped
+            .as_ref()
+            .map(|stopped| stopped.thread_id)
+            .ok_or_else(|| anyhow::anyhow!("The debuggee is not stopped"))
+    }
+
+    pub(crate) fn resume(&mut self, resume: DebugResume) -> anyhow::Result<()> {
+        let thread_id = self.stopped_thread_id()?;
+        self.send_request(
+            resume.command(),
+            json!({ "threadId": thread_id }),
+            PendingRequest::Other,
+        )?;
+        self.stopped = None;
+        Ok(())
+    }
+
+    /// Evaluates `expression` in the current stack frame.
+    pub(crate) fn evaluate(&mut self, expression: &str) -> anyhow::Result<()> {
+        let frame_id = self
+            .stopped
+            .as_ref()
+            .and_then(|stopped| stopped.stack_frames.first())
+            .map(|frame| frame.id);
+        self.send_request(
+            "evaluate",
+            json!({
+                "expression": expression,
+                "frameId": frame_id,
+                "context": "repl",
+            }),
+            PendingRequest::Evaluate {
+                expression: expression.to_string(),
+            },
+        )
+    }
+
+    pub(crate) fn disconnect(&mut self) -> anyhow::Result<()> {
+        self.send_request(
+            "disconnect",
+            json!({ "terminateDebuggee": true }),
+            PendingRequest::Other,
+        )
+    }
+
+    pub(crate) fn render_call_stack(&self) -> String {
+        match &self.stopped {
+            None => "Running".to_string(),
+            Some(stopped) => stopped
+                .stack_frames
+                .iter()
+                .map(|frame| {
+                    let location = frame
+                        .source
+                        .as_ref()
+                        .and_then(|source| source.path.as_ref())
+                        .map(|path| format!("{}:{}", path.display(), frame.line))
+                        .unwrap_or_default();
+                    format!("{} {}", frame.name, location)
+                        .trim_end()
+                        .to_string()
+                })
+                .join("\n"),
+        }
+    }
+
+    pub(crate) fn render_variables(&self) -> String {
+        let Some(stopped) = &self.stopped else {
+            return String::new();
+        };
+        stopped
+            .scopes
+            .iter()
+            .map(|(scope, variables)| {
+                std::iter::once(format!("{}:", scope))
+                    .chain(variables.iter().map(|variable| match &variable.r#type {
+                        Some(r#type) => {
+                            format!("  {}: {} = {}", variable.name, r#type, variable.value)
+                        }
+                        None => format!("  {} = {}", variable.name, variable.value),
+                    }))
+                    .join("\n")
+            })
+            .join("\n")
+    }
+}
+
+/// Replaces the variables in the string values of `template`,
+/// see `shared::language::DapCommand`.
+pub(crate) fn launch_arguments(
+    template: &str,
+    file: &CanonicalizedPath,
+    working_directory: &CanonicalizedPath,
+) -> anyhow::Result<Value> {
+    fn substitute(value: Value, variables: &[(&str, String)]) -> Value {
+        match value {
+            Value::String(string) => Value::String(
+                variables
+                    .iter()
+                    .fold(string, |string, (name, value)| string.replace(name, value)),
+            ),
+            Value::Array(values) => Value::Array(
+                values
+                    .into_iter()
+                    .map(|value| substitute(value, variables))
+                    .collect(),
+            ),
+            Value::Object(map) => Value::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, substitute(value, variables)))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+    let file_dirname = file
+        .parent()?
+        .map(|parent| parent.display_absolute())
+        .unwrap_or_default();
+    let variables = [
+        ("${file}", file.display_absolute()),
+        ("${fileDirname}", file_dirname),
+        ("${workspaceFolder}", working_directory.display_absolute()),
+        (
+            "${workspaceFolderBasename}",
+            working_directory
+                .to_path_buf()
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        ),
+    ];
+    Ok(substitute(serde_json::from_str(template)?, &variables))
+}
+
+#[cfg(test)]
+mod test_debug_session {
+    use std::time::Duration;
+
+    use shared::{canonicalized_path::CanonicalizedPath, process_command::ProcessCommand};
+
+    use super::{DebugResume, DebugSession, DebugUpdate};
+    use crate::{app::AppMessage, dap::breakpoints::Breakpoints, position::Position};
+
+    /// A debug adapter that stops at line 2 of the file given by `program`,
+    /// and terminates when the debuggee is continued.
+    const STUB_ADAPTER: &str = r#"
+seq=0
+send() {
+    seq=$((seq + 1))
+    body=$(printf "$1" "$seq")
+    printf 'Content-Length: %d\r\n\r\n%s' "${#body}" "$body"
+}
+respond() {
+    send '{"seq":%d,"type":"response","request_seq":'"$request_seq"',"success":true,"command":"'"$command"'","body":'"$1"'}'
+}
+while read -r header; do
+    length=$(printf '%s' "$header" | tr -d '\r' | sed 's/Content-Length: //')
+    read -r _
+    message=$(head -c "$length")
+    command=$(printf '%s' "$message" | sed 's/.*"command":"\([^"]*\)".*/\1/')
+    request_seq=$(printf '%s' "$message" | sed 's/.*"seq":\([0-9]*\).*/\1/')
+    case "$command" in
+    initialize)
+        respond '{}'
+        send '{"seq":%d,"type":"event","event":"initialized"}'
+        ;;
+    launch)
+        program=$(printf '%s' "$message" | sed 's/.*"program":"\([^"]*\)".*/\1/')
+        respond '{}'
+        ;;
+    configurationDone)
+        respond '{}'
+        send '{"seq":%d,"type":"event","event":"stopped","body":{"reason":"breakpoint","threadId":1}}'
+        ;;
+    stackTrace)
+        respond '{"stackFrames":[{"id":7,"name":"main","source":{"path":"'"$program"'"},"line":2,"column":5}]}'
+        ;;
+    scopes)
+        respond '{"scopes":[{"name":"Locals","variablesReference":3,"expensive":false}]}'
+        ;;
+    variables)
+        respond '{"variables":[{"name":"x","value":"42","type":"i32","variablesReference":0}]}'
+        ;;
+    evaluate)
+        respond '{"result":"84","variablesReference":0}'
+        ;;
+    continue)
+        respond '{}'
+        send '{"seq":%d,"type":"event","event":"terminated"}'
+        ;;
+    *)
+        respond '{}'
+        ;;
+    esac
+done
+"#;
+
+    #[test]
+    fn stop_inspect_evaluate_and_continue() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        std::fs::write(directory.path().join("main.rs"), "fn main() {\n    x\n}")?;
+        let working_directory: CanonicalizedPath = directory.path().try_into()?;
+        let file = working_directory.join("main.rs")?;
+        let mut breakpoints = Breakpoints::default();
+        breakpoints.toggle(&file, 1);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut session = DebugSession::start(
+            ProcessCommand::new("sh", &["-c", STUB_ADAPTER]),
+            "stub",
+            super::launch_arguments(r#"{"program": "${file}"}"#, &file, &working_directory)?,
+            breakpoints,
+            sender,
+        )?;
+        let mut updates = Vec::new();
+        let mut receive_until = |session: &mut DebugSession,
+                                 done: fn(&DebugSession, &[DebugUpdate]) -> bool|
+         -> anyhow::Result<Vec<DebugUpdate>> {
+            while !done(session, &updates) {
+                match receiver.recv_timeout(Duration::from_secs(5))? {
+                    AppMessage::DapMessage(message) => {
+                        updates.extend(session.handle_message(message)?)
+                    }
+                    other => panic!("Unexpected message {:?}", other),
+                }
+            }
+            Ok(std::mem::take(&mut updates))
+        };
+
+        // Variables are the last to arrive after the debuggee stopped
+        let stopped = receive_until(&mut session, |session, _| {
+            session.render_variables().lines().count() > 1
+        })?;
+        assert!(stopped.contains(&DebugUpdate::GotoLocation {
+            path: file.to_path_buf().clone(),
+            position: Position::new(1, 4)
+        }));
+        assert_eq!(
+            session.render_call_stack(),
+            format!("main {}:2", file.display_absolute())
+        );
+        assert_eq!(session.render_variables(), "Locals:\n  x: i32 = 42");
+
+        session.evaluate("x * 2")?;
+        let evaluated = receive_until(&mut session, |_, updates| {
+            updates
+                .iter()
+                .any(|update| matches!(update, DebugUpdate::Evaluated { .. }))
+        })?;
+        assert!(evaluated.contains(&DebugUpdate::Evaluated {
+            expression: "x * 2".to_string(),
+            result: "84".to_string()
+        }));
+
+        session.resume(DebugResume::Continue)?;
+        receive_until(&mut session, |_, updates| {
+            updates.contains(&DebugUpdate::Terminated)
+        })?;
+        assert_eq!(session.render_call_stack(), "Running");
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+use std::path::PathBuf;
+
+use itertools::{EitherOrBoth, Itertools};
+use similar::{ChangeTag, DiffOp, TextDiff};
+use unicode_width::UnicodeWidthChar;
+
+use crate::{
+    components::suggestive_editor::Decoration, git::DiffMode, grid::StyleKey, position::Position,
+    selection_range::SelectionRange,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DiffViewKind {
+    /// The old content is shown on the left, and the new content is shown on the right.
+    /// Both sides are rendered in the same buffer, so that they always scroll together.
+    SideBySide,
+    /// Only the changed lines and their context are shown, prefixed with `-` or `+`.
+    Unified,
+}
+
+impl DiffViewKind {
+    pub(crate) fn display(&self) -> &'static str {
+        match self {
+            DiffViewKind::SideBySide => "side by side",
+            DiffViewKind::Unified => "unified",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DiffSource {
+    /// Compare the current file against its content in the given diff mode
+    Git(DiffMode),
+    /// Compare the current selection against the latest copied text
+    Clipboard,
+    /// Compare the current file against the given file
+    File(PathBuf),
+}
+
+/// Matches the header line of every hunk of a unified diff or a patch
+pub(crate) const HUNK_HEADER_REGEX: &str = r"@@ -\d+(,\d+)? \+\d+(,\d+)? @@";
+
+const SEPARATOR: &str = " │ ";
+const CONTEXT_LEN: usize = 3;
+
+pub(crate) struct DiffView {
+    pub(crate) content: String,
+    pub(crate) decorations: Vec<Decoration>,
+}
+
+impl DiffView {
+    /// `width` is the width available for rendering, it is only used by `DiffViewKind::SideBySide`.
+    pub(crate) fn new(kind: DiffViewKind, old: &str, new: &str, width: usize) -> DiffView {
+        let diff = TextDiff::from_lines(old, new);
+        match kind {
+            DiffViewKind::SideBySide => Self::side_by_side(&diff, width),
+            DiffViewKind::Unified => Self::unified(&diff),
+        }
+    }
+
+    fn side_by_side(diff: &TextDiff<'_, '_, '_, str>, width: usize) -> DiffView {
+        let old_lines = diff.old_slices();
+        let new_lines = diff.new_slices();
+        // Leave room for the line number gutter of the editor
+        let gutter_width = (old_lines.len() + new_lines.len()).to_string().len() + 2;
+        let column_width = width.saturating_sub(gutter_width + SEPARATOR.chars().count()) / 2;
+        let rows = diff.ops().iter().flat_map(|op| {
+            let old = old_lines[op.old_range()]
+                .iter()
+                .map(|line| trim_newline(line));
+            let new = new_lines[op.new_range()]
+                .iter()
+                .map(|line| trim_newline(line));
+            let changed = !matches!(op, DiffOp::Equal { .. });
+            old.zip_longest(new)
+                .map(move |pair| match pair {
+                    EitherOrBoth::Both(left, right) => (Some(left), Some(right), changed),
+                    EitherOrBoth::Left(left) => (Some(left), None, changed),
+                    EitherOrBoth::Right(right) => (None, Some(right), changed),
+                })
+                .collect_vec()
+        });
+        let (lines, decorations): (Vec<_>, Vec<_>) = rows
+            .enumerate()
+            .map(|(line_index, (left, right, changed))| {
+                let left = fit(left.unwrap_or_default(), column_width);
+                let right_start = left.chars().count() + SEPARATOR.chars().count();
+                let right_len = right.unwrap_or_default().chars().count();
+                let decorations = [
+                    (changed && !left.trim().is_empty()).then(|| {
+                        Decoration::new(
+                            SelectionRange::Position(
+                                Position::new(line_index, 0)
+                                    ..Position::new(line_index, left.chars().count()),
+                            ),
+                            StyleKey::HunkOld,
+                        )
+                    }),
+                    (changed && right_len > 0).then(|| {
+                        Decoration::new(
+                            SelectionRange::Position(
+                                Position::new(line_index, right_start)
+                                    ..Position::new(line_index, right_start + right_len),
+                            ),
+                            StyleKey::HunkNew,
+                        )
+                    }),
+                ];
+                (
+                    format!("{}{}{}", left, SEPARATOR, right.unwrap_or_default()),
+                    decorations.into_iter().flatten().collect_vec(),
+                )
+            })
+            .unzip();
+        DiffView {
+            content: lines.join("\n"),
+            decorations: decorations.into_iter().flatten().collect(),
+        }
+    }
+
+    fn unified(diff: &TextDiff<'_, '_, '_, str>) -> DiffView {
+        let mut lines = Vec::new();
+        let mut decorations = Vec::new();
+        for group in diff.grouped_ops(CONTEXT_LEN) {
+            let (Some(first), Some(last)) = (group.first(), group.last()) else {
+                continue;
+            };
+            lines.push(format!(
+                "@@ -{},{} +{},{} @@",
+                first.old_range().start + 1,
+                last.old_range().end - first.old_range().start,
+                first.new_range().start + 1,
+                last.new_range().end - first.new_range().start,
+            ));
+            for op in &group {
+                for change in diff.iter_inline_changes(op) {
+                    let line_index = lines.len();
+                    let (prefix, style_keys) = match change.tag() {
+                        ChangeTag::Equal => (" ", None),
+                        ChangeTag::Delete => {
+                            ("-", Some((StyleKey::HunkOld, StyleKey::HunkOldEmphasized)))
+                        }
+                        ChangeTag::Insert => {
+                            ("+", Some((StyleKey::HunkNew, StyleKey::HunkNewEmphasized)))
+                        }
+                    };
+                    let mut line = prefix.to_string();
+                    for (emphasized, value) in change.iter_strings_lossy() {
+                        let value = trim_newline(&value);
+                        let start = line.chars().count();
+                        line.push_str(value);
+                        if let Some((normal, emphasis)) = &style_keys {
+                            decorations.push(Decoration::new(
+                                SelectionRange::Position(
+                                    Position::new(line_index, start)
+                                        ..Position::new(line_index, line.chars().count()),
+                                ),
+                                if emphasized {
+                                    emphasis.clone()
+                                } else {
+                                    normal.clone()
+                                },
+                            ))
+                        }
+                    }
+                    lines.push(line);
+                }
+            }
+        }
+        DiffView {
+            content: lines.join("\n"),
+            decorations,
+        }
+    }
+
+    /// Highlights the added and removed lines of a patch, such as the output of `git show`.
+    pub(crate) fn from_patch(patch: &str) -> DiffView {
+        let decorations = patch
+            .lines()
+            .enumerate()
+            .filter_map(|(line_index, line)| {
+                let style_key = if line.starts_with("+++ ") || line.starts_with("--- ") {
+                    return None;
+                } else if line.starts_with('+') {
+                    StyleKey::HunkNew
+                } else if line.starts_with('-') {
+                    StyleKey::HunkOld
+                } else {
+                    return None;
+                };
+                Some(Decoration::new(
+                    SelectionRange::Position(
+                        Position::new(line_index, 0)
+                            ..Position::new(line_index, line.chars().count()),
+                    ),
+                    style_key,
+                ))
+            })
+            .collect();
+        DiffView {
+            content: patch.to_string(),
+            decorations,
+        }
+    }
+}
+
+fn trim_newline(line: &str) -> &str {
+    line.trim_end_matches(['\n', '\r'])
+}
+
+/// Truncate or pad `line` with spaces, so that its display width is exactly `width`.
+fn fit(line: &str, width: usize) -> String {
+    let mut result = String::new();
+    let mut result_width = 0;
+    for c in line.chars() {
+        let c = if c == '\t' { ' ' } else { c };
+        let char_width = c.width().unwrap_or_default();
+        if result_width + char_width > width {
+            break;
+        }
+        result.push(c);
+        result_width += char_width;
+    }
+    result + &" ".repeat(width - result_width)
+}
+
+#[cfg(test)]
+mod test_diff_view {
+    use super::{DiffView, DiffViewKind};
+
+    #[test]
+    fn side_by_side() {
+        let view = DiffView::new(
+            DiffViewKind::SideBySide,
+            "a\nb\nc\n",
+            "a\nbee\nc\nd\n",
+            // The gutter width is 3, the separator width is 3
+            3 + 3 + 2 * 3,
+        );
+        assert_eq!(
+            view.content,
+            ["a   │ a", "b   │ bee", "c   │ c", "    │ d"].join("\n")
+        );
+        // "b" and "bee", and "d"
+        assert_eq!(view.decorations.len(), 3);
+    }
+
+    #[test]
+    fn unified() {
+        let view = DiffView::new(DiffViewKind::Unified, "a\nb\nc\n", "a\nbee\nc\n", 0);
+        assert_eq!(
+            view.content,
+            ["@@ -1,3 +1,3 @@", " a", "-b", "+bee", " c"].join("\n")
+        );
+    }
+
+    #[test]
+    fn from_patch() {
+        let view = DiffView::from_patch(
+            &[
+                "--- a/a.txt",
+                "+++ b/a.txt",
+                "@@ -1,2 +1,2 @@",
+                "-b",
+                "+bee",
+                " c",
+            ]
+            .join("\n"),
+        );
+        // Only "-b" and "+bee" are highlighted
+        assert_eq!(view.decorations.len(), 2);
+        let regex = regex::Regex::new(super::HUNK_HEADER_REGEX).unwrap();
+        assert_eq!(regex.find_iter(&view.content).count(), 1);
+    }
+}
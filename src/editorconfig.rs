@@ -0,0 +1,314 @@
+use std::path::Path;
+
+use globset::GlobBuilder;
+use shared::indent::Indent;
+
+use crate::file_format::LineEnding;
+
+const FILE_NAME: &str = ".editorconfig";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IndentStyle {
+    Tab,
+    Space,
+}
+
+/// The properties of [EditorConfig](https://editorconfig.org) that apply to a file.
+/// `None` means the property is not specified.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct EditorConfig {
+    indent_style: Option<IndentStyle>,
+    /// `None` when `indent_size` is `tab`, which means it follows `tab_width`
+    indent_size: Option<Option<usize>>,
+    tab_width: Option<usize>,
+    end_of_line: Option<LineEnding>,
+    trim_trailing_whitespace: Option<bool>,
+    insert_final_newline: Option<bool>,
+}
+
+struct ConfigFile {
+    root: bool,
+    sections: Vec<Section>,
+}
+
+struct Section {
+    glob: String,
+    properties: Vec<(String, String)>,
+}
+
+impl EditorConfig {
+    /// Resolve the properties of `path` from the `.editorconfig` files of its ancestor directories.
+    /// Properties in closer files take precedence, and the search stops at a file with `root = true`.
+    pub(crate) fn for_path(path: &Path) -> EditorConfig {
+        let mut config_files = Vec::new();
+        for directory in path.ancestors().skip(1) {
+            let Ok(content) = std::fs::read_to_string(directory.join(FILE_NAME)) else {
+                continue;
+            };
+            let config_file = ConfigFile::parse(&content);
+            let root = config_file.root;
+            config_files.push((directory.to_path_buf(), config_file));
+            if root {
+                break;
+            }
+        }
+        config_files
+            .into_iter()
+            .rev()
+            .flat_map(|(directory, config_file)| {
+                config_file
+                    .sections
+                    .into_iter()
+                    .filter(move |section| section.matches(&directory, path))
+            })
+            .flat_map(|section| section.properties)
+            .fold(EditorConfig::default(), |config, (key, value)| {
+                config.set(&key, &value)
+            })
+    }
+
+    fn set(self, key: &str, value: &str) -> EditorConfig {
+        let unset = value == "unset";
+        let parse_bool = || match value {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        };
+        match key {
+            "indent_style" => EditorConfig {
+                indent_style: match value {
+                    "tab" => Some(IndentStyle::Tab),
+                    "space" => Some(IndentStyle::Space),
+                    _ => None,
+                },
+                ..self
+            },
+            "indent_size" => EditorConfig {
+                indent_size: if unset {
+                    None
+                } else if value == "tab" {
+                    Some(None)
+                } else {
+                    value.parse().ok().map(Some)
+                },
+                ..self
+            },
+            "tab_width" => EditorConfig {
+                tab_width: value.parse().ok(),
+                ..self
+            },
+            "end_of_line" => EditorConfig {
+                end_of_line: match value {
+                    "lf" => Some(LineEnding::Lf),
+                    "crlf" => Some(LineEnding::Crlf),
+                    "cr" => Some(LineEnding::Cr),
+                    _ => None,
+                },
+                ..self
+            },
+            "trim_trailing_whitespace" => EditorConfig {
+                trim_trailing_whitespace: parse_bool(),
+                ..self
+            },
+            "insert_final_newline" => EditorConfig {
+                insert_final_newline: parse_bool(),
+                ..self
+            },
+            _ => self,
+        }
+    }
+
+    /// The indent style and the indent size are resolved independently,
+    /// each of them is taken from the first of `fallbacks` that specifies it if it is not specified here.
+    pub(crate) fn indent(&self, fallbacks: impl IntoIterator<Item = Indent>) -> Indent {
+        let fallbacks: Vec<Indent> = fallbacks.into_iter().collect();
+        let style = self.indent_style.unwrap_or_else(|| {
+            match fallbacks.first().copied().unwrap_or_default() {
+                Indent::Tabs => IndentStyle::Tab,
+                Indent::Spaces(_) => IndentStyle::Space,
+            }
+        });
+        match style {
+            IndentStyle::Tab => Indent::Tabs,
+            IndentStyle::Space => {
+                let size = match self.indent_size {
+                    Some(Some(size)) => Some(size),
+                    Some(None) => self.tab_width,
+                    None => None,
+                };
+                size.or_else(|| {
+                    fallbacks.iter().find_map(|indent| match indent {
+                        Indent::Spaces(size) => Some(*size),
+                        Indent::Tabs => None,
+                    })
+                })
+                .map(Indent::Spaces)
+                .unwrap_or_default()
+            }
+        }
+    }
+
+    pub(crate) fn end_of_line(&self) -> Option<LineEnding> {
+        self.end_of_line
+    }
+
+    /// Apply the whitespace properties to `content`, this is used before saving a file.
+    ///
+    /// `end_of_line` is not applied here, because line endings are restored by
+    /// [`crate::file_format::FileFormat`] when the content is written.
+    pub(crate) fn apply(&self, content: &str) -> String {
+        let mut content = content.to_string();
+        if self.trim_trailing_whitespace == Some(true) {
+            content = content
+                .split_inclusive('\n')
+                .map(|line| {
+                    let body = line.trim_end_matches(['\n', '\r']);
+                    format!(
+                        "{}{}",
+                        body.trim_end_matches([' ', '\t']),
+                        &line[body.len()..]
+                    )
+                })
+                .collect();
+        }
+        match self.insert_final_newline {
+            Some(true) if !content.is_empty() && !content.ends_with('\n') => content.push('\n'),
+            Some(false) => {
+                content = content.trim_end_matches(['\n', '\r']).to_string();
+            }
+            _ => {}
+        }
+        content
+    }
+}
+
+impl ConfigFile {
+    fn parse(content: &str) -> ConfigFile {
+        let mut root = false;
+        let mut sections: Vec<Section> = Vec::new();
+        for line in content
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with(['#', ';']))
+        {
+            if let Some(glob) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                sections.push(Section {
+                    glob: glob.to_string(),
+                    properties: Vec::new(),
+                });
+            } else if let Some((key, value)) = line.split_once('=') {
+                let key = key.trim().to_lowercase();
+                let value = value.trim().to_lowercase();
+                match sections.last_mut() {
+                    Some(section) => section.properties.push((key, value)),
+                    None if key == "root" => root = value == "true",
+                    None => {}
+                }
+            }
+        }
+        ConfigFile { root, sections }
+    }
+}
+
+impl Section {
+    /// Globs without a slash match files in any subdirectory,
+    /// otherwise they are relative to the directory of the `.editorconfig` file.
+    fn matches(&self, directory: &Path, path: &Path) -> bool {
+        let Ok(relative_path) = path.strip_prefix(directory) else {
+            return false;
+        };
+        let glob = if self.glob.contains('/') {
+            self.glob.trim_start_matches('/').to_string()
+        } else {
+            format!("**/{}", self.glob)
+        };
+        GlobBuilder::new(&glob)
+            .literal_separator(true)
+            .build()
+            .map(|glob| glob.compile_matcher().is_match(relative_path))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod test_editorconfig {
+    use super::{EditorConfig, IndentStyle};
+    use crate::file_format::LineEnding;
+    use shared::indent::Indent;
+
+    #[test]
+    fn resolve_properties() -> anyhow::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let root = tempdir.path();
+        std::fs::write(
+            root.join(".editorconfig"),
+            "
+root = true
+
+[*]
+indent_style = space
+indent_size = 2
+end_of_line = lf
+
+[Makefile]
+indent_style = tab
+
+[src/**/*.rs]
+indent_size = 4
+",
+        )?;
+        std::fs::create_dir(root.join("src"))?;
+        std::fs::write(
+            root.join("src").join(".editorconfig"),
+            "[*.rs]\ntrim_trailing_whitespace = true",
+        )?;
+        let config = |path: &str| EditorConfig::for_path(&root.join(path));
+
+        assert_eq!(config("README.md").indent(None), Indent::Spaces(2));
+        assert_eq!(config("nested/Makefile").indent(None), Indent::Tabs);
+        let rust = config("src/main.rs");
+        assert_eq!(rust.indent(None), Indent::Spaces(4));
+        assert_eq!(rust.end_of_line(), Some(LineEnding::Lf));
+        assert_eq!(rust.trim_trailing_whitespace, Some(true));
+        assert_eq!(config("main.rs").trim_trailing_whitespace, None);
+        Ok(())
+    }
+
+    #[test]
+    fn indent_style_and_size_fall_back_independently() {
+        let style = |indent_style| EditorConfig {
+            indent_style: Some(indent_style),
+            ..Default::default()
+        };
+        let size = |indent_size| EditorConfig {
+            indent_size: Some(Some(indent_size)),
+            ..Default::default()
+        };
+        // The size of `indent_style = space` is taken from the first fallback with a size
+        assert_eq!(
+            style(IndentStyle::Space).indent([Indent::Tabs, Indent::Spaces(2)]),
+            Indent::Spaces(2)
+        );
+        assert_eq!(style(IndentStyle::Space).indent(None), Indent::Spaces(4));
+        // The style of `indent_size` is taken from the first fallback
+        assert_eq!(size(2).indent([Indent::Spaces(8)]), Indent::Spaces(2));
+        assert_eq!(size(2).indent([Indent::Tabs]), Indent::Tabs);
+        assert_eq!(
+            EditorConfig::default().indent([Indent::Spaces(3)]),
+            Indent::Spaces(3)
+        );
+    }
+
+    #[test]
+    fn apply() {
+        let config = EditorConfig {
+            trim_trailing_whitespace: Some(true),
+            insert_final_newline: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(config.apply("a  \nb\t\nc "), "a\nb\nc\n");
+    }
+}
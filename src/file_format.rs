@@ -0,0 +1,281 @@
+use std::path::Path;
+
+use anyhow::Context;
+
+/// The line ending used when saving a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+    Cr,
+}
+
+impl LineEnding {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+
+    pub(crate) fn display(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::Crlf => "CRLF",
+            LineEnding::Cr => "CR",
+        }
+    }
+
+    /// The line ending used by most lines of `content`.
+    fn detect(content: &str) -> LineEnding {
+        let crlf_count = content.matches("\r\n").count();
+        let lf_count = content.matches('\n').count() - crlf_count;
+        if crlf_count > lf_count {
+            LineEnding::Crlf
+        } else if lf_count == 0 && content.contains('\r') {
+            LineEnding::Cr
+        } else {
+            LineEnding::Lf
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Encoding {
+    #[default]
+    Utf8,
+    /// ISO-8859-1, where every byte is the code point of the same value
+    Latin1,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    pub(crate) fn display(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Latin1 => "Latin-1",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+        }
+    }
+
+    fn byte_order_mark(&self) -> &'static [u8] {
+        match self {
+            Encoding::Utf8 => &[0xEF, 0xBB, 0xBF],
+            Encoding::Latin1 => &[],
+            Encoding::Utf16Le => &[0xFF, 0xFE],
+            Encoding::Utf16Be => &[0xFE, 0xFF],
+        }
+    }
+}
+
+/// How the content of a buffer is stored on disk.
+///
+/// Buffers always hold `\n` line endings in memory when the file uses `CRLF` or `CR`,
+/// the original line ending is restored when saving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct FileFormat {
+    pub(crate) encoding: Encoding,
+    pub(crate) byte_order_mark: bool,
+    pub(crate) line_ending: LineEnding,
+}
+
+impl FileFormat {
+    pub(crate) fn display(&self) -> String {
+        format!(
+            "{}{} {}",
+            self.encoding.display(),
+            if self.byte_order_mark { " BOM" } else { "" },
+            self.line_ending.display()
+        )
+    }
+
+    pub(crate) fn read(path: &Path) -> anyhow::Result<(String, FileFormat)> {
+        FileFormat::decode(&std::fs::read(path)?)
+            .with_context(|| format!("Unable to read {}", path.display()))
+    }
+
+    pub(crate) fn write(&self, path: &Path, content: &str) -> anyhow::Result<()> {
+        Ok(std::fs::write(path, self.encode(content)?)?)
+    }
+
+    /// Detect the format of `bytes`, and decode it into content with normalized line endings.
+    ///
+    /// Files without a byte order mark that are not valid UTF-8 are decoded as Latin-1,
+    /// unless they look binary, which are refused because editing them would corrupt them.
+    pub(crate) fn decode(bytes: &[u8]) -> anyhow::Result<(String, FileFormat)> {
+        let (encoding, byte_order_mark, bytes) =
+            [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be]
+                .into_iter()
+                .find_map(|encoding| {
+                    bytes
+                        .strip_prefix(encoding.byte_order_mark())
+                        .map(|rest| (encoding, true, rest))
+                })
+                .unwrap_or((Encoding::Utf8, false, bytes));
+        if !byte_order_mark && is_binary(bytes) {
+            return Err(anyhow::anyhow!("Binary content cannot be edited"));
+        }
+        let (encoding, content) = match encoding {
+            Encoding::Utf8 => match std::str::from_utf8(bytes) {
+                Ok(content) => (Encoding::Utf8, content.to_string()),
+                Err(_) => (Encoding::Latin1, decode_latin1(bytes)),
+            },
+            Encoding::Utf16Le => (encoding, decode_utf16(bytes, u16::from_le_bytes)),
+            Encoding::Utf16Be => (encoding, decode_utf16(bytes, u16::from_be_bytes)),
+            Encoding::Latin1 => (encoding, decode_latin1(bytes)),
+        };
+        let line_ending = LineEnding::detect(&content);
+        let content = match line_ending {
+            // Keep the content untouched, so that stray CRLFs in an LF file are preserved
+            LineEnding::Lf => content,
+            LineEnding::Crlf => content.replace("\r\n", "\n"),
+            LineEnding::Cr => content.replace('\r', "\n"),
+        };
+        Ok((
+            content,
+            FileFormat {
+                encoding,
+                byte_order_mark,
+                line_ending,
+            },
+        ))
+    }
+
+    pub(crate) fn encode(&self, content: &str) -> anyhow::Result<Vec<u8>> {
+        let content = match self.line_ending {
+            LineEnding::Lf => content.to_string(),
+            line_ending => content
+                .replace("\r\n", "\n")
+                .replace('\n', line_ending.as_str()),
+        };
+        let byte_order_mark: &[u8] = if self.byte_order_mark {
+            self.encoding.byte_order_mark()
+        } else {
+            &[]
+        };
+        let bytes = match self.encoding {
+            Encoding::Utf8 => content.into_bytes(),
+            Encoding::Latin1 => content
+                .chars()
+                .map(|char| {
+                    u8::try_from(u32::from(char)).map_err(|_| {
+                        anyhow::anyhow!("The character {:?} cannot be encoded in Latin-1", char)
+                    })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?,
+            Encoding::Utf16Le => content
+                .encode_utf16()
+                .flat_map(|unit| unit.to_le_bytes())
+                .collect(),
+            Encoding::Utf16Be => content
+                .encode_utf16()
+                .flat_map(|unit| unit.to_be_bytes())
+                .collect(),
+        };
+        Ok(byte_order_mark.iter().copied().chain(bytes).collect())
+    }
+}
+
+/// Like Git, content with a NUL byte near its start is considered binary.
+fn is_binary(bytes: &[u8]) -> bool {
+    const BINARY_DETECTION_LENGTH: usize = 8000;
+    bytes
+        .iter()
+        .take(BINARY_DETECTION_LENGTH)
+        .any(|byte| *byte == 0)
+}
+
+fn decode_latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| char::from(*byte)).collect()
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    char::decode_utf16(
+        bytes
+            .chunks_exact(2)
+            .map(|chunk| from_bytes([chunk[0], chunk[1]])),
+    )
+    .map(|char| char.unwrap_or(char::REPLACEMENT_CHARACTER))
+    .collect()
+}
+
+#[cfg(test)]
+mod test_file_format {
+    use super::{Encoding, FileFormat, LineEnding};
+
+    #[test]
+    fn decode_then_encode_should_preserve_bytes() {
+        let cases: &[(&[u8], FileFormat, &str)] = &[
+            (b"a\nb\n", FileFormat::default(), "a\nb\n"),
+            (
+                b"a\r\nb\r\n",
+                FileFormat {
+                    line_ending: LineEnding::Crlf,
+                    ..Default::default()
+                },
+                "a\nb\n",
+            ),
+            (
+                b"\xEF\xBB\xBFa\r\n",
+                FileFormat {
+                    byte_order_mark: true,
+                    line_ending: LineEnding::Crlf,
+                    ..Default::default()
+                },
+                "a\n",
+            ),
+            (
+                b"caf\xE9\n",
+                FileFormat {
+                    encoding: Encoding::Latin1,
+                    ..Default::default()
+                },
+                "café\n",
+            ),
+            (
+                b"\xFF\xFEh\x00\xE9\x00\n\x00",
+                FileFormat {
+                    encoding: Encoding::Utf16Le,
+                    byte_order_mark: true,
+                    ..Default::default()
+                },
+                "hé\n",
+            ),
+            (
+                b"\xFE\xFF\x00h\x00\r\x00\n",
+                FileFormat {
+                    encoding: Encoding::Utf16Be,
+                    byte_order_mark: true,
+                    line_ending: LineEnding::Crlf,
+                },
+                "h\n",
+            ),
+        ];
+        for (bytes, expected_format, expected_content) in cases {
+            let (content, format) = FileFormat::decode(bytes).unwrap();
+            assert_eq!(&format, expected_format);
+            assert_eq!(&content, expected_content);
+            assert_eq!(&format.encode(&content).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn refuse_binary_content() {
+        assert!(FileFormat::decode(b"\x7FELF\x02\x01\x00\x00").is_err());
+        // UTF-16 content has NUL bytes, but it is not binary
+        assert!(FileFormat::decode(b"\xFF\xFEh\x00").is_ok());
+    }
+
+    #[test]
+    fn encode_unrepresentable_character_in_latin1() {
+        let format = FileFormat {
+            encoding: Encoding::Latin1,
+            ..Default::default()
+        };
+        assert!(format.encode("😊").is_err());
+    }
+}
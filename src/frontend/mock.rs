@@ -1,9 +1,10 @@
-#! #[cfg(test)]
-
 use crate::{components::component::Cursor, screen::Screen};
 
-use super::{MyWriter, StringWriter};
+use super::MyWriter;
+#[cfg(test)]
+use super::StringWriter;
 
+/// A frontend that renders nothing, used by tests and the headless batch mode.
 pub(crate) struct MockFrontend {
     /// Used for diffing to reduce unnecessary re-painting.
     previous_screen: Screen,
@@ -1,10 +1,8 @@
 pub(crate) mod crossterm;
-#[cfg(test)]
 pub(crate) mod mock;
 
 use std::any::Any;
 use std::io::Write;
-#[cfg(test)]
 use std::io::{self};
 
 use crate::{app::Dimension, components::component::Cursor, screen::Screen};
@@ -111,8 +109,8 @@ impl MyWriter for StringWriter {
     }
 }
 
-#[cfg(test)]
 impl MyWriter for NullWriter {
+    #[cfg(test)]
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -146,10 +144,8 @@ impl Write for StringWriter {
     }
 }
 
-#[cfg(test)]
 pub(crate) struct NullWriter;
 
-#[cfg(test)]
 impl Write for NullWriter {
     fn write(&mut self, _: &[u8]) -> io::Result<usize> {
         Ok(0)
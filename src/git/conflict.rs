@@ -0,0 +1,223 @@
+//! Conflict regions that Git writes into files after a failed merge or rebase, for example:
+//!
+//! ```text
+//! <<<<<<< HEAD
+//! ours
+//! ||||||| base (only present when `merge.conflictStyle` is `diff3`)
+//! base
+//! =======
+//! theirs
+//! >>>>>>> feature
+//! ```
+use std::ops::Range;
+
+/// Byte ranges of a conflict region, every range includes the trailing newline of its last line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Conflict {
+    /// From the start of the `<<<<<<<` line to the end of the `>>>>>>>` line
+    pub(crate) range: Range<usize>,
+    /// The lines of the markers
+    pub(crate) markers: Vec<Range<usize>>,
+    pub(crate) ours: Range<usize>,
+    pub(crate) base: Option<Range<usize>>,
+    pub(crate) theirs: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConflictResolution {
+    Ours,
+    Theirs,
+    /// Ours followed by theirs
+    Both,
+    Base,
+}
+
+impl ConflictResolution {
+    pub(crate) fn display(&self) -> &'static str {
+        match self {
+            ConflictResolution::Ours => "Accept ours",
+            ConflictResolution::Theirs => "Accept theirs",
+            ConflictResolution::Both => "Accept both",
+            ConflictResolution::Base => "Accept base",
+        }
+    }
+}
+
+impl Conflict {
+    /// Returns the text that replaces this conflict region.
+    pub(crate) fn resolve(
+        &self,
+        content: &str,
+        resolution: ConflictResolution,
+    ) -> anyhow::Result<String> {
+        let ours = &content[self.ours.clone()];
+        let theirs = &content[self.theirs.clone()];
+        Ok(match resolution {
+            ConflictResolution::Ours => ours.to_string(),
+            ConflictResolution::Theirs => theirs.to_string(),
+            ConflictResolution::Both => format!("{ours}{theirs}"),
+            ConflictResolution::Base => content[self.base.clone().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "This conflict has no base section, it is only written when `merge.conflictStyle` is `diff3`"
+                )
+            })?]
+            .to_string(),
+        })
+    }
+}
+
+enum State {
+    Ours {
+        start: usize,
+        markers: Vec<Range<usize>>,
+    },
+    Base {
+        start: usize,
+        markers: Vec<Range<usize>>,
+        ours: Range<usize>,
+    },
+    Theirs {
+        start: usize,
+        markers: Vec<Range<usize>>,
+        ours: Range<usize>,
+        base: Option<Range<usize>>,
+    },
+}
+
+/// Incomplete conflict regions are ignored.
+pub(crate) fn parse_conflicts(content: &str) -> Vec<Conflict> {
+    if !content.contains("<<<<<<<") {
+        return Vec::new();
+    }
+    let mut conflicts = Vec::new();
+    let mut state = None;
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let line_range = offset..offset + line.len();
+        offset = line_range.end;
+        state = match (state.take(), line) {
+            // A new conflict also discards an incomplete one
+            (_, line) if is_marker(line, "<<<<<<<") => Some(State::Ours {
+                start: line_range.start,
+                markers: vec![line_range],
+            }),
+            (Some(State::Ours { start, mut markers }), line) if is_marker(line, "|||||||") => {
+                let ours = markers[0].end..line_range.start;
+                markers.push(line_range);
+                Some(State::Base {
+                    start,
+                    markers,
+                    ours,
+                })
+            }
+            (Some(State::Ours { start, mut markers }), line) if is_marker(line, "=======") => {
+                let ours = markers[0].end..line_range.start;
+                markers.push(line_range);
+                Some(State::Theirs {
+                    start,
+                    markers,
+                    ours,
+                    base: None,
+                })
+            }
+            (
+                Some(State::Base {
+                    start,
+                    mut markers,
+                    ours,
+                }),
+                line,
+            ) if is_marker(line, "=======") => {
+                let base = markers[1].end..line_range.start;
+                markers.push(line_range);
+                Some(State::Theirs {
+                    start,
+                    markers,
+                    ours,
+                    base: Some(base),
+                })
+            }
+            (
+                Some(State::Theirs {
+                    start,
+                    mut markers,
+                    ours,
+                    base,
+                }),
+                line,
+            ) if is_marker(line, ">>>>>>>") => {
+                let theirs = markers[markers.len() - 1].end..line_range.start;
+                markers.push(line_range.clone());
+                conflicts.push(Conflict {
+                    range: start..line_range.end,
+                    markers,
+                    ours,
+                    base,
+                    theirs,
+                });
+                None
+            }
+            (state, _) => state,
+        }
+    }
+    conflicts
+}
+
+fn is_marker(line: &str, marker: &str) -> bool {
+    line.strip_prefix(marker)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\r', '\n']))
+}
+
+#[cfg(test)]
+mod test_conflict {
+    use super::{parse_conflicts, ConflictResolution};
+
+    #[test]
+    fn parse_and_resolve() -> anyhow::Result<()> {
+        let content = "\
+fn main() {
+<<<<<<< HEAD
+    ours();
+=======
+    theirs();
+>>>>>>> feature
+}
+<<<<<<< HEAD
+a
+||||||| base
+b
+=======
+c
+>>>>>>> feature
+";
+        let conflicts = parse_conflicts(content);
+        assert_eq!(conflicts.len(), 2);
+
+        let [first, second] = [&conflicts[0], &conflicts[1]];
+        assert_eq!(
+            &content[first.range.clone()],
+            "<<<<<<< HEAD\n    ours();\n=======\n    theirs();\n>>>>>>> feature\n"
+        );
+        assert_eq!(first.markers.len(), 3);
+        assert_eq!(
+            first.resolve(content, ConflictResolution::Ours)?,
+            "    ours();\n"
+        );
+        assert_eq!(
+            first.resolve(content, ConflictResolution::Both)?,
+            "    ours();\n    theirs();\n"
+        );
+        assert!(first.resolve(content, ConflictResolution::Base).is_err());
+
+        assert_eq!(second.markers.len(), 4);
+        assert_eq!(second.resolve(content, ConflictResolution::Base)?, "b\n");
+        assert_eq!(second.resolve(content, ConflictResolution::Theirs)?, "c\n");
+        Ok(())
+    }
+
+    #[test]
+    fn incomplete_conflicts_are_ignored() {
+        assert_eq!(parse_conflicts("<<<<<<< HEAD\na\n=======\nb\n"), Vec::new());
+        assert_eq!(parse_conflicts("a\n=======\nb\n>>>>>>> x\n"), Vec::new());
+    }
+}
@@ -12,6 +12,8 @@ use crate::{
 
 #[derive(Debug, Clone)]
 pub(crate) struct Hunk {
+    /// 0-based index
+    old_line_range: Range<usize>,
     /// 0-based index
     new_line_range: Range<usize>,
 
@@ -30,28 +32,29 @@ impl Hunk {
             .iter()
             .filter_map(|group| {
                 // I'm going to assume each group only has one change (i.e. Delete/Insert/Replace), while the other diff_ops are Equal
-                let (_, new_line_range) = group.iter().find_map(|diff_op| match diff_op {
-                    similar::DiffOp::Equal { .. } => None,
-                    similar::DiffOp::Delete {
-                        new_index,
-                        old_index,
-                        old_len,
-                    } => Some((*old_index..(old_index + old_len), *new_index..*new_index)),
-                    similar::DiffOp::Insert {
-                        new_index,
-                        new_len,
-                        old_index,
-                    } => Some((*old_index..*old_index, *new_index..(new_index + new_len))),
-                    similar::DiffOp::Replace {
-                        new_index,
-                        new_len,
-                        old_index,
-                        old_len,
-                    } => Some((
-                        *old_index..(old_index + old_len),
-                        *new_index..(new_index + new_len),
-                    )),
-                })?;
+                let (old_line_range, new_line_range) =
+                    group.iter().find_map(|diff_op| match diff_op {
+                        similar::DiffOp::Equal { .. } => None,
+                        similar::DiffOp::Delete {
+                            new_index,
+                            old_index,
+                            old_len,
+                        } => Some((*old_index..(old_index + old_len), *new_index..*new_index)),
+                        similar::DiffOp::Insert {
+                            new_index,
+                            new_len,
+                            old_index,
+                        } => Some((*old_index..*old_index, *new_index..(new_index + new_len))),
+                        similar::DiffOp::Replace {
+                            new_index,
+                            new_len,
+                            old_index,
+                            old_len,
+                        } => Some((
+                            *old_index..(old_index + old_len),
+                            *new_index..(new_index + new_len),
+                        )),
+                    })?;
 
                 #[derive(PartialEq)]
                 enum LineKind {
@@ -109,6 +112,7 @@ impl Hunk {
                     .collect_vec();
                 let content = trim_start(content, min_leading_whitespaces_count);
                 Some(Hunk {
+                    old_line_range,
                     new_line_range,
                     content,
                     decorations,
@@ -120,8 +124,13 @@ impl Hunk {
         &self.new_line_range
     }
 
+    pub(crate) fn old_line_range(&self) -> &Range<usize> {
+        &self.old_line_range
+    }
+
     pub(crate) fn one_insert(message: &str) -> Hunk {
         Hunk {
+            old_line_range: 0..0,
             new_line_range: 0..0,
             content: message.to_string(),
             decorations: Vec::new(),
@@ -135,6 +144,69 @@ impl Hunk {
     }
 }
 
+/// Returns the content obtained by applying `hunks` to `old`.
+/// `hunks` should be obtained via `Hunk::get(old, new)`.
+pub(crate) fn apply_hunks(old: &str, new: &str, hunks: &[Hunk]) -> String {
+    let old_lines = old.split_inclusive('\n').collect_vec();
+    let new_lines = new.split_inclusive('\n').collect_vec();
+    let (result, old_index) = hunks
+        .iter()
+        .sorted_by_key(|hunk| hunk.old_line_range.start)
+        .fold((String::new(), 0), |(result, old_index), hunk| {
+            let unchanged = old_lines
+                .get(old_index..hunk.old_line_range.start)
+                .unwrap_or_default();
+            let replacement = new_lines
+                .get(hunk.new_line_range.clone())
+                .unwrap_or_default();
+            (
+                result + &unchanged.concat() + &replacement.concat(),
+                hunk.old_line_range.end,
+            )
+        });
+    result + &old_lines.get(old_index..).unwrap_or_default().concat()
+}
+
+/// Maps `line_ranges` of `new` to the line ranges of `old` that they correspond to.
+/// `hunks` should be obtained via `Hunk::get(old, new)`.
+/// A line within a hunk is mapped to every old line of that hunk.
+pub(crate) fn map_line_ranges_to_old(
+    hunks: &[Hunk],
+    line_ranges: &[Range<usize>],
+) -> Vec<Range<usize>> {
+    let map_line = |line: usize| -> Range<usize> {
+        let mut offset = 0isize;
+        for hunk in hunks.iter().sorted_by_key(|hunk| hunk.new_line_range.start) {
+            if hunk.new_line_range.contains(&line) {
+                return hunk.old_line_range.clone();
+            }
+            if hunk.new_line_range.start > line {
+                break;
+            }
+            offset += hunk.old_line_range.len() as isize - hunk.new_line_range.len() as isize;
+        }
+        let line = (line as isize + offset).max(0) as usize;
+        line..line + 1
+    };
+    line_ranges
+        .iter()
+        .map(|range| {
+            let start = map_line(range.start);
+            let end = map_line(range.end.max(range.start + 1) - 1);
+            start.start..end.end.max(start.end)
+        })
+        .collect_vec()
+}
+
+/// Returns true if `line_range` intersects with any of `line_ranges`.
+/// An empty `line_range` (i.e. a deletion) is treated as the line it precedes.
+pub(crate) fn intersects(line_range: &Range<usize>, line_ranges: &[Range<usize>]) -> bool {
+    let end = line_range.end.max(line_range.start + 1);
+    line_ranges
+        .iter()
+        .any(|range| line_range.start < range.end && range.start < end)
+}
+
 fn leading_whitespace_count(s: &str) -> usize {
     s.chars().take_while(|c| c.is_whitespace()).count()
 }
@@ -226,6 +298,29 @@ mod test_hunk {
             .collect_vec();
         assert_eq!(words, vec!["Hello(", "world", ")", "Hello(", "bumi", ")"]);
     }
+    #[test]
+    fn apply_hunks() {
+        let old = "a\nb\nc\nd\ne\n";
+        let new = "a\nB\nc\nd\nE\nf\n";
+        let hunks = Hunk::get(old, new);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(super::apply_hunks(old, new, &hunks), new);
+        assert_eq!(super::apply_hunks(old, new, &[]), old);
+        assert_eq!(
+            super::apply_hunks(old, new, &hunks[1..]),
+            "a\nb\nc\nd\nE\nf\n"
+        );
+    }
+
+    #[test]
+    fn intersects() {
+        assert!(super::intersects(&(1..3), &[2..3]));
+        assert!(!super::intersects(&(1..3), &[3..4]));
+        // Deletion hunk
+        assert!(super::intersects(&(2..2), &[2..3]));
+        assert!(!super::intersects(&(2..2), &[0..2]));
+    }
+
     #[test]
     fn to_info_insertion() {
         let hunk = Hunk::get("a\nd", "a\nb\nc\nd")[0].clone();
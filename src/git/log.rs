@@ -0,0 +1,373 @@
+//! Commit history, and the content of commits and revisions.
+//! A revision is anything that `git rev-parse` understands, for example a commit id or a branch name.
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use git2::{
+    BranchType, Delta, DiffFindOptions, DiffFormat, Oid, Sort, Tree, TreeWalkMode, TreeWalkResult,
+};
+use itertools::Itertools;
+use shared::canonicalized_path::CanonicalizedPath;
+
+use crate::file_format::FileFormat;
+
+use super::GitRepo;
+
+/// The maximum number of commits listed by the log browser
+pub(crate) const MAX_LOG_LENGTH: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CommitSummary {
+    pub(crate) id: String,
+    pub(crate) summary: String,
+    pub(crate) author: String,
+    /// Seconds since the Unix epoch
+    pub(crate) time: i64,
+}
+
+impl CommitSummary {
+    pub(crate) fn short_id(&self) -> &str {
+        short_revision(&self.id)
+    }
+
+    pub(crate) fn age(&self) -> String {
+        age(self.time)
+    }
+}
+
+impl GitRepo {
+    /// Returns at most `limit` commits reachable from HEAD, newest first.
+    /// If `path` is given, only the commits that changed it are returned,
+    /// including the commits that changed it before it was renamed, like `git log --follow`.
+    pub(crate) fn log(
+        &self,
+        path: Option<&CanonicalizedPath>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<CommitSummary>> {
+        let relative_path = path.map(|path| self.relative_path(path)).transpose()?;
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push_head()?;
+        // When following a file, the path of the file in a commit
+        // has to be known before its parents are visited
+        revwalk.set_sorting(if relative_path.is_some() {
+            Sort::TOPOLOGICAL | Sort::TIME
+        } else {
+            Sort::TIME
+        })?;
+        // The path of the file in the commits that are yet to be visited,
+        // because the file might be renamed in one line of history but not in another
+        let mut parent_paths: HashMap<Oid, PathBuf> = HashMap::new();
+        let mut commits = Vec::new();
+        for oid in revwalk {
+            if commits.len() >= limit {
+                break;
+            }
+            let oid = oid?;
+            let commit = self.repo.find_commit(oid)?;
+            if let Some(relative_path) = relative_path.as_ref() {
+                let path = parent_paths
+                    .remove(&oid)
+                    .unwrap_or_else(|| relative_path.clone());
+                let entry_id =
+                    |tree: &Tree<'_>| -> Option<Oid> { Some(tree.get_path(&path).ok()?.id()) };
+                let tree = commit.tree()?;
+                let id = entry_id(&tree);
+                // Merge commits are compared against their first parent
+                let mut first_parent_id = None;
+                for (index, parent) in commit.parents().enumerate() {
+                    let parent_tree = parent.tree()?;
+                    let parent_id = entry_id(&parent_tree);
+                    if index == 0 {
+                        first_parent_id = parent_id
+                    }
+                    // The file is added by this commit, possibly by renaming another file
+                    let parent_path = match (id, parent_id) {
+                        (Some(_), None) => self.renamed_from(&parent_tree, &tree, &path)?,
+                        _ => None,
+                    }
+                    .unwrap_or_else(|| path.clone());
+                    parent_paths.entry(parent.id()).or_insert(parent_path);
+                }
+                if id == first_parent_id {
+                    continue;
+                }
+            }
+            commits.push(CommitSummary {
+                id: commit.id().to_string(),
+                summary: commit.summary().unwrap_or_default().to_string(),
+                author: commit.author().name().unwrap_or_default().to_string(),
+                time: commit.time().seconds(),
+            })
+        }
+        Ok(commits)
+    }
+
+    /// Returns the path that `path` was renamed from between the trees, if any.
+    fn renamed_from(
+        &self,
+        old_tree: &Tree<'_>,
+        new_tree: &Tree<'_>,
+        path: &Path,
+    ) -> anyhow::Result<Option<PathBuf>> {
+        let mut diff = self
+            .repo
+            .diff_tree_to_tree(Some(old_tree), Some(new_tree), None)?;
+        diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+        Ok(diff
+            .deltas()
+            .find(|delta| delta.status() == Delta::Renamed && delta.new_file().path() == Some(path))
+            .and_then(|delta| Some(delta.old_file().path()?.to_path_buf())))
+    }
+
+    /// Returns the header and the patch of the commit, similar to `git show`.
+    /// Merge commits are compared against their first parent.
+    pub(crate) fn commit_patch(&self, revision: &str) -> anyhow::Result<String> {
+        let commit = self.repo.revparse_single(revision)?.peel_to_commit()?;
+        let parent_tree = commit
+            .parents()
+            .next()
+            .map(|parent| parent.tree())
+            .transpose()?;
+        let diff =
+            self.repo
+                .diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        let author = commit.author();
+        let mut patch = format!(
+            "commit {}\nAuthor: {} <{}>\nDate:   {}\n\n{}\n\n",
+            commit.id(),
+            author.name().unwrap_or_default(),
+            author.email().unwrap_or_default(),
+            age(commit.time().seconds()),
+            commit
+                .message()
+                .unwrap_or_default()
+                .trim_end()
+                .lines()
+                .map(|line| format!("    {line}"))
+                .join("\n")
+        );
+        diff.print(DiffFormat::Patch, |_, _, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                patch.push(line.origin())
+            }
+            patch.push_str(&String::from_utf8_lossy(line.content()));
+            true
+        })?;
+        Ok(patch)
+    }
+
+    /// `path` is relative to the repository.
+    pub(crate) fn content_at_revision(
+        &self,
+        path: &Path,
+        revision: &str,
+    ) -> anyhow::Result<String> {
+        let tree = self.repo.revparse_single(revision)?.peel_to_tree()?;
+        let blob = tree.get_path(path)?.to_object(&self.repo)?.peel_to_blob()?;
+        Ok(FileFormat::decode(blob.content())?.0)
+    }
+
+    /// Returns the paths of the files at the revision, relative to the repository.
+    pub(crate) fn files_at_revision(&self, revision: &str) -> anyhow::Result<Vec<PathBuf>> {
+        let tree = self.repo.revparse_single(revision)?.peel_to_tree()?;
+        let mut paths = Vec::new();
+        tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+            if entry.kind() == Some(git2::ObjectType::Blob) {
+                if let Some(name) = entry.name() {
+                    paths.push(Path::new(root).join(name))
+                }
+            }
+            TreeWalkResult::Ok
+        })?;
+        Ok(paths)
+    }
+
+    /// Local branches come before remote branches.
+    pub(crate) fn branches(&self) -> anyhow::Result<Vec<String>> {
+        let mut branches = Vec::new();
+        for branch_type in [BranchType::Local, BranchType::Remote] {
+            branches.extend(
+                self.repo
+                    .branches(Some(branch_type))?
+                    .filter_map(|branch| Some(branch.ok()?.0.name().ok()??.to_string()))
+                    .sorted(),
+            )
+        }
+        Ok(branches)
+    }
+}
+
+/// Commit ids are shortened to 7 characters, other revisions are returned as is.
+pub(crate) fn short_revision(revision: &str) -> &str {
+    if revision.len() == 40 && revision.chars().all(|c| c.is_ascii_hexdigit()) {
+        &revision[..7]
+    } else {
+        revision
+    }
+}
+
+fn age(time: i64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default();
+    let seconds = now.saturating_sub(time).max(0);
+    let (count, unit) = [
+        (60 * 60 * 24 * 365, "year"),
+        (60 * 60 * 24 * 30, "month"),
+        (60 * 60 * 24, "day"),
+        (60 * 60, "hour"),
+        (60, "minute"),
+    ]
+    .into_iter()
+    .find(|(unit_seconds, _)| seconds >= *unit_seconds)
+    .map(|(unit_seconds, unit)| (seconds / unit_seconds, unit))
+    .unwrap_or((seconds, "second"));
+    format!("{count} {unit}{} ago", if count == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod test_log {
+    use std::{path::PathBuf, process::Command};
+
+    use itertools::Itertools;
+    use shared::canonicalized_path::CanonicalizedPath;
+
+    use crate::git::{DiffMode, GitRepo};
+
+    fn run_command(dir: &tempfile::TempDir, command: &str, args: &[&str]) {
+        Command::new(command)
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to run command");
+    }
+
+    #[test]
+    fn log_and_revisions() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        run_command(&dir, "git", &["init", "--initial-branch=main"]);
+        run_command(&dir, "git", &["config", "user.name", "Ki"]);
+        run_command(&dir, "git", &["config", "user.email", "ki@example.com"]);
+        let commit = |file: &str, content: &str, message: &str| -> anyhow::Result<()> {
+            std::fs::write(dir.path().join(file), content)?;
+            run_command(&dir, "git", &["add", "."]);
+            run_command(&dir, "git", &["commit", "-m", message]);
+            Ok(())
+        };
+        commit("a.txt", "a\n", "Add a")?;
+        commit("b.txt", "b\n", "Add b")?;
+        commit("a.txt", "A\n", "Change a")?;
+        run_command(&dir, "git", &["branch", "feature"]);
+
+        let repo = GitRepo::try_from(&dir.path().try_into()?)?;
+        let summaries = |path: Option<&str>| -> anyhow::Result<Vec<String>> {
+            let path: Option<CanonicalizedPath> = path
+                .map(|path| dir.path().join(path).try_into())
+                .transpose()?;
+            Ok(repo
+                .log(path.as_ref(), 10)?
+                .into_iter()
+                .map(|commit| commit.summary)
+                .collect())
+        };
+        assert_eq!(summaries(None)?, ["Change a", "Add b", "Add a"]);
+        assert_eq!(summaries(Some("a.txt"))?, ["Change a", "Add a"]);
+        assert_eq!(repo.log(None, 1)?.len(), 1);
+
+        let first = repo.log(None, 10)?.last().unwrap().id.clone();
+        let patch = repo.commit_patch("HEAD")?;
+        assert!(patch.contains("    Change a\n"));
+        assert!(patch.contains("@@ -1 +1 @@\n-a\n+A\n"));
+
+        assert_eq!(
+            repo.content_at_revision(&PathBuf::from("a.txt"), &first)?,
+            "a\n"
+        );
+        assert_eq!(
+            repo.files_at_revision(&first)?,
+            [PathBuf::from("a.txt")].to_vec()
+        );
+        assert_eq!(repo.branches()?, ["feature", "main"]);
+
+        // Compare the working directory against an arbitrary revision
+        let working_directory: CanonicalizedPath = dir.path().try_into()?;
+        let paths = repo
+            .diff_entries(DiffMode::UnstagedAgainstRevision(first))?
+            .into_iter()
+            .map(|entry| entry.new_path().display_relative_to(&working_directory))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        assert_eq!(paths, ["a.txt", "b.txt"]);
+
+        // The history of a renamed file includes the commits before the rename
+        run_command(&dir, "git", &["mv", "a.txt", "c.txt"]);
+        run_command(&dir, "git", &["commit", "-m", "Rename a"]);
+        assert_eq!(summaries(Some("c.txt"))?, ["Rename a", "Change a", "Add a"]);
+
+        // The content is decoded like a file, so CRLF line endings are normalized
+        commit("d.txt", "d\r\n", "Add d")?;
+        assert_eq!(
+            repo.content_at_revision(&PathBuf::from("d.txt"), "HEAD")?,
+            "d\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn file_history_follows_renames_per_line_of_history() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        run_command(&dir, "git", &["init", "--initial-branch=main"]);
+        run_command(&dir, "git", &["config", "user.name", "Ki"]);
+        run_command(&dir, "git", &["config", "user.email", "ki@example.com"]);
+        let git_at = |time: u32, args: &[&str]| {
+            let date = format!("{time} +0000");
+            Command::new("git")
+                .args(args)
+                .env("GIT_AUTHOR_DATE", &date)
+                .env("GIT_COMMITTER_DATE", &date)
+                .current_dir(dir.path())
+                .output()
+                .expect("Failed to run command");
+        };
+        std::fs::write(dir.path().join("x.txt"), "x\n")?;
+        run_command(&dir, "git", &["add", "."]);
+        git_at(1_700_001_000, &["commit", "-m", "Add x"]);
+
+        // Both branches rename the file, but only the older rename is followed by a change
+        run_command(&dir, "git", &["checkout", "-b", "b"]);
+        run_command(&dir, "git", &["mv", "x.txt", "y.txt"]);
+        git_at(1_700_002_000, &["commit", "-m", "Rename x on b"]);
+        std::fs::write(dir.path().join("y.txt"), "x\ny\n")?;
+        run_command(&dir, "git", &["add", "."]);
+        git_at(1_700_002_500, &["commit", "-m", "Change y on b"]);
+
+        run_command(&dir, "git", &["checkout", "main"]);
+        run_command(&dir, "git", &["checkout", "-b", "a"]);
+        run_command(&dir, "git", &["mv", "x.txt", "y.txt"]);
+        git_at(1_700_003_000, &["commit", "-m", "Rename x on a"]);
+        git_at(1_700_004_000, &["merge", "b", "-m", "Merge b"]);
+
+        let repo = GitRepo::try_from(&dir.path().try_into()?)?;
+        let path: CanonicalizedPath = dir.path().join("y.txt").try_into()?;
+        // The order of commits in different lines of history is not specified
+        let summaries = repo
+            .log(Some(&path), 10)?
+            .into_iter()
+            .map(|commit| commit.summary)
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summaries,
+            [
+                "Add x",
+                "Change y on b",
+                "Merge b",
+                "Rename x on a",
+                "Rename x on b",
+            ]
+        );
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
+pub(crate) mod conflict;
 pub(crate) mod hunk;
+pub(crate) mod log;
 
 use anyhow::bail;
 use rayon::prelude::*;
@@ -7,6 +9,12 @@ use git2::Repository;
 
 use itertools::Itertools;
 use shared::canonicalized_path::CanonicalizedPath;
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::file_format::FileFormat;
 
 use self::hunk::Hunk;
 
@@ -82,9 +90,7 @@ impl GitRepo {
                     .to_string();
 
                 let get_blob_content = |oid: git2::Oid| -> anyhow::Result<_> {
-                    Ok(repo
-                        .find_blob(oid)
-                        .map(|blob| str::from_utf8(blob.content()).unwrap_or("").to_string())?)
+                    Ok(FileFormat::decode(repo.find_blob(oid)?.content())?.0)
                 };
                 // Get the old content
                 let old_oid = delta.old_file().id();
@@ -93,13 +99,15 @@ impl GitRepo {
                 // Get the new content
                 let new_oid = delta.new_file().id();
                 let new_content = get_blob_content(new_oid).or_else(|_| -> anyhow::Result<_> {
-                    Ok(std::fs::read_to_string(
-                        repo.workdir()
+                    Ok(FileFormat::read(
+                        &repo
+                            .workdir()
                             .ok_or(anyhow::anyhow!(
                                 "Unable to get repository working directory."
                             ))?
                             .join(new_path.clone()),
-                    )?)
+                    )?
+                    .0)
                 })?;
 
                 Ok(Some(DiffEntry {
@@ -114,6 +122,318 @@ impl GitRepo {
         Ok(entries)
     }
 
+    /// Stage the hunks of `path` that intersect with `line_ranges`.
+    /// `line_ranges` are 0-based line ranges of the working copy.
+    /// If `line_ranges` is `None`, the whole file is staged.
+    ///
+    /// Both sides are decoded with [`FileFormat`] before being compared,
+    /// and the staged content is encoded back in the format of the indexed file,
+    /// so that line endings and encodings do not show up as changes.
+    pub(crate) fn stage_hunks(
+        &self,
+        path: &CanonicalizedPath,
+        line_ranges: Option<&[Range<usize>]>,
+    ) -> anyhow::Result<()> {
+        let relative_path = self.relative_path(path)?;
+        let mut index = self.repo.index()?;
+        match line_ranges {
+            None => index.add_path(&relative_path)?,
+            Some(line_ranges) => {
+                let (current_content, current_format) = FileFormat::read(path.as_ref())?;
+                let (indexed_content, format) = self
+                    .index_content(&index, &relative_path)?
+                    .unwrap_or((String::new(), current_format));
+                let hunks = Hunk::get(&indexed_content, &current_content)
+                    .into_iter()
+                    .filter(|hunk| hunk::intersects(hunk.line_range(), line_ranges))
+                    .collect_vec();
+                let content = hunk::apply_hunks(&indexed_content, &current_content, &hunks);
+                self.write_index_content(&mut index, &relative_path, &format.encode(&content)?)?
+            }
+        }
+        index.write()?;
+        Ok(())
+    }
+
+    /// Unstage the staged hunks of `path` that intersect with `line_ranges`.
+    /// `line_ranges` are 0-based line ranges of the working copy,
+    /// which are mapped to the staged content through the changes that are not staged.
+    /// If `line_ranges` is `None`, the whole file is unstaged.
+    pub(crate) fn unstage_hunks(
+        &self,
+        path: &CanonicalizedPath,
+        line_ranges: Option<&[Range<usize>]>,
+    ) -> anyhow::Result<()> {
+        let relative_path = self.relative_path(path)?;
+        let mut index = self.repo.index()?;
+        let head_blob = self.head_blob(&relative_path)?;
+        match (line_ranges, head_blob) {
+            (None, None) => index.remove_path(&relative_path)?,
+            (None, Some(head_blob)) => {
+                self.write_index_content(&mut index, &relative_path, head_blob.content())?
+            }
+            (Some(line_ranges), head_blob) => {
+                let (head_content, head_format) = head_blob
+                    .map(|blob| FileFormat::decode(blob.content()))
+                    .transpose()?
+                    .unwrap_or_default();
+                let (indexed_content, format) = self
+                    .index_content(&index, &relative_path)?
+                    .unwrap_or((String::new(), head_format));
+                let (current_content, _) = FileFormat::read(path.as_ref())?;
+                let line_ranges = hunk::map_line_ranges_to_old(
+                    &Hunk::get(&indexed_content, &current_content),
+                    line_ranges,
+                );
+                // The hunks are computed from the index towards HEAD,
+                // so that their old line ranges refer to the staged content
+                let hunks = Hunk::get(&indexed_content, &head_content)
+                    .into_iter()
+                    .filter(|hunk| hunk::intersects(hunk.old_line_range(), &line_ranges))
+                    .collect_vec();
+                let content = hunk::apply_hunks(&indexed_content, &head_content, &hunks);
+                self.write_index_content(&mut index, &relative_path, &format.encode(&content)?)?
+            }
+        }
+        index.write()?;
+        Ok(())
+    }
+
+    /// Returns the paths of files with unresolved conflicts according to the index,
+    /// which are left behind by a failed merge or rebase.
+    pub(crate) fn conflicted_paths(&self) -> anyhow::Result<Vec<PathBuf>> {
+        Ok(self
+            .repo
+            .index()?
+            .conflicts()?
+            .filter_map(|conflict| {
+                let conflict = conflict.ok()?;
+                // A file might be missing on either side, for example when it is deleted by one of them
+                let entry = conflict.our.or(conflict.their).or(conflict.ancestor)?;
+                Some(
+                    self.path
+                        .to_path_buf()
+                        .join(String::from_utf8_lossy(&entry.path).as_ref()),
+                )
+            })
+            .unique()
+            .collect_vec())
+    }
+
+    /// Returns the status of every changed file that exists in the working directory.
+    pub(crate) fn file_statuses(&self) -> anyhow::Result<Vec<(CanonicalizedPath, FileStatus)>> {
+        let mut options = git2::StatusOptions::new();
+        options.include_untracked(true).recurse_untracked_dirs(true);
+        Ok(self
+            .repo
+            .statuses(Some(&mut options))?
+            .iter()
+            .filter_map(|entry| {
+                let status = entry.status();
+                let file_status = if status.is_index_new() {
+                    FileStatus::Added
+                } else if status.is_wt_new() {
+                    FileStatus::Untracked
+                } else {
+                    FileStatus::Modified
+                };
+                // Deleted files cannot be canonicalized
+                Some((self.path.join(entry.path()?).ok()?, file_status))
+            })
+            .collect_vec())
+    }
+
+    pub(crate) fn commit_message_path(&self) -> PathBuf {
+        self.repo.path().join("COMMIT_EDITMSG")
+    }
+
+    /// Returns the initial content of the commit message file,
+    /// which consists of the message of the last commit if `amend` is true,
+    /// followed by a commented summary of the staged changes.
+    /// When amending, the changes are summarized against the parent of the last commit,
+    /// because the amended commit replaces the last commit.
+    pub(crate) fn commit_message_template(&self, amend: bool) -> anyhow::Result<String> {
+        let head_commit = self.head_commit();
+        let message = match (&head_commit, amend) {
+            (Some(commit), true) => commit.message().unwrap_or_default().trim_end().to_string(),
+            _ => String::new(),
+        };
+        let base_commit = match (head_commit, amend) {
+            (Some(commit), true) => commit.parent(0).ok(),
+            (head_commit, _) => head_commit,
+        };
+        let base_tree = base_commit.map(|commit| commit.tree()).transpose()?;
+        let diff = self
+            .repo
+            .diff_tree_to_index(base_tree.as_ref(), None, None)?;
+        let changes = diff
+            .deltas()
+            .filter_map(|delta| {
+                let status = match delta.status() {
+                    git2::Delta::Added => "new file",
+                    git2::Delta::Deleted => "deleted",
+                    git2::Delta::Renamed => "renamed",
+                    git2::Delta::Typechange => "typechange",
+                    _ => "modified",
+                };
+                let path = delta.new_file().path()?.display().to_string();
+                Some(format!("#\t{}:   {}", status, path))
+            })
+            .join("\n");
+        let stats = diff
+            .stats()?
+            .to_buf(git2::DiffStatsFormat::SHORT, 80)?
+            .as_str()
+            .unwrap_or_default()
+            .trim_end()
+            .to_string();
+        Ok(format!(
+            "{message}
+
+# Please enter the commit message for your changes. Lines starting
+# with '#' will be ignored, and an empty message aborts the commit.
+#
+# Changes to be committed:
+{changes}
+#
+#{stats}
+"
+        ))
+    }
+
+    /// Create a commit out of the index, using the message stored in `commit_message_path`.
+    /// The `pre-commit` and `commit-msg` hooks are executed if they exist.
+    /// Returns the summary of the created commit.
+    pub(crate) fn commit(&self, amend: bool) -> anyhow::Result<String> {
+        let head_commit = self.head_commit();
+        let mut index = self.repo.index()?;
+        let tree = self.repo.find_tree(index.write_tree()?)?;
+        if !amend
+            && head_commit
+                .as_ref()
+                .is_some_and(|commit| commit.tree_id() == tree.id())
+        {
+            bail!("Nothing to commit, no changes are staged.")
+        }
+
+        self.run_hook("pre-commit", &[])?;
+        let message_path = self.commit_message_path();
+        self.run_hook("commit-msg", &[&message_path.display().to_string()])?;
+        let message = clean_commit_message(&std::fs::read_to_string(message_path)?);
+        if message.is_empty() {
+            bail!("Aborting commit due to empty commit message.")
+        }
+
+        let oid = match (head_commit, amend) {
+            (Some(head_commit), true) => {
+                head_commit.amend(Some("HEAD"), None, None, None, Some(&message), Some(&tree))?
+            }
+            (None, true) => bail!("There is no commit to amend."),
+            (head_commit, false) => {
+                let signature = self.repo.signature()?;
+                let parents = head_commit.iter().collect_vec();
+                self.repo.commit(
+                    Some("HEAD"),
+                    &signature,
+                    &signature,
+                    &message,
+                    &tree,
+                    &parents,
+                )?
+            }
+        };
+        let summary = self
+            .repo
+            .find_commit(oid)?
+            .summary()
+            .unwrap_or_default()
+            .to_string();
+        Ok(format!("{:.7} {}", oid.to_string(), summary))
+    }
+
+    fn head_commit(&self) -> Option<git2::Commit<'_>> {
+        self.repo.head().ok()?.peel_to_commit().ok()
+    }
+
+    fn run_hook(&self, name: &str, args: &[&str]) -> anyhow::Result<()> {
+        let hook = self.repo.path().join("hooks").join(name);
+        if !hook.is_file() {
+            return Ok(());
+        }
+        let output = std::process::Command::new(hook)
+            .args(args)
+            .current_dir(self.path())
+            .output()?;
+        if !output.status.success() {
+            bail!(
+                "The {} hook failed:\n{}{}",
+                name,
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            )
+        }
+        Ok(())
+    }
+
+    fn relative_path(&self, path: &CanonicalizedPath) -> anyhow::Result<PathBuf> {
+        Ok(PathBuf::from(path.display_relative_to(self.path())?))
+    }
+
+    fn index_content(
+        &self,
+        index: &git2::Index,
+        relative_path: &Path,
+    ) -> anyhow::Result<Option<(String, FileFormat)>> {
+        index
+            .get_path(relative_path, 0)
+            .map(|entry| -> anyhow::Result<_> {
+                FileFormat::decode(self.repo.find_blob(entry.id)?.content())
+            })
+            .transpose()
+    }
+
+    fn head_blob(&self, relative_path: &Path) -> anyhow::Result<Option<git2::Blob<'_>>> {
+        // A repository without any commit has no HEAD
+        let Ok(tree) = self.get_tree(&DiffMode::UnstagedAgainstCurrentBranch) else {
+            return Ok(None);
+        };
+        let Ok(entry) = tree.get_path(relative_path) else {
+            return Ok(None);
+        };
+        Ok(Some(self.repo.find_blob(entry.id())?))
+    }
+
+    fn write_index_content(
+        &self,
+        index: &mut git2::Index,
+        relative_path: &Path,
+        content: &[u8],
+    ) -> anyhow::Result<()> {
+        let entry = match index.get_path(relative_path, 0) {
+            Some(entry) => entry,
+            None => git2::IndexEntry {
+                ctime: git2::IndexTime::new(0, 0),
+                mtime: git2::IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode: 0o100644,
+                uid: 0,
+                gid: 0,
+                file_size: 0,
+                id: git2::Oid::zero(),
+                flags: 0,
+                flags_extended: 0,
+                path: relative_path
+                    .to_str()
+                    .ok_or(anyhow::anyhow!("Unable to convert path to string."))?
+                    .as_bytes()
+                    .to_vec(),
+            },
+        };
+        index.add_frombuffer(&entry, content)?;
+        Ok(())
+    }
+
     fn get_tree(&self, diff_mode: &DiffMode) -> Result<git2::Tree<'_>, anyhow::Error> {
         match diff_mode {
             DiffMode::UnstagedAgainstMainBranch => Ok(self
@@ -125,10 +445,26 @@ impl GitRepo {
             DiffMode::UnstagedAgainstCurrentBranch => {
                 Ok(self.repo.head()?.peel_to_commit()?.tree()?)
             }
+            DiffMode::UnstagedAgainstRevision(revision) => Ok(self
+                .repo
+                .revparse_single(revision)?
+                .peel_to_commit()?
+                .tree()?),
         }
     }
 }
 
+/// Removes comment lines and surrounding blank lines from the commit message.
+fn clean_commit_message(message: &str) -> String {
+    message
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| line.trim_end())
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
 pub(crate) struct FileDiff {
     path: CanonicalizedPath,
     hunks: Vec<Hunk>,
@@ -146,6 +482,7 @@ impl FileDiff {
 pub trait GitOperation {
     fn file_diff(&self, diff_mode: &DiffMode, repo: &CanonicalizedPath)
         -> anyhow::Result<FileDiff>;
+    /// The content is decoded with [`FileFormat`], like the content of buffers.
     fn content_at_last_commit(
         &self,
         diff_mode: &DiffMode,
@@ -162,7 +499,7 @@ impl GitOperation for CanonicalizedPath {
         if let Ok(latest_committed_content) =
             self.content_at_last_commit(diff_mode, &repo_path.try_into()?)
         {
-            let current_content = self.read()?;
+            let (current_content, _) = FileFormat::read(self.as_ref())?;
             let hunks = Hunk::get(&latest_committed_content, &current_content);
 
             Ok(FileDiff {
@@ -187,14 +524,11 @@ impl GitOperation for CanonicalizedPath {
             &self.display_relative_to(repo.path())?,
         ))?;
         let blob = entry.to_object(&repo.repo)?.peel_to_blob()?;
-        let content = blob.content().to_vec();
-        Ok(String::from_utf8(content)?)
+        Ok(FileFormat::decode(blob.content())?.0)
     }
 }
 use git2::DiffOptions;
 
-use std::str;
-
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct DiffEntry {
     new_path: CanonicalizedPath,
@@ -223,10 +557,20 @@ impl DiffEntry {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FileStatus {
+    Modified,
+    /// Staged, but not committed yet
+    Added,
+    Untracked,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum DiffMode {
     UnstagedAgainstMainBranch,
     UnstagedAgainstCurrentBranch,
+    /// The revision can be anything that `git rev-parse` understands, for example a commit id or a branch name
+    UnstagedAgainstRevision(String),
 }
 
 impl DiffMode {
@@ -234,6 +578,9 @@ impl DiffMode {
         match self {
             DiffMode::UnstagedAgainstMainBranch => "against main branch".to_string(),
             DiffMode::UnstagedAgainstCurrentBranch => "against current branch".to_string(),
+            DiffMode::UnstagedAgainstRevision(revision) => {
+                format!("against {}", log::short_revision(revision))
+            }
         }
     }
 }
@@ -343,4 +690,140 @@ mod test_git {
         )?;
         Ok(())
     }
+
+    #[test]
+    fn stage_and_unstage_hunks() -> anyhow::Result<()> {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        run_command(&dir, "git", &["init"]);
+        std::fs::write(file.clone(), "a\nb\nc\nd\ne\n")?;
+        run_command(&dir, "git", &["add", "."]);
+        run_command(&dir, "git", &["commit", "-m", "First commit"]);
+
+        std::fs::write(file.clone(), "A\nb\nc\nd\nE\n")?;
+
+        let repo = super::GitRepo::try_from(&dir.path().try_into()?)?;
+        let path: shared::canonicalized_path::CanonicalizedPath = file.try_into()?;
+        let relative_path = std::path::Path::new("file.txt");
+        let index_content = || -> anyhow::Result<String> {
+            Ok(repo
+                .index_content(&repo.repo.index()?, relative_path)?
+                .map(|(content, _)| content)
+                .unwrap_or_default())
+        };
+
+        // Only the hunk at the last line is staged
+        repo.stage_hunks(&path, Some(&[4..5]))?;
+        assert_eq!(index_content()?, "a\nb\nc\nd\nE\n");
+
+        // Stage the whole file
+        repo.stage_hunks(&path, None)?;
+        assert_eq!(index_content()?, "A\nb\nc\nd\nE\n");
+
+        // Only the hunk at the first line is unstaged
+        repo.unstage_hunks(&path, Some(&[0..1]))?;
+        assert_eq!(index_content()?, "a\nb\nc\nd\nE\n");
+
+        // Unstage the whole file
+        repo.unstage_hunks(&path, None)?;
+        assert_eq!(index_content()?, "a\nb\nc\nd\ne\n");
+        Ok(())
+    }
+
+    #[test]
+    fn unstage_hunks_by_lines_of_the_working_copy() -> anyhow::Result<()> {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        run_command(&dir, "git", &["init"]);
+        std::fs::write(file.clone(), "a\nb\nc\n")?;
+        run_command(&dir, "git", &["add", "."]);
+        run_command(&dir, "git", &["commit", "-m", "First commit"]);
+
+        std::fs::write(file.clone(), "x\ny\na\nb\nC\n")?;
+
+        let repo = super::GitRepo::try_from(&dir.path().try_into()?)?;
+        let path: shared::canonicalized_path::CanonicalizedPath = file.try_into()?;
+        let index_content = || -> anyhow::Result<String> {
+            Ok(repo
+                .index_content(&repo.repo.index()?, std::path::Path::new("file.txt"))?
+                .map(|(content, _)| content)
+                .unwrap_or_default())
+        };
+
+        repo.stage_hunks(&path, Some(&[4..5]))?;
+        assert_eq!(index_content()?, "a\nb\nC\n");
+
+        // The last line of the working copy is the third line of the index
+        repo.unstage_hunks(&path, Some(&[4..5]))?;
+        assert_eq!(index_content()?, "a\nb\nc\n");
+        Ok(())
+    }
+
+    #[test]
+    fn stage_hunks_of_crlf_file() -> anyhow::Result<()> {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        run_command(&dir, "git", &["init"]);
+        run_command(&dir, "git", &["config", "core.autocrlf", "false"]);
+        std::fs::write(file.clone(), "a\r\nb\r\nc\r\n")?;
+        run_command(&dir, "git", &["add", "."]);
+        run_command(&dir, "git", &["commit", "-m", "First commit"]);
+
+        std::fs::write(file.clone(), "A\r\nb\r\nC\r\n")?;
+
+        let repo = super::GitRepo::try_from(&dir.path().try_into()?)?;
+        let path: shared::canonicalized_path::CanonicalizedPath = file.try_into()?;
+        let indexed_bytes = || -> anyhow::Result<Vec<u8>> {
+            let index = repo.repo.index()?;
+            let entry = index
+                .get_path(std::path::Path::new("file.txt"), 0)
+                .ok_or(anyhow::anyhow!("file.txt is not indexed"))?;
+            Ok(repo.repo.find_blob(entry.id)?.content().to_vec())
+        };
+
+        // Only the last line is staged, and the line endings are kept
+        repo.stage_hunks(&path, Some(&[2..3]))?;
+        assert_eq!(indexed_bytes()?, b"a\r\nb\r\nC\r\n");
+
+        repo.unstage_hunks(&path, Some(&[2..3]))?;
+        assert_eq!(indexed_bytes()?, b"a\r\nb\r\nc\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn commit() -> anyhow::Result<()> {
+        let dir = tempdir().unwrap();
+        run_command(&dir, "git", &["init"]);
+        run_command(&dir, "git", &["config", "user.name", "Ki"]);
+        run_command(&dir, "git", &["config", "user.email", "ki@example.com"]);
+        std::fs::write(dir.path().join("file.txt"), "hello\n")?;
+        run_command(&dir, "git", &["add", "."]);
+
+        let repo = super::GitRepo::try_from(&dir.path().try_into()?)?;
+        let template = repo.commit_message_template(false)?;
+        assert!(template.contains("#\tnew file:   file.txt"));
+
+        // Empty message should abort the commit
+        std::fs::write(repo.commit_message_path(), &template)?;
+        assert!(repo.commit(false).is_err());
+
+        std::fs::write(
+            repo.commit_message_path(),
+            format!("First commit{template}"),
+        )?;
+        assert!(repo.commit(false)?.ends_with(" First commit"));
+
+        // Nothing is staged, so only amending is allowed
+        assert!(repo.commit(false).is_err());
+        let template = repo.commit_message_template(true)?;
+        assert!(template.starts_with("First commit\n"));
+        // The amended commit still adds the file
+        assert!(template.contains("#\tnew file:   file.txt"));
+        std::fs::write(
+            repo.commit_message_path(),
+            template.replace("First", "Amended"),
+        )?;
+        assert!(repo.commit(true)?.ends_with(" Amended commit"));
+        Ok(())
+    }
 }
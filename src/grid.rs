@@ -10,6 +10,7 @@ use itertools::Itertools;
 use my_proc_macros::hex;
 #[cfg(test)]
 use ropey::Rope;
+use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthChar;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -164,12 +165,22 @@ impl std::fmt::Display for Grid {
     }
 }
 
+/// The number of columns between the line numbers and the content.
+pub(crate) const LINE_NUMBER_SEPARATOR_WIDTH: usize = 1;
+
+/// The number of columns taken by the line numbers, which fit the largest line number.
+pub(crate) fn line_number_width(max_line_number: usize) -> usize {
+    max_line_number.max(1).to_string().len()
+}
+
 pub(crate) enum RenderContentLineNumber {
     NoLineNumber,
     LineNumber {
         /// 0-based
         start_line_index: usize,
         max_line_number: usize,
+        /// 0-based indices of the lines whose separator is replaced by a breakpoint marker
+        breakpoints: Vec<usize>,
     },
 }
 
@@ -339,10 +350,11 @@ impl Grid {
             RenderContentLineNumber::LineNumber {
                 start_line_index: start_line_number,
                 max_line_number,
+                ..
             } => (
                 start_line_number,
-                max_line_number.max(1).to_string().len(),
-                1,
+                line_number_width(max_line_number),
+                LINE_NUMBER_SEPARATOR_WIDTH,
             ),
         };
         let content_container_width = (width as usize)
@@ -355,14 +367,19 @@ impl Grid {
                 .lines()
                 .enumerate()
                 .flat_map(|(line_index, line)| {
-                    line.chars()
-                        .enumerate()
-                        .map(move |(column_index, character)| CellUpdate {
+                    // Each grapheme cluster is rendered as one symbol at the column of its first character
+                    line.graphemes(true)
+                        .scan(0, |column_index, grapheme| {
+                            let column = *column_index;
+                            *column_index += grapheme.chars().count();
+                            Some((column, grapheme))
+                        })
+                        .map(move |(column_index, grapheme)| CellUpdate {
                             position: Position {
                                 line: line_index,
                                 column: column_index,
                             },
-                            symbol: Some(character.to_string()),
+                            symbol: Some(grapheme.to_string()),
                             style: Style::default().foreground_color(theme.ui.text_foreground),
                             ..CellUpdate::default()
                         })
@@ -441,10 +458,7 @@ impl Grid {
         let line_numbers = {
             match line_number {
                 RenderContentLineNumber::NoLineNumber => Vec::new(),
-                RenderContentLineNumber::LineNumber {
-                    start_line_index: _,
-                    max_line_number: _,
-                } => line_numbers
+                RenderContentLineNumber::LineNumber { breakpoints, .. } => line_numbers
                     .into_iter()
                     .enumerate()
                     .flat_map(
@@ -475,13 +489,23 @@ impl Grid {
                                 &theme.ui.line_number,
                             )
                             .into_iter()
-                            .chain(grid.get_row_cell_updates(
-                                line_index,
-                                Some(max_line_number_len),
-                                Some(max_line_number_len + 1),
-                                "│",
-                                &theme.ui.border,
-                            ))
+                            .chain(if !wrapped && breakpoints.contains(&line_number) {
+                                grid.get_row_cell_updates(
+                                    line_index,
+                                    Some(max_line_number_len),
+                                    Some(max_line_number_len + 1),
+                                    "●",
+                                    &theme.ui.breakpoint,
+                                )
+                            } else {
+                                grid.get_row_cell_updates(
+                                    line_index,
+                                    Some(max_line_number_len),
+                                    Some(max_line_number_len + 1),
+                                    "│",
+                                    &theme.ui.border,
+                                )
+                            })
                             .map(|cell_update| {
                                 CalibratableCellUpdate {
                                     cell_update,
@@ -589,6 +613,10 @@ pub(crate) enum StyleKey {
     HunkOldEmphasized,
     HunkNew,
     HunkNewEmphasized,
+    ConflictMarker,
+    ConflictOurs,
+    ConflictBase,
+    ConflictTheirs,
     KeymapHint,
     KeymapArrow,
     KeymapKey,
@@ -598,7 +626,29 @@ pub(crate) enum StyleKey {
 
 /// TODO: in the future, tab size should be configurable
 pub(crate) fn get_string_width(str: &str) -> usize {
-    str.chars().map(get_char_width).sum()
+    str.graphemes(true).map(get_grapheme_width).sum()
+}
+
+/// The width of an extended grapheme cluster, which is rendered as one unit by the terminal.
+///
+/// A cluster is as wide as its widest character, because combining marks and
+/// zero-width joiners take no space, except a flag made of two regional indicators,
+/// which is rendered as one emoji.
+pub(crate) fn get_grapheme_width(grapheme: &str) -> usize {
+    let mut chars = grapheme.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => get_char_width(c),
+        (Some(_), Some(_)) if grapheme.chars().all(is_regional_indicator) => 2,
+        _ => grapheme
+            .chars()
+            .map(get_char_width)
+            .max()
+            .unwrap_or_default(),
+    }
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
 }
 
 pub(crate) fn get_char_width(c: char) -> usize {
@@ -650,6 +700,7 @@ mod test_grid {
                 RenderContentLineNumber::LineNumber {
                     max_line_number: 1,
                     start_line_index: 1,
+                    breakpoints: Vec::new(),
                 },
                 Vec::new(),
                 Vec::new(),
@@ -671,6 +722,7 @@ mod test_grid {
                 RenderContentLineNumber::LineNumber {
                     max_line_number: 10,
                     start_line_index: 10,
+                    breakpoints: Vec::new(),
                 },
                 Vec::new(),
                 Vec::new(),
@@ -699,6 +751,7 @@ mod test_grid {
                 RenderContentLineNumber::LineNumber {
                     max_line_number: 0,
                     start_line_index: 0,
+                    breakpoints: Vec::new(),
                 },
                 Vec::new(),
                 Vec::new(),
@@ -731,6 +784,7 @@ mod test_grid {
                 RenderContentLineNumber::LineNumber {
                     max_line_number: 1,
                     start_line_index: 1,
+                    breakpoints: Vec::new(),
                 },
                 [CellUpdate {
                     symbol: Some(cursor.to_string()),
@@ -770,6 +824,7 @@ mod test_grid {
                 RenderContentLineNumber::LineNumber {
                     max_line_number: 1,
                     start_line_index: 1,
+                    breakpoints: Vec::new(),
                 },
                 [CellUpdate {
                     symbol: Some(cursor.to_string()),
@@ -805,6 +860,7 @@ mod test_grid {
                 RenderContentLineNumber::LineNumber {
                     max_line_number: 100,
                     start_line_index: 1,
+                    breakpoints: Vec::new(),
                 },
                 [].to_vec(),
                 Vec::new(),
@@ -829,6 +885,7 @@ mod test_grid {
                 RenderContentLineNumber::LineNumber {
                     max_line_number: 1,
                     start_line_index: 1,
+                    breakpoints: Vec::new(),
                 },
                 [].to_vec(),
                 [LineUpdate {
@@ -869,6 +926,7 @@ mod test_grid {
                     RenderContentLineNumber::LineNumber {
                         max_line_number: 0,
                         start_line_index: 0,
+                        breakpoints: Vec::new(),
                     },
                     Vec::new(),
                     Vec::new(),
@@ -1008,6 +1066,16 @@ x
     fn test_get_string_width() {
         assert_eq!(get_string_width("\t\t"), 8)
     }
+
+    #[test]
+    fn test_get_string_width_of_grapheme_clusters() {
+        // Family emoji joined by zero-width joiners
+        assert_eq!(get_string_width("👨\u{200D}👩\u{200D}👧"), 2);
+        // Flag made of two regional indicators
+        assert_eq!(get_string_width("🇯🇵"), 2);
+        // "e" followed by a combining acute accent
+        assert_eq!(get_string_width("e\u{301}"), 1);
+    }
 }
 
 #[cfg(test)]
@@ -6,14 +6,15 @@ use crate::{
     components::{
         component::{Component, ComponentId},
         editor::Editor,
-        file_explorer::FileExplorer,
+        file_explorer::{self, DispatchFileExplorer, FileExplorer},
         keymap_legend::{KeymapLegend, KeymapLegendConfig},
         prompt::Prompt,
-        suggestive_editor::{Info, SuggestiveEditor},
+        suggestive_editor::{Info, SuggestiveEditor, SuggestiveEditorFilter},
     },
     context::QuickfixListSource,
+    position::Position,
     quickfix_list::{Location, QuickfixListItem},
-    rectangle::{Border, LayoutKind, Rectangle},
+    rectangle::{Border, LayoutKind, Rectangle, SplitOrientation, SplitTree, WindowDirection},
 };
 use anyhow::anyhow;
 use indexmap::IndexMap;
@@ -30,30 +31,44 @@ pub(crate) struct Layout {
     background_suggestive_editors: IndexMap<CanonicalizedPath, Rc<RefCell<SuggestiveEditor>>>,
     background_file_explorer: Rc<RefCell<FileExplorer>>,
     background_quickfix_list: Option<Rc<RefCell<Editor>>>,
+    background_task_output: Option<Rc<RefCell<Editor>>>,
+    background_debug_call_stack: Option<Rc<RefCell<Editor>>>,
+    background_debug_variables: Option<Rc<RefCell<Editor>>>,
 
     rectangles: Vec<Rectangle>,
     borders: Vec<Border>,
 
     terminal_dimension: Dimension,
     tree: UiTree,
+
+    /// How the main panel is divided into the windows of `tree`.
+    split_tree: SplitTree,
 }
 
+const WINDOW_RESIZE_STEP: f32 = 0.05;
+
 impl Layout {
     pub(crate) fn new(
         terminal_dimension: Dimension,
         working_directory: &CanonicalizedPath,
     ) -> anyhow::Result<Layout> {
         let (layout_kind, ratio) = layout_kind(&terminal_dimension);
-        let (rectangles, borders) = Rectangle::generate(layout_kind, 1, ratio, terminal_dimension);
+        let split_tree = SplitTree::default();
+        let (rectangles, borders) =
+            Rectangle::generate(layout_kind, 1, ratio, terminal_dimension, &split_tree);
         let tree = UiTree::new();
         Ok(Layout {
             background_quickfix_list: None,
+            background_task_output: None,
+            background_debug_call_stack: None,
+            background_debug_variables: None,
             background_suggestive_editors: IndexMap::new(),
             background_file_explorer: Rc::new(RefCell::new(FileExplorer::new(working_directory)?)),
             rectangles,
             borders,
             terminal_dimension,
             tree,
+            split_tree,
         })
     }
 
@@ -74,7 +89,12 @@ impl Layout {
 
     pub(crate) fn remove_current_component(&mut self) {
         let node = self.tree.get_current_node();
-        if let Some(path) = node.data().component().borrow().path() {
+        if self.is_one_of_many_windows(node.node_id()) {
+            if let Some(path) = node.data().component().borrow().path() {
+                self.background_suggestive_editors.shift_remove(&path);
+            }
+            self.close_window(node.node_id());
+        } else if let Some(path) = node.data().component().borrow().path() {
             self.background_suggestive_editors.shift_remove(&path);
             if let Some((_, editor)) = self
                 .background_suggestive_editors
@@ -115,11 +135,15 @@ impl Layout {
     pub(crate) fn recalculate_layout(&mut self) {
         let (layout_kind, ratio) = layout_kind(&self.terminal_dimension);
 
+        // The windows share the first rectangle, which is divided by `split_tree`
+        let components_count = self.tree.components().len();
+        let windows_count = self.tree.window_ids().len();
         let (rectangles, borders) = Rectangle::generate(
             layout_kind,
-            self.components().len(),
+            components_count - windows_count.saturating_sub(1),
             ratio,
             self.terminal_dimension,
+            &self.split_tree,
         );
         self.rectangles = rectangles;
         self.borders = borders;
@@ -241,27 +265,230 @@ impl Layout {
         Ok(dispatches)
     }
 
+    /// The unfocused windows showing `path` are closed as well.
     pub(crate) fn remove_suggestive_editor(&mut self, path: &CanonicalizedPath) {
         self.background_suggestive_editors.shift_remove(path);
+        let focused_window_id = self.tree.focused_window_id();
+        for window_id in self.tree.window_ids() {
+            let shows_path = self
+                .tree
+                .get(window_id)
+                .is_some_and(|node| node.data().component().borrow().path().as_ref() == Some(path));
+            if shows_path && Some(window_id) != focused_window_id {
+                self.close_window(window_id)
+            }
+        }
+    }
+
+    /// Split the focused window, and focus the new window,
+    /// which shows the same file as the focused window.
+    pub(crate) fn split_window(&mut self, orientation: SplitOrientation) -> anyhow::Result<()> {
+        let (Some(window_id), Some(index)) =
+            (self.tree.focused_window_id(), self.focused_window_index())
+        else {
+            return Err(anyhow!("There is no window to split"));
+        };
+        let editor = {
+            let component = self.get_component(window_id);
+            let component = component.borrow();
+            if component.path().is_none() {
+                return Err(anyhow!("Only windows showing a file can be split"));
+            }
+            let mut editor = SuggestiveEditor::from_buffer(
+                component.editor().buffer_rc(),
+                SuggestiveEditorFilter::CurrentWord,
+            );
+            editor.editor_mut().selection_set = component.editor().selection_set.clone();
+            editor
+        };
+        self.split_tree.split(index, orientation);
+        self.tree.insert_window_after(
+            window_id,
+            KindedComponent::new(
+                ComponentKind::SuggestiveEditor,
+                Rc::new(RefCell::new(editor)),
+            ),
+        );
+        self.recalculate_layout();
+        Ok(())
+    }
+
+    /// Show `editor` in a new window after the focused window, without moving the focus.
+    /// This is for editors without a path, which cannot be reopened from the background.
+    pub(crate) fn open_window(&mut self, editor: Rc<RefCell<SuggestiveEditor>>) {
+        let (Some(window_id), Some(index)) =
+            (self.tree.focused_window_id(), self.focused_window_index())
+        else {
+            return self.replace_and_focus_current_suggestive_editor(editor);
+        };
+        let focused_component_id = self.tree.focused_component_id();
+        self.split_tree.split(index, SplitOrientation::SideBySide);
+        self.tree.insert_window_after(
+            window_id,
+            KindedComponent::new(ComponentKind::SuggestiveEditor, editor),
+        );
+        self.tree.set_focus_component_id(focused_component_id);
+        self.recalculate_layout();
+    }
+
+    /// Focus the closest window at the given `direction` of the focused window.
+    pub(crate) fn move_window_focus(&mut self, direction: WindowDirection) {
+        self.recalculate_layout();
+        let windows_rectangles = self
+            .rectangles
+            .iter()
+            .take(self.split_tree.windows_count())
+            .cloned()
+            .collect_vec();
+        if let Some(window_id) = self
+            .focused_window_index()
+            .and_then(|index| windows_rectangles.get(index))
+            .and_then(|rectangle| rectangle.neighbour(&windows_rectangles, direction))
+            .and_then(|index| self.tree.window_ids().get(index).copied())
+        {
+            self.tree.set_focus_component_id(window_id)
+        }
+    }
+
+    /// Returns the topmost component that is rendered at the given `position` of the screen.
+    pub(crate) fn component_at(&self, position: &Position) -> Option<KindedComponent> {
+        self.components().into_iter().rev().find(|component| {
+            component
+                .component()
+                .borrow()
+                .rectangle()
+                .contains(position)
+        })
+    }
+
+    /// Returns the parent of the component of the given `id`, for example the owner of a dropdown.
+    pub(crate) fn parent_component(&self, id: ComponentId) -> Option<Rc<RefCell<dyn Component>>> {
+        self.tree
+            .root()
+            .traverse_pre_order()
+            .find(|node| node.data().component().borrow().id() == id)?
+            .parent()
+            .map(|parent| parent.data().component())
+    }
+
+    pub(crate) fn focus_component(&mut self, id: ComponentId) {
+        if let Some(node_id) = self.node_id_of(id) {
+            self.tree.set_focus_component_id(node_id)
+        }
+    }
+
+    fn node_id_of(&self, id: ComponentId) -> Option<NodeId> {
+        self.tree
+            .root()
+            .traverse_pre_order()
+            .find(|node| node.data().component().borrow().id() == id)
+            .map(|node| node.node_id())
+    }
+
+    pub(crate) fn resize_window(&mut self, grow: bool) {
+        let delta = if grow {
+            WINDOW_RESIZE_STEP
+        } else {
+            -WINDOW_RESIZE_STEP
+        };
+        if let Some(index) = self.focused_window_index() {
+            self.split_tree.resize(index, delta);
+        }
+        self.recalculate_layout();
+    }
+
+    /// Remove the window of `node_id` and its space in `split_tree`.
+    /// If the focus was in the removed window, its next window is focused,
+    /// or the previous window if it is the last.
+    fn close_window(&mut self, node_id: NodeId) {
+        let Some(index) = self.tree.window_ids().iter().position(|id| id == &node_id) else {
+            return;
+        };
+        let focus_is_removed = self.tree.focused_window_id() == Some(node_id);
+        self.tree.remove(node_id, false);
+        self.split_tree.remove(index);
+        if focus_is_removed {
+            let window_ids = self.tree.window_ids();
+            if let Some(window_id) = window_ids.get(index).or(window_ids.last()) {
+                self.tree.set_focus_component_id(*window_id)
+            }
+        }
+        self.recalculate_layout();
+    }
+
+    /// The index of the focused window in `split_tree`.
+    fn focused_window_index(&self) -> Option<usize> {
+        let window_id = self.tree.focused_window_id()?;
+        self.tree
+            .window_ids()
+            .iter()
+            .position(|id| id == &window_id)
+    }
+
+    /// The last window cannot be closed as a window, because `split_tree` always has a window.
+    fn is_one_of_many_windows(&self, node_id: NodeId) -> bool {
+        let window_ids = self.tree.window_ids();
+        window_ids.len() > 1 && window_ids.contains(&node_id)
+    }
+
+    /// Returns the window showing the component of the given `id`.
+    fn window_of(&self, id: ComponentId) -> Option<NodeId> {
+        self.tree.window_ids().into_iter().find(|window_id| {
+            self.tree
+                .get(*window_id)
+                .is_some_and(|node| node.data().component().borrow().id() == id)
+        })
+    }
+
+    pub(crate) fn handle_dispatch_file_explorer(
+        &mut self,
+        dispatch: DispatchFileExplorer,
+    ) -> anyhow::Result<Dispatches> {
+        self.background_file_explorer
+            .borrow_mut()
+            .handle_dispatch(dispatch)
     }
 
     pub(crate) fn refresh_file_explorer(
         &self,
         working_directory: &CanonicalizedPath,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<Dispatches> {
         self.background_file_explorer
             .borrow_mut()
             .refresh(working_directory)
     }
 
+    pub(crate) fn set_file_explorer_index(
+        &self,
+        index: file_explorer::Index,
+    ) -> anyhow::Result<()> {
+        self.background_file_explorer.borrow_mut().set_index(index)
+    }
+
     pub(crate) fn open_file_explorer(&mut self) {
-        self.tree.remove_all_root_children();
-        self.tree.replace_root_node_child(
+        let file_explorer_id = self.background_file_explorer.borrow().id();
+        if let Some(window_id) = self.window_of(file_explorer_id) {
+            return self.tree.set_focus_component_id(window_id);
+        }
+        self.tree.remove_root_panels();
+        self.tree.replace_focused_window(KindedComponent::new(
             ComponentKind::FileExplorer,
             self.background_file_explorer.clone(),
-            true,
-        );
-        debug_assert_eq!(self.tree.root().children().count(), 1);
+        ));
+    }
+
+    /// The diff viewer replaces the component of the focused window, other panels are kept.
+    pub(crate) fn show_diff_viewer(&mut self, editor: Rc<RefCell<Editor>>) {
+        self.tree
+            .replace_focused_window(KindedComponent::new(ComponentKind::DiffViewer, editor));
+        self.recalculate_layout();
+    }
+
+    pub(crate) fn focused_window_width(&self) -> u16 {
+        self.tree
+            .focused_window_id()
+            .map(|window_id| self.get_component(window_id).borrow().rectangle().width)
+            .unwrap_or(self.terminal_dimension.width)
     }
 
     pub(crate) fn update_highlighted_spans(
@@ -269,11 +496,18 @@ impl Layout {
         component_id: ComponentId,
         highlighted_spans: crate::syntax_highlight::HighlighedSpans,
     ) -> Result<(), anyhow::Error> {
+        // The editor of a split window might not be a background editor
         let component = self
             .background_suggestive_editors
-            .iter()
-            .find(|(_, component)| component.borrow().id() == component_id)
-            .map(|(_, component)| component)
+            .values()
+            .map(|component| component.clone() as Rc<RefCell<dyn Component>>)
+            .chain(
+                self.tree
+                    .window_ids()
+                    .into_iter()
+                    .map(|window_id| self.get_component(window_id)),
+            )
+            .find(|component| component.borrow().id() == component_id)
             .ok_or_else(|| anyhow!("Couldn't find component with id {:?}", component_id))?;
 
         let mut component = component.borrow_mut();
@@ -285,6 +519,15 @@ impl Layout {
         Ok(())
     }
 
+    /// Returns the editors of files with unsaved changes.
+    pub(crate) fn dirty_editors(&self) -> Vec<Rc<RefCell<SuggestiveEditor>>> {
+        self.background_suggestive_editors
+            .values()
+            .filter(|editor| editor.borrow().editor().buffer().is_dirty())
+            .cloned()
+            .collect_vec()
+    }
+
     pub(crate) fn buffers(&self) -> Vec<Rc<RefCell<Buffer>>> {
         self.background_suggestive_editors
             .iter()
@@ -401,6 +644,81 @@ impl Layout {
         Ok(dispatches)
     }
 
+    /// Shows the output of the task below the main panel, scrolled to the last line,
+    /// without moving the focus.
+    pub(crate) fn show_task_output(
+        &mut self,
+        title: String,
+        output: &str,
+    ) -> anyhow::Result<Dispatches> {
+        let editor = self.task_output_editor();
+        let mut editor = editor.borrow_mut();
+        editor.set_content(output)?;
+        editor.set_title(title);
+        editor.select_line_at(output.lines().count().saturating_sub(1))
+    }
+
+    /// Like `show_task_output`, but only `output` is appended to the shown output.
+    pub(crate) fn append_task_output(
+        &mut self,
+        title: String,
+        output: &str,
+    ) -> anyhow::Result<Dispatches> {
+        let editor = self.task_output_editor();
+        let mut editor = editor.borrow_mut();
+        editor.set_title(title);
+        editor.append_and_select_last_line(output)
+    }
+
+    fn task_output_editor(&mut self) -> Rc<RefCell<Editor>> {
+        let editor = self
+            .background_task_output
+            .get_or_insert_with(|| Rc::new(RefCell::new(Editor::from_text(None, ""))))
+            .clone();
+        self.tree
+            .replace_root_node_child(ComponentKind::TaskOutput, editor.clone(), false);
+        editor
+    }
+
+    /// Shows the call stack and variables of the debugging session below the main panel,
+    /// without moving the focus.
+    pub(crate) fn show_debug_panels(
+        &mut self,
+        call_stack: &str,
+        variables: &str,
+    ) -> anyhow::Result<()> {
+        for (kind, title, content, editor) in [
+            (
+                ComponentKind::DebugCallStack,
+                "Call stack",
+                call_stack,
+                &mut self.background_debug_call_stack,
+            ),
+            (
+                ComponentKind::DebugVariables,
+                "Variables",
+                variables,
+                &mut self.background_debug_variables,
+            ),
+        ] {
+            let editor =
+                editor.get_or_insert_with(|| Rc::new(RefCell::new(Editor::from_text(None, ""))));
+            self.tree
+                .replace_root_node_child(kind, editor.clone(), false);
+            let mut editor = editor.borrow_mut();
+            editor.set_content(content)?;
+            editor.set_title(title.to_string());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn hide_debug_panels(&mut self) {
+        let root_id = self.tree.root_id();
+        for kind in [ComponentKind::DebugCallStack, ComponentKind::DebugVariables] {
+            self.tree.remove_node_child(root_id, kind);
+        }
+    }
+
     #[cfg(test)]
     pub(crate) fn get_dropdown_infos_count(&self) -> usize {
         self.tree.count_by_kind(ComponentKind::DropdownInfo)
@@ -414,16 +732,6 @@ impl Layout {
         )
     }
 
-    fn replace_node_child(
-        &mut self,
-        id: NodeId,
-        kind: ComponentKind,
-        component: Rc<RefCell<dyn Component>>,
-        focus: bool,
-    ) {
-        self.tree.replace_node_child(id, kind, component, focus);
-    }
-
     #[cfg(test)]
     pub(crate) fn editor_info_open(&self) -> bool {
         self.tree.count_by_kind(ComponentKind::EditorInfo) > 0
@@ -512,16 +820,23 @@ impl Layout {
         editor: Rc<RefCell<SuggestiveEditor>>,
     ) {
         self.add_suggestive_editor(editor.clone());
-        self.replace_node_child(
-            self.tree.root_id(),
+        let editor_id = editor.borrow().id();
+        if let Some(window_id) = self.window_of(editor_id) {
+            return self.tree.set_focus_component_id(window_id);
+        }
+        self.tree.replace_focused_window(KindedComponent::new(
             ComponentKind::SuggestiveEditor,
             editor,
-            true,
-        );
+        ));
     }
 
     pub(crate) fn close_current_window_and_focus_parent(&mut self) {
-        self.tree.close_current_and_focus_parent()
+        let node_id = self.tree.focused_component_id();
+        if self.is_one_of_many_windows(node_id) {
+            self.close_window(node_id)
+        } else {
+            self.tree.close_current_and_focus_parent()
+        }
     }
 
     #[cfg(test)]
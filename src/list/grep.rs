@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use grep_regex::RegexMatcher;
 use grep_searcher::{sinks, SearcherBuilder};
 
@@ -56,6 +58,33 @@ pub(crate) fn replace(
         .collect())
 }
 
+/// Returns the content of every affected file before and after the replacement,
+/// without modifying any file.
+/// `buffer_contents` are the contents of opened buffers, which are used instead of their files,
+/// because their files might be outdated.
+pub(crate) fn preview_replace(
+    walk_builder_config: WalkBuilderConfig,
+    local_search_config: LocalSearchConfig,
+    buffer_contents: HashMap<CanonicalizedPath, String>,
+) -> anyhow::Result<Vec<(CanonicalizedPath, String, String)>> {
+    walk_builder_config.run(Box::new(move |path, sender| {
+        let path: CanonicalizedPath = path.try_into()?;
+        let mut buffer = Buffer::from_path(&path, local_search_config.require_tree_sitter())?;
+        if let Some(content) = buffer_contents.get(&path) {
+            buffer.update(content)
+        }
+        let before = buffer.content();
+        let (modified, _) = buffer.replace(local_search_config.clone(), Default::default())?;
+        if modified {
+            sender
+                .send((path, before, buffer.content()))
+                .map_err(|err| log::info!("Error = {:?}", err))
+                .unwrap_or_default();
+        }
+        Ok(())
+    }))
+}
+
 pub(crate) fn run(
     pattern: &str,
     walk_builder_config: WalkBuilderConfig,
@@ -68,7 +97,7 @@ pub(crate) fn run(
     Ok(walk_builder_config
         .run(Box::new(move |path, sender| {
             let path = path.try_into()?;
-            let buffer = Buffer::from_path(&path, false)?;
+            let buffer = Buffer::for_search(&path, false)?;
             // Tree-sitter should be disabled whenever possible during
             // global search, because it will slow down the operation tremendously
             debug_assert!(buffer.tree().is_none());
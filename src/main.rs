@@ -4,9 +4,14 @@ mod git;
 pub(crate) mod char_index_range;
 mod cli;
 mod clipboard;
+mod comment;
 mod components;
 mod context;
+mod dap;
+mod diff_view;
 mod edit;
+mod editorconfig;
+mod file_format;
 pub(crate) mod frontend;
 mod grid;
 #[cfg(test)]
@@ -18,23 +23,30 @@ mod lsp;
 mod position;
 
 mod app;
+mod auto_indent;
+mod batch;
 #[cfg(test)]
 mod generate_recipes;
 pub(crate) mod history;
 mod non_empty_extensions;
 mod osc52;
+mod plugin;
 mod quickfix_list;
 #[cfg(test)]
 mod recipes;
+mod recovery;
 mod rectangle;
+mod replace_preview;
 mod screen;
 mod selection;
 pub(crate) mod selection_mode;
 pub(crate) mod selection_range;
+mod server;
 pub(crate) mod soft_wrap;
 pub(crate) mod style;
 pub(crate) mod surround;
 pub(crate) mod syntax_highlight;
+mod task;
 mod terminal;
 #[cfg(test)]
 mod test_app;
@@ -51,7 +63,7 @@ use frontend::crossterm::Crossterm;
 use log::LevelFilter;
 use shared::canonicalized_path::CanonicalizedPath;
 
-use app::{App, StatusLineComponent};
+use app::{App, EntryConfig, StatusLineComponent};
 
 use crate::app::AppMessage;
 
@@ -61,7 +73,7 @@ fn main() {
 
 #[derive(Default)]
 pub(crate) struct RunConfig {
-    pub(crate) entry_path: Option<CanonicalizedPath>,
+    pub(crate) entry: EntryConfig,
     pub(crate) working_directory: Option<CanonicalizedPath>,
 }
 
@@ -80,11 +92,15 @@ pub(crate) fn run(config: RunConfig) -> anyhow::Result<()> {
             StatusLineComponent::GitBranch,
             StatusLineComponent::Mode,
             StatusLineComponent::SelectionMode,
+            StatusLineComponent::FileFormat,
             StatusLineComponent::LastDispatch,
         ]
         .to_vec(),
     )?;
     app.set_syntax_highlight_request_sender(syntax_highlighter_sender);
+    app.start_server();
+    app.start_plugins();
+    app.start_recovery();
 
     let sender = app.sender();
 
@@ -98,7 +114,7 @@ pub(crate) fn run(config: RunConfig) -> anyhow::Result<()> {
         }
     });
 
-    app.run(config.entry_path)
+    app.run(config.entry)
         .map_err(|error| anyhow::anyhow!("screen.run {:?}", error))?;
 
     crossterm_join_handle.join().unwrap();
@@ -0,0 +1,139 @@
+//! Plugins are external processes declared in `plugins.json` of the config directory,
+//! for example:
+//!
+//! ```json
+//! [{ "name": "todo", "command": "python3", "args": ["/path/to/todo.py"] }]
+//! ```
+//!
+//! Each plugin communicates with Ki through its stdin and stdout, see [`protocol`] for the messages.
+mod process;
+pub(crate) mod protocol;
+
+use std::{path::PathBuf, sync::mpsc::Sender};
+
+use serde::Deserialize;
+use shared::canonicalized_path::CanonicalizedPath;
+
+use crate::app::AppMessage;
+
+use self::{process::PluginProcess, protocol::EditorNotification};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub(crate) struct PluginConfig {
+    pub(crate) name: String,
+    pub(crate) command: String,
+    #[serde(default)]
+    pub(crate) args: Vec<String>,
+}
+
+pub(crate) fn config_path() -> PathBuf {
+    grammar::config_dir().join("plugins.json")
+}
+
+/// Returns an empty list if the config file does not exist.
+pub(crate) fn load_configs() -> anyhow::Result<Vec<PluginConfig>> {
+    let path = config_path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    serde_json5::from_str(&std::fs::read_to_string(&path)?)
+        .map_err(|error| anyhow::anyhow!("Invalid {}: {}", path.display(), error))
+}
+
+/// A command registered by a plugin, which is listed in the Plugins keymap legend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PluginCommand {
+    pub(crate) plugin: String,
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) key: String,
+}
+
+#[derive(Default)]
+pub(crate) struct PluginManager {
+    plugins: Vec<PluginProcess>,
+    /// Used for notifying plugins only when the selections actually changed
+    last_selections: Option<EditorNotification>,
+}
+
+impl PluginManager {
+    /// Plugins that fail to start are skipped, their errors are returned.
+    pub(crate) fn start(
+        configs: Vec<PluginConfig>,
+        app_message_sender: Sender<AppMessage>,
+        working_directory: &CanonicalizedPath,
+    ) -> (PluginManager, Vec<anyhow::Error>) {
+        let mut errors = Vec::new();
+        let mut manager = PluginManager::default();
+        for config in configs {
+            match PluginProcess::start(&config, app_message_sender.clone()) {
+                Ok(plugin) => manager.plugins.push(plugin),
+                Err(error) => errors.push(error),
+            }
+        }
+        manager.notify(EditorNotification::Initialize {
+            working_directory: working_directory.to_path_buf().clone(),
+        });
+        (manager, errors)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Sends `notification` to every plugin.
+    pub(crate) fn notify(&mut self, notification: EditorNotification) {
+        for plugin in &mut self.plugins {
+            Self::send(plugin, &notification)
+        }
+    }
+
+    pub(crate) fn notify_plugin(&mut self, name: &str, notification: EditorNotification) {
+        if let Some(plugin) = self.plugins.iter_mut().find(|plugin| plugin.name() == name) {
+            Self::send(plugin, &notification)
+        }
+    }
+
+    /// Only notifies if the selections are different from the last notified selections.
+    pub(crate) fn notify_selection_changed(&mut self, notification: EditorNotification) {
+        if self.last_selections.as_ref() != Some(&notification) {
+            self.notify(notification.clone());
+            self.last_selections = Some(notification);
+        }
+    }
+
+    fn send(plugin: &mut PluginProcess, notification: &EditorNotification) {
+        serde_json::to_value(notification)
+            .map_err(anyhow::Error::from)
+            .and_then(|value| plugin.send_json(value))
+            .unwrap_or_else(|error| {
+                log::error!(
+                    "[Plugin {}] Unable to send notification: {:?}",
+                    plugin.name(),
+                    error
+                )
+            })
+    }
+
+    pub(crate) fn respond(
+        &mut self,
+        name: &str,
+        id: serde_json::Value,
+        result: anyhow::Result<serde_json::Value>,
+    ) -> anyhow::Result<()> {
+        let Some(plugin) = self.plugins.iter_mut().find(|plugin| plugin.name() == name) else {
+            return Ok(());
+        };
+        plugin.send_json(match result {
+            Ok(result) => serde_json::json!({ "id": id, "result": result }),
+            Err(error) => serde_json::json!({
+                "id": id,
+                "error": {
+                    // Internal error
+                    "code": -32603,
+                    "message": error.to_string()
+                }
+            }),
+        })
+    }
+}
@@ -0,0 +1,100 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, Command, Stdio},
+    sync::mpsc::Sender,
+};
+
+use crate::app::AppMessage;
+
+use super::{protocol::PluginMessage, PluginConfig};
+
+pub(crate) struct PluginProcess {
+    name: String,
+    child: Child,
+    /// Messages are written to the plugin by a separate thread,
+    /// so that a plugin that does not read its stdin cannot block the UI thread.
+    sender: Sender<serde_json::Value>,
+}
+
+impl PluginProcess {
+    pub(crate) fn start(
+        config: &PluginConfig,
+        app_message_sender: Sender<AppMessage>,
+    ) -> anyhow::Result<PluginProcess> {
+        let mut child = Command::new(&config.command)
+            .args(&config.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|error| anyhow::anyhow!("Unable to start `{}`: {}", config.command, error))?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Unable to obtain stdin"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Unable to obtain stdout"))?;
+        let (sender, receiver) = std::sync::mpsc::channel::<serde_json::Value>();
+        let name = config.name.clone();
+        std::thread::spawn(move || {
+            let mut stdin = stdin;
+            for value in receiver {
+                if let Err(error) = writeln!(stdin, "{}", value).and_then(|_| stdin.flush()) {
+                    log::error!("[Plugin {}] Unable to write: {:?}", name, error);
+                    break;
+                }
+            }
+        });
+        let name = config.name.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match PluginMessage::parse(&line) {
+                    Ok(Some(message)) => {
+                        let message = AppMessage::PluginMessage {
+                            plugin: name.clone(),
+                            message,
+                        };
+                        if app_message_sender.send(message).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(error) => {
+                        log::error!("[Plugin {}] Invalid message {:?}: {:?}", name, line, error)
+                    }
+                }
+            }
+            log::info!("[Plugin {}] Stopped listening", name);
+        });
+        Ok(PluginProcess {
+            name: config.name.clone(),
+            child,
+            sender,
+        })
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn send_json(&mut self, mut value: serde_json::Value) -> anyhow::Result<()> {
+        if let Some(object) = value.as_object_mut() {
+            object.insert("jsonrpc".to_string(), "2.0".into());
+        }
+        self.sender
+            .send(value)
+            .map_err(|_| anyhow::anyhow!("Plugin `{}` is no longer running", self.name))
+    }
+}
+
+impl Drop for PluginProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
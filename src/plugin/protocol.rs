@@ -0,0 +1,188 @@
+//! The messages exchanged between Ki and plugins.
+//!
+//! Each message is a JSON-RPC 2.0 object written on a single line.
+//! Positions are 0-based, and ranges are objects of the form
+//! `{"start": {"line": 0, "column": 0}, "end": {"line": 0, "column": 5}}`.
+//! Paths that are not absolute are relative to the working directory of Ki.
+
+use std::{ops::Range, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::position::Position;
+
+/// Notifications sent from Ki to plugins.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "method", content = "params")]
+pub(crate) enum EditorNotification {
+    #[serde(rename = "initialize")]
+    Initialize { working_directory: PathBuf },
+    #[serde(rename = "buffer/opened")]
+    BufferOpened { path: PathBuf },
+    #[serde(rename = "buffer/saved")]
+    BufferSaved { path: PathBuf },
+    /// Sent after each event that changed the selections of the focused editor.
+    /// `path` is null if the focused editor is not a file.
+    #[serde(rename = "selection/changed")]
+    SelectionChanged {
+        path: Option<PathBuf>,
+        selections: Vec<Range<Position>>,
+    },
+    /// Sent when the user executes a command registered by the plugin.
+    #[serde(rename = "command/execute")]
+    ExecuteCommand { name: String },
+}
+
+/// Requests sent from plugins to Ki, which replies with a response of the same `id`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "method", content = "params")]
+pub(crate) enum PluginRequest {
+    /// The result is the content of the buffer as a string.
+    /// If `path` is null, the buffer of the focused editor is used.
+    #[serde(rename = "buffer/content")]
+    BufferContent { path: Option<PathBuf> },
+    /// The result is a list of ranges, the primary selection comes first.
+    /// If `path` is null, the buffer of the focused editor is used.
+    #[serde(rename = "buffer/selections")]
+    BufferSelections { path: Option<PathBuf> },
+}
+
+/// Notifications sent from plugins to Ki.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "method", content = "params")]
+pub(crate) enum PluginNotification {
+    /// The command is listed in the Plugins keymap legend (`space P`) under `key`,
+    /// registering a command of the same name again replaces it.
+    #[serde(rename = "command/register")]
+    RegisterCommand {
+        name: String,
+        description: String,
+        key: String,
+    },
+    #[serde(rename = "edits/apply")]
+    ApplyEdits {
+        path: PathBuf,
+        edits: Vec<PluginEdit>,
+    },
+    /// Replaces the decorations that this plugin set on the buffer,
+    /// the decorations set by other plugins are kept.
+    #[serde(rename = "decorations/set")]
+    SetDecorations {
+        path: PathBuf,
+        decorations: Vec<PluginDecoration>,
+    },
+    #[serde(rename = "quickfix/set")]
+    SetQuickfixList {
+        title: String,
+        items: Vec<PluginQuickfixItem>,
+    },
+    #[serde(rename = "info/show")]
+    ShowInfo { title: String, content: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub(crate) struct PluginEdit {
+    pub(crate) range: Range<Position>,
+    pub(crate) new_text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub(crate) struct PluginDecoration {
+    pub(crate) range: Range<Position>,
+    /// A highlight name of the theme, for example `keyword` or `comment`
+    pub(crate) style: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub(crate) struct PluginQuickfixItem {
+    pub(crate) path: PathBuf,
+    pub(crate) range: Range<Position>,
+    pub(crate) info: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum PluginMessage {
+    Request {
+        /// Sent back as is in the response, so it is kept as a JSON value
+        id: serde_json::Value,
+        request: PluginRequest,
+    },
+    Notification(PluginNotification),
+}
+
+impl PluginMessage {
+    /// Returns `None` if `line` is a response, because Ki does not send requests to plugins.
+    pub(crate) fn parse(line: &str) -> anyhow::Result<Option<PluginMessage>> {
+        let value: serde_json::Value = serde_json::from_str(line)?;
+        if value.get("method").is_none() {
+            return Ok(None);
+        }
+        match value.get("id") {
+            Some(id) => Ok(Some(PluginMessage::Request {
+                id: id.clone(),
+                request: serde_json::from_value(value)?,
+            })),
+            None => Ok(Some(PluginMessage::Notification(serde_json::from_value(
+                value,
+            )?))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_protocol {
+    use std::path::PathBuf;
+
+    use crate::position::Position;
+
+    use super::{EditorNotification, PluginMessage, PluginNotification, PluginRequest};
+
+    #[test]
+    fn parse_plugin_messages() -> anyhow::Result<()> {
+        assert_eq!(
+            PluginMessage::parse(
+                r#"{"jsonrpc":"2.0","id":1,"method":"buffer/content","params":{"path":null}}"#
+            )?,
+            Some(PluginMessage::Request {
+                id: serde_json::json!(1),
+                request: PluginRequest::BufferContent { path: None }
+            })
+        );
+        assert_eq!(
+            PluginMessage::parse(
+                r#"{"jsonrpc":"2.0","method":"info/show","params":{"title":"Hi","content":"there"}}"#
+            )?,
+            Some(PluginMessage::Notification(PluginNotification::ShowInfo {
+                title: "Hi".to_string(),
+                content: "there".to_string()
+            }))
+        );
+        assert_eq!(
+            PluginMessage::parse(r#"{"jsonrpc":"2.0","id":1,"result":null}"#)?,
+            None
+        );
+        assert!(PluginMessage::parse(r#"{"jsonrpc":"2.0","method":"unknown"}"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_editor_notification() -> anyhow::Result<()> {
+        assert_eq!(
+            serde_json::to_value(EditorNotification::SelectionChanged {
+                path: Some(PathBuf::from("/main.rs")),
+                selections: vec![Position::new(0, 1)..Position::new(2, 3)]
+            })?,
+            serde_json::json!({
+                "method": "selection/changed",
+                "params": {
+                    "path": "/main.rs",
+                    "selections": [{
+                        "start": {"line": 0, "column": 1},
+                        "end": {"line": 2, "column": 3}
+                    }]
+                }
+            })
+        );
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{buffer::Buffer, selection::CharIndex};
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Default)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Default, Serialize, Deserialize)]
 pub(crate) struct Position {
     /// 0-based
     pub(crate) line: usize,
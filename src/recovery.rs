@@ -0,0 +1,182 @@
+//! Crash recovery and auto-save.
+//!
+//! The unsaved content of every modified buffer is periodically written to a recovery file
+//! in the cache directory, which is removed once the buffer is saved.
+//! Recovery files that are left behind by a Ki process that is no longer running
+//! (for example, because it panicked or its terminal died) are offered for recovery on the next launch.
+//!
+//! Auto-save is disabled by default, and can be enabled in `auto_save.json` of the config directory,
+//! for example:
+//!
+//! ```json
+//! { "on_focus_change": true, "after_idle_seconds": 5 }
+//! ```
+use std::{
+    io::Write,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use shared::canonicalized_path::CanonicalizedPath;
+
+use crate::buffer::content_hash;
+
+/// How often recovery files are written, which is also the precision of `after_idle_seconds`
+pub(crate) const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub(crate) struct AutoSaveConfig {
+    /// Save modified buffers when the focused component changes
+    #[serde(default)]
+    pub(crate) on_focus_change: bool,
+    /// Save modified buffers when no key is pressed for this many seconds
+    #[serde(default)]
+    pub(crate) after_idle_seconds: Option<u64>,
+}
+
+impl AutoSaveConfig {
+    pub(crate) fn after_idle(&self) -> Option<Duration> {
+        self.after_idle_seconds.map(Duration::from_secs)
+    }
+}
+
+pub(crate) fn auto_save_config_path() -> PathBuf {
+    grammar::config_dir().join("auto_save.json")
+}
+
+/// Returns the default config if the config file does not exist.
+pub(crate) fn load_auto_save_config() -> anyhow::Result<AutoSaveConfig> {
+    let path = auto_save_config_path();
+    if !path.exists() {
+        return Ok(AutoSaveConfig::default());
+    }
+    serde_json5::from_str(&std::fs::read_to_string(&path)?)
+        .map_err(|error| anyhow::anyhow!("Invalid {}: {}", path.display(), error))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct RecoveryFile {
+    pub(crate) path: PathBuf,
+    pub(crate) content: String,
+    /// The process that wrote this file
+    pub(crate) pid: u32,
+}
+
+pub(crate) fn default_directory() -> PathBuf {
+    grammar::cache_dir().join("recovery")
+}
+
+pub(crate) fn recovery_file_path(directory: &Path, path: &CanonicalizedPath) -> PathBuf {
+    directory.join(format!(
+        "{:016x}.json",
+        content_hash(&path.display_absolute())
+    ))
+}
+
+/// The recovery file is only readable by the current user, like the file being edited might be.
+pub(crate) fn write(
+    directory: &Path,
+    path: &CanonicalizedPath,
+    content: &str,
+) -> anyhow::Result<()> {
+    let recovery_file = RecoveryFile {
+        path: path.to_path_buf().clone(),
+        content: content.to_string(),
+        pid: std::process::id(),
+    };
+    std::fs::create_dir_all(directory)?;
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(recovery_file_path(directory, path))?
+        .write_all(serde_json::to_string(&recovery_file)?.as_bytes())?;
+    Ok(())
+}
+
+pub(crate) fn remove(directory: &Path, path: &CanonicalizedPath) -> anyhow::Result<()> {
+    let recovery_file_path = recovery_file_path(directory, path);
+    if recovery_file_path.exists() {
+        std::fs::remove_file(recovery_file_path)?;
+    }
+    Ok(())
+}
+
+/// Returns the recovery files whose writer is no longer running.
+pub(crate) fn load_orphaned(directory: &Path) -> anyhow::Result<Vec<RecoveryFile>> {
+    if !directory.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(std::fs::read_dir(directory)?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let recovery_file: RecoveryFile =
+                serde_json::from_str(&std::fs::read_to_string(&path).ok()?)
+                    .map_err(|error| log::error!("Invalid {}: {}", path.display(), error))
+                    .ok()?;
+            Some(recovery_file)
+        })
+        .filter(|recovery_file| !is_running(recovery_file.pid))
+        .collect())
+}
+
+/// Signal 0 is not sent, it only checks whether the process exists.
+/// A process of another user also exists if the signal is not permitted.
+fn is_running(pid: u32) -> bool {
+    pid == std::process::id()
+        || unsafe { libc::kill(pid as libc::pid_t, 0) } == 0
+        || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(test)]
+mod test_recovery {
+    use std::os::unix::fs::PermissionsExt;
+
+    use shared::canonicalized_path::CanonicalizedPath;
+
+    use super::RecoveryFile;
+
+    #[test]
+    fn orphaned_recovery_files_are_loaded() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        std::fs::write(directory.path().join("main.rs"), "saved")?;
+        let path: CanonicalizedPath = directory.path().join("main.rs").try_into()?;
+        let recovery_directory = directory.path().join("recovery");
+
+        // Recovery files of the current process are not orphaned
+        super::write(&recovery_directory, &path, "unsaved")?;
+        let mode = std::fs::metadata(super::recovery_file_path(&recovery_directory, &path))?
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let find = || -> anyhow::Result<Option<RecoveryFile>> {
+            Ok(super::load_orphaned(&recovery_directory)?
+                .into_iter()
+                .find(|recovery_file| recovery_file.path == *path.to_path_buf()))
+        };
+        assert_eq!(find()?, None);
+
+        // Simulate a recovery file written by a process that is no longer running
+        let mut child = std::process::Command::new("true").spawn()?;
+        child.wait()?;
+        std::fs::write(
+            super::recovery_file_path(&recovery_directory, &path),
+            serde_json::to_string(&RecoveryFile {
+                path: path.to_path_buf().clone(),
+                content: "unsaved".to_string(),
+                pid: child.id(),
+            })?,
+        )?;
+        assert_eq!(
+            find()?.map(|recovery_file| recovery_file.content),
+            Some("unsaved".to_string())
+        );
+
+        super::remove(&recovery_directory, &path)?;
+        assert_eq!(find()?, None);
+        Ok(())
+    }
+}
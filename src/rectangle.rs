@@ -389,6 +389,12 @@ impl Rectangle {
         }
     }
 
+    pub(crate) fn contains(&self, position: &Position) -> bool {
+        (self.origin.line..self.origin.line + self.height as usize).contains(&position.line)
+            && (self.origin.column..self.origin.column + self.width as usize)
+                .contains(&position.column)
+    }
+
     /// Split the rectangle horizontally at the given line.
     pub(crate) fn split_horizontally_at(&self, line: usize) -> (Rectangle, Rectangle) {
         let up = Rectangle {
@@ -441,6 +447,55 @@ impl Rectangle {
             .collect()
     }
 
+    fn clamp_top(&self, height: usize) -> Rectangle {
+        Rectangle {
+            origin: self.origin.move_down(height),
+            height: self.height.saturating_sub(height as u16),
+            ..*self
+        }
+    }
+
+    /// Returns the index of the closest rectangle in `rectangles` that is located at the given
+    /// `direction` of this rectangle, and overlaps with this rectangle in the other axis.
+    pub(crate) fn neighbour(
+        &self,
+        rectangles: &[Rectangle],
+        direction: WindowDirection,
+    ) -> Option<usize> {
+        let (top, left) = (self.origin.line, self.origin.column);
+        let (bottom, right) = (top + self.height as usize, left + self.width as usize);
+        rectangles
+            .iter()
+            .enumerate()
+            .filter_map(|(index, other)| {
+                let (other_top, other_left) = (other.origin.line, other.origin.column);
+                let (other_bottom, other_right) = (
+                    other_top + other.height as usize,
+                    other_left + other.width as usize,
+                );
+                let overlaps_vertically = other_top < bottom && top < other_bottom;
+                let overlaps_horizontally = other_left < right && left < other_right;
+                let (distance, offset) = match direction {
+                    WindowDirection::Left if overlaps_vertically => {
+                        (left.checked_sub(other_right)?, other_top.abs_diff(top))
+                    }
+                    WindowDirection::Right if overlaps_vertically => {
+                        (other_left.checked_sub(right)?, other_top.abs_diff(top))
+                    }
+                    WindowDirection::Up if overlaps_horizontally => {
+                        (top.checked_sub(other_bottom)?, other_left.abs_diff(left))
+                    }
+                    WindowDirection::Down if overlaps_horizontally => {
+                        (other_top.checked_sub(bottom)?, other_left.abs_diff(left))
+                    }
+                    _ => return None,
+                };
+                Some((distance, offset, index))
+            })
+            .min()
+            .map(|(_, _, index)| index)
+    }
+
     fn clamp_left(&self, width: usize) -> Rectangle {
         Rectangle {
             origin: Position {
@@ -452,16 +507,29 @@ impl Rectangle {
         }
     }
 
+    /// The first rectangle, which is the main panel, is further divided by `split_tree`,
+    /// so the number of rectangles returned is `count - 1 + split_tree.windows_count()`.
+    /// The rectangles of the windows come first.
     pub(crate) fn generate(
         kind: LayoutKind,
         count: usize,
         ratio: f32,
         dimension: Dimension,
+        split_tree: &SplitTree,
     ) -> (Vec<Rectangle>, Vec<Border>) {
-        match kind {
+        let (rectangles, borders) = match kind {
             LayoutKind::Tall => Rectangle::generate_tall(count, ratio, dimension),
             LayoutKind::Wide => Rectangle::generate_wide(count, ratio, dimension),
-        }
+        };
+        let mut rectangles = rectangles.into_iter();
+        let Some(main_panel) = rectangles.next() else {
+            return (Vec::new(), borders);
+        };
+        let (windows, window_borders) = split_tree.generate(&main_panel);
+        (
+            windows.into_iter().chain(rectangles).collect(),
+            borders.into_iter().chain(window_borders).collect(),
+        )
     }
 }
 
@@ -471,6 +539,168 @@ pub(crate) enum LayoutKind {
     Wide,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WindowDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SplitOrientation {
+    /// The windows are placed next to each other, separated by a vertical border
+    SideBySide,
+    /// The windows are placed on top of each other, separated by a horizontal border
+    Stacked,
+}
+
+const MIN_SPLIT_RATIO: f32 = 0.1;
+const MAX_SPLIT_RATIO: f32 = 0.9;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The arrangement of the windows in the main panel, as a binary tree.
+/// The windows are identified by the order of the leaves, from left to right.
+pub(crate) enum SplitTree {
+    #[default]
+    Window,
+    Split {
+        orientation: SplitOrientation,
+        /// The portion of the space given to `first`, between 0 and 1
+        ratio: f32,
+        first: Box<SplitTree>,
+        second: Box<SplitTree>,
+    },
+}
+
+impl SplitTree {
+    pub(crate) fn windows_count(&self) -> usize {
+        match self {
+            SplitTree::Window => 1,
+            SplitTree::Split { first, second, .. } => {
+                first.windows_count() + second.windows_count()
+            }
+        }
+    }
+
+    /// Split the window at `index` into two windows of the same size.
+    /// The new window is located at `index + 1`.
+    pub(crate) fn split(&mut self, index: usize, orientation: SplitOrientation) {
+        if let Some(window) = self.window_mut(&mut index.clone()) {
+            *window = SplitTree::Split {
+                orientation,
+                ratio: 0.5,
+                first: Box::new(SplitTree::Window),
+                second: Box::new(SplitTree::Window),
+            }
+        }
+    }
+
+    /// Remove the window at `index`, the space of the removed window is given to its sibling.
+    /// The last window cannot be removed.
+    pub(crate) fn remove(&mut self, index: usize) {
+        self.update_parent(&mut index.clone(), &mut |parent, is_first| {
+            if let SplitTree::Split { first, second, .. } = parent {
+                let sibling = if is_first { &**second } else { &**first }.clone();
+                *parent = sibling
+            }
+        });
+    }
+
+    /// Grow the window at `index` by `delta` of the space shared with its sibling.
+    /// A negative `delta` shrinks the window.
+    pub(crate) fn resize(&mut self, index: usize, delta: f32) {
+        self.update_parent(&mut index.clone(), &mut |parent, is_first| {
+            if let SplitTree::Split { ratio, .. } = parent {
+                let delta = if is_first { delta } else { -delta };
+                *ratio = (*ratio + delta).clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO)
+            }
+        });
+    }
+
+    fn window_mut(&mut self, index: &mut usize) -> Option<&mut SplitTree> {
+        if *self == SplitTree::Window {
+            if *index == 0 {
+                return Some(self);
+            }
+            *index -= 1;
+            return None;
+        }
+        let SplitTree::Split { first, second, .. } = self else {
+            return None;
+        };
+        match first.window_mut(index) {
+            Some(window) => Some(window),
+            None => second.window_mut(index),
+        }
+    }
+
+    /// Call `f` with the parent of the window at `index`,
+    /// and whether the window is the first child of the parent.
+    /// Returns true if the window is found.
+    fn update_parent(
+        &mut self,
+        index: &mut usize,
+        f: &mut impl FnMut(&mut SplitTree, bool),
+    ) -> bool {
+        let SplitTree::Split { first, second, .. } = self else {
+            *index = index.saturating_sub(1);
+            return false;
+        };
+        if **first == SplitTree::Window && *index == 0 {
+            f(self, true);
+            return true;
+        }
+        if first.update_parent(index, f) {
+            return true;
+        }
+        if **second == SplitTree::Window && *index == 0 {
+            f(self, false);
+            return true;
+        }
+        second.update_parent(index, f)
+    }
+
+    fn generate(&self, rectangle: &Rectangle) -> (Vec<Rectangle>, Vec<Border>) {
+        let SplitTree::Split {
+            orientation,
+            ratio,
+            first,
+            second,
+        } = self
+        else {
+            return (vec![rectangle.clone()], Vec::new());
+        };
+        let (first_rectangle, second_rectangle, border) = match orientation {
+            SplitOrientation::SideBySide => {
+                let column = (rectangle.width as f32 * ratio) as usize;
+                let (left, right) = rectangle.split_vertically_at(column);
+                let border = Border::new_vertical(right.origin, right.height);
+                (left, right.clamp_left(1), border)
+            }
+            SplitOrientation::Stacked => {
+                let line = (rectangle.height as f32 * ratio) as usize;
+                let (up, bottom) = rectangle.split_horizontally_at(line);
+                let border = Border::new_horizontal(bottom.origin, bottom.width);
+                (up, bottom.clamp_top(1), border)
+            }
+        };
+        let (first_rectangles, first_borders) = first.generate(&first_rectangle);
+        let (second_rectangles, second_borders) = second.generate(&second_rectangle);
+        (
+            first_rectangles
+                .into_iter()
+                .chain(second_rectangles)
+                .collect(),
+            Some(border)
+                .into_iter()
+                .chain(first_borders)
+                .chain(second_borders)
+                .collect(),
+        )
+    }
+}
+
 #[cfg(test)]
 mod test_rectangle {
 
@@ -481,6 +711,9 @@ mod test_rectangle {
     use crate::rectangle::Border;
     use crate::rectangle::Split;
     use crate::rectangle::SplitKind;
+    use crate::rectangle::SplitOrientation;
+    use crate::rectangle::SplitTree;
+    use crate::rectangle::WindowDirection;
 
     use super::BorderDirection::*;
     use super::Rectangle;
@@ -556,6 +789,86 @@ mod test_rectangle {
         );
     }
 
+    #[test]
+    fn split_tree() {
+        let mut split_tree = SplitTree::default();
+        split_tree.split(0, SplitOrientation::SideBySide);
+        split_tree.split(1, SplitOrientation::Stacked);
+        assert_eq!(split_tree.windows_count(), 3);
+        let main_panel = Rectangle {
+            origin: Position::new(0, 0),
+            width: 11,
+            height: 11,
+        };
+        let (rectangles, borders) = split_tree.generate(&main_panel);
+        assert_eq!(
+            rectangles,
+            [
+                Rectangle {
+                    origin: Position::new(0, 0),
+                    width: 5,
+                    height: 11
+                },
+                Rectangle {
+                    origin: Position::new(0, 6),
+                    width: 5,
+                    height: 5
+                },
+                Rectangle {
+                    origin: Position::new(6, 6),
+                    width: 5,
+                    height: 5
+                }
+            ]
+            .to_vec()
+        );
+        assert_eq!(borders.len(), 2);
+
+        // Directional neighbours
+        assert_eq!(
+            rectangles[0].neighbour(&rectangles, WindowDirection::Right),
+            Some(1)
+        );
+        assert_eq!(
+            rectangles[2].neighbour(&rectangles, WindowDirection::Up),
+            Some(1)
+        );
+        assert_eq!(
+            rectangles[2].neighbour(&rectangles, WindowDirection::Left),
+            Some(0)
+        );
+        assert_eq!(
+            rectangles[0].neighbour(&rectangles, WindowDirection::Left),
+            None
+        );
+
+        // Resizing the second window shrinks its sibling
+        split_tree.resize(1, 0.2);
+        let (rectangles, _) = split_tree.generate(&main_panel);
+        assert_eq!(rectangles[1].height, 7);
+        assert_eq!(rectangles[2].height, 3);
+
+        // The space of a removed window is given to its sibling
+        split_tree.remove(1);
+        let (rectangles, _) = split_tree.generate(&main_panel);
+        assert_eq!(
+            rectangles,
+            [
+                Rectangle {
+                    origin: Position::new(0, 0),
+                    width: 5,
+                    height: 11
+                },
+                Rectangle {
+                    origin: Position::new(0, 6),
+                    width: 5,
+                    height: 11
+                }
+            ]
+            .to_vec()
+        );
+    }
+
     mod generate_tall {
 
         use std::collections::HashSet;
@@ -597,7 +910,8 @@ mod test_rectangle {
             layout_kind: LayoutKind,
             dimension: Dimension,
         ) -> bool {
-            let (rectangles, borders) = Rectangle::generate(layout_kind, count.0, 0.5, dimension);
+            let (rectangles, borders) =
+                Rectangle::generate(layout_kind, count.0, 0.5, dimension, &SplitTree::default());
             let rectangles_area: usize = rectangles.iter().map(|r| r.area()).sum();
             let borders_area: usize = borders.iter().map(|b| b.area(&dimension)).sum();
             let dimension_area = dimension.area();
@@ -611,7 +925,8 @@ mod test_rectangle {
             layout_kind: LayoutKind,
             dimension: Dimension,
         ) -> bool {
-            let (rectangles, borders) = Rectangle::generate(layout_kind, count.0, 0.5, dimension);
+            let (rectangles, borders) =
+                Rectangle::generate(layout_kind, count.0, 0.5, dimension, &SplitTree::default());
 
             let rectangle_and_border_positions = rectangles
                 .iter()
@@ -639,7 +954,8 @@ mod test_rectangle {
             layout_kind: LayoutKind,
             dimension: Dimension,
         ) -> bool {
-            let (rectangles, borders) = Rectangle::generate(layout_kind, count.0, 0.5, dimension);
+            let (rectangles, borders) =
+                Rectangle::generate(layout_kind, count.0, 0.5, dimension, &SplitTree::default());
 
             let rectangles_intersections = rectangles
                 .iter()
@@ -0,0 +1,340 @@
+use std::{collections::HashMap, ops::Range};
+
+use itertools::Itertools;
+use shared::canonicalized_path::CanonicalizedPath;
+use similar::{DiffTag, TextDiff};
+
+/// A review step for project-wide replacements.
+///
+/// The proposed changes are rendered as a plain text document, grouped by file,
+/// which the user can edit like any other buffer.
+/// Every line is marked by its prefix, so that paths are never mistaken for changes:
+/// - `=== ` starts the changes of a file
+/// - changing `[x]` to `[ ]` rejects a change
+/// - editing the lines prefixed with `+` changes the replacement of a change
+///
+/// The accepted changes are applied when the document is saved.
+pub(crate) struct ReplacePreview {
+    files: Vec<FilePreview>,
+}
+
+struct FilePreview {
+    path: CanonicalizedPath,
+    /// The path shown in the preview document, relative to the working directory
+    display: String,
+    /// The content of the file, or of its buffer if it is opened, when the preview was generated
+    content: String,
+    changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Change {
+    /// 0-based line range in the original content
+    old_line_range: Range<usize>,
+    old_lines: Vec<String>,
+    new_lines: Vec<String>,
+}
+
+const FILE_HEADER_PREFIX: &str = "=== ";
+
+/// A file modified by a replacement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ReplacedFile {
+    pub(crate) path: CanonicalizedPath,
+    pub(crate) before: String,
+    pub(crate) after: String,
+}
+
+/// The user's decision on a change, as parsed from the preview document.
+#[derive(Debug, PartialEq, Eq)]
+struct Decision {
+    accepted: bool,
+    new_lines: Vec<String>,
+}
+
+impl ReplacePreview {
+    /// `files` is a list of (path, content before replacement, content after replacement).
+    pub(crate) fn new(
+        working_directory: &CanonicalizedPath,
+        files: Vec<(CanonicalizedPath, String, String)>,
+    ) -> ReplacePreview {
+        ReplacePreview {
+            files: files
+                .into_iter()
+                .sorted_by(|(a, _, _), (b, _, _)| a.cmp(b))
+                .map(|(path, before, after)| FilePreview {
+                    display: path
+                        .display_relative_to(working_directory)
+                        .unwrap_or_else(|_| path.display_absolute()),
+                    changes: changes(&before, &after),
+                    content: before,
+                    path,
+                })
+                .filter(|file| !file.changes.is_empty())
+                .collect(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub(crate) fn render(&self) -> String {
+        let changes_count: usize = self.files.iter().map(|file| file.changes.len()).sum();
+        let header = [
+            format!(
+                "# Replace preview: {} change(s) in {} file(s).",
+                changes_count,
+                self.files.len()
+            ),
+            "# Change `[x]` to `[ ]` to reject a change.".to_string(),
+            "# Edit the lines starting with `+` to change a replacement.".to_string(),
+            "# Save this file to apply the accepted changes.".to_string(),
+        ]
+        .join("\n");
+        let files = self.files.iter().map(|file| {
+            let changes = file.changes.iter().map(|change| {
+                Some(format!("[x] line {}", change.old_line_range.start + 1))
+                    .into_iter()
+                    .chain(change.old_lines.iter().map(|line| format!("- {}", line)))
+                    .chain(change.new_lines.iter().map(|line| format!("+ {}", line)))
+                    .join("\n")
+            });
+            Some(format!("{}{}", FILE_HEADER_PREFIX, file.display))
+                .into_iter()
+                .chain(changes)
+                .join("\n")
+        });
+        Some(header).into_iter().chain(files).join("\n\n") + "\n"
+    }
+
+    /// Apply the changes accepted in the `edited` preview document
+    /// to the content that the preview was generated from.
+    /// Returns the files that are modified, without modifying them.
+    pub(crate) fn apply(&self, edited: &str) -> anyhow::Result<Vec<ReplacedFile>> {
+        let decisions = parse(edited)?;
+        let mut replaced_files = Vec::new();
+        for file in &self.files {
+            let accepted = file
+                .changes
+                .iter()
+                .filter_map(|change| {
+                    // Changes that are removed from the document are rejected
+                    let decision =
+                        decisions.get(&(file.display.clone(), change.old_line_range.start + 1))?;
+                    decision
+                        .accepted
+                        .then_some((change, decision.new_lines.as_slice()))
+                })
+                .collect_vec();
+            if accepted.is_empty() {
+                continue;
+            }
+            let after = apply_changes(&file.content, accepted);
+            if after != file.content {
+                replaced_files.push(ReplacedFile {
+                    path: file.path.clone(),
+                    before: file.content.clone(),
+                    after,
+                })
+            }
+        }
+        Ok(replaced_files)
+    }
+}
+
+/// Group the changed lines between `before` and `after`.
+fn changes(before: &str, after: &str) -> Vec<Change> {
+    let diff = TextDiff::from_lines(before, after);
+    let old_lines = diff.old_slices();
+    let new_lines = diff.new_slices();
+    let to_lines = |lines: &[&str]| {
+        lines
+            .iter()
+            .map(|line| line.trim_end_matches(['\n', '\r']).to_string())
+            .collect_vec()
+    };
+    diff.ops()
+        .iter()
+        .copied()
+        .filter(|op| op.tag() != DiffTag::Equal)
+        // Adjacent deletions and insertions belong to the same change
+        .coalesce(|previous, current| {
+            if previous.old_range().end == current.old_range().start
+                && previous.new_range().end == current.new_range().start
+            {
+                Ok(similar::DiffOp::Replace {
+                    old_index: previous.old_range().start,
+                    old_len: previous.old_range().len() + current.old_range().len(),
+                    new_index: previous.new_range().start,
+                    new_len: previous.new_range().len() + current.new_range().len(),
+                })
+            } else {
+                Err((previous, current))
+            }
+        })
+        .map(|op| Change {
+            old_lines: to_lines(&old_lines[op.old_range()]),
+            new_lines: to_lines(&new_lines[op.new_range()]),
+            old_line_range: op.old_range(),
+        })
+        .collect()
+}
+
+/// Parse the preview document into decisions, keyed by (file, 1-based line number).
+fn parse(edited: &str) -> anyhow::Result<HashMap<(String, usize), Decision>> {
+    let mut decisions = HashMap::new();
+    let mut file: Option<String> = None;
+    let mut current: Option<((String, usize), Decision)> = None;
+    for line in edited.lines() {
+        if let Some(path) = line.strip_prefix(FILE_HEADER_PREFIX) {
+            decisions.extend(current.take());
+            file = Some(path.to_string());
+        } else if line.starts_with('#') || line.trim().is_empty() || line.starts_with('-') {
+            continue;
+        } else if let Some(new_line) = line.strip_prefix('+') {
+            if let Some((_, decision)) = current.as_mut() {
+                let new_line = new_line.strip_prefix(' ').unwrap_or(new_line);
+                decision.new_lines.push(new_line.to_string())
+            }
+        } else if let Some(rest) = line.strip_prefix('[') {
+            let (mark, rest) = rest
+                .split_once(']')
+                .ok_or_else(|| anyhow::anyhow!("Invalid change header: {:?}", line))?;
+            let line_number = rest
+                .trim()
+                .strip_prefix("line ")
+                .and_then(|number| number.trim().parse::<usize>().ok())
+                .ok_or_else(|| anyhow::anyhow!("Invalid change header: {:?}", line))?;
+            let file = file
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("Change at line {} has no file", line_number))?;
+            decisions.extend(current.take());
+            current = Some((
+                (file, line_number),
+                Decision {
+                    accepted: !mark.trim().is_empty(),
+                    new_lines: Vec::new(),
+                },
+            ));
+        } else {
+            return Err(anyhow::anyhow!("Invalid line: {:?}", line));
+        }
+    }
+    decisions.extend(current.take());
+    Ok(decisions)
+}
+
+/// `changes` must be sorted by their line ranges.
+fn apply_changes<'a>(content: &str, changes: Vec<(&'a Change, &'a [String])>) -> String {
+    let lines = content.split_inclusive('\n').collect_vec();
+    let (result, cursor) = changes.into_iter().fold(
+        (String::new(), 0),
+        |(mut result, cursor), (change, new_lines)| {
+            let range = change.old_line_range.clone();
+            result.extend(lines[cursor..range.start].iter().copied());
+            let replaced = &lines[range.clone()];
+            let line_ending = if replaced.iter().any(|line| line.ends_with("\r\n")) {
+                "\r\n"
+            } else {
+                "\n"
+            };
+            let new = new_lines
+                .iter()
+                .map(|line| format!("{}{}", line, line_ending))
+                .join("");
+            // Preserve the lack of trailing newline at the end of the file
+            let ends_with_newline = replaced.last().map_or(true, |line| line.ends_with('\n'));
+            result.push_str(if ends_with_newline {
+                &new
+            } else {
+                new.strip_suffix(line_ending).unwrap_or(&new)
+            });
+            (result, range.end)
+        },
+    );
+    result + &lines[cursor..].concat()
+}
+
+#[cfg(test)]
+mod test_replace_preview {
+    use itertools::Itertools;
+
+    use super::{apply_changes, changes, parse, Decision};
+
+    #[test]
+    fn apply_accepted_changes_only() {
+        let before = "a foo\nb\nc foo\nd\ne foo";
+        let after = "a bar\nb\nc bar\nd\ne bar";
+        let changes = changes(before, after);
+        assert_eq!(changes.len(), 3);
+        let edited = vec!["baz".to_string()];
+        let result = apply_changes(
+            before,
+            vec![
+                (&changes[0], edited.as_slice()),
+                (&changes[2], changes[2].new_lines.as_slice()),
+            ],
+        );
+        assert_eq!(result, "baz\nb\nc foo\nd\ne bar");
+    }
+
+    #[test]
+    fn parse_edited_preview() {
+        let decisions = parse(
+            "
+# comment
+=== src/main.rs
+[x] line 1
+- a foo
++ a baz
+
+[ ] line 3
+- c foo
++ c bar
+"
+            .trim(),
+        )
+        .unwrap();
+        assert_eq!(
+            decisions.get(&("src/main.rs".to_string(), 1)),
+            Some(&Decision {
+                accepted: true,
+                new_lines: vec!["a baz".to_string()]
+            })
+        );
+        assert_eq!(
+            decisions.get(&("src/main.rs".to_string(), 3)),
+            Some(&Decision {
+                accepted: false,
+                new_lines: vec!["c bar".to_string()]
+            })
+        );
+    }
+
+    #[test]
+    fn parse_paths_that_look_like_changes() {
+        let decisions = parse(
+            "
+=== #weird/[x] line 2
+[x] line 2
+- b
++ c
+=== -dir/+file
+[ ] line 1
+- a
++ b
+"
+            .trim(),
+        )
+        .unwrap();
+        assert_eq!(
+            decisions
+                .keys()
+                .map(|(file, line)| (file.as_str(), *line))
+                .sorted()
+                .collect_vec(),
+            [("#weird/[x] line 2", 2), ("-dir/+file", 1)]
+        );
+    }
+}
@@ -6,14 +6,14 @@ use crate::{
     buffer::Buffer,
     char_index_range::CharIndexRange,
     components::{
-        editor::{Direction, Movement},
+        editor::{Direction, Movement, SurroundKind},
         suggestive_editor::Info,
     },
     context::{LocalSearchConfigMode, Search},
     non_empty_extensions::{NonEmptyTryCollectOption, NonEmptyTryCollectResult},
     position::Position,
     quickfix_list::DiagnosticSeverityRange,
-    selection_mode::{self, ApplyMovementResult, SelectionModeParams},
+    selection_mode::{self, text_object::TextObjectKind, ApplyMovementResult, SelectionModeParams},
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -75,17 +75,18 @@ impl SelectionSet {
         })
     }
 
-    pub(crate) fn move_left(&mut self, cursor_direction: &Direction) {
+    pub(crate) fn move_left(&mut self, buffer: &Buffer, cursor_direction: &Direction) {
         self.apply_mut(|selection| {
             let cursor_char_index = selection.to_char_index(cursor_direction);
-            selection.range = (cursor_char_index - 1..cursor_char_index - 1).into()
+            let previous = buffer.previous_grapheme_boundary(cursor_char_index);
+            selection.range = (previous..previous).into()
         });
     }
 
-    pub(crate) fn move_right(&mut self, cursor_direction: &Direction, len_chars: usize) {
+    pub(crate) fn move_right(&mut self, buffer: &Buffer, cursor_direction: &Direction) {
         self.apply_mut(|selection| {
             let cursor_char_index = selection.to_char_index(cursor_direction);
-            let next = (cursor_char_index + 1).min(CharIndex(len_chars));
+            let next = buffer.next_grapheme_boundary(cursor_char_index);
             selection.range = (next..next).into()
         });
     }
@@ -397,19 +398,28 @@ pub(crate) enum SelectionMode {
     Line,
     Character,
     Custom,
-    Find { search: Search },
+    Find {
+        search: Search,
+    },
     // Syntax-tree
     SyntaxNode,
     SyntaxNodeFine,
+    TextObject {
+        kind: TextObjectKind,
+        surround_kind: SurroundKind,
+    },
 
     // LSP
     Diagnostic(DiagnosticSeverityRange),
 
     // Git
     GitHunk(crate::git::DiffMode),
+    MergeConflict,
 
     // Local quickfix
-    LocalQuickfix { title: String },
+    LocalQuickfix {
+        title: String,
+    },
 
     // Mark
     Mark,
@@ -432,6 +442,10 @@ impl SelectionMode {
             SelectionMode::Custom => "CUSTOM".to_string(),
             SelectionMode::SyntaxNode => "SYNTAX NODE".to_string(),
             SelectionMode::SyntaxNodeFine => "FINE SYNTAX NODE".to_string(),
+            SelectionMode::TextObject {
+                kind,
+                surround_kind,
+            } => format!("{} ({})", kind.display(), surround_kind).to_uppercase(),
             SelectionMode::Find { search } => {
                 format!("{} {:?}", search.mode.display(), search.search)
             }
@@ -442,6 +456,7 @@ impl SelectionMode {
             SelectionMode::GitHunk(diff_mode) => {
                 format!("GIT HUNK ({})", diff_mode.display()).to_string()
             }
+            SelectionMode::MergeConflict => "MERGE CONFLICT".to_string(),
             SelectionMode::Mark => "MARK".to_string(),
             SelectionMode::LocalQuickfix { title } => title.to_string(),
         }
@@ -485,12 +500,20 @@ impl SelectionMode {
             },
             SelectionMode::SyntaxNode => Box::new(selection_mode::SyntaxNode { coarse: true }),
             SelectionMode::SyntaxNodeFine => Box::new(selection_mode::SyntaxNode { coarse: false }),
+            SelectionMode::TextObject {
+                kind,
+                surround_kind,
+            } => Box::new(selection_mode::TextObject {
+                kind: *kind,
+                surround_kind: surround_kind.clone(),
+            }),
             SelectionMode::Diagnostic(severity) => {
                 Box::new(selection_mode::Diagnostic::new(*severity, params))
             }
             SelectionMode::GitHunk(diff_mode) => {
                 Box::new(selection_mode::GitHunk::new(diff_mode, buffer)?)
             }
+            SelectionMode::MergeConflict => Box::new(selection_mode::MergeConflict::new(buffer)),
             SelectionMode::Mark => Box::new(selection_mode::Mark),
             SelectionMode::EmptyLine => Box::new(selection_mode::Regex::new(buffer, r"(?m)^\s*$")?),
             SelectionMode::LocalQuickfix { .. } => {
@@ -28,14 +28,20 @@ impl SelectionMode for Character {
         let line = buffer.char_to_line(char_index)?;
         let line_start_char_index = buffer.line_to_char(line)?;
         let current_line = buffer.get_line_by_char_index(char_index)?;
-        let line_len = line_len_without_new_line(&current_line);
-
-        Ok(Box::new((0..line_len).flat_map(
-            move |column| -> anyhow::Result<ByteRange> {
-                let byte = buffer.char_to_byte(line_start_char_index + column)?;
-                Ok(ByteRange::new(byte..byte + 1))
-            },
-        )))
+        let line_end = line_start_char_index + line_len_without_new_line(&current_line);
+
+        // Each extended grapheme cluster is one character
+        Ok(Box::new(
+            buffer
+                .line_graphemes(char_index)
+                .into_iter()
+                .filter(move |range| range.start < line_end)
+                .flat_map(move |range| -> anyhow::Result<ByteRange> {
+                    Ok(ByteRange::new(
+                        buffer.char_to_byte(range.start)?..buffer.char_to_byte(range.end)?,
+                    ))
+                }),
+        ))
     }
     fn up(
         &self,
@@ -106,7 +112,7 @@ impl Character {
         let column = self.current_column.min(line_len.saturating_sub(1));
         let char_index =
             buffer.position_to_char(crate::position::Position::new(line_index, column))?;
-        Ok(Some(Selection::new((char_index..char_index + 1).into())))
+        Ok(Some(Selection::new(buffer.grapheme_range(char_index))))
     }
 }
 
@@ -126,7 +132,7 @@ fn get_char(
             params
                 .current_selection
                 .clone()
-                .set_range((start..start + 1).into()),
+                .set_range(params.buffer.grapheme_range(start)),
         ));
     }
     Ok(None)
@@ -0,0 +1,27 @@
+use crate::{buffer::Buffer, git::conflict::parse_conflicts};
+use itertools::Itertools;
+
+use super::{ByteRange, SelectionMode};
+
+pub(crate) struct MergeConflict {
+    ranges: Vec<super::ByteRange>,
+}
+
+impl MergeConflict {
+    pub(crate) fn new(buffer: &Buffer) -> MergeConflict {
+        let ranges = parse_conflicts(&buffer.content())
+            .into_iter()
+            .map(|conflict| ByteRange::new(conflict.range))
+            .collect_vec();
+        MergeConflict { ranges }
+    }
+}
+
+impl SelectionMode for MergeConflict {
+    fn iter<'a>(
+        &'a self,
+        _: super::SelectionModeParams<'a>,
+    ) -> anyhow::Result<Box<dyn Iterator<Item = super::ByteRange> + 'a>> {
+        Ok(Box::new(self.ranges.clone().into_iter()))
+    }
+}
@@ -4,6 +4,7 @@ pub(crate) mod custom;
 pub(crate) mod diagnostic;
 pub(crate) mod git_hunk;
 pub(crate) mod mark;
+pub(crate) mod merge_conflict;
 pub(crate) mod naming_convention_agnostic;
 pub(crate) mod syntax_token;
 
@@ -14,6 +15,7 @@ pub(crate) mod line_trimmed;
 pub(crate) mod local_quickfix;
 pub(crate) mod regex;
 pub(crate) mod syntax_node;
+pub(crate) mod text_object;
 pub(crate) mod token;
 pub(crate) mod word;
 pub(crate) use self::regex::Regex;
@@ -27,11 +29,13 @@ pub(crate) use line_full::LineFull;
 pub(crate) use line_trimmed::LineTrimmed;
 pub(crate) use local_quickfix::LocalQuickfix;
 pub(crate) use mark::Mark;
+pub(crate) use merge_conflict::MergeConflict;
 pub(crate) use naming_convention_agnostic::NamingConventionAgnostic;
 use position_pair::ParsedChar;
 use std::ops::Range;
 pub(crate) use syntax_node::SyntaxNode;
 pub(crate) use syntax_token::SyntaxToken;
+pub(crate) use text_object::TextObject;
 pub(crate) use token::Token;
 pub(crate) use top_node::TopNode;
 pub(crate) use word::Word;
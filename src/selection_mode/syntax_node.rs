@@ -180,6 +180,12 @@ impl SyntaxNode {
         else {
             return Ok(None);
         };
+        if !go_up {
+            // Descend into the injected tree whose content is the current node
+            if let Some(root) = params.buffer.get_injected_root(node.byte_range()) {
+                node = root;
+            }
+        }
         while let Some(some_node) = get_node(node, go_up, self.coarse) {
             // This is necessary because sometimes the parent node can have the same range as
             // the current node
@@ -0,0 +1,187 @@
+use std::{
+    collections::HashMap,
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+use itertools::Itertools;
+use once_cell::sync::OnceCell;
+use shared::language::Language;
+use tree_sitter::{Query, QueryCursor, QueryMatch, QueryPredicateArg};
+
+use crate::components::editor::SurroundKind;
+
+use super::{ByteRange, SelectionMode};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum TextObjectKind {
+    Function,
+    Class,
+    Parameter,
+    Comment,
+    Test,
+    Block,
+}
+
+impl TextObjectKind {
+    pub(crate) fn display(&self) -> &'static str {
+        match self {
+            TextObjectKind::Function => "Function",
+            TextObjectKind::Class => "Class",
+            TextObjectKind::Parameter => "Parameter",
+            TextObjectKind::Comment => "Comment",
+            TextObjectKind::Test => "Test",
+            TextObjectKind::Block => "Block",
+        }
+    }
+
+    /// The capture names of this textobject in `textobjects.scm`.
+    /// `nvim-treesitter-textobjects` uses `inner`/`outer`, while `helix` uses `inside`/`around`.
+    fn capture_names(&self, surround_kind: &SurroundKind) -> [String; 2] {
+        let name = self.display().to_lowercase();
+        match surround_kind {
+            SurroundKind::Inside => [format!("{}.inner", name), format!("{}.inside", name)],
+            SurroundKind::Around => [format!("{}.outer", name), format!("{}.around", name)],
+        }
+    }
+}
+
+type TreeSitterGrammarId = String;
+
+/// The textobjects queries compiled when the highlight configurations are loaded,
+/// because fetching and compiling them is too slow for the UI thread.
+static QUERIES: OnceCell<Mutex<HashMap<TreeSitterGrammarId, Arc<Vec<Query>>>>> = OnceCell::new();
+
+fn queries() -> &'static Mutex<HashMap<TreeSitterGrammarId, Arc<Vec<Query>>>> {
+    QUERIES.get_or_init(Default::default)
+}
+
+pub(crate) fn is_registered(grammar_id: &str) -> bool {
+    queries()
+        .lock()
+        .is_ok_and(|queries| queries.contains_key(grammar_id))
+}
+
+pub(crate) fn register_queries(language: &Language, sources: Vec<String>) {
+    let (Some(grammar_id), Some(tree_sitter_language)) = (
+        language.tree_sitter_grammar_id(),
+        language.tree_sitter_language(),
+    ) else {
+        return;
+    };
+    let compiled = sources
+        .into_iter()
+        .filter_map(|source| {
+            Query::new(&tree_sitter_language, &source)
+                .map_err(|error| log::info!("textobjects_query_error = {:#?}", error))
+                .ok()
+        })
+        .collect_vec();
+    if let Ok(mut queries) = queries().lock() {
+        queries.insert(grammar_id, Arc::new(compiled));
+    }
+}
+
+/// Returns `None` if the textobjects queries of `language` are not loaded yet.
+fn get_queries(language: &Language) -> Option<Arc<Vec<Query>>> {
+    Some(
+        queries()
+            .lock()
+            .ok()?
+            .get(&language.tree_sitter_grammar_id()?)?
+            .clone(),
+    )
+}
+
+pub(crate) struct TextObject {
+    pub(crate) kind: TextObjectKind,
+    pub(crate) surround_kind: SurroundKind,
+}
+
+impl SelectionMode for TextObject {
+    fn iter<'a>(
+        &'a self,
+        params: super::SelectionModeParams<'a>,
+    ) -> anyhow::Result<Box<dyn Iterator<Item = ByteRange> + 'a>> {
+        let buffer = params.buffer;
+        let (Some(language), Some(tree)) = (buffer.language(), buffer.tree()) else {
+            return Err(anyhow::anyhow!(
+                "TextObject::iter: cannot find Treesitter language"
+            ));
+        };
+        let grammar_id = language.tree_sitter_grammar_id().unwrap_or_default();
+        let queries = get_queries(&language).ok_or_else(|| {
+            anyhow::anyhow!(
+                "TextObject::iter: the textobjects query for {} is not loaded yet",
+                grammar_id
+            )
+        })?;
+        if queries.is_empty() {
+            return Err(anyhow::anyhow!(
+                "TextObject::iter: no textobjects query for {}",
+                grammar_id
+            ));
+        }
+        let capture_names = self.kind.capture_names(&self.surround_kind);
+        let source_code = buffer.content();
+        // The queries define overlapping textobjects differently,
+        // so only the first query that defines this textobject is used
+        let ranges = queries
+            .iter()
+            .map(|query| {
+                QueryCursor::new()
+                    .matches(query, tree.root_node(), source_code.as_bytes())
+                    .filter_map(|query_match| get_range(query, &query_match, &capture_names))
+                    .sorted_by_key(|range| (range.start, range.end))
+                    .dedup()
+                    .collect_vec()
+            })
+            .find(|ranges| !ranges.is_empty())
+            .unwrap_or_default();
+        Ok(Box::new(ranges.into_iter().map(ByteRange::new)))
+    }
+}
+
+/// The range spanned by the captures named `capture_names`,
+/// a capture can be quantified, for example a block of consecutive comments.
+///
+/// Ranges created by the `#make-range!` directive of `nvim-treesitter` are also supported.
+fn get_range(
+    query: &Query,
+    query_match: &QueryMatch,
+    capture_names: &[String; 2],
+) -> Option<Range<usize>> {
+    let is_wanted = |name: &str| capture_names.iter().any(|wanted| wanted == name);
+    let nodes_of = |index: u32| {
+        query_match
+            .captures
+            .iter()
+            .filter(move |capture| capture.index == index)
+            .map(|capture| capture.node)
+    };
+    let captured = query_match
+        .captures
+        .iter()
+        .filter(|capture| {
+            let name: &str = &query.capture_names()[capture.index as usize];
+            is_wanted(name)
+        })
+        .map(|capture| capture.node.byte_range());
+    let made = query
+        .general_predicates(query_match.pattern_index)
+        .iter()
+        .filter(|predicate| &*predicate.operator == "make-range!")
+        .filter_map(|predicate| match &predicate.args[..] {
+            [QueryPredicateArg::String(name), QueryPredicateArg::Capture(start), QueryPredicateArg::Capture(end)]
+                if is_wanted(name) =>
+            {
+                let start = nodes_of(*start).map(|node| node.start_byte()).min()?;
+                let end = nodes_of(*end).map(|node| node.end_byte()).max()?;
+                Some(start..end)
+            }
+            _ => None,
+        });
+    captured
+        .chain(made)
+        .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end))
+}
@@ -0,0 +1,205 @@
+//! A Unix socket server that allows other processes to control a running Ki instance.
+//!
+//! Each instance listens on a socket derived from its working directory,
+//! so that `ki edit` can open files in the instance that is already running
+//! in the same project, instead of starting a new process with its own LSP servers.
+//!
+//! The protocol is line-based: each line sent by the client is a JSON-serialized [`ServerCommand`],
+//! and the server replies with one line, either `ok` or `error: <reason>`.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::{MetadataExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+};
+
+use serde::{Deserialize, Serialize};
+use shared::canonicalized_path::CanonicalizedPath;
+
+use crate::{app::AppMessage, buffer::content_hash};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub(crate) enum ServerCommand {
+    /// Open the file at the given path, optionally moving the cursor
+    /// to the given 0-based line and column.
+    Open {
+        path: PathBuf,
+        line: Option<usize>,
+        column: Option<usize>,
+    },
+    RevealInExplorer {
+        path: PathBuf,
+    },
+    SetQuickfixList {
+        title: String,
+        items: Vec<ServerLocation>,
+    },
+    /// Run the action of the command palette with the given description,
+    /// for example "Align view to top".
+    Dispatch {
+        action: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ServerLocation {
+    pub(crate) path: PathBuf,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+}
+
+/// Returns the path of the socket of the instance running in `working_directory`.
+///
+/// The socket is placed in the runtime directory of the user, which only the user can access,
+/// or in the temporary directory if there is none.
+/// It is not placed in the cache directory
+/// because the length of a socket path is limited to around 100 bytes.
+pub(crate) fn socket_path(working_directory: &Path) -> PathBuf {
+    let directory = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    directory.join(format!(
+        "ki-{:x}.sock",
+        content_hash(&working_directory.to_string_lossy())
+    ))
+}
+
+/// Whether the socket at `socket_path` is owned by the current user,
+/// so that commands are never sent to an instance of another user.
+fn is_owned_by_current_user(socket_path: &Path) -> bool {
+    std::fs::metadata(socket_path)
+        // SAFETY: `getuid` has no preconditions and cannot fail
+        .is_ok_and(|metadata| metadata.uid() == unsafe { libc::getuid() })
+}
+
+pub(crate) struct Server {
+    socket_path: PathBuf,
+}
+
+impl Server {
+    /// Fails if another instance is already listening in `working_directory`.
+    pub(crate) fn start(
+        working_directory: &CanonicalizedPath,
+        sender: Sender<AppMessage>,
+    ) -> anyhow::Result<Server> {
+        let socket_path = socket_path(working_directory.as_ref());
+        if socket_path.exists() {
+            if UnixStream::connect(&socket_path).is_ok() {
+                return Err(anyhow::anyhow!(
+                    "Another instance is already running in {}",
+                    working_directory.display_absolute()
+                ));
+            }
+            // The socket is left behind by an instance that did not exit cleanly
+            std::fs::remove_file(&socket_path)?;
+        }
+        let listener = UnixListener::bind(&socket_path)?;
+        // Only the current user may send commands, because they can open and modify files
+        std::fs::set_permissions(&socket_path, std::fs::Permissions::from_mode(0o600))?;
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                let sender = sender.clone();
+                std::thread::spawn(move || {
+                    if let Err(error) = handle_connection(stream, sender) {
+                        log::error!("server::handle_connection {:?}", error)
+                    }
+                });
+            }
+        });
+        Ok(Server { socket_path })
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.socket_path);
+    }
+}
+
+fn handle_connection(stream: UnixStream, sender: Sender<AppMessage>) -> anyhow::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str::<ServerCommand>(&line) {
+            Ok(command) => {
+                sender.send(AppMessage::ServerCommand(command))?;
+                "ok".to_string()
+            }
+            Err(error) => format!("error: {}", error),
+        };
+        writeln!(writer, "{}", reply)?;
+    }
+    Ok(())
+}
+
+/// Sends `command` to the instance running in `working_directory`.
+///
+/// Returns `false` if there is no running instance of the current user.
+pub(crate) fn forward(
+    working_directory: &CanonicalizedPath,
+    command: &ServerCommand,
+) -> anyhow::Result<bool> {
+    let socket_path = socket_path(working_directory.as_ref());
+    if !is_owned_by_current_user(&socket_path) {
+        return Ok(false);
+    }
+    let Ok(stream) = UnixStream::connect(&socket_path) else {
+        return Ok(false);
+    };
+    let mut writer = stream.try_clone()?;
+    writeln!(writer, "{}", serde_json::to_string(command)?)?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    match reply.trim().strip_prefix("error: ") {
+        Some(error) => Err(anyhow::anyhow!("{}", error)),
+        None => Ok(true),
+    }
+}
+
+#[cfg(test)]
+mod test_server {
+    use super::*;
+
+    #[test]
+    fn forward_command_to_running_instance() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let working_directory: CanonicalizedPath = directory.path().try_into()?;
+        std::fs::create_dir(directory.path().join("nested"))?;
+        let nested = working_directory.join("nested")?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        let command = ServerCommand::Open {
+            path: PathBuf::from("main.rs"),
+            line: Some(1),
+            column: None,
+        };
+        assert!(!forward(&working_directory, &command)?);
+
+        let server = Server::start(&working_directory, sender.clone())?;
+        assert!(Server::start(&working_directory, sender).is_err());
+        let permissions = std::fs::metadata(socket_path(working_directory.as_ref()))?.permissions();
+        assert_eq!(permissions.mode() & 0o777, 0o600);
+
+        assert!(forward(&working_directory, &command)?);
+        match receiver.recv()? {
+            AppMessage::ServerCommand(received) => assert_eq!(received, command),
+            _ => panic!("Expected a server command"),
+        }
+
+        // Only the instance running in the exact working directory receives commands
+        assert!(!forward(&nested, &command)?);
+
+        drop(server);
+        assert!(!forward(&working_directory, &command)?);
+        Ok(())
+    }
+}
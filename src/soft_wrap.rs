@@ -1,10 +1,11 @@
-use std::fmt::Display;
+use std::{collections::HashSet, fmt::Display};
 
 use itertools::Itertools;
 use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::{
-    grid::{get_char_width, get_string_width},
+    grid::{get_grapheme_width, get_string_width},
     position::Position,
 };
 
@@ -96,6 +97,42 @@ impl WrappedLines {
         ))))
     }
 
+    /// The inverse of `calibrate`: converts a position on the screen
+    /// into the position of the character occupying that cell.
+    ///
+    /// Clicking beyond the end of a line yields the position right after its last character.
+    /// Returns `None` if the line of `position` is beyond the wrapped lines.
+    pub(crate) fn uncalibrate(&self, position: Position) -> Option<Position> {
+        let mut vertical_offset = 0;
+        let line = self.lines.iter().find(|line| {
+            let found = position.line < vertical_offset + line.count();
+            if !found {
+                vertical_offset += line.count();
+            }
+            found
+        })?;
+        let line_index = position.line - vertical_offset;
+        let previous_chars_count = line
+            .chars_with_line_index
+            .iter()
+            .take_while(|char| char.line_index < line_index)
+            .count();
+        let mut width = 0;
+        let column = line
+            .chars_with_line_index
+            .iter()
+            .skip(previous_chars_count)
+            .take_while(|char| {
+                width += char.width;
+                char.line_index == line_index && width <= position.column
+            })
+            .count();
+        Some(Position::new(
+            line.line_number,
+            previous_chars_count + column,
+        ))
+    }
+
     pub(crate) fn lines(&self) -> &Vec<WrappedLine> {
         &self.lines
     }
@@ -113,7 +150,18 @@ pub(crate) struct WrappedLine {
     wrapped: Vec<String>,
     /// This can be computed on demand, but it is stored as cache to
     /// greatly improve the performace of `WrappedLines::calibrate`
-    chars_with_line_index: Vec<(usize /* line index (0-based) */, char)>,
+    chars_with_line_index: Vec<WrappedChar>,
+}
+
+#[derive(Debug, Clone)]
+struct WrappedChar {
+    /// 0-based
+    line_index: usize,
+    /// The width of the grapheme cluster for its first character, and 0 for the rest of its characters,
+    /// because a grapheme cluster is rendered as a whole at the position of its first character.
+    width: usize,
+    /// The index of the first character of the grapheme cluster of this character
+    grapheme_start: usize,
 }
 impl Display for WrappedLine {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -140,20 +188,20 @@ impl WrappedLine {
         if column > chars_with_line_index.len() {
             return None;
         }
+        let column = chars_with_line_index
+            .get(column)
+            .map(|char| char.grapheme_start)
+            .unwrap_or(column);
         let (left, right) = chars_with_line_index.split_at(column);
         let line = right
-            .split_first()
-            .map(|((line, _), _)| *line)
-            .or_else(|| Some(chars_with_line_index.last()?.0))?;
-        let previous_columns_chars = left.iter().filter(|(line_, _)| &line == line_);
-
-        let char_width = right
             .first()
-            .map(|(_, char)| get_char_width(*char))
-            .unwrap_or(1);
-        let previous_columns_chars_total_width: usize = previous_columns_chars
-            .map(move |(_, char)| get_char_width(*char))
-            .sum();
+            .map(|char| char.line_index)
+            .or_else(|| Some(chars_with_line_index.last()?.line_index))?;
+        let previous_columns_chars = left.iter().filter(|char| char.line_index == line);
+
+        let char_width = right.first().map(|char| char.width).unwrap_or(1);
+        let previous_columns_chars_total_width: usize =
+            previous_columns_chars.map(|char| char.width).sum();
         Some(Positions(Box::new((0..char_width).map(move |column| {
             let calibrated_column = column + previous_columns_chars_total_width;
             debug_assert!(calibrated_column <= width);
@@ -179,8 +227,8 @@ pub(crate) fn soft_wrap(text: &str, width: usize) -> WrappedLines {
         .lines()
         .enumerate()
         .filter_map(|(line_number, line)| {
-            let wrapped_lines: Vec<String> = re
-                .split(line)
+            let wrapped_lines: Vec<String> = split_words(&re, line)
+                .into_iter()
                 .flat_map(|chunk| chop_str(chunk, wrap_width))
                 .fold(
                     vec![],
@@ -206,11 +254,26 @@ pub(crate) fn soft_wrap(text: &str, width: usize) -> WrappedLines {
                 line_number,
                 wrapped: wrapped.to_vec(),
                 chars_with_line_index: wrapped_lines
-                    .into_iter()
+                    .iter()
                     .enumerate()
                     .flat_map(|(line_index, line)| {
-                        line.chars().map(|char| (line_index, char)).collect_vec()
+                        line.graphemes(true)
+                            .flat_map(move |grapheme| {
+                                let width = get_grapheme_width(grapheme);
+                                grapheme.chars().enumerate().map(move |(index, _)| {
+                                    (line_index, if index == 0 { width } else { 0 }, index)
+                                })
+                            })
+                            .collect_vec()
                     })
+                    .enumerate()
+                    .map(
+                        |(char_index, (line_index, width, index_in_grapheme))| WrappedChar {
+                            line_index,
+                            width,
+                            grapheme_start: char_index - index_in_grapheme,
+                        },
+                    )
                     .collect_vec(),
             })
         })
@@ -227,6 +290,30 @@ pub(crate) fn soft_wrap(text: &str, width: usize) -> WrappedLines {
     result
 }
 
+/// Split `line` at word boundaries, without breaking grapheme clusters apart.
+fn split_words<'a>(word_boundary: &Regex, line: &'a str) -> Vec<&'a str> {
+    let grapheme_boundaries: HashSet<usize> = line
+        .grapheme_indices(true)
+        .map(|(index, _)| index)
+        .collect();
+    let mut start = 0;
+    let mut words = Vec::new();
+    for boundary in word_boundary
+        .find_iter(line)
+        .map(|boundary| boundary.start())
+        .chain(Some(line.len()))
+    {
+        if boundary > start && (boundary == line.len() || grapheme_boundaries.contains(&boundary)) {
+            words.push(&line[start..boundary]);
+            start = boundary;
+        }
+    }
+    if words.is_empty() {
+        words.push("");
+    }
+    words
+}
+
 /// Chop the given string into chunks by the given `max_width`
 /// The width of each chunk is paired with each chunk in the result vector.
 fn chop_str(s: &str, max_width: usize) -> Vec<(usize, String)> {
@@ -236,21 +323,21 @@ fn chop_str(s: &str, max_width: usize) -> Vec<(usize, String)> {
             return vec![(width, s.to_string())];
         }
         let mut result = vec![];
-        let mut current = vec![];
+        let mut current = String::new();
         let mut current_width = 0;
-        for c in s.chars() {
-            let char_width = get_char_width(c);
-            if char_width + current_width <= max_width {
-                current.push(c);
-                current_width += char_width;
+        for grapheme in s.graphemes(true) {
+            let grapheme_width = get_grapheme_width(grapheme);
+            if grapheme_width + current_width <= max_width {
+                current.push_str(grapheme);
+                current_width += grapheme_width;
             } else {
-                result.push((current_width, current.drain(..).join("")));
-                current_width = char_width;
-                current = vec![c];
+                result.push((current_width, std::mem::take(&mut current)));
+                current_width = grapheme_width;
+                current = grapheme.to_string();
             }
         }
         if !current.is_empty() {
-            result.push((current_width, current.drain(..).join("")));
+            result.push((current_width, current));
         }
 
         result
@@ -504,5 +591,71 @@ mod test_soft_wrap {
                 vec![Position::new(1, 4)]
             );
         }
+
+        #[test]
+        fn grapheme_cluster() {
+            // "e" followed by a combining acute accent
+            let wrapped_lines = soft_wrap("e\u{301}x", 10);
+            assert_eq!(
+                wrapped_lines
+                    .calibrate(Position::new(0, 1))
+                    .unwrap()
+                    .into_vec(),
+                vec![Position::new(0, 0)]
+            );
+            assert_eq!(
+                wrapped_lines
+                    .calibrate(Position::new(0, 2))
+                    .unwrap()
+                    .into_vec(),
+                vec![Position::new(0, 1)]
+            );
+
+            // The family emoji should not be wrapped in the middle
+            let content = "ab👨\u{200D}👩\u{200D}👧";
+            let wrapped_lines = soft_wrap(content, 4);
+            assert_eq!(wrapped_lines.to_string(), "ab\n👨\u{200D}👩\u{200D}👧");
+        }
+    }
+
+    mod uncalibrate {
+        use crate::position::Position;
+        use crate::soft_wrap::soft_wrap;
+
+        #[test]
+        fn wrapped_line() {
+            let wrapped_lines = soft_wrap(
+                "hey jude
+hi",
+                5,
+            );
+            assert_eq!(
+                wrapped_lines.uncalibrate(Position::new(1, 1)),
+                Some(Position::new(0, 5))
+            );
+            // Beyond the end of a line
+            assert_eq!(
+                wrapped_lines.uncalibrate(Position::new(1, 9)),
+                Some(Position::new(0, 8))
+            );
+            assert_eq!(
+                wrapped_lines.uncalibrate(Position::new(2, 1)),
+                Some(Position::new(1, 1))
+            );
+            assert_eq!(wrapped_lines.uncalibrate(Position::new(3, 0)), None);
+        }
+
+        #[test]
+        fn multi_width_unicode() {
+            let wrapped_lines = soft_wrap("🦀a", 10);
+            assert_eq!(
+                wrapped_lines.uncalibrate(Position::new(0, 1)),
+                Some(Position::new(0, 0))
+            );
+            assert_eq!(
+                wrapped_lines.uncalibrate(Position::new(0, 2)),
+                Some(Position::new(0, 1))
+            );
+        }
     }
 }
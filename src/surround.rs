@@ -87,6 +87,17 @@ pub(crate) fn get_surrounding_indices(
 }
 
 impl EnclosureKind {
+    /// The enclosures that are auto-paired in insert mode.
+    /// Angular brackets are excluded, because `<` is more often a comparison than an opening symbol.
+    const AUTO_PAIRED: [EnclosureKind; 6] = [
+        EnclosureKind::Parentheses,
+        EnclosureKind::CurlyBraces,
+        EnclosureKind::SquareBrackets,
+        EnclosureKind::DoubleQuotes,
+        EnclosureKind::SingleQuotes,
+        EnclosureKind::Backticks,
+    ];
+
     pub(crate) const fn open_close_symbols(&self) -> (char, char) {
         match self {
             EnclosureKind::Parentheses => ('(', ')'),
@@ -124,6 +135,71 @@ impl EnclosureKind {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum AutoPair {
+    /// Insert the typed character followed by this closing symbol
+    InsertPair(char),
+    /// Move over the closing symbol that is already there
+    SkipClosing,
+    /// Insert the typed character only
+    None,
+}
+
+fn auto_paired_symbols() -> impl Iterator<Item = (char, char)> {
+    EnclosureKind::AUTO_PAIRED
+        .into_iter()
+        .map(|kind| kind.open_close_symbols())
+}
+
+fn is_closing_symbol(c: char) -> bool {
+    auto_paired_symbols().any(|(_, close)| close == c)
+}
+
+/// Decide how typing `c` between `previous` and `next` is auto-paired.
+///
+/// A pair is only inserted when the cursor is not followed by a word,
+/// and quotes are not paired after a word, so that apostrophes can be typed.
+/// The closing symbol `next` is only moved over if `next_is_auto_inserted`,
+/// so that typing a closing symbol that was typed by the user inserts it.
+pub(crate) fn auto_pair(
+    c: char,
+    previous: Option<char>,
+    next: Option<char>,
+    next_is_auto_inserted: bool,
+) -> AutoPair {
+    if next == Some(c) && next_is_auto_inserted && is_closing_symbol(c) {
+        return AutoPair::SkipClosing;
+    }
+    let Some((_, close)) = auto_paired_symbols().find(|(open, _)| open == &c) else {
+        return AutoPair::None;
+    };
+    let next_allows_pair = next.map_or(true, |next| {
+        next.is_whitespace() || (is_closing_symbol(next) && next != c)
+    });
+    let previous_allows_pair =
+        c != close || !previous.is_some_and(|previous| previous.is_alphanumeric() || previous == c);
+    if next_allows_pair && previous_allows_pair {
+        AutoPair::InsertPair(close)
+    } else {
+        AutoPair::None
+    }
+}
+
+/// Returns true if `previous` and `next` are the open and close symbols of an auto-paired enclosure,
+/// which are deleted together by backspace.
+pub(crate) fn is_auto_pair(previous: Option<char>, next: Option<char>) -> bool {
+    let (Some(previous), Some(next)) = (previous, next) else {
+        return false;
+    };
+    auto_paired_symbols().any(|pair| pair == (previous, next))
+}
+
+/// Returns true if `previous` and `next` are the open and close symbols of a bracket,
+/// between which a newline opens an indented line.
+pub(crate) fn is_bracket_pair(previous: Option<char>, next: Option<char>) -> bool {
+    is_auto_pair(previous, next) && previous != next
+}
+
 #[cfg(test)]
 mod test_surround {
     use super::*;
@@ -165,4 +241,32 @@ mod test_surround {
     fn test_get_surrounding_indices_3() {
         run_test("'hello'", SingleQuotes, 2, Some((0, 6)));
     }
+
+    #[test]
+    fn test_auto_pair() {
+        let auto_pair = |c, previous, next| auto_pair(c, previous, next, true);
+        assert_eq!(auto_pair('(', None, None), AutoPair::InsertPair(')'));
+        assert_eq!(
+            auto_pair('(', Some('f'), Some(')')),
+            AutoPair::InsertPair(')')
+        );
+        assert_eq!(auto_pair('(', None, Some('x')), AutoPair::None);
+        assert_eq!(auto_pair(')', Some('('), Some(')')), AutoPair::SkipClosing);
+        assert_eq!(
+            auto_pair('"', Some(' '), Some('\n')),
+            AutoPair::InsertPair('"')
+        );
+        assert_eq!(auto_pair('"', Some('a'), Some('"')), AutoPair::SkipClosing);
+        // Apostrophe
+        assert_eq!(auto_pair('\'', Some('t'), None), AutoPair::None);
+        assert_eq!(auto_pair('\'', Some(' '), None), AutoPair::InsertPair('\''));
+        assert_eq!(auto_pair('<', None, None), AutoPair::None);
+        assert_eq!(auto_pair('x', None, None), AutoPair::None);
+    }
+
+    #[test]
+    fn test_auto_pair_only_skips_auto_inserted_closing_symbol() {
+        assert_eq!(auto_pair(')', Some('('), Some(')'), false), AutoPair::None);
+        assert_eq!(auto_pair('"', Some('a'), Some('"'), false), AutoPair::None);
+    }
 }
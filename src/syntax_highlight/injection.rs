@@ -0,0 +1,100 @@
+use std::{
+    collections::HashMap,
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+use once_cell::sync::OnceCell;
+use shared::language::Language;
+use tree_sitter::{Parser, Query, QueryCursor, Tree};
+
+type TreeSitterGrammarId = String;
+
+/// The injection queries compiled when the highlight configurations are loaded,
+/// so that the syntax trees of injected languages can be parsed without fetching the queries again.
+static QUERIES: OnceCell<Mutex<HashMap<TreeSitterGrammarId, Arc<Query>>>> = OnceCell::new();
+
+fn queries() -> &'static Mutex<HashMap<TreeSitterGrammarId, Arc<Query>>> {
+    QUERIES.get_or_init(Default::default)
+}
+
+pub(crate) fn register_query(
+    grammar_id: TreeSitterGrammarId,
+    language: &tree_sitter::Language,
+    source: &str,
+) {
+    match Query::new(language, source) {
+        Ok(query) => {
+            if let Ok(mut queries) = queries().lock() {
+                queries.insert(grammar_id, Arc::new(query));
+            }
+        }
+        Err(error) => log::info!("injection_query_error = {:#?}", error),
+    }
+}
+
+/// The syntax tree of a language embedded in another language,
+/// for example a fenced code block in Markdown, or `<script>` in HTML.
+#[derive(Clone)]
+pub(crate) struct Injection {
+    pub(crate) byte_range: Range<usize>,
+    pub(crate) language: Language,
+    /// The nodes of this tree are positioned relative to the whole buffer
+    pub(crate) tree: Tree,
+}
+
+/// Returns `None` if the injection query of `language` is not loaded yet.
+pub(crate) fn parse_injections(
+    language: &Language,
+    tree: &Tree,
+    source_code: &str,
+) -> Option<Vec<Injection>> {
+    let query = queries()
+        .lock()
+        .ok()?
+        .get(&language.tree_sitter_grammar_id()?)?
+        .clone();
+    let Some(content_index) = query.capture_index_for_name("injection.content") else {
+        return Some(Vec::new());
+    };
+    let language_index = query.capture_index_for_name("injection.language");
+    let mut cursor = QueryCursor::new();
+    let mut parser = Parser::new();
+    let injections = cursor
+        .matches(&query, tree.root_node(), source_code.as_bytes())
+        .filter_map(|query_match| {
+            let content = query_match
+                .captures
+                .iter()
+                .find(|capture| capture.index == content_index)?
+                .node;
+            let name = language_index
+                .and_then(|index| {
+                    query_match
+                        .captures
+                        .iter()
+                        .find(|capture| capture.index == index)
+                })
+                .and_then(|capture| capture.node.utf8_text(source_code.as_bytes()).ok())
+                .map(|name| name.to_string())
+                .or_else(|| {
+                    query
+                        .property_settings(query_match.pattern_index)
+                        .iter()
+                        .find(|property| &*property.key == "injection.language")
+                        .and_then(|property| Some(property.value.as_ref()?.to_string()))
+                })?;
+            let language = shared::language::from_name(&name)?;
+            parser
+                .set_language(&language.tree_sitter_language()?)
+                .ok()?;
+            parser.set_included_ranges(&[content.range()]).ok()?;
+            Some(Injection {
+                byte_range: content.byte_range(),
+                language,
+                tree: parser.parse(source_code, None)?,
+            })
+        })
+        .collect();
+    Some(injections)
+}
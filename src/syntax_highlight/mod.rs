@@ -1,10 +1,12 @@
-use std::{collections::HashMap, ops::Range, sync::mpsc::Sender, time::Duration};
+pub(crate) mod injection;
+
+use std::{cell::RefCell, collections::HashMap, ops::Range, sync::mpsc::Sender, time::Duration};
 
 use tree_sitter_highlight::{HighlightConfiguration, HighlightEvent, Highlighter};
 
 use crate::{
-    app::AppMessage, char_index_range::apply_edit, components::component::ComponentId,
-    grid::StyleKey, themes::highlight_names,
+    app::AppMessage, auto_indent, char_index_range::apply_edit, components::component::ComponentId,
+    grid::StyleKey, selection_mode::text_object, themes::highlight_names,
 };
 use shared::language::Language;
 
@@ -35,13 +37,18 @@ impl GetHighlightConfig for Language {
         };
 
         let highlights_query = &self.highlight_query().unwrap_or_default();
+        let injection_query = &self.injection_query().unwrap_or_default();
         let mut config = HighlightConfiguration::new(
-            tree_sitter_language,
+            tree_sitter_language.clone(),
             "highlight".to_string(),
             highlights_query,
-            self.injection_query().unwrap_or_default(),
+            injection_query,
             self.locals_query().unwrap_or_default(),
         )?;
+        if let Some(grammar_id) = self.tree_sitter_grammar_id() {
+            // Also used for navigating injected syntax trees
+            injection::register_query(grammar_id, &tree_sitter_language, injection_query);
+        }
 
         config.configure(crate::themes::highlight_names().as_slice());
 
@@ -50,14 +57,25 @@ impl GetHighlightConfig for Language {
 }
 
 pub trait Highlight {
-    fn highlight(&self, source_code: &str) -> anyhow::Result<HighlighedSpans>;
+    /// `get_injection` returns the configuration of an injected language by its name.
+    fn highlight<'a>(
+        &'a self,
+        source_code: &'a str,
+        get_injection: impl FnMut(&str) -> Option<&'a HighlightConfiguration> + 'a,
+    ) -> anyhow::Result<HighlighedSpans>;
 }
 
 impl Highlight for HighlightConfiguration {
-    fn highlight(&self, source_code: &str) -> anyhow::Result<HighlighedSpans> {
+    fn highlight<'a>(
+        &'a self,
+        source_code: &'a str,
+        mut get_injection: impl FnMut(&str) -> Option<&'a HighlightConfiguration> + 'a,
+    ) -> anyhow::Result<HighlighedSpans> {
         let mut highlighter = Highlighter::new();
 
-        let highlights = highlighter.highlight(self, source_code.as_bytes(), None, |_| None)?;
+        let highlights = highlighter.highlight(self, source_code.as_bytes(), None, |name| {
+            get_injection(name)
+        })?;
 
         let mut highlight = None;
 
@@ -142,8 +160,11 @@ pub(crate) fn start_thread(callback: Sender<AppMessage>) -> Sender<SyntaxHighlig
 }
 type TreeSitterGrammarId = String;
 /// We have to cache the highlight configurations because they load slowly.
+///
+/// `None` means the language has no highlight configuration,
+/// which is cached as well so that injected languages are not loaded repeatedly.
 pub(crate) struct HighlightConfigs(
-    HashMap<TreeSitterGrammarId, tree_sitter_highlight::HighlightConfiguration>,
+    HashMap<TreeSitterGrammarId, Option<tree_sitter_highlight::HighlightConfiguration>>,
 );
 
 impl HighlightConfigs {
@@ -159,20 +180,56 @@ impl HighlightConfigs {
         let Some(grammar_id) = language.tree_sitter_grammar_id() else {
             return Ok(Default::default());
         };
-        let config = match self.0.get(&grammar_id) {
-            Some(config) => config,
-            None => {
-                if let Some(highlight_config) = language.get_highlight_config()? {
-                    self.0.insert(grammar_id.clone(), highlight_config);
-                    let get_error = || {
-                        anyhow::anyhow!("Unreachable: should be able to obtain a value that is inserted to the HashMap")
-                    };
-                    self.0.get(&grammar_id).ok_or_else(get_error)?
-                } else {
+        if !self.0.contains_key(&grammar_id) {
+            let highlight_config = language.get_highlight_config()?;
+            self.0.insert(grammar_id.clone(), highlight_config);
+        }
+        // Loaded along with the highlight configuration to keep the downloads off the UI thread.
+        // A failed download is not registered, so that it is retried on the next request.
+        if !text_object::is_registered(&grammar_id) {
+            match language.textobjects_queries() {
+                Ok(sources) => text_object::register_queries(&language, sources),
+                Err(error) => log::info!("textobjects_queries_error = {:#?}", error),
+            }
+        }
+        if !auto_indent::is_registered(&grammar_id) {
+            match language.indent_query() {
+                Ok(source) => auto_indent::register_query(&language, source),
+                Err(error) => log::info!("indent_query_error = {:#?}", error),
+            }
+        }
+        // The injected languages are only known while highlighting,
+        // so highlight again whenever new injected languages are loaded
+        loop {
+            let missing_languages = RefCell::new(Vec::new());
+            let highlighted_spans = {
+                let configs = &self.0;
+                let Some(Some(config)) = configs.get(&grammar_id) else {
                     return Ok(Default::default());
-                }
+                };
+                config.highlight(source_code, |name| {
+                    let language = shared::language::from_name(name)?;
+                    let grammar_id = language.tree_sitter_grammar_id()?;
+                    match configs.get(&grammar_id) {
+                        Some(config) => config.as_ref(),
+                        None => {
+                            missing_languages.borrow_mut().push((grammar_id, language));
+                            None
+                        }
+                    }
+                })?
+            };
+            let missing_languages = missing_languages.into_inner();
+            if missing_languages.is_empty() {
+                return Ok(highlighted_spans);
             }
-        };
-        config.highlight(source_code)
+            for (grammar_id, language) in missing_languages {
+                let highlight_config = language.get_highlight_config().unwrap_or_else(|error| {
+                    log::info!("injected_language_highlight_error = {:#?}", error);
+                    None
+                });
+                self.0.insert(grammar_id, highlight_config);
+            }
+        }
     }
 }
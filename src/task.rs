@@ -0,0 +1,599 @@
+//! Run project commands, such as builds and tests, without blocking the editor.
+//!
+//! The tasks of a project are declared in `.ki/tasks.json` of the working directory,
+//! for example:
+//!
+//! ```json
+//! [
+//!   { "name": "build", "command": "cargo build", "format": "rustc" },
+//!   { "name": "lint", "command": "make lint", "format": { "pattern": "^(?P<path>\\S+) line (?P<line>\\d+): (?P<message>.*)$" } }
+//! ]
+//! ```
+//!
+//! When a task exits, the locations found in its output populate the quickfix list.
+
+use std::{
+    io::{BufRead, BufReader, Read},
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::Sender,
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use itertools::Itertools;
+use regex::Regex;
+use serde::Deserialize;
+use shared::canonicalized_path::CanonicalizedPath;
+
+use crate::{
+    app::AppMessage,
+    components::suggestive_editor::Info,
+    position::Position,
+    quickfix_list::{Location, QuickfixListItem},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub(crate) struct Task {
+    pub(crate) name: String,
+    /// Executed by `sh -c` in the working directory
+    pub(crate) command: String,
+    #[serde(default)]
+    pub(crate) format: ErrorFormatConfig,
+}
+
+impl Task {
+    /// A task that is not declared in the config file, its name is the command itself.
+    pub(crate) fn from_command(command: &str) -> Task {
+        Task {
+            name: command.to_string(),
+            command: command.to_string(),
+            format: ErrorFormatConfig::default(),
+        }
+    }
+}
+
+pub(crate) fn config_path(working_directory: &CanonicalizedPath) -> PathBuf {
+    working_directory
+        .to_path_buf()
+        .join(".ki")
+        .join("tasks.json")
+}
+
+/// Returns an empty list if the config file does not exist.
+pub(crate) fn load_tasks(working_directory: &CanonicalizedPath) -> anyhow::Result<Vec<Task>> {
+    let path = config_path(working_directory);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    serde_json5::from_str(&std::fs::read_to_string(&path)?)
+        .map_err(|error| anyhow::anyhow!("Invalid {}: {}", path.display(), error))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum ErrorFormatConfig {
+    Preset(ErrorFormatPreset),
+    /// `pattern` must have the named groups `path` and `line`,
+    /// and optionally `column` and `message`.
+    /// If `pattern` has no `message` group, the message is taken from
+    /// the nearest preceding line that matches `message_pattern`.
+    Custom {
+        pattern: String,
+        message_pattern: Option<String>,
+    },
+}
+
+impl Default for ErrorFormatConfig {
+    fn default() -> Self {
+        ErrorFormatConfig::Preset(ErrorFormatPreset::Auto)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ErrorFormatPreset {
+    /// Tries every other preset
+    Auto,
+    Rustc,
+    Tsc,
+    Gcc,
+    Pytest,
+}
+
+impl ErrorFormatPreset {
+    fn error_formats(self) -> anyhow::Result<Vec<ErrorFormat>> {
+        use ErrorFormatPreset::*;
+        let format = match self {
+            Auto => {
+                return [Rustc, Tsc, Gcc, Pytest]
+                    .into_iter()
+                    .map(ErrorFormatPreset::error_formats)
+                    .flatten_ok()
+                    .collect()
+            }
+            Rustc => ErrorFormat::new(
+                r"^\s*--> (?P<path>[^:]+):(?P<line>\d+):(?P<column>\d+)$",
+                Some(r"^(?P<message>(error|warning)(\[\w+\])?: .*)$"),
+            ),
+            Tsc => ErrorFormat::new(
+                r"^(?P<path>[^\s(]+)\((?P<line>\d+),(?P<column>\d+)\): (?P<message>.*)$",
+                None,
+            ),
+            Gcc => ErrorFormat::new(
+                r"^(?P<path>[^\s:]+):(?P<line>\d+):(?P<column>\d+): (?P<message>(fatal error|error|warning|note): .*)$",
+                None,
+            ),
+            Pytest => ErrorFormat::new(
+                r"^(?P<path>[^\s:]+\.py):(?P<line>\d+): (?P<message>.*)$",
+                None,
+            ),
+        }?;
+        Ok(vec![format])
+    }
+}
+
+impl ErrorFormatConfig {
+    fn error_formats(&self) -> anyhow::Result<Vec<ErrorFormat>> {
+        match self {
+            ErrorFormatConfig::Preset(preset) => preset.error_formats(),
+            ErrorFormatConfig::Custom {
+                pattern,
+                message_pattern,
+            } => Ok(vec![ErrorFormat::new(pattern, message_pattern.as_deref())?]),
+        }
+    }
+
+    /// Line and column numbers in `output` are 1-based.
+    /// Locations of files that do not exist are skipped.
+    pub(crate) fn parse(
+        &self,
+        output: &str,
+        working_directory: &CanonicalizedPath,
+    ) -> anyhow::Result<Vec<QuickfixListItem>> {
+        let formats = self.error_formats()?;
+        let mut messages: Vec<Option<String>> = vec![None; formats.len()];
+        let mut items = Vec::new();
+        for line in output.lines() {
+            for (format, message) in formats.iter().zip(messages.iter_mut()) {
+                if let Some(item) = format.parse_location(line, message, working_directory) {
+                    items.push(item);
+                    break;
+                }
+                if let Some(captures) = format
+                    .message
+                    .as_ref()
+                    .and_then(|regex| regex.captures(line))
+                {
+                    *message = captures
+                        .name("message")
+                        .map(|found| found.as_str().to_string())
+                }
+            }
+        }
+        Ok(items)
+    }
+}
+
+struct ErrorFormat {
+    location: Regex,
+    message: Option<Regex>,
+}
+
+impl ErrorFormat {
+    fn new(location: &str, message: Option<&str>) -> anyhow::Result<ErrorFormat> {
+        let location = Regex::new(location)?;
+        for name in ["path", "line"] {
+            if !location
+                .capture_names()
+                .flatten()
+                .any(|group| group == name)
+            {
+                return Err(anyhow::anyhow!(
+                    "The error format {:?} has no `{}` group",
+                    location.as_str(),
+                    name
+                ));
+            }
+        }
+        Ok(ErrorFormat {
+            location,
+            message: message.map(Regex::new).transpose()?,
+        })
+    }
+
+    /// `message` is the message found in the preceding lines, it is consumed if `line` matches the location pattern.
+    fn parse_location(
+        &self,
+        line: &str,
+        message: &mut Option<String>,
+        working_directory: &CanonicalizedPath,
+    ) -> Option<QuickfixListItem> {
+        let captures = self.location.captures(line)?;
+        let preceding_message = message.take();
+        let number = |name: &str| -> Option<usize> {
+            Some(
+                captures
+                    .name(name)?
+                    .as_str()
+                    .parse::<usize>()
+                    .ok()?
+                    .saturating_sub(1),
+            )
+        };
+        let path = working_directory
+            .join(captures.name("path")?.as_str())
+            .ok()?;
+        let position = Position::new(number("line")?, number("column").unwrap_or(0));
+        let message = captures
+            .name("message")
+            .map(|found| found.as_str().to_string())
+            .or(preceding_message);
+        Some(QuickfixListItem::new(
+            Location {
+                path,
+                range: position..position,
+            },
+            message.map(|message| Info::new("Task".to_string(), message)),
+        ))
+    }
+}
+
+static NEXT_TASK_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// The oldest lines of the output are dropped beyond this length (in bytes),
+/// so that a task that never stops printing does not exhaust the memory.
+const MAX_OUTPUT_LENGTH: usize = 1024 * 1024;
+
+/// How often `AppMessage::TaskOutput` is sent while the task prints.
+const OUTPUT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A task that is running or has exited, along with its output so far.
+pub(crate) struct TaskRun {
+    id: usize,
+    task: Task,
+    child: Arc<Mutex<Child>>,
+    /// The task runs in its own process group, whose ID is the ID of `sh`,
+    /// so that the processes spawned by the command can be killed along with `sh`.
+    process_group_id: u32,
+    /// The output (stdout and stderr) that is not received by `TaskRun::receive_output` yet.
+    /// It is not sent line by line, because commands can print faster than the screen can be rendered.
+    pending_output: Arc<Mutex<String>>,
+    output: String,
+}
+
+/// The result of `TaskRun::receive_output`
+pub(crate) enum ReceivedOutput {
+    Nothing,
+    Appended(String),
+    /// The oldest lines were dropped, so the whole output has to be shown again.
+    Truncated,
+}
+
+impl TaskRun {
+    /// While the task prints, `AppMessage::TaskOutput` is sent once per `OUTPUT_INTERVAL`,
+    /// so that the output can be collected with `TaskRun::receive_output`,
+    /// followed by `AppMessage::TaskExited`.
+    pub(crate) fn start(
+        task: Task,
+        working_directory: &Path,
+        app_message_sender: Sender<AppMessage>,
+    ) -> anyhow::Result<TaskRun> {
+        let id = NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed);
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&task.command)
+            .current_dir(working_directory)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .process_group(0)
+            .spawn()
+            .map_err(|error| anyhow::anyhow!("Unable to run `{}`: {}", task.command, error))?;
+        let process_group_id = child.id();
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Unable to obtain stdout"))?;
+        let stderr = child
+            .stderr
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Unable to obtain stderr"))?;
+        let child = Arc::new(Mutex::new(child));
+        let pending_output = Arc::new(Mutex::new(String::new()));
+        {
+            let child = child.clone();
+            let pending_output = pending_output.clone();
+            std::thread::spawn(move || {
+                let readers: [Box<dyn Read + Send>; 2] = [Box::new(stdout), Box::new(stderr)];
+                let readers = readers.map(|reader| {
+                    let pending_output = pending_output.clone();
+                    std::thread::spawn(move || Self::collect_lines(reader, pending_output))
+                });
+                while !readers.iter().all(|reader| reader.is_finished()) {
+                    std::thread::sleep(OUTPUT_INTERVAL);
+                    let has_output = pending_output
+                        .lock()
+                        .is_ok_and(|pending_output| !pending_output.is_empty());
+                    if has_output
+                        && app_message_sender
+                            .send(AppMessage::TaskOutput { id })
+                            .is_err()
+                    {
+                        break;
+                    }
+                }
+                let code = child
+                    .lock()
+                    .map_err(|error| anyhow::anyhow!("{}", error))
+                    .and_then(|mut child| Ok(child.wait()?))
+                    .map(|status| status.code())
+                    .unwrap_or_else(|error| {
+                        log::error!("TaskRun::start {:?}", error);
+                        None
+                    });
+                let _ = app_message_sender.send(AppMessage::TaskExited { id, code });
+            });
+        }
+        Ok(TaskRun {
+            id,
+            task,
+            child,
+            process_group_id,
+            pending_output,
+            output: String::new(),
+        })
+    }
+
+    fn collect_lines(reader: impl Read, pending_output: Arc<Mutex<String>>) {
+        for line in BufReader::new(reader).lines() {
+            let Ok(line) = line else { break };
+            let Ok(mut pending_output) = pending_output.lock() else {
+                break;
+            };
+            pending_output.push_str(&line);
+            pending_output.push('\n');
+            drop_oldest_lines(&mut pending_output, MAX_OUTPUT_LENGTH);
+        }
+    }
+
+    pub(crate) fn id(&self) -> usize {
+        self.id
+    }
+
+    pub(crate) fn task(&self) -> &Task {
+        &self.task
+    }
+
+    pub(crate) fn output(&self) -> &str {
+        &self.output
+    }
+
+    /// Moves the output printed since the last call into `output`.
+    pub(crate) fn receive_output(&mut self) -> ReceivedOutput {
+        let received = match self.pending_output.lock() {
+            Ok(mut pending_output) => std::mem::take(&mut *pending_output),
+            Err(_) => return ReceivedOutput::Nothing,
+        };
+        if received.is_empty() {
+            return ReceivedOutput::Nothing;
+        }
+        self.output.push_str(&received);
+        if drop_oldest_lines(&mut self.output, MAX_OUTPUT_LENGTH) {
+            ReceivedOutput::Truncated
+        } else {
+            ReceivedOutput::Appended(received)
+        }
+    }
+
+    /// `sh` is killed along with every process in its group.
+    /// The child is not locked, because it is locked while it is being waited for.
+    pub(crate) fn kill(&self) {
+        unsafe {
+            libc::killpg(self.process_group_id as libc::pid_t, libc::SIGKILL);
+        }
+        if let Ok(mut child) = self.child.try_lock() {
+            let _ = child.kill();
+        }
+    }
+}
+
+/// Drops the oldest lines of `output` until it is at most `max_length` bytes long.
+/// Returns true if any line was dropped.
+fn drop_oldest_lines(output: &mut String, max_length: usize) -> bool {
+    let Some(excess) = output
+        .len()
+        .checked_sub(max_length)
+        .filter(|excess| *excess > 0)
+    else {
+        return false;
+    };
+    let end = output.as_bytes()[excess..]
+        .iter()
+        .position(|byte| *byte == b'\n')
+        .map(|index| excess + index + 1)
+        .unwrap_or(output.len());
+    output.drain(..end);
+    true
+}
+
+#[cfg(test)]
+mod test_task {
+    use shared::canonicalized_path::CanonicalizedPath;
+
+    use itertools::Itertools;
+
+    use super::{
+        drop_oldest_lines, ErrorFormatConfig, ErrorFormatPreset, ReceivedOutput, Task, TaskRun,
+    };
+    use crate::{app::AppMessage, position::Position};
+
+    fn parse(
+        format: ErrorFormatConfig,
+        output: &str,
+    ) -> anyhow::Result<Vec<(String, Position, Option<String>)>> {
+        let directory = tempfile::tempdir()?;
+        std::fs::create_dir(directory.path().join("src"))?;
+        std::fs::write(directory.path().join("src/main.rs"), "")?;
+        std::fs::write(directory.path().join("test_app.py"), "")?;
+        let working_directory: CanonicalizedPath = directory.path().try_into()?;
+        Ok(format
+            .parse(output, &working_directory)?
+            .into_iter()
+            .map(|item| {
+                (
+                    item.location()
+                        .path
+                        .display_relative_to(&working_directory)
+                        .unwrap_or_default(),
+                    item.location().range.start,
+                    item.info().as_ref().map(|info| info.content().clone()),
+                )
+            })
+            .collect())
+    }
+
+    #[test]
+    fn rustc() -> anyhow::Result<()> {
+        let output = "
+error[E0425]: cannot find value `x` in this scope
+ --> src/main.rs:2:5
+  |
+warning: unused variable: `y`
+  --> src/main.rs:10:9
+";
+        assert_eq!(
+            parse(ErrorFormatConfig::Preset(ErrorFormatPreset::Rustc), output)?,
+            vec![
+                (
+                    "src/main.rs".to_string(),
+                    Position::new(1, 4),
+                    Some("error[E0425]: cannot find value `x` in this scope".to_string())
+                ),
+                (
+                    "src/main.rs".to_string(),
+                    Position::new(9, 8),
+                    Some("warning: unused variable: `y`".to_string())
+                )
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn auto_detects_every_preset() -> anyhow::Result<()> {
+        let output = "
+src/main.rs(3,7): error TS2322: Type 'string' is not assignable to type 'number'.
+src/main.rs:4:1: error: expected ';' before '}' token
+test_app.py:12: AssertionError
+src/missing.rs:1:1: error: the file does not exist
+";
+        assert_eq!(
+            parse(ErrorFormatConfig::default(), output)?,
+            vec![
+                (
+                    "src/main.rs".to_string(),
+                    Position::new(2, 6),
+                    Some(
+                        "error TS2322: Type 'string' is not assignable to type 'number'."
+                            .to_string()
+                    )
+                ),
+                (
+                    "src/main.rs".to_string(),
+                    Position::new(3, 0),
+                    Some("error: expected ';' before '}' token".to_string())
+                ),
+                (
+                    "test_app.py".to_string(),
+                    Position::new(11, 0),
+                    Some("AssertionError".to_string())
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn custom_format() -> anyhow::Result<()> {
+        let format = ErrorFormatConfig::Custom {
+            pattern: r"^(?P<path>\S+) line (?P<line>\d+)$".to_string(),
+            message_pattern: Some(r"^E: (?P<message>.*)$".to_string()),
+        };
+        assert_eq!(
+            parse(format, "E: bad indentation\nsrc/main.rs line 5\n")?,
+            vec![(
+                "src/main.rs".to_string(),
+                Position::new(4, 0),
+                Some("bad indentation".to_string())
+            )]
+        );
+
+        let format = ErrorFormatConfig::Custom {
+            pattern: r"^(?P<path>\S+)$".to_string(),
+            message_pattern: None,
+        };
+        assert!(parse(format, "").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn kill_processes_spawned_by_the_command() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        // The background `sleep` keeps stdout open, so the task only exits when it is killed too
+        let task_run = TaskRun::start(
+            Task::from_command("sleep 30 & sleep 30"),
+            directory.path(),
+            sender,
+        )?;
+        task_run.kill();
+        let exited = std::iter::from_fn(|| {
+            receiver
+                .recv_timeout(std::time::Duration::from_secs(5))
+                .ok()
+        })
+        .any(|message| matches!(message, AppMessage::TaskExited { .. }));
+        assert!(exited);
+        Ok(())
+    }
+
+    #[test]
+    fn receive_output_of_both_streams() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut task_run = TaskRun::start(
+            Task::from_command("echo out; echo err >&2"),
+            directory.path(),
+            sender,
+        )?;
+        let exited = std::iter::from_fn(|| {
+            receiver
+                .recv_timeout(std::time::Duration::from_secs(5))
+                .ok()
+        })
+        .any(|message| matches!(message, AppMessage::TaskExited { code: Some(0), .. }));
+        assert!(exited);
+        let ReceivedOutput::Appended(received) = task_run.receive_output() else {
+            panic!("Expected the output to be appended")
+        };
+        assert_eq!(received.lines().sorted().collect_vec(), vec!["err", "out"]);
+        assert_eq!(task_run.output(), received);
+        assert!(matches!(task_run.receive_output(), ReceivedOutput::Nothing));
+        Ok(())
+    }
+
+    #[test]
+    fn drop_oldest_lines_keeps_whole_lines() {
+        let mut output = "first\nsecond\nthird\n".to_string();
+        assert!(!drop_oldest_lines(&mut output, 100));
+        assert!(drop_oldest_lines(&mut output, 10));
+        assert_eq!(output, "third\n");
+        assert!(drop_oldest_lines(&mut output, 3));
+        assert_eq!(output, "");
+    }
+}
@@ -20,8 +20,8 @@ use shared::canonicalized_path::CanonicalizedPath;
 
 use crate::{
     app::{
-        App, Dimension, Dispatch, LocalSearchConfigUpdate, RequestParams, Scope,
-        StatusLineComponent,
+        App, AppMessage, Dimension, Dispatch, EntryConfig, EntryPath, LocalSearchConfigUpdate,
+        RequestParams, Scope, StatusLineComponent,
     },
     char_index_range::CharIndexRange,
     clipboard::CopiedTexts,
@@ -43,8 +43,14 @@ use crate::{
         signature_help::SignatureInformation,
         workspace_edit::{TextDocumentEdit, WorkspaceEdit},
     },
+    plugin::{
+        protocol::{PluginMessage, PluginNotification},
+        PluginConfig,
+    },
     position::Position,
     quickfix_list::{DiagnosticSeverityRange, Location, QuickfixListItem},
+    recovery::{self, AutoSaveConfig, RecoveryFile},
+    rectangle::{Rectangle, SplitOrientation, WindowDirection},
     selection::SelectionMode,
     style::Style,
     themes::Theme,
@@ -61,6 +67,13 @@ pub(crate) enum Step {
     SuggestiveEditor(DispatchSuggestiveEditor),
     ExpectLater(Box<dyn Fn() -> ExpectKind>),
     ExpectCustom(Box<dyn Fn()>),
+    /// Handle the messages sent by background threads until one of them satisfies the predicate
+    WaitForAppMessage(fn(&AppMessage) -> bool),
+    /// Open what is given on the command line
+    OpenEntry(EntryConfig),
+    StartPlugins(Vec<PluginConfig>),
+    /// Start recovery and offer the recovery files, like launching Ki does
+    StartRecovery(AutoSaveConfig),
 }
 
 #[derive(Debug, Clone)]
@@ -113,6 +126,7 @@ pub(crate) enum ExpectKind {
     CurrentGlobalMode(Option<GlobalMode>),
     LspRequestSent(FromEditor),
     CurrentCopiedTextHistoryOffset(isize),
+    CurrentBufferReadonly(bool),
 }
 fn log<T: std::fmt::Debug>(s: T) {
     println!("===========\n{s:?}",);
@@ -183,6 +197,14 @@ impl ExpectKind {
                 contextualize(actual, grid.to_string().trim_matches('\n').to_string())
             }
             CurrentPath(path) => contextualize(app.get_current_file_path().unwrap(), path.clone()),
+            CurrentBufferReadonly(expected) => contextualize(
+                *expected,
+                app.current_component()
+                    .borrow()
+                    .editor()
+                    .buffer()
+                    .readonly(),
+            ),
             Not(expect_kind) => {
                 let (result, context) = expect_kind.get_result(app)?;
                 (!result, format!("NOT ({context})"))
@@ -372,6 +394,7 @@ pub(crate) struct State {
     main_rs: CanonicalizedPath,
     foo_rs: CanonicalizedPath,
     git_ignore: CanonicalizedPath,
+    recovery_dir: PathBuf,
 }
 impl State {
     pub(crate) fn main_rs(&self) -> CanonicalizedPath {
@@ -393,6 +416,11 @@ impl State {
     pub(crate) fn temp_dir(&self) -> CanonicalizedPath {
         self.temp_dir.clone()
     }
+
+    /// Where the app writes and loads recovery files, outside of `temp_dir`
+    pub(crate) fn recovery_dir(&self) -> PathBuf {
+        self.recovery_dir.clone()
+    }
 }
 
 pub(crate) fn execute_test(callback: impl Fn(State) -> Box<[Step]>) -> anyhow::Result<()> {
@@ -434,6 +462,7 @@ fn execute_test_helper(
                 foo_rs: temp_dir.join("src/foo.rs").unwrap(),
                 git_ignore: temp_dir.join(".gitignore").unwrap(),
                 temp_dir,
+                recovery_dir: app.recovery_directory().to_path_buf(),
             })
         };
 
@@ -469,6 +498,13 @@ fn execute_test_helper(
                     log(dispatch);
                     app.handle_dispatch_suggestive_editor(dispatch.to_owned())?
                 }
+                WaitForAppMessage(is_expected) => app.wait_for_app_message(*is_expected)?,
+                OpenEntry(entry) => app.open_entry(entry.clone())?,
+                StartPlugins(configs) => app.start_plugin_processes(configs.clone()),
+                StartRecovery(auto_save) => {
+                    app.start_recovery_with_auto_save(auto_save.clone());
+                    app.offer_recovery()?
+                }
             };
         }
 
@@ -494,6 +530,9 @@ fn run_test(
             status_line_components.clone(),
         )?;
         app.disable_lsp();
+        let cache_dir = tempfile::tempdir()?;
+        app.set_undo_history_directory(Some(cache_dir.path().join("undo_history")));
+        app.set_recovery_directory(cache_dir.path().join("recovery"));
         callback(app, temp_dir)?;
         use std::borrow::Borrow;
         let output = frontend.lock().unwrap().borrow().string_content();
@@ -889,6 +928,131 @@ pub(crate) fn repo_git_hunks() -> Result<(), anyhow::Error> {
     })
 }
 
+#[test]
+fn revert_hunks_of_crlf_file() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        let temp_dir = s.temp_dir();
+        let foo_rs = s.foo_rs();
+        Box::new([
+            // Commit foo.rs with CRLF line endings
+            ExpectCustom(Box::new(move || {
+                std::fs::write(&foo_rs, "a\r\nb\r\nc\r\n").unwrap();
+                for args in [
+                    ["add", "."].as_slice(),
+                    &[
+                        "-c",
+                        "core.autocrlf=false",
+                        "-c",
+                        "user.name=Ki",
+                        "-c",
+                        "user.email=ki@example.com",
+                        "commit",
+                        "-m",
+                        "CRLF",
+                    ],
+                ] {
+                    std::process::Command::new("git")
+                        .args(args)
+                        .current_dir(&temp_dir)
+                        .output()
+                        .unwrap();
+                }
+            })),
+            App(OpenFile(s.foo_rs())),
+            Editor(SetContent("a\nB\nc\nd\n".to_string())),
+            // Hunks are only reverted once the buffer is saved
+            App(SaveAll),
+            Editor(SetSelectionMode(IfCurrentNotFound::LookForward, Line)),
+            Editor(SelectAll),
+            Editor(RevertHunks(
+                crate::git::DiffMode::UnstagedAgainstCurrentBranch,
+            )),
+            // Only the changed lines are reverted, and the line endings stay normalized
+            Expect(CurrentComponentContent("a\nb\nc\n")),
+        ])
+    })
+}
+
+#[test]
+fn repo_merge_conflicts_of_opened_files_are_read_from_their_buffers() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        let temp_dir = s.temp_dir();
+        let foo_rs = s.foo_rs();
+        Box::new([
+            // Make both branches change foo.rs, then merge them
+            ExpectCustom(Box::new(move || {
+                let git = |args: &[&str]| {
+                    std::process::Command::new("git")
+                        .args([
+                            "-c",
+                            "user.name=Ki",
+                            "-c",
+                            "user.email=ki@example.com",
+                            "-c",
+                            "merge.conflictStyle=merge",
+                        ])
+                        .args(args)
+                        .current_dir(&temp_dir)
+                        .output()
+                        .unwrap();
+                };
+                git(&["checkout", "-b", "ours"]);
+                git(&["checkout", "-b", "theirs"]);
+                std::fs::write(&foo_rs, "b\n").unwrap();
+                git(&["commit", "-am", "theirs"]);
+                git(&["checkout", "ours"]);
+                std::fs::write(&foo_rs, "c\n").unwrap();
+                git(&["commit", "-am", "ours"]);
+                git(&["merge", "theirs"]);
+            })),
+            App(GetRepoMergeConflicts),
+            Expect(Quickfixes(Box::new([QuickfixListItem::new(
+                Location {
+                    path: s.foo_rs(),
+                    range: Position::new(0, 0)..Position::new(5, 0),
+                },
+                None,
+            )]))),
+            // Resolve the conflict without saving
+            App(OpenFile(s.foo_rs())),
+            Editor(SetContent("c\n".to_string())),
+            App(GetRepoMergeConflicts),
+            Expect(Quickfixes(Box::new([]))),
+        ])
+    })
+}
+
+#[test]
+fn breakpoints_move_along_with_edited_lines() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        let temp_dir = s.temp_dir();
+        Box::new([
+            App(OpenFile(s.foo_rs())),
+            Editor(SetContent("a\nb\nc\n".to_string())),
+            App(ToggleBreakpoint {
+                path: s.foo_rs(),
+                line: 1,
+            }),
+            Editor(SetSelectionMode(IfCurrentNotFound::LookForward, Line)),
+            Editor(EnterInsertMode(Direction::Start)),
+            Editor(Insert("x\ny\n".to_string())),
+            Expect(CurrentComponentContent("x\ny\na\nb\nc\n")),
+            App(ToggleBreakpoint {
+                path: s.foo_rs(),
+                line: 0,
+            }),
+            // The breakpoint of `b` moved from the 2nd line to the 4th line
+            ExpectCustom(Box::new(move || {
+                assert_eq!(
+                    std::fs::read_to_string(crate::dap::breakpoints::config_path(&temp_dir))
+                        .unwrap(),
+                    "{\n  \"src/foo.rs\": [\n    1,\n    4\n  ]\n}"
+                )
+            })),
+        ])
+    })
+}
+
 #[test]
 pub(crate) fn non_git_ignored_files() -> Result<(), anyhow::Error> {
     execute_test(|s| {
@@ -941,6 +1105,56 @@ pub(crate) fn non_git_ignored_files() -> Result<(), anyhow::Error> {
     })
 }
 
+#[test]
+fn commit_is_created_when_the_saved_commit_message_is_closed() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        let temp_dir = s.temp_dir();
+        let git = move |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .args(args)
+                .current_dir(&temp_dir)
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        let last_commit_summary = {
+            let git = git.clone();
+            move || git(&["log", "-1", "--format=%s"])
+        };
+        let initial_summary = last_commit_summary();
+        let foo_rs = s.foo_rs();
+        Box::new([
+            ExpectCustom(Box::new(move || {
+                git(&["config", "user.name", "Ki"]);
+                git(&["config", "user.email", "ki@example.com"]);
+                std::fs::write(&foo_rs, "changed").unwrap();
+                git(&["add", "."]);
+            })),
+            // Closing the commit message without saving it aborts the commit
+            App(OpenCommitMessageEditor { amend: false }),
+            Editor(Insert("Unsaved".to_string())),
+            App(CloseCurrentWindow),
+            ExpectCustom(Box::new({
+                let last_commit_summary = last_commit_summary.clone();
+                let initial_summary = initial_summary.clone();
+                move || assert_eq!(last_commit_summary(), initial_summary)
+            })),
+            // Saving the commit message does not commit until it is closed
+            App(OpenCommitMessageEditor { amend: false }),
+            Editor(Insert("Change foo".to_string())),
+            Editor(Save),
+            ExpectCustom(Box::new({
+                let last_commit_summary = last_commit_summary.clone();
+                move || assert_eq!(last_commit_summary(), initial_summary)
+            })),
+            App(CloseCurrentWindow),
+            ExpectCustom(Box::new(move || {
+                assert_eq!(last_commit_summary(), "Change foo")
+            })),
+        ])
+    })
+}
+
 #[test]
 fn align_view_bottom_with_outbound_parent_lines() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -1294,6 +1508,107 @@ fn global_search_replace_naming_convention_agnostic() -> Result<(), anyhow::Erro
     })
 }
 
+#[test]
+fn global_replace_preview() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        let new_dispatch = |update: LocalSearchConfigUpdate| -> Dispatch {
+            UpdateLocalSearchConfig {
+                update,
+                scope: Scope::Global,
+                show_config_after_enter: false,
+                if_current_not_found: IfCurrentNotFound::LookForward,
+            }
+        };
+        let main_content = "a kitty\nb\nc kitty\n";
+        Box::new([
+            App(OpenFile(s.foo_rs())),
+            Editor(SetContent("d".to_string())),
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(main_content.to_string())),
+            App(SaveAll),
+            App(new_dispatch(LocalSearchConfigUpdate::Search(
+                "kitty".to_string(),
+            ))),
+            App(new_dispatch(LocalSearchConfigUpdate::Replacement(
+                "cat".to_string(),
+            ))),
+            App(OpenGlobalReplacePreview),
+            Expect(CurrentComponentContent(
+                "# Replace preview: 2 change(s) in 1 file(s).
+# Change `[x]` to `[ ]` to reject a change.
+# Edit the lines starting with `+` to change a replacement.
+# Save this file to apply the accepted changes.
+
+=== src/main.rs
+[x] line 1
+- a kitty
++ a cat
+[x] line 3
+- c kitty
++ c cat
+",
+            )),
+            // Edit the first replacement, and reject the second change
+            Editor(SetContent(
+                "
+=== src/main.rs
+[x] line 1
+- a kitty
++ a tiger
+[ ] line 3
+- c kitty
++ c cat
+"
+                .to_string(),
+            )),
+            Editor(Save),
+            Expect(FileContent(
+                s.main_rs(),
+                "a tiger\nb\nc kitty\n".to_string(),
+            )),
+            // The replacement is undone in the buffer of the file
+            App(OpenFile(s.main_rs())),
+            Editor(Undo),
+            Expect(CurrentComponentContent(main_content)),
+        ])
+    })
+}
+
+#[test]
+fn global_replace_preview_keeps_unsaved_changes() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        let new_dispatch = |update: LocalSearchConfigUpdate| -> Dispatch {
+            UpdateLocalSearchConfig {
+                update,
+                scope: Scope::Global,
+                show_config_after_enter: false,
+                if_current_not_found: IfCurrentNotFound::LookForward,
+            }
+        };
+        Box::new([
+            App(OpenFile(s.foo_rs())),
+            Editor(SetContent("d".to_string())),
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("a kitty\n".to_string())),
+            App(SaveAll),
+            // The unsaved content of the buffer is previewed instead of the file content
+            Editor(SetContent("a kitty\nb kitty\n".to_string())),
+            App(new_dispatch(LocalSearchConfigUpdate::Search(
+                "kitty".to_string(),
+            ))),
+            App(new_dispatch(LocalSearchConfigUpdate::Replacement(
+                "cat".to_string(),
+            ))),
+            App(OpenGlobalReplacePreview),
+            Editor(Save),
+            // The buffer is not saved, so that its unsaved changes are not written unexpectedly
+            Expect(FileContent(s.main_rs(), "a kitty\n".to_string())),
+            App(OpenFile(s.main_rs())),
+            Expect(CurrentComponentContent("a cat\nb cat\n")),
+        ])
+    })
+}
+
 #[test]
 fn quickfix_list() -> Result<(), anyhow::Error> {
     execute_test(|s| {
@@ -1876,6 +2191,145 @@ fn dropdown_can_only_be_rendered_on_suggestive_editor_or_prompt() -> anyhow::Res
     })
 }
 
+#[test]
+fn split_windows() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(SplitWindow(SplitOrientation::SideBySide)),
+            // The new window shows the same file
+            Expect(CurrentPath(s.main_rs())),
+            Expect(ComponentsLength(2)),
+            // Saving removes the other components, but not the other windows
+            Editor(Save),
+            Expect(ComponentsLength(2)),
+            App(OpenFile(s.foo_rs())),
+            Expect(CurrentPath(s.foo_rs())),
+            App(MoveWindowFocus(WindowDirection::Left)),
+            Expect(CurrentPath(s.main_rs())),
+            // There is no window above
+            App(MoveWindowFocus(WindowDirection::Up)),
+            Expect(CurrentPath(s.main_rs())),
+            App(MoveWindowFocus(WindowDirection::Right)),
+            Expect(CurrentPath(s.foo_rs())),
+            // Opening a file shown in another window focuses that window
+            App(OpenFile(s.main_rs())),
+            Expect(CurrentPath(s.main_rs())),
+            Expect(ComponentsLength(2)),
+            App(MoveWindowFocus(WindowDirection::Right)),
+            App(CloseCurrentWindow),
+            Expect(CurrentPath(s.main_rs())),
+            Expect(ComponentsLength(1)),
+        ])
+    })
+}
+
+#[test]
+fn diff_viewer_replaces_only_the_focused_window() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(SplitWindow(SplitOrientation::SideBySide)),
+            App(OpenDiffView {
+                kind: crate::diff_view::DiffViewKind::SideBySide,
+                source: crate::diff_view::DiffSource::File(s.foo_rs().to_path_buf().clone()),
+            }),
+            // The other window is kept
+            Expect(ComponentsLength(2)),
+            App(MoveWindowFocus(WindowDirection::Left)),
+            Expect(CurrentPath(s.main_rs())),
+        ])
+    })
+}
+
+#[test]
+fn open_entry_readonly() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            OpenEntry(EntryConfig {
+                paths: [EntryPath {
+                    path: s.main_rs().into_path_buf(),
+                    position: None,
+                }]
+                .to_vec(),
+                readonly: true,
+                ..Default::default()
+            }),
+            Expect(CurrentPath(s.main_rs())),
+            Expect(CurrentBufferReadonly(true)),
+        ])
+    })
+}
+
+#[test]
+fn open_entry_focuses_the_first_path_and_shows_stdin_in_another_window() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            OpenEntry(EntryConfig {
+                paths: [s.main_rs(), s.foo_rs()]
+                    .into_iter()
+                    .map(|path| EntryPath {
+                        path: path.into_path_buf(),
+                        position: None,
+                    })
+                    .collect(),
+                stdin: Some("from stdin".to_string()),
+                ..Default::default()
+            }),
+            Expect(CurrentPath(s.main_rs())),
+            Expect(ComponentsLength(2)),
+            App(MoveWindowFocus(WindowDirection::Right)),
+            Expect(CurrentComponentContent("from stdin")),
+            Expect(CurrentComponentTitle("[stdin]")),
+        ])
+    })
+}
+
+#[test]
+fn open_entry_diff() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            OpenEntry(EntryConfig {
+                diff: Some((s.foo_rs(), s.main_rs())),
+                readonly: true,
+                ..Default::default()
+            }),
+            Expect(CurrentComponentTitle(
+                "Diff side by side: src/foo.rs ↔ src/main.rs",
+            )),
+            // The new file is opened readonly in the background
+            Expect(OpenedFilesCount(1)),
+            App(OpenFile(s.main_rs())),
+            Expect(CurrentBufferReadonly(true)),
+        ])
+    })
+}
+
+#[test]
+fn open_entry_creates_missing_file_on_save() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let path = s.temp_dir().to_path_buf().join("new.txt");
+        let path_clone = path.clone();
+        Box::new([
+            OpenEntry(EntryConfig {
+                paths: [EntryPath {
+                    path: path.clone(),
+                    position: None,
+                }]
+                .to_vec(),
+                ..Default::default()
+            }),
+            Expect(CurrentComponentContent("")),
+            ExpectCustom(Box::new(move || assert!(!path_clone.exists()))),
+            Editor(Insert("hello".to_string())),
+            Editor(Save),
+            ExpectLater(Box::new(move || {
+                FileContent(path.as_path().try_into().unwrap(), "hello".to_string())
+            })),
+        ])
+    })
+}
+
 #[test]
 fn only_children_of_root_can_remove_all_other_components() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -2104,3 +2558,226 @@ c1 c2 a1\nb1\nc1
         }
     })
 }
+
+#[test]
+fn command_palette() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("a();".to_string())),
+            App(HandleKeyEvents(keys!("space p").to_vec())),
+            Expect(CurrentComponentTitle("Command palette")),
+            App(HandleKeyEvents(
+                keys!("T o g g l e space c o m m e n t enter").to_vec(),
+            )),
+            Expect(CurrentComponentContent("// a();")),
+        ])
+    })
+}
+
+#[test]
+fn command_palette_lists_insert_mode_actions() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("hello world".to_string())),
+            Editor(MatchLiteral("world".to_string())),
+            Editor(EnterInsertMode(Direction::End)),
+            App(OpenCommandPalette),
+            App(HandleKeyEvents(
+                keys!("D e l e t e space t o k e n space b a c k w a r d enter").to_vec(),
+            )),
+            Expect(CurrentComponentContent("hello ")),
+        ])
+    })
+}
+
+#[test]
+fn command_palette_enters_the_mode_of_the_action() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("hello world".to_string())),
+            Editor(MatchLiteral("world".to_string())),
+            Expect(CurrentMode(Mode::Normal)),
+            App(OpenCommandPalette),
+            App(HandleKeyEvents(
+                keys!("D e l e t e space t o k e n space b a c k w a r d enter").to_vec(),
+            )),
+            Expect(CurrentMode(Mode::Insert)),
+            Expect(CurrentComponentContent("world")),
+        ])
+    })
+}
+
+#[test]
+fn plugins_set_their_own_decorations_and_receive_responses() -> anyhow::Result<()> {
+    // Sets a decoration on the given columns, requests the buffer content,
+    // and shows an info once the content is received
+    let script = r#"
+read -r initialize
+printf '{"jsonrpc":"2.0","method":"decorations/set","params":{"path":"src/main.rs","decorations":[{"range":{"start":{"line":0,"column":%s},"end":{"line":0,"column":%s}},"style":"%s"}]}}\n' "$1" "$2" "$3"
+echo '{"jsonrpc":"2.0","id":1,"method":"buffer/content","params":{"path":"src/main.rs"}}'
+while read -r line; do
+  case "$line" in
+    *'"result":"hello world"'*) echo '{"jsonrpc":"2.0","method":"info/show","params":{"title":"Plugin","content":"Received"}}' ;;
+  esac
+done
+"#;
+    let stub = |name: &str, start: &str, end: &str, style: &str| PluginConfig {
+        name: name.to_string(),
+        command: "sh".to_string(),
+        args: ["-c", script, name, start, end, style]
+            .into_iter()
+            .map(|arg| arg.to_string())
+            .collect(),
+    };
+    let received_content = |message: &AppMessage| {
+        matches!(
+            message,
+            AppMessage::PluginMessage {
+                message: PluginMessage::Notification(PluginNotification::ShowInfo { .. }),
+                ..
+            }
+        )
+    };
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("hello world".to_string())),
+            Editor(SetRectangle(Rectangle {
+                origin: Position::default(),
+                width: 20,
+                height: 3,
+            })),
+            StartPlugins(
+                [
+                    stub("first", "0", "5", "keyword"),
+                    stub("second", "6", "11", "comment"),
+                ]
+                .to_vec(),
+            ),
+            // Each plugin sets its decorations before requesting the content
+            WaitForAppMessage(received_content),
+            WaitForAppMessage(received_content),
+            // The first row is the title, and the first 2 columns are the line number
+            Expect(GridCellStyleKey(
+                Position::new(1, 4),
+                Some(StyleKey::Syntax("keyword".to_string())),
+            )),
+            Expect(GridCellStyleKey(
+                Position::new(1, 9),
+                Some(StyleKey::Syntax("comment".to_string())),
+            )),
+        ])
+    })
+}
+
+/// Writes a recovery file of `path` as if it was left behind by a process that is no longer running.
+fn write_orphaned_recovery_file(
+    recovery_dir: &std::path::Path,
+    path: &CanonicalizedPath,
+    content: &str,
+) {
+    let mut child = std::process::Command::new("true").spawn().unwrap();
+    child.wait().unwrap();
+    std::fs::create_dir_all(recovery_dir).unwrap();
+    std::fs::write(
+        recovery::recovery_file_path(recovery_dir, path),
+        serde_json::to_string(&RecoveryFile {
+            path: path.to_path_buf().clone(),
+            content: content.to_string(),
+            pid: child.id(),
+        })
+        .unwrap(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn recover_orphaned_recovery_file() -> anyhow::Result<()> {
+    execute_test(|s| {
+        write_orphaned_recovery_file(&s.recovery_dir(), &s.main_rs(), "recovered");
+        let original = s.main_rs().read().unwrap();
+        let recovery_file_path = recovery::recovery_file_path(&s.recovery_dir(), &s.main_rs());
+        let main_rs = s.main_rs();
+        Box::new([
+            StartRecovery(AutoSaveConfig::default()),
+            App(HandleKeyEvent(key!("r"))),
+            Expect(CurrentComponentPath(Some(s.main_rs()))),
+            Expect(CurrentComponentContent("recovered")),
+            ExpectCustom(Box::new({
+                let original = original.clone();
+                move || {
+                    // The recovered content is not saved
+                    assert_eq!(main_rs.read().unwrap(), original);
+                    assert!(!recovery_file_path.exists())
+                }
+            })),
+            // The recovery can be undone
+            Editor(Undo),
+            Expect(FileContent(s.main_rs(), original)),
+        ])
+    })
+}
+
+#[test]
+fn discard_orphaned_recovery_files() -> anyhow::Result<()> {
+    execute_test(|s| {
+        write_orphaned_recovery_file(&s.recovery_dir(), &s.main_rs(), "unsaved main");
+        write_orphaned_recovery_file(&s.recovery_dir(), &s.foo_rs(), "unsaved foo");
+        let original_main_rs = s.main_rs().read().unwrap();
+        let main_rs = s.main_rs();
+        let recovery_dir = s.recovery_dir();
+        Box::new([
+            StartRecovery(AutoSaveConfig::default()),
+            // The next recovery file is offered once one is discarded
+            App(HandleKeyEvent(key!("d"))),
+            App(HandleKeyEvent(key!("d"))),
+            Expect(OpenedFilesCount(0)),
+            ExpectCustom(Box::new(move || {
+                assert_eq!(main_rs.read().unwrap(), original_main_rs);
+                assert_eq!(std::fs::read_dir(&recovery_dir).unwrap().count(), 0)
+            })),
+        ])
+    })
+}
+
+#[test]
+fn auto_save_on_focus_change() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let main_rs = s.main_rs();
+        Box::new([
+            StartRecovery(AutoSaveConfig {
+                on_focus_change: true,
+                ..Default::default()
+            }),
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn main() {}\n".to_string())),
+            App(HandleKeyEvents(keys!("space p").to_vec())),
+            Expect(CurrentComponentTitle("Command palette")),
+            ExpectCustom(Box::new(move || {
+                assert_eq!(main_rs.read().unwrap(), "fn main() {}\n")
+            })),
+        ])
+    })
+}
+
+#[test]
+fn auto_save_after_idle_without_formatting() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let main_rs = s.main_rs();
+        Box::new([
+            StartRecovery(AutoSaveConfig {
+                after_idle_seconds: Some(0),
+                ..Default::default()
+            }),
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn   main() {}".to_string())),
+            WaitForAppMessage(|message| matches!(message, AppMessage::Tick)),
+            ExpectCustom(Box::new(move || {
+                assert_eq!(main_rs.read().unwrap(), "fn   main() {}")
+            })),
+        ])
+    })
+}
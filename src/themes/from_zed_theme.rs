@@ -149,6 +149,9 @@ pub fn from_zed_theme(url: &str) -> anyhow::Result<Vec<Theme>> {
                         .background_color(background),
                     mark: Style::new()
                         .set_some_background_color(from_some_hex(theme.style.conflict_background)),
+                    breakpoint: Style::new()
+                        .set_some_foreground_color(from_some_hex(theme.style.error.clone()))
+                        .background_color(background),
                     possible_selection_background: from_some_hex(
                         theme.style.search_match_background,
                     )
@@ -182,6 +185,11 @@ pub fn from_zed_theme(url: &str) -> anyhow::Result<Vec<Theme>> {
                 } else {
                     super::HunkStyles::dark()
                 },
+                conflict: if theme.appearance == AppearanceContent::Light {
+                    super::ConflictStyles::light()
+                } else {
+                    super::ConflictStyles::dark()
+                },
             })
         })
         .collect_vec())
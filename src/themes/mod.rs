@@ -19,6 +19,7 @@ pub(crate) struct Theme {
     pub(crate) ui: UiStyles,
     pub(crate) diagnostic: DiagnosticStyles,
     pub(crate) hunk: HunkStyles,
+    pub(crate) conflict: ConflictStyles,
 }
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub(crate) struct HunkStyles {
@@ -45,6 +46,32 @@ impl HunkStyles {
         }
     }
 }
+/// Backgrounds of the sections of merge conflict regions.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) struct ConflictStyles {
+    pub(crate) marker_background: Color,
+    pub(crate) ours_background: Color,
+    pub(crate) base_background: Color,
+    pub(crate) theirs_background: Color,
+}
+impl ConflictStyles {
+    fn dark() -> Self {
+        Self {
+            marker_background: hex!("#4B4B4B"),
+            ours_background: hex!("#27403B"),
+            base_background: hex!("#383838"),
+            theirs_background: hex!("#28384B"),
+        }
+    }
+    fn light() -> Self {
+        Self {
+            marker_background: hex!("#DADADA"),
+            ours_background: hex!("#E1F4EF"),
+            base_background: hex!("#F0F0F0"),
+            theirs_background: hex!("#E0ECFA"),
+        }
+    }
+}
 impl Theme {
     pub(crate) fn get_style(&self, source: &StyleKey) -> Style {
         match source {
@@ -79,6 +106,15 @@ impl Theme {
                 Style::new().background_color(self.hunk.new_emphasized_background)
             }
 
+            StyleKey::ConflictMarker => {
+                Style::new().background_color(self.conflict.marker_background)
+            }
+            StyleKey::ConflictOurs => Style::new().background_color(self.conflict.ours_background),
+            StyleKey::ConflictBase => Style::new().background_color(self.conflict.base_background),
+            StyleKey::ConflictTheirs => {
+                Style::new().background_color(self.conflict.theirs_background)
+            }
+
             StyleKey::Syntax(highlight_group) => {
                 self.syntax.get_style(highlight_group).unwrap_or_default()
             }
@@ -163,6 +199,8 @@ pub(crate) struct UiStyles {
     pub(crate) line_number: Style,
     pub(crate) border: Style,
     pub(crate) mark: Style,
+    /// The marker of breakpoints in the gutter
+    pub(crate) breakpoint: Style,
     pub(crate) keymap_key: Style,
     pub(crate) keymap_arrow: Style,
     pub(crate) keymap_hint: Style,
@@ -60,6 +60,9 @@ pub fn vscode_dark() -> Theme {
                 .background_color(hex!("#1E1E1E"))
                 .foreground_color(hex!("#858585")),
             mark: Style::new().background_color(hex!("#ffcc00")),
+            breakpoint: Style::new()
+                .background_color(hex!("#1E1E1E"))
+                .foreground_color(hex!("#E51400")),
             possible_selection_background: hex!("#5C3521"),
             keymap_hint: Style::new().underline(hex!("#af00db")),
             keymap_key: Style::new().bold().foreground_color(hex!("#af00db")),
@@ -68,5 +71,6 @@ pub fn vscode_dark() -> Theme {
         },
         diagnostic: DiagnosticStyles::default(),
         hunk: super::HunkStyles::dark(),
+        conflict: super::ConflictStyles::dark(),
     }
 }
@@ -60,6 +60,9 @@ pub fn vscode_light() -> Theme {
                 .foreground_color(hex!("#aaaaaa"))
                 .background_color(hex!("#ffffff")),
             mark: Style::new().background_color(hex!("#ffcc00")),
+            breakpoint: Style::new()
+                .foreground_color(hex!("#E51400"))
+                .background_color(hex!("#ffffff")),
             possible_selection_background: hex!("#f6f7b2"),
             keymap_hint: Style::new().underline(hex!("#af00db")),
             keymap_key: Style::new().bold().foreground_color(hex!("#af00db")),
@@ -68,5 +71,6 @@ pub fn vscode_light() -> Theme {
         },
         diagnostic: DiagnosticStyles::default(),
         hunk: super::HunkStyles::light(),
+        conflict: super::ConflictStyles::light(),
     }
 }
@@ -73,11 +73,17 @@ impl UiTree {
             return;
         }
         let current_component_id = self.focused_component_id();
+        let window_ids = self.window_ids();
         let root = self.root();
         let root_id = root.node_id();
+        // The other windows are kept, because they are part of the main panel
         for node_id in root
             .traverse_pre_order()
-            .filter(|node| node.node_id() != root_id && node.node_id() != self.focused_component_id)
+            .filter(|node| {
+                node.node_id() != root_id
+                    && node.node_id() != self.focused_component_id
+                    && !window_ids.contains(&node.node_id())
+            })
             .map(|node| node.node_id())
             .collect_vec()
         {
@@ -114,20 +120,97 @@ impl UiTree {
 
     /// This return everything except the root, but if only root exists, then the root will be returned.
     /// This behaviour ensures that the tree always contain a component.
+    /// The windows come first, in their order, followed by the other components.
     pub(crate) fn components(&self) -> Vec<KindedComponent> {
         if self.root().children().count() == 0 {
             Some(self.root().data().clone()).into_iter().collect_vec()
         } else {
             let root_id = self.root().node_id();
-            self.root()
-                .traverse_pre_order()
-                .filter(|node| node.node_id() != root_id)
-                .sorted_by_key(|node| node.data().kind)
-                .map(|node| node.data().clone())
+            let window_ids = self.window_ids();
+            window_ids
+                .iter()
+                .filter_map(|id| Some(self.get(*id)?.data().clone()))
+                .chain(
+                    self.root()
+                        .traverse_pre_order()
+                        .filter(|node| {
+                            node.node_id() != root_id && !window_ids.contains(&node.node_id())
+                        })
+                        .sorted_by_key(|node| node.data().kind)
+                        .map(|node| node.data().clone()),
+                )
                 .collect_vec()
         }
     }
 
+    /// The windows of the main panel are the children of the root whose kind occupies a window.
+    /// Their order is the order of the windows of the split tree of the layout.
+    pub(crate) fn window_ids(&self) -> Vec<NodeId> {
+        self.root()
+            .children()
+            .filter(|node| node.data().kind.is_window())
+            .map(|node| node.node_id())
+            .collect_vec()
+    }
+
+    /// Returns the window that contains the focused component,
+    /// or the first window if the focused component is outside of every window, such as the quickfix list.
+    pub(crate) fn focused_window_id(&self) -> Option<NodeId> {
+        let window_ids = self.window_ids();
+        let mut node = Some(self.get_current_node());
+        while let Some(current) = node {
+            if window_ids.contains(&current.node_id()) {
+                return Some(current.node_id());
+            }
+            node = current.parent();
+        }
+        window_ids.first().copied()
+    }
+
+    /// Replace the component of the focused window, and focus it.
+    /// The children of the replaced component are removed.
+    /// If there is no window yet, `component` becomes the first window.
+    pub(crate) fn replace_focused_window(&mut self, component: KindedComponent) -> NodeId {
+        debug_assert!(component.kind.is_window());
+        let Some(window_id) = self.focused_window_id() else {
+            return self.append_component(self.root_id(), component, true);
+        };
+        let children_ids = self
+            .get(window_id)
+            .map(|node| node.children().map(|child| child.node_id()).collect_vec())
+            .unwrap_or_default();
+        for child_id in children_ids {
+            self.tree.remove(child_id, RemoveBehavior::DropChildren);
+        }
+        if let Some(mut node) = self.get_mut(window_id) {
+            *node.data() = component;
+        }
+        self.set_focus_component_id(window_id);
+        window_id
+    }
+
+    /// Insert `component` as the window next to the window of `window_id`, and focus it.
+    pub(crate) fn insert_window_after(
+        &mut self,
+        window_id: NodeId,
+        component: KindedComponent,
+    ) -> NodeId {
+        debug_assert!(component.kind.is_window());
+        let following_siblings_count = self
+            .root()
+            .children()
+            .skip_while(|node| node.node_id() != window_id)
+            .skip(1)
+            .count();
+        let id = self.append_component(self.root_id(), component, true);
+        if let Some(mut node) = self.get_mut(id) {
+            for _ in 0..following_siblings_count {
+                node.swap_prev_sibling();
+            }
+        }
+        id
+    }
+
     pub(crate) fn root_id(&self) -> NodeId {
         self.root().node_id()
     }
@@ -249,16 +332,17 @@ impl UiTree {
         self.replace_node_child(self.root_id(), kind, component, focus)
     }
 
-    pub(crate) fn remove_all_root_children(&mut self) {
+    /// Remove the children of the root except the windows, such as the quickfix list.
+    pub(crate) fn remove_root_panels(&mut self) {
         let children_ids = self
             .root()
             .children()
+            .filter(|node| !node.data().kind.is_window())
             .map(|node| node.node_id())
             .collect_vec();
         for child_id in children_ids {
             self.tree.remove(child_id, RemoveBehavior::DropChildren);
         }
-        debug_assert_eq!(self.root().children().count(), 0);
     }
 
     pub(crate) fn get_component_by_kind(
@@ -326,9 +410,13 @@ impl std::fmt::Debug for KindedComponent {
 pub(crate) enum ComponentKind {
     SuggestiveEditor,
     FileExplorer,
+    DiffViewer,
     GlobalInfo,
     QuickfixList,
     QuickfixListInfo,
+    TaskOutput,
+    DebugCallStack,
+    DebugVariables,
     Prompt,
     Dropdown,
     DropdownInfo,
@@ -337,3 +425,15 @@ pub(crate) enum ComponentKind {
     /// The root should not be rendered
     Root,
 }
+
+impl ComponentKind {
+    /// Components of these kinds occupy a window of the main panel
+    pub(crate) fn is_window(&self) -> bool {
+        matches!(
+            self,
+            ComponentKind::SuggestiveEditor
+                | ComponentKind::FileExplorer
+                | ComponentKind::DiffViewer
+        )
+    }
+}
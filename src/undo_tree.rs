@@ -1,10 +1,14 @@
-use std::fmt::Display;
+use std::{fmt::Display, time::SystemTime};
 
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use undo::History;
 
 use crate::components::editor::{Direction, Movement};
 
-#[derive(Clone, PartialEq, Debug)]
+/// Older edits are dropped, so that the persisted undo history of a file stays small
+const LIMIT: usize = 1000;
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub(crate) struct OldNew<T> {
     pub(crate) old_to_new: T,
     pub(crate) new_to_old: T,
@@ -15,7 +19,8 @@ pub trait Applicable: Clone + Display + PartialEq {
     fn apply(&self, target: &mut Self::Target) -> anyhow::Result<Self::Output>;
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Serialize", deserialize = "T: DeserializeOwned"))]
 pub(crate) struct UndoTree<T: Applicable> {
     history: History<OldNew<T>>,
 }
@@ -46,7 +51,7 @@ impl<T: Applicable> UndoTree<T> {
 
     pub(crate) fn new() -> UndoTree<T> {
         Self {
-            history: History::new(),
+            history: History::builder().limit(LIMIT).build(),
         }
     }
 
@@ -76,6 +81,27 @@ impl<T: Applicable> UndoTree<T> {
         }
     }
 
+    /// Moves to the last edit of the current branch that was made at or before `time`.
+    pub(crate) fn go_to_time(
+        &mut self,
+        target: &mut T::Target,
+        time: SystemTime,
+    ) -> anyhow::Result<Option<T::Output>> {
+        // Edits of a branch are chronological
+        let index = (0..self.history.len())
+            .take_while(|index| {
+                self.history
+                    .get_entry(*index)
+                    .is_some_and(|entry| entry.st_edit() <= time)
+            })
+            .count();
+        let at = undo::At {
+            root: self.history.head().root,
+            index,
+        };
+        self.history.go_to(target, at).pop().transpose()
+    }
+
     fn go_to_history_branch(
         &mut self,
         target: &mut T::Target,